@@ -0,0 +1,318 @@
+//! A `main` bootstrap with middleware hooks.
+//!
+//! [`App::run`] parses the command line (handling errors with an [`ErrorHandler`]), runs the application, reports any
+//! error, and returns the exit code. Errors are logged, or written to standard error if no logger is installed (or
+//! errors are not enabled), so that failures in hooks that run before logging is initialized are not lost.
+//! [`Middleware`] can hook into each stage, so that cross-cutting concerns (timing, auditing, custom validation,
+//! rewriting arguments, or loading `.env` files with the `dotenv` feature's `Dotenv` middleware) compose without each
+//! one wrapping `main`:
+//!
+//! ```rust,no_run
+//! use cli_helpers::app::{App, Middleware, Outcome};
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(long)]
+//!     limit: Option<usize>,
+//! }
+//!
+//! struct Timing;
+//!
+//! impl Middleware<Opts> for Timing {
+//!     fn post_run(&mut self, outcome: &Outcome) {
+//!         eprintln!("Finished in {:?}", outcome.duration);
+//!     }
+//! }
+//!
+//! let exit_code = App::new().with_middleware(Timing).run(|opts: Opts| {
+//!     // ...
+//!     Ok::<_, cli_helpers::Error>(())
+//! });
+//!
+//! std::process::exit(exit_code);
+//! ```
+//!
+//! Hooks run in the order in which the middleware was added, except for [`Middleware::post_run`], which runs in the
+//! reverse order (so that each middleware's post-run hook sees the state left by those added after it).
+
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+
+use crate::parse_error::{ErrorHandler, USAGE_EXIT_CODE};
+use crate::Error;
+
+/// The exit code for an application error (or a failed middleware hook).
+pub const FAILURE_EXIT_CODE: i32 = 1;
+
+/// Hooks around the stages of [`App::run`] (all of which do nothing by default).
+pub trait Middleware<T> {
+    /// Inspect or rewrite the arguments before they are parsed.
+    fn pre_parse(&mut self, _args: &mut Vec<OsString>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Validate the parsed options (an error is reported as a usage error).
+    fn post_parse(&mut self, _opts: &T) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Prepare for the run (for example by initializing logging).
+    fn pre_run(&mut self, _opts: &T) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Observe the result of the run (this is also called if an earlier hook failed).
+    fn post_run(&mut self, _outcome: &Outcome) {}
+}
+
+/// The result of a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub exit_code: i32,
+    /// The time since the start of [`App::run`].
+    pub duration: Duration,
+    /// The rendered error, if the run (or a hook) failed.
+    pub error: Option<String>,
+}
+
+impl Outcome {
+    pub fn is_success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+pub struct App<T> {
+    error_handler: ErrorHandler,
+    middleware: Vec<Box<dyn Middleware<T>>>,
+}
+
+impl<T: Parser> App<T> {
+    pub fn new() -> Self {
+        Self {
+            error_handler: ErrorHandler::new(),
+            middleware: vec![],
+        }
+    }
+
+    pub fn with_error_handler(self, error_handler: ErrorHandler) -> Self {
+        Self {
+            error_handler,
+            ..self
+        }
+    }
+
+    pub fn with_middleware<M: Middleware<T> + 'static>(mut self, middleware: M) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Run the application with the process's arguments, returning the exit code.
+    pub fn run<E: Display, F: FnOnce(T) -> Result<(), E>>(self, f: F) -> i32 {
+        self.run_from(std::env::args_os(), f)
+    }
+
+    /// Run the application with the given arguments, returning the exit code.
+    ///
+    /// Parsing errors are handled by the [`ErrorHandler`], which exits the process.
+    pub fn run_from<
+        I: IntoIterator<Item = A>,
+        A: Into<OsString>,
+        E: Display,
+        F: FnOnce(T) -> Result<(), E>,
+    >(
+        mut self,
+        args: I,
+        f: F,
+    ) -> i32 {
+        let start = Instant::now();
+        let mut args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+
+        let result = self.run_stages(&mut args, f);
+
+        let outcome = Outcome {
+            exit_code: match &result {
+                Ok(()) => 0,
+                Err((exit_code, _)) => *exit_code,
+            },
+            duration: start.elapsed(),
+            error: result.err().map(|(_, error)| error),
+        };
+
+        if let Some(error) = &outcome.error {
+            if log::log_enabled!(log::Level::Error) {
+                log::error!("{error}");
+            } else {
+                eprintln!("Error: {error}");
+            }
+        }
+
+        for middleware in self.middleware.iter_mut().rev() {
+            middleware.post_run(&outcome);
+        }
+
+        outcome.exit_code
+    }
+
+    fn run_stages<E: Display, F: FnOnce(T) -> Result<(), E>>(
+        &mut self,
+        args: &mut Vec<OsString>,
+        f: F,
+    ) -> Result<(), (i32, String)> {
+        let failure = |error: Error| (FAILURE_EXIT_CODE, error.to_string());
+
+        for middleware in &mut self.middleware {
+            middleware.pre_parse(args).map_err(failure)?;
+        }
+
+        let opts = self
+            .error_handler
+            .parse_from::<T, _, _>(args.iter().cloned());
+
+        for middleware in &mut self.middleware {
+            middleware
+                .post_parse(&opts)
+                .map_err(|error| (USAGE_EXIT_CODE, error.to_string()))?;
+        }
+
+        for middleware in &mut self.middleware {
+            middleware.pre_run(&opts).map_err(failure)?;
+        }
+
+        f(opts).map_err(|error| (FAILURE_EXIT_CODE, error.to_string()))
+    }
+}
+
+impl<T: Parser> Default for App<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> std::fmt::Debug for App<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("App")
+            .field("error_handler", &self.error_handler)
+            .field("middleware", &self.middleware.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(long)]
+        limit: Option<usize>,
+    }
+
+    struct Recorder {
+        name: &'static str,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn record(&self, event: String) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("{}: {event}", self.name));
+        }
+    }
+
+    impl Middleware<Opts> for Recorder {
+        fn pre_parse(&mut self, args: &mut Vec<OsString>) -> Result<(), Error> {
+            self.record(format!("pre_parse {}", args.len()));
+
+            if self.name == "defaults" {
+                args.extend(["--limit".into(), "10".into()]);
+            }
+
+            Ok(())
+        }
+
+        fn post_parse(&mut self, opts: &Opts) -> Result<(), Error> {
+            self.record(format!("post_parse {:?}", opts.limit));
+
+            match opts.limit {
+                Some(0) => Err(Error::InvalidBatchSize(0)),
+                _ => Ok(()),
+            }
+        }
+
+        fn pre_run(&mut self, _opts: &Opts) -> Result<(), Error> {
+            self.record("pre_run".to_string());
+            Ok(())
+        }
+
+        fn post_run(&mut self, outcome: &Outcome) {
+            self.record(format!("post_run {}", outcome.exit_code));
+        }
+    }
+
+    fn app(events: &Arc<Mutex<Vec<String>>>) -> App<Opts> {
+        App::new()
+            .with_middleware(Recorder {
+                name: "defaults",
+                events: events.clone(),
+            })
+            .with_middleware(Recorder {
+                name: "audit",
+                events: events.clone(),
+            })
+    }
+
+    #[test]
+    fn test_app() {
+        let events = Arc::new(Mutex::new(vec![]));
+
+        let exit_code = app(&events).run_from(["test"], |opts| {
+            events.lock().unwrap().push(format!("run {:?}", opts.limit));
+            Ok::<_, Error>(())
+        });
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "defaults: pre_parse 1",
+                "audit: pre_parse 3",
+                "defaults: post_parse Some(10)",
+                "audit: post_parse Some(10)",
+                "defaults: pre_run",
+                "audit: pre_run",
+                "run Some(10)",
+                "audit: post_run 0",
+                "defaults: post_run 0",
+            ]
+        );
+
+        // A failed hook skips the run, but not the post-run hooks.
+        let events = Arc::new(Mutex::new(vec![]));
+        let exit_code = App::new()
+            .with_middleware(Recorder {
+                name: "audit",
+                events: events.clone(),
+            })
+            .run_from(["test", "--limit", "0"], |_| Ok::<_, Error>(()));
+
+        assert_eq!(exit_code, USAGE_EXIT_CODE);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "audit: pre_parse 3",
+                "audit: post_parse Some(0)",
+                "audit: post_run 64",
+            ]
+        );
+
+        let exit_code = App::<Opts>::new().run_from(["test"], |_| Err("failed"));
+        assert_eq!(exit_code, FAILURE_EXIT_CODE);
+    }
+}
@@ -0,0 +1,77 @@
+//! Standard per-application directory locations.
+
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+use crate::Error;
+
+/// The configuration, data, cache, and state directories for an application.
+///
+/// These follow the platform conventions (the XDG base directory specification on Linux, for example). None of the
+/// directories are created until they are needed.
+#[derive(Debug, Clone)]
+pub struct AppDirs {
+    name: String,
+    dirs: ProjectDirs,
+}
+
+impl AppDirs {
+    /// Resolve the directories for the named application (typically `env!("CARGO_PKG_NAME")`).
+    pub fn new(name: &str) -> Result<Self, Error> {
+        let dirs = ProjectDirs::from("", "", name).ok_or(Error::NoAppDirs)?;
+
+        Ok(Self {
+            name: name.to_string(),
+            dirs,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        self.dirs.config_dir()
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        self.dirs.data_dir()
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        self.dirs.cache_dir()
+    }
+
+    /// The state directory, which falls back to the local data directory on platforms without one.
+    pub fn state_dir(&self) -> &Path {
+        self.dirs
+            .state_dir()
+            .unwrap_or_else(|| self.dirs.data_local_dir())
+    }
+
+    /// The application's directory in the system temporary directory (`$TMPDIR/<name>`).
+    pub fn temp_dir(&self) -> PathBuf {
+        std::env::temp_dir().join(&self.name)
+    }
+
+    /// Return the given path, or the default file in the indicated directory, creating the parent directory.
+    pub fn resolve_file(
+        path: Option<&Path>,
+        default_dir: &Path,
+        default_name: &str,
+    ) -> Result<PathBuf, Error> {
+        let path = path
+            .map(|path| path.to_path_buf())
+            .unwrap_or_else(|| default_dir.join(default_name));
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        Ok(path)
+    }
+}
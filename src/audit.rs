@@ -0,0 +1,269 @@
+//! An opt-in audit trail of invocations.
+//!
+//! An [`AuditTrail`] started at the beginning of `main` appends a record to `audit.ndjson` in the application state
+//! directory when the run finishes. Records include the start time, the command-line arguments (with the values of
+//! secret options redacted by [`redact_args`]), the application version, the exit code, and the duration, and are
+//! written to a [`Journal`], so that concurrent invocations can share the file:
+//!
+//! ```rust,no_run
+//! use cli_helpers::{app_dirs::AppDirs, audit::AuditTrail};
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let dirs = AppDirs::new("mytool")?;
+//! let audit = AuditTrail::start(&dirs, env!("CARGO_PKG_VERSION"));
+//! let exit_code = 0;
+//! // ...
+//! audit.finish(exit_code)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`HistoryArgs`] provides a reusable `history` subcommand that lists past runs:
+//!
+//! ```text
+//! $ mytool history --limit 2
+//! STARTED               EXIT  DURATION  VERSION  COMMAND
+//! 2026-10-15T02:31:33Z  0     3m12s     1.4.0    import --since yesterday
+//! 2026-10-14T21:05:10Z  1     850ms     1.4.0    export --token ***
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::journal::{read_journal, Journal};
+use crate::output::{Column, OutputFormat, OutputRecord};
+use crate::redact::redact_args;
+use crate::summary::format_elapsed;
+use crate::timezone::TimezoneArg;
+use crate::{app_dirs::AppDirs, Error, Timestamp, TimestampFormat};
+
+/// The name of the audit file in the application state directory.
+pub const AUDIT_FILE_NAME: &str = "audit.ndjson";
+
+const DEFAULT_HISTORY_LIMIT: usize = 20;
+
+/// A record of a single invocation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub timestamp: Timestamp,
+    pub args: Vec<String>,
+    pub version: String,
+    pub exit_code: i32,
+    pub duration: Duration,
+}
+
+impl AuditRecord {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "timestamp": self.timestamp.format_as(TimestampFormat::Rfc3339 { use_z: true }),
+            "args": self.args,
+            "version": self.version,
+            "exit_code": self.exit_code,
+            "duration_ms": u64::try_from(self.duration.as_millis()).unwrap_or(u64::MAX),
+        })
+    }
+
+    /// Parse a record, returning `None` if it is invalid.
+    pub fn from_json(value: &Value) -> Option<Self> {
+        Some(Self {
+            timestamp: value.get("timestamp")?.as_str()?.parse().ok()?,
+            args: value
+                .get("args")?
+                .as_array()?
+                .iter()
+                .map(|arg| arg.as_str().map(str::to_string))
+                .collect::<Option<_>>()?,
+            version: value.get("version")?.as_str()?.to_string(),
+            exit_code: value.get("exit_code")?.as_i64()?.try_into().ok()?,
+            duration: Duration::from_millis(value.get("duration_ms")?.as_u64()?),
+        })
+    }
+
+    /// The arguments after the program name.
+    pub fn command(&self) -> String {
+        self.args.get(1..).unwrap_or_default().join(" ")
+    }
+}
+
+impl OutputRecord for AuditRecord {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("started"),
+            Column::new("exit"),
+            Column::new("duration"),
+            Column::new("version").with_priority(1),
+            Column::new("command"),
+        ]
+    }
+
+    fn row(&self) -> Vec<Value> {
+        self.row_in(TimezoneArg::Utc)
+    }
+
+    fn row_in(&self, tz: TimezoneArg) -> Vec<Value> {
+        vec![
+            tz.format(self.timestamp, TimestampFormat::Rfc3339 { use_z: true })
+                .to_string()
+                .into(),
+            self.exit_code.into(),
+            format_elapsed(self.duration).into(),
+            self.version.clone().into(),
+            self.command().into(),
+        ]
+    }
+}
+
+/// Records the current invocation when finished.
+#[derive(Debug, Clone)]
+pub struct AuditTrail {
+    path: PathBuf,
+    timestamp: Timestamp,
+    start: Instant,
+    args: Vec<String>,
+    version: String,
+}
+
+impl AuditTrail {
+    /// Start recording the current invocation in the application's audit file.
+    pub fn start(dirs: &AppDirs, version: &str) -> Self {
+        Self::start_in(Self::path(dirs), version)
+    }
+
+    /// Start recording the current invocation in the given file.
+    pub fn start_in<P: AsRef<Path>>(path: P, version: &str) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            timestamp: Timestamp::now(),
+            start: Instant::now(),
+            args: redact_args(std::env::args_os().map(|arg| arg.to_string_lossy().into_owned())),
+            version: version.to_string(),
+        }
+    }
+
+    /// Record these arguments instead of the process's (they are still redacted).
+    pub fn with_args<I: IntoIterator<Item = S>, S: Into<String>>(self, args: I) -> Self {
+        Self {
+            args: redact_args(args),
+            ..self
+        }
+    }
+
+    /// The audit file path (which may not exist).
+    pub fn path(dirs: &AppDirs) -> PathBuf {
+        dirs.state_dir().join(AUDIT_FILE_NAME)
+    }
+
+    /// Append the record for this invocation.
+    pub fn finish(self, exit_code: i32) -> Result<AuditRecord, Error> {
+        let record = AuditRecord {
+            timestamp: self.timestamp,
+            args: self.args,
+            version: self.version,
+            exit_code,
+            duration: self.start.elapsed(),
+        };
+
+        Journal::open(&self.path)?.append(&record.to_json())?;
+
+        Ok(record)
+    }
+}
+
+/// Read all records from an audit file, from oldest to newest (invalid records are skipped).
+pub fn read_history<P: AsRef<Path>>(path: P) -> Result<Vec<AuditRecord>, Error> {
+    Ok(read_journal(path)?
+        .iter()
+        .filter_map(AuditRecord::from_json)
+        .collect())
+}
+
+/// Standard history subcommand arguments.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryArgs {
+    /// The number of runs to show (20 by default)
+    #[clap(long, short = 'n')]
+    limit: Option<usize>,
+    /// Only show runs that failed
+    #[clap(long)]
+    failed: bool,
+}
+
+impl HistoryArgs {
+    pub fn new(limit: Option<usize>, failed: bool) -> Self {
+        Self { limit, failed }
+    }
+
+    /// The selected records from the audit file, from newest to oldest.
+    pub fn history<P: AsRef<Path>>(&self, path: P) -> Result<Vec<AuditRecord>, Error> {
+        Ok(read_history(path)?
+            .into_iter()
+            .rev()
+            .filter(|record| !self.failed || record.exit_code != 0)
+            .take(self.limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+            .collect())
+    }
+
+    /// Print a table of past runs from the application's audit file.
+    pub fn run(&self, dirs: &AppDirs) -> Result<(), Error> {
+        let history = self.history(AuditTrail::path(dirs))?;
+
+        OutputFormat::Table.write(std::io::stdout().lock(), &history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_audit_trail() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join(AUDIT_FILE_NAME);
+
+        let record = AuditTrail::start_in(&path, "1.4.0")
+            .with_args(["mytool", "export", "--token", "abc123"])
+            .finish(1)
+            .unwrap();
+        assert_eq!(record.command(), "export --token ***");
+
+        AuditTrail::start_in(&path, "1.4.1")
+            .with_args(["mytool", "import"])
+            .finish(0)
+            .unwrap();
+
+        let history = read_history(&path).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].args, record.args);
+        assert_eq!(history[0].exit_code, 1);
+        assert_eq!(history[1].version, "1.4.1");
+
+        let latest = HistoryArgs::new(Some(1), false).history(&path).unwrap();
+        assert_eq!(latest[0].command(), "import");
+
+        let failed = HistoryArgs::new(None, true).history(&path).unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].version, "1.4.0");
+    }
+
+    #[test]
+    fn test_audit_record_timezone() {
+        let record = AuditRecord::from_json(&json!({
+            "timestamp": "2023-08-25T06:47:09Z",
+            "args": ["mytool", "import"],
+            "version": "1.4.0",
+            "exit_code": 0,
+            "duration_ms": 1500,
+        }))
+        .unwrap();
+
+        assert_eq!(record.row()[0], "2023-08-25T06:47:09Z");
+        assert_eq!(
+            OutputFormat::Csv.render_in(&[record], "+02:00".parse().unwrap()),
+            "started,exit,duration,version,command\n2023-08-25T08:47:09+02:00,0,1s,1.4.0,import\n"
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Backups of files that are about to be overwritten (`--backup`).
+//!
+//! [`backup_then_write`] writes the new contents to a temporary file next to the original, links (or copies) the
+//! original to the backup path, and then renames the new file over the original, so that the file is always present. With one backup, the previous version is kept as `notes.txt.bak`; with
+//! more, versions are kept as timestamped copies (`notes.txt.20261015T023133.123456Z.bak`), and the oldest are removed
+//! once there are more than the requested number:
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::backup::BackupArgs;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     backup: BackupArgs,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//! let contents = std::fs::read_to_string("notes.txt")?.to_uppercase();
+//!
+//! opts.backup.write("notes.txt", contents)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The new file takes the permissions of the file it replaces. Timestamps come from the current [`clock`](crate::clock).
+
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+use crate::{clock, edit::InPlaceEdit, Error};
+
+const BACKUP_EXTENSION: &str = "bak";
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.6fZ";
+
+/// Standard backup arguments.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BackupArgs {
+    /// Back up files before overwriting them
+    #[clap(long)]
+    backup: bool,
+    /// The number of backups to keep for each file (1 by default, which keeps a single `.bak` copy)
+    #[clap(long, requires = "backup")]
+    backup_keep: Option<NonZeroUsize>,
+}
+
+impl BackupArgs {
+    pub fn new(backup: bool, backup_keep: Option<NonZeroUsize>) -> Self {
+        Self {
+            backup,
+            backup_keep,
+        }
+    }
+
+    /// The number of backups to keep (zero if `--backup` was not given).
+    pub fn keep(&self) -> usize {
+        if self.backup {
+            self.backup_keep.map_or(1, NonZeroUsize::get)
+        } else {
+            0
+        }
+    }
+
+    /// Replace the file's contents, backing up the previous version if requested.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        path: P,
+        contents: C,
+    ) -> Result<Option<PathBuf>, Error> {
+        backup_then_write(path, self.keep(), contents)
+    }
+
+    /// Transform the file's contents in place, backing up the previous version if requested.
+    pub fn edit<P, F, E>(&self, path: P, f: F) -> Result<Option<PathBuf>, E>
+    where
+        P: AsRef<Path>,
+        F: FnOnce(&mut BufReader<File>, &mut BufWriter<File>) -> Result<(), E>,
+        E: From<Error>,
+    {
+        InPlaceEdit::new(path).with_backups(self.keep()).run(f)
+    }
+}
+
+/// Replace the file's contents, keeping up to `keep` backups of previous versions.
+///
+/// Returns the path of the new backup, if the file already existed and `keep` is not zero.
+pub fn backup_then_write<P: AsRef<Path>, C: AsRef<[u8]>>(
+    path: P,
+    keep: usize,
+    contents: C,
+) -> Result<Option<PathBuf>, Error> {
+    let path = path.as_ref();
+    let temp_path = temp_sibling(path)?;
+
+    let result = write_temp(path, &temp_path, contents.as_ref()).and_then(|()| {
+        let backup_path = backup(path, keep)?;
+        std::fs::rename(&temp_path, path)?;
+
+        Ok(backup_path)
+    });
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Back up the file (if it exists), removing the oldest backups beyond `keep`.
+///
+/// The backup is a hard link to the file where possible (and a copy otherwise), and the file itself is left in place,
+/// so that it can then be replaced atomically. Returns the path of the new backup, if the file existed and `keep` is
+/// not zero.
+pub fn backup<P: AsRef<Path>>(path: P, keep: usize) -> Result<Option<PathBuf>, Error> {
+    let path = path.as_ref();
+
+    if keep == 0 || !path.try_exists()? {
+        return Ok(None);
+    }
+
+    let backup_path = if keep == 1 {
+        sibling(path, |name| with_suffix(name, BACKUP_EXTENSION))?
+    } else {
+        let timestamp = clock::now().format(TIMESTAMP_FORMAT).to_string();
+        let mut attempt = 0;
+
+        loop {
+            let suffix = if attempt == 0 {
+                format!("{timestamp}.{BACKUP_EXTENSION}")
+            } else {
+                format!("{timestamp}-{attempt}.{BACKUP_EXTENSION}")
+            };
+            let candidate = sibling(path, |name| with_suffix(name, &suffix))?;
+
+            if !candidate.try_exists()? {
+                break candidate;
+            }
+
+            attempt += 1;
+        }
+    };
+
+    // An existing `.bak` file is only replaced once the new backup is complete.
+    let staged_path = temp_sibling(&backup_path)?;
+    let _ = std::fs::remove_file(&staged_path);
+
+    let result = std::fs::hard_link(path, &staged_path)
+        .or_else(|_| std::fs::copy(path, &staged_path).map(|_| ()))
+        .and_then(|()| std::fs::rename(&staged_path, &backup_path));
+
+    // The rename does nothing if the backup is already a link to the file, so the staged link may remain.
+    let _ = std::fs::remove_file(&staged_path);
+    result?;
+
+    if keep > 1 {
+        let backups = timestamped_backups(path)?;
+
+        for stale in &backups[..backups.len().saturating_sub(keep)] {
+            std::fs::remove_file(stale)?;
+        }
+    }
+
+    Ok(Some(backup_path))
+}
+
+/// Timestamped backups of the file, from oldest to newest.
+pub fn timestamped_backups<P: AsRef<Path>>(path: P) -> Result<Vec<PathBuf>, Error> {
+    let path = path.as_ref();
+    let name = file_name(path)?.to_string_lossy().into_owned();
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut backups = vec![];
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let entry_name = entry.file_name();
+
+        if let Some(key) = entry_name
+            .to_str()
+            .and_then(|entry_name| entry_name.strip_prefix(name.as_str()))
+            .and_then(|rest| rest.strip_prefix('.'))
+            .and_then(|rest| rest.strip_suffix(BACKUP_EXTENSION))
+            .and_then(|rest| rest.strip_suffix('.'))
+            .and_then(sort_key)
+        {
+            backups.push((key, entry.path()));
+        }
+    }
+
+    backups.sort();
+
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Parse the timestamp (and any collision counter) from the middle of a backup name.
+fn sort_key(middle: &str) -> Option<(NaiveDateTime, u32)> {
+    let (timestamp, attempt) = match middle.split_once('-') {
+        Some((timestamp, attempt)) => (timestamp, attempt.parse().ok()?),
+        None => (middle, 0),
+    };
+
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .ok()
+        .map(|timestamp| (timestamp, attempt))
+}
+
+fn write_temp(path: &Path, temp_path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let mut file = File::create(temp_path)?;
+
+    // The permissions are set before anything is written, so that the contents are never more widely readable.
+    match std::fs::metadata(path) {
+        Ok(metadata) => file.set_permissions(metadata.permissions())?,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error.into()),
+    }
+
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// A hidden temporary path in the same directory as the file (so that it can be renamed over it).
+pub(crate) fn temp_sibling(path: &Path) -> Result<PathBuf, Error> {
+    sibling(path, |name| {
+        let mut temp_name = OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".tmp-{}", std::process::id()));
+        temp_name
+    })
+}
+
+fn file_name(path: &Path) -> Result<&OsStr, Error> {
+    path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a file path", path.display()),
+        )
+        .into()
+    })
+}
+
+fn sibling<F: FnOnce(&OsStr) -> OsString>(path: &Path, f: F) -> Result<PathBuf, Error> {
+    Ok(path.with_file_name(f(file_name(path)?)))
+}
+
+fn with_suffix(name: &OsStr, suffix: &str) -> OsString {
+    let mut name = name.to_os_string();
+    name.push(".");
+    name.push(suffix);
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use crate::testing::FixedClock;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_backup_then_write_simple() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("notes.txt");
+
+        assert_eq!(backup_then_write(&path, 1, "one").unwrap(), None);
+        let backup_path = backup_then_write(&path, 1, "two").unwrap().unwrap();
+        assert_eq!(backup_path, dir.join("notes.txt.bak"));
+        backup_then_write(&path, 1, "three").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "three");
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "two");
+
+        backup_then_write(&path, 0, "four").unwrap();
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "two");
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_backup_then_write_timestamped() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("notes.txt");
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2026, 10, 15, 2, 31, 33).unwrap());
+        let unrelated = dir.join("notes.txt.old.bak");
+        std::fs::write(&unrelated, "old").unwrap();
+
+        clock::with_clock(clock.clone(), || {
+            for contents in ["one", "two", "three", "four"] {
+                backup_then_write(&path, 2, contents).unwrap();
+            }
+
+            // Backups made within the same instant get a counter.
+            assert_eq!(
+                timestamped_backups(&path).unwrap(),
+                vec![
+                    dir.join("notes.txt.20261015T023133.000000Z-1.bak"),
+                    dir.join("notes.txt.20261015T023133.000000Z-2.bak"),
+                ]
+            );
+
+            clock.advance(chrono::Duration::minutes(1));
+            backup_then_write(&path, 2, "five").unwrap();
+        });
+
+        let backups = timestamped_backups(&path).unwrap();
+        assert_eq!(
+            backups[1],
+            dir.join("notes.txt.20261015T023233.000000Z.bak")
+        );
+        assert_eq!(std::fs::read_to_string(&backups[0]).unwrap(), "three");
+        assert_eq!(std::fs::read_to_string(&backups[1]).unwrap(), "four");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "five");
+        assert!(unrelated.exists());
+    }
+
+    #[test]
+    fn test_backup_keeps_original() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("notes.txt");
+        std::fs::write(&path, "one").unwrap();
+        std::fs::write(dir.join("notes.txt.bak"), "zero").unwrap();
+
+        let backup_path = backup(&path, 1).unwrap().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one");
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "one");
+
+        backup_then_write(&path, 1, "two").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "two");
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "one");
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_backup_then_write_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("secret.txt");
+        std::fs::write(&path, "one").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+        backup_then_write(&path, 1, "two").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_backup_args() {
+        use clap::Parser;
+
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(flatten)]
+            backup: BackupArgs,
+        }
+
+        assert_eq!(Opts::try_parse_from(["test"]).unwrap().backup.keep(), 0);
+        assert_eq!(
+            Opts::try_parse_from(["test", "--backup"])
+                .unwrap()
+                .backup
+                .keep(),
+            1
+        );
+        assert_eq!(
+            Opts::try_parse_from(["test", "--backup", "--backup-keep", "3"])
+                .unwrap()
+                .backup
+                .keep(),
+            3
+        );
+        assert!(Opts::try_parse_from(["test", "--backup-keep", "3"]).is_err());
+        assert!(Opts::try_parse_from(["test", "--backup", "--backup-keep", "0"]).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! A standard batch size argument and an iterator adapter for processing items in batches.
+
+use crate::Error;
+
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+pub const MAX_BATCH_SIZE: usize = 10_000;
+
+#[derive(clap::Args, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSize {
+    /// Number of items per batch
+    #[clap(
+        long,
+        global = true,
+        default_value_t = DEFAULT_BATCH_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=MAX_BATCH_SIZE as u64)
+    )]
+    batch_size: usize,
+}
+
+impl BatchSize {
+    pub fn new(batch_size: usize) -> Result<Self, Error> {
+        if (1..=MAX_BATCH_SIZE).contains(&batch_size) {
+            Ok(Self { batch_size })
+        } else {
+            Err(Error::InvalidBatchSize(batch_size))
+        }
+    }
+
+    pub fn get(&self) -> usize {
+        self.batch_size
+    }
+}
+
+impl Default for BatchSize {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+impl From<BatchSize> for usize {
+    fn from(value: BatchSize) -> Self {
+        value.batch_size
+    }
+}
+
+pub trait ChunksOfExt: Iterator + Sized {
+    /// Group the items of this iterator into batches of the given size (the last may be smaller).
+    ///
+    /// Panics if the size is zero.
+    fn chunks_of<B: Into<usize>>(self, batch_size: B) -> ChunksOf<Self> {
+        let batch_size = batch_size.into();
+        assert!(batch_size > 0, "batch size must be positive");
+
+        ChunksOf {
+            underlying: self,
+            batch_size,
+        }
+    }
+}
+
+impl<I: Iterator> ChunksOfExt for I {}
+
+pub struct ChunksOf<I> {
+    underlying: I,
+    batch_size: usize,
+}
+
+impl<I: Iterator> Iterator for ChunksOf<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let batch = self
+            .underlying
+            .by_ref()
+            .take(self.batch_size)
+            .collect::<Vec<_>>();
+
+        if batch.is_empty() {
+            None
+        } else {
+            Some(batch)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(flatten)]
+        batch_size: BatchSize,
+    }
+
+    #[test]
+    fn test_batch_size() {
+        let opts = Opts::try_parse_from(["test"]).unwrap();
+        assert_eq!(opts.batch_size.get(), DEFAULT_BATCH_SIZE);
+
+        let opts = Opts::try_parse_from(["test", "--batch-size", "3"]).unwrap();
+        let batches = (0..8).chunks_of(opts.batch_size).collect::<Vec<_>>();
+        assert_eq!(batches, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7]]);
+
+        assert!(Opts::try_parse_from(["test", "--batch-size", "0"]).is_err());
+        assert!(Opts::try_parse_from(["test", "--batch-size", "10001"]).is_err());
+        assert!(matches!(BatchSize::new(0), Err(Error::InvalidBatchSize(0))));
+    }
+}
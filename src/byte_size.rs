@@ -0,0 +1,85 @@
+//! Byte counts with units, for arguments such as `--max-memory 2GiB`.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::summary::format_bytes;
+use crate::Error;
+
+/// A number of bytes, parsed from strings such as `512`, `64k`, `1.5GB`, or `2 GiB`.
+///
+/// Units are case-insensitive. Single-letter units (`k`, `m`, `g`, `t`) and IEC units (`KiB`, `MiB`, ...) are powers
+/// of 1024, while SI units (`kB`, `MB`, ...) are powers of 1000.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim();
+        let split = input
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(input.len());
+        let value = input[..split].parse::<f64>().ok();
+
+        let multiplier = match input[split..].trim_start().to_lowercase().as_str() {
+            "" | "b" => Some(1_u64),
+            "k" | "kib" => Some(1 << 10),
+            "m" | "mib" => Some(1 << 20),
+            "g" | "gib" => Some(1 << 30),
+            "t" | "tib" => Some(1 << 40),
+            "kb" => Some(1_000),
+            "mb" => Some(1_000_000),
+            "gb" => Some(1_000_000_000),
+            "tb" => Some(1_000_000_000_000),
+            _ => None,
+        };
+
+        value
+            .zip(multiplier)
+            .map(|(value, multiplier)| value * multiplier as f64)
+            .filter(|bytes| bytes.is_finite() && *bytes < u64::MAX as f64)
+            .map(|bytes| Self(bytes.round() as u64))
+            .ok_or_else(|| Error::InvalidByteSize(s.to_string()))
+    }
+}
+
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format_bytes(self.0))
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_size() {
+        assert_eq!("512".parse::<ByteSize>().unwrap(), ByteSize(512));
+        assert_eq!("64k".parse::<ByteSize>().unwrap(), ByteSize(65_536));
+        assert_eq!("2 GiB".parse::<ByteSize>().unwrap(), ByteSize(2 << 30));
+        assert_eq!(
+            "1.5GB".parse::<ByteSize>().unwrap(),
+            ByteSize(1_500_000_000)
+        );
+        assert_eq!("0.5M".parse::<ByteSize>().unwrap(), ByteSize(1 << 19));
+        assert_eq!(ByteSize(1 << 30).to_string(), "1.0 GiB");
+        assert!("".parse::<ByteSize>().is_err());
+        assert!("1.2.3k".parse::<ByteSize>().is_err());
+        assert!("10 parsecs".parse::<ByteSize>().is_err());
+    }
+}
@@ -0,0 +1,415 @@
+//! A file-based cache in the application cache directory.
+//!
+//! Entries are stored one per file, named by a hash of the key. Each file records the key and the time the entry was
+//! created (for TTL checks), and the file modification time is updated on access, so that eviction can discard the
+//! least-recently-used entries when the total size exceeds a limit.
+
+use std::fs::{File, FileTimes};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{app_dirs::AppDirs, Error};
+
+const TEMP_FILE_PREFIX: &str = ".tmp-";
+
+/// Distinguishes temporary files written at the same time by different threads.
+static NEXT_TEMP_FILE: AtomicU64 = AtomicU64::new(0);
+
+/// Standard cache control flags.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheArgs {
+    /// Do not read from or write to the cache
+    #[clap(long, global = true)]
+    no_cache: bool,
+    /// Ignore cached entries (but update the cache with fresh results)
+    #[clap(long, global = true, conflicts_with = "no_cache")]
+    refresh: bool,
+}
+
+impl CacheArgs {
+    pub fn new(no_cache: bool, refresh: bool) -> Self {
+        Self { no_cache, refresh }
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        if self.no_cache {
+            CacheMode::Disabled
+        } else if self.refresh {
+            CacheMode::Refresh
+        } else {
+            CacheMode::Normal
+        }
+    }
+
+    /// Open the cache in the application cache directory.
+    pub fn open(&self, dirs: &AppDirs) -> Result<Cache, Error> {
+        Ok(Cache::open(dirs.cache_dir())?.with_mode(self.mode()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Read and write cache entries.
+    #[default]
+    Normal,
+    /// Ignore existing entries, but write new ones.
+    Refresh,
+    /// Neither read nor write entries.
+    Disabled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub created: SystemTime,
+    pub value: Vec<u8>,
+}
+
+impl CacheEntry {
+    /// The age of the entry (zero if it appears to have been created in the future).
+    pub fn age(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(self.created)
+            .unwrap_or_default()
+    }
+
+    pub fn is_fresh(&self, ttl: Duration) -> bool {
+        self.age() < ttl
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    max_size: Option<u64>,
+    mode: CacheMode,
+}
+
+impl Cache {
+    /// Open a cache in the given directory, creating it if necessary.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            max_size: None,
+            mode: CacheMode::default(),
+        })
+    }
+
+    /// Limit the total size of the cache in bytes, evicting least-recently-used entries on insertion.
+    pub fn with_max_size(self, max_size: u64) -> Self {
+        Self {
+            max_size: Some(max_size),
+            ..self
+        }
+    }
+
+    pub fn with_mode(self, mode: CacheMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    /// Return the value for the key if there is an entry younger than the TTL, and otherwise fetch and insert it.
+    pub fn get_or_insert_with<E: From<Error>, F: FnOnce() -> Result<Vec<u8>, E>>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        fetch_fn: F,
+    ) -> Result<Vec<u8>, E> {
+        if let Some(entry) = self.get(key)?.filter(|entry| entry.is_fresh(ttl)) {
+            return Ok(entry.value);
+        }
+
+        let value = fetch_fn()?;
+        self.insert(key, &value)?;
+
+        Ok(value)
+    }
+
+    /// Look up an entry regardless of age (always `None` unless the mode is [`CacheMode::Normal`]).
+    pub fn get(&self, key: &str) -> Result<Option<CacheEntry>, Error> {
+        if self.mode != CacheMode::Normal {
+            return Ok(None);
+        }
+
+        let path = self.entry_path(key);
+
+        let mut file = match File::open(&path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+
+        match decode_entry(&contents, key) {
+            Some(entry) => {
+                // The modification time is only used for eviction, so failing to update it (for example on a
+                // read-only file, or on platforms that require write access) is not an error.
+                if let Err(error) = file.set_times(FileTimes::new().set_modified(SystemTime::now()))
+                {
+                    log::debug!(
+                        "Unable to update the modification time of {}: {error}",
+                        path.display()
+                    );
+                }
+
+                Ok(Some(entry))
+            }
+            // A hash collision or a corrupted entry.
+            None => Ok(None),
+        }
+    }
+
+    /// Insert an entry (a no-op if the cache is disabled).
+    pub fn insert(&self, key: &str, value: &[u8]) -> Result<(), Error> {
+        if self.mode == CacheMode::Disabled {
+            return Ok(());
+        }
+
+        let name = entry_name(key);
+        let temp_path = self.dir.join(format!(
+            "{TEMP_FILE_PREFIX}{}-{}-{name}",
+            std::process::id(),
+            NEXT_TEMP_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let mut file = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        file.write_all(&encode_entry(key, value, SystemTime::now()))?;
+        drop(file);
+
+        std::fs::rename(temp_path, self.dir.join(name))?;
+
+        if let Some(max_size) = self.max_size {
+            self.evict(max_size)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove an entry, returning `true` if it existed.
+    pub fn remove(&self, key: &str) -> Result<bool, Error> {
+        match std::fs::remove_file(self.entry_path(key)) {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// The total size in bytes of all entries.
+    pub fn size(&self) -> Result<u64, Error> {
+        Ok(self.entries()?.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Remove least-recently-used entries until the total size is no greater than `max_size`.
+    pub fn evict(&self, max_size: u64) -> Result<(), Error> {
+        let mut entries = self.entries()?;
+        let mut size: u64 = entries.iter().map(|(_, size, _)| size).sum();
+
+        entries.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, entry_size, _) in entries {
+            if size <= max_size {
+                break;
+            }
+
+            match std::fs::remove_file(path) {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+
+            size -= entry_size;
+        }
+
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>, Error> {
+        let mut entries = vec![];
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+
+            if metadata.is_file() {
+                entries.push((entry.path(), metadata.len(), metadata.modified()?));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(entry_name(key))
+    }
+}
+
+fn entry_name(key: &str) -> String {
+    format!("{:016x}", fnv1a(key.as_bytes()))
+}
+
+/// A stable hash for file names (the standard library's hasher is not guaranteed to be stable across releases).
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn encode_entry(key: &str, value: &[u8], created: SystemTime) -> Vec<u8> {
+    let created_ms = created
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    let mut contents = Vec::with_capacity(12 + key.len() + value.len());
+    contents.extend_from_slice(&created_ms.to_le_bytes());
+    contents.extend_from_slice(&(key.len() as u32).to_le_bytes());
+    contents.extend_from_slice(key.as_bytes());
+    contents.extend_from_slice(value);
+    contents
+}
+
+fn decode_entry(contents: &[u8], key: &str) -> Option<CacheEntry> {
+    let created_ms = u64::from_le_bytes(contents.get(0..8)?.try_into().ok()?);
+    let key_len = u32::from_le_bytes(contents.get(8..12)?.try_into().ok()?) as usize;
+    let stored_key = contents.get(12..12 + key_len)?;
+
+    if stored_key == key.as_bytes() {
+        Some(CacheEntry {
+            created: UNIX_EPOCH + Duration::from_millis(created_ms),
+            value: contents[12 + key_len..].to_vec(),
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    fn fetch(value: &str) -> Result<Vec<u8>, Error> {
+        Ok(value.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_cache() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let cache = Cache::open(dir).unwrap();
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(
+            cache.get_or_insert_with("a", ttl, || fetch("1")).unwrap(),
+            b"1"
+        );
+        assert_eq!(
+            cache.get_or_insert_with("a", ttl, || fetch("2")).unwrap(),
+            b"1"
+        );
+        assert_eq!(
+            cache
+                .get_or_insert_with("a", Duration::ZERO, || fetch("3"))
+                .unwrap(),
+            b"3"
+        );
+
+        let refresh = cache.clone().with_mode(CacheMode::Refresh);
+        assert_eq!(
+            refresh.get_or_insert_with("a", ttl, || fetch("4")).unwrap(),
+            b"4"
+        );
+
+        let disabled = cache.clone().with_mode(CacheMode::Disabled);
+        assert_eq!(
+            disabled
+                .get_or_insert_with("a", ttl, || fetch("5"))
+                .unwrap(),
+            b"5"
+        );
+        assert_eq!(cache.get("a").unwrap().unwrap().value, b"4");
+
+        assert!(cache.remove("a").unwrap());
+        assert!(cache.get("a").unwrap().is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cache_read_only_entry() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = test_dir();
+        let cache = Cache::open(temp_dir.path()).unwrap();
+        cache.insert("a", b"1").unwrap();
+
+        let path = cache.entry_path("a");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o444)).unwrap();
+
+        assert_eq!(cache.get("a").unwrap().unwrap().value, b"1");
+    }
+
+    #[test]
+    fn test_cache_concurrent_inserts() {
+        let temp_dir = test_dir();
+        let cache = Cache::open(temp_dir.path()).unwrap();
+
+        std::thread::scope(|scope| {
+            for i in 0..8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        cache.insert("a", format!("{i}").as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+
+        let value = cache.get("a").unwrap().unwrap().value;
+        assert_eq!(value.len(), 1);
+        assert_eq!(cache.entries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let cache = Cache::open(dir).unwrap();
+
+        for key in ["a", "b", "c"] {
+            cache.insert(key, &[0; 100]).unwrap();
+            // Ensure distinct modification times.
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        cache.get("a").unwrap();
+
+        let cache = cache.with_max_size(350);
+        cache.insert("d", &[0; 100]).unwrap();
+
+        assert!(cache.get("a").unwrap().is_some());
+        assert!(cache.get("b").unwrap().is_none());
+        assert!(cache.get("c").unwrap().is_some());
+        assert!(cache.get("d").unwrap().is_some());
+    }
+}
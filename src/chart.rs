@@ -0,0 +1,111 @@
+//! Simple terminal charts: sparklines and horizontal histograms.
+//!
+//! Both use block characters, falling back to ASCII for terminals without Unicode support (see
+//! [`term::supports_unicode`](crate::term::supports_unicode)) when rendered through [`sparkline`] and [`histogram`].
+
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const ASCII_LEVELS: [char; 8] = ['_', '.', '-', '~', '=', '+', '*', '#'];
+
+/// The default width of the longest histogram bar.
+pub const HISTOGRAM_WIDTH: usize = 40;
+
+/// Render a series as a single line with one character per value.
+pub fn sparkline<I: IntoIterator<Item = V>, V: Into<f64>>(series: I) -> String {
+    render_sparkline(series, crate::term::supports_unicode())
+}
+
+/// Render a sparkline using block characters if `unicode` is true, and ASCII otherwise.
+pub fn render_sparkline<I: IntoIterator<Item = V>, V: Into<f64>>(
+    series: I,
+    unicode: bool,
+) -> String {
+    let values = series.into_iter().map(Into::into).collect::<Vec<f64>>();
+    let levels = if unicode { BLOCKS } else { ASCII_LEVELS };
+
+    let finite = values.iter().copied().filter(|value| value.is_finite());
+    let min = finite.clone().fold(f64::INFINITY, f64::min);
+    let max = finite.fold(f64::NEG_INFINITY, f64::max);
+
+    values
+        .into_iter()
+        .map(|value| {
+            if !value.is_finite() {
+                ' '
+            } else if max > min {
+                levels[(((value - min) / (max - min)) * 7.0).round() as usize]
+            } else {
+                levels[3]
+            }
+        })
+        .collect()
+}
+
+/// Render labeled values as horizontal bars scaled to [`HISTOGRAM_WIDTH`], one line per value.
+pub fn histogram<I: IntoIterator<Item = (L, V)>, L: std::fmt::Display, V: Into<f64>>(
+    values: I,
+) -> String {
+    render_histogram(values, HISTOGRAM_WIDTH, crate::term::supports_unicode())
+}
+
+/// Render a histogram with bars of at most `width` characters, using block characters if `unicode` is true.
+///
+/// Labels are left-aligned and values are shown after each bar. Negative and non-finite values have empty bars.
+pub fn render_histogram<I: IntoIterator<Item = (L, V)>, L: std::fmt::Display, V: Into<f64>>(
+    values: I,
+    width: usize,
+    unicode: bool,
+) -> String {
+    let values = values
+        .into_iter()
+        .map(|(label, value)| (label.to_string(), value.into()))
+        .collect::<Vec<(String, f64)>>();
+
+    let label_width = values
+        .iter()
+        .map(|(label, _)| label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let max = values
+        .iter()
+        .map(|(_, value)| *value)
+        .filter(|value| value.is_finite())
+        .fold(0.0, f64::max);
+    let bar = if unicode { '█' } else { '#' };
+
+    let mut output = String::new();
+
+    for (label, value) in values {
+        let length = if max > 0.0 && value.is_finite() && value > 0.0 {
+            ((value / max) * width as f64).round() as usize
+        } else {
+            0
+        };
+        let padding = " ".repeat(label_width - label.chars().count());
+        let bar = bar.to_string().repeat(length);
+
+        output.push_str(&format!("{label}{padding} {bar} {value}\n"));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(render_sparkline([1, 2, 3, 4, 5, 6, 7, 8], true), "▁▂▃▄▅▆▇█");
+        assert_eq!(render_sparkline([0.0, 10.0, f64::NAN, 5.0], false), "_# =");
+        assert_eq!(render_sparkline([3, 3], true), "▄▄");
+        assert_eq!(render_sparkline(Vec::<f64>::new(), true), "");
+    }
+
+    #[test]
+    fn test_histogram() {
+        assert_eq!(
+            render_histogram([("2024-01", 10), ("2024-02", 5), ("2024-03", 0)], 4, false),
+            "2024-01 #### 10\n2024-02 ## 5\n2024-03  0\n"
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Clap version compatibility.
+//!
+//! This module re-exports all of clap (the same crate as [`prelude::clap`](crate::prelude::clap)), so that
+//! applications can refer to any clap item (builders, errors, value parsers) through this crate without a direct
+//! clap dependency, and so never need to upgrade clap in lockstep with it:
+//!
+//! ```rust,no_run
+//! use cli_helpers::clap_compat::{builder::PossibleValuesParser, Arg, Command};
+//!
+//! let command = Command::new("mytool").arg(
+//!     Arg::new("format").long("format").value_parser(PossibleValuesParser::new(["json", "csv"])),
+//! );
+//! ```
+//!
+//! [`CLAP_MAJOR_VERSION`] identifies the version, for example for checking that a plugin or an application that does
+//! depend on clap directly uses a compatible one.
+
+pub use clap::*;
+
+/// The version requirement for this crate's clap dependency.
+pub const CLAP_VERSION_REQ: &str = "4";
+
+/// The major version of clap that this crate uses.
+pub const CLAP_MAJOR_VERSION: u64 = 4;
+
+/// Whether a clap version (such as `4.5.1`) is compatible with the one this crate uses.
+pub fn is_compatible(version: &str) -> bool {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u64>().ok())
+        == Some(CLAP_MAJOR_VERSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clap_version() {
+        let manifest = include_str!("../Cargo.toml");
+        assert!(manifest.contains(&format!("clap = {{ version = \"{CLAP_VERSION_REQ}\"")));
+        assert_eq!(
+            CLAP_VERSION_REQ.parse::<u64>().ok(),
+            Some(CLAP_MAJOR_VERSION)
+        );
+
+        assert!(is_compatible("4.5.1"));
+        assert!(is_compatible("v4"));
+        assert!(!is_compatible("5.0.0"));
+        assert!(!is_compatible("four"));
+    }
+}
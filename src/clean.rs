@@ -0,0 +1,364 @@
+//! A `clean` subcommand for the application's cache, state, and temporary directories.
+//!
+//! [`CleanArgs::run`] reports the number and total size of the files in each directory and removes them (or, with
+//! `--dry-run`, only reports what would be removed). `--older-than` restricts this to files last modified before a
+//! [`Timestamp`] (such as `30 days ago`), and `--only` selects the directories (the cache and temporary directories by
+//! default, since the state directory must be given explicitly):
+//!
+//! ```text
+//! $ mytool clean --only cache --older-than '30 days ago' --dry-run
+//! TARGET  DIRECTORY                   FILES  SIZE     ACTION
+//! cache   /home/travis/.cache/mytool  214    3.4 MiB  would remove
+//! ```
+//!
+//! Symbolic links are removed rather than followed, and the directories themselves are kept. Files in the state
+//! directory that belong to other parts of the crate (such as the audit file, the daemon's PID and log files, and the
+//! store's database) are never removed, and neither are the [run directories](crate::temp::RunTempDir) of processes
+//! that are still running (including the current one).
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use crate::output::{Column, OutputFormat, OutputRecord};
+use crate::summary::{format_bytes, format_count};
+use crate::{app_dirs::AppDirs, Error, Timestamp};
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    Cache,
+    State,
+    Temp,
+}
+
+impl Target {
+    pub const ALL: [Self; 3] = [Self::Cache, Self::State, Self::Temp];
+
+    /// The directories that are cleaned when none are selected.
+    pub const DEFAULT: [Self; 2] = [Self::Cache, Self::Temp];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cache => "cache",
+            Self::State => "state",
+            Self::Temp => "temp",
+        }
+    }
+
+    pub fn dir(&self, dirs: &AppDirs) -> PathBuf {
+        match self {
+            Self::Cache => dirs.cache_dir().to_path_buf(),
+            Self::State => dirs.state_dir().to_path_buf(),
+            Self::Temp => dirs.temp_dir(),
+        }
+    }
+
+    /// Paths in the directory that are never removed.
+    pub fn kept_paths(&self, dirs: &AppDirs) -> Vec<PathBuf> {
+        match self {
+            Self::State => {
+                let state_dir = dirs.state_dir();
+
+                #[allow(unused_mut)]
+                let mut paths = vec![
+                    state_dir.join(crate::audit::AUDIT_FILE_NAME),
+                    state_dir.join(crate::first_run::MARKER_FILE_NAME),
+                    state_dir.join(crate::last_run::LAST_RUN_FILE_NAME),
+                ];
+
+                #[cfg(all(unix, feature = "daemon"))]
+                paths.extend([
+                    crate::daemon::default_pid_file(dirs),
+                    crate::daemon::default_log_file(dirs),
+                ]);
+
+                #[cfg(feature = "telemetry")]
+                paths.push(state_dir.join(crate::telemetry::TELEMETRY_DIR_NAME));
+
+                // The state directory may be the data directory (on macOS and Windows).
+                #[cfg(feature = "store")]
+                paths.extend(["", "-journal", "-wal", "-shm"].map(|suffix| {
+                    dirs.data_dir()
+                        .join(format!("{}{suffix}", crate::store::DEFAULT_DB_FILE_NAME))
+                }));
+
+                paths
+            }
+            Self::Temp => live_run_dirs(&dirs.temp_dir()),
+            Self::Cache => vec![],
+        }
+    }
+}
+
+/// The run directories in the directory that belong to running processes.
+fn live_run_dirs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let current = std::process::id();
+
+    entries
+        .flatten()
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .and_then(crate::temp::run_dir_pid)
+                .is_some_and(|pid| pid == current || crate::pid_file::process_exists(pid))
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// The files found (and possibly removed) in a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cleaned {
+    pub target: Target,
+    pub dir: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+    pub removed: bool,
+}
+
+impl OutputRecord for Cleaned {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("target"),
+            Column::new("directory").with_priority(1),
+            Column::new("files"),
+            Column::new("size"),
+            Column::new("action"),
+        ]
+    }
+
+    fn row(&self) -> Vec<Value> {
+        let action = match (self.files, self.removed) {
+            (0, _) => "none",
+            (_, true) => "removed",
+            (_, false) => "would remove",
+        };
+
+        vec![
+            self.target.name().into(),
+            self.dir.display().to_string().into(),
+            format_count(self.files).into(),
+            format_bytes(self.bytes).into(),
+            action.into(),
+        ]
+    }
+}
+
+/// Standard clean subcommand arguments.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CleanArgs {
+    /// Only clean the given directories (cache and temp by default)
+    #[clap(long, value_enum, value_delimiter = ',')]
+    only: Vec<Target>,
+    /// Only remove files last modified before this time (for example `30 days ago`)
+    #[clap(long)]
+    older_than: Option<Timestamp>,
+    /// Show what would be removed without removing anything
+    #[clap(long)]
+    dry_run: bool,
+}
+
+impl CleanArgs {
+    pub fn new(only: Vec<Target>, older_than: Option<Timestamp>, dry_run: bool) -> Self {
+        Self {
+            only,
+            older_than,
+            dry_run,
+        }
+    }
+
+    pub fn targets(&self) -> Vec<Target> {
+        if self.only.is_empty() {
+            Target::DEFAULT.to_vec()
+        } else {
+            let mut targets = self.only.clone();
+            targets.sort();
+            targets.dedup();
+            targets
+        }
+    }
+
+    /// Clean the selected directories, returning what was found.
+    pub fn clean(&self, dirs: &AppDirs) -> Result<Vec<Cleaned>, Error> {
+        let cutoff = self
+            .older_than
+            .map(|timestamp| SystemTime::from(DateTime::<Utc>::from(timestamp)));
+
+        self.targets()
+            .into_iter()
+            .map(|target| {
+                let dir = target.dir(dirs);
+                let keep = target.kept_paths(dirs);
+                let (files, bytes) = clean_dir(&dir, cutoff, self.dry_run, &keep)?;
+
+                Ok(Cleaned {
+                    target,
+                    dir,
+                    files,
+                    bytes,
+                    removed: !self.dry_run,
+                })
+            })
+            .collect()
+    }
+
+    /// Clean the selected directories and print a table of what was found.
+    pub fn run(&self, dirs: &AppDirs) -> Result<(), Error> {
+        let cleaned = self.clean(dirs)?;
+
+        OutputFormat::Table.write(std::io::stdout().lock(), &cleaned)
+    }
+}
+
+/// Count (and unless `dry_run` is set, remove) the files in the directory, returning the count and total size.
+///
+/// Paths in `keep` (and anything under them) are skipped.
+fn clean_dir(
+    dir: &Path,
+    cutoff: Option<SystemTime>,
+    dry_run: bool,
+    keep: &[PathBuf],
+) -> Result<(u64, u64), Error> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok((0, 0)),
+        Err(error) => return Err(error.into()),
+    };
+
+    let (mut files, mut bytes) = (0, 0);
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if keep.contains(&path) {
+            continue;
+        }
+
+        let metadata = std::fs::symlink_metadata(&path)?;
+
+        if metadata.is_dir() {
+            let (dir_files, dir_bytes) = clean_dir(&path, cutoff, dry_run, keep)?;
+            files += dir_files;
+            bytes += dir_bytes;
+
+            // Only directories that are now empty are removed.
+            if !dry_run && std::fs::read_dir(&path)?.next().is_none() {
+                std::fs::remove_dir(&path)?;
+            }
+        } else {
+            let is_old = match cutoff {
+                Some(cutoff) => metadata.modified()? < cutoff,
+                None => true,
+            };
+
+            if is_old {
+                files += 1;
+                bytes += metadata.len();
+
+                if !dry_run {
+                    std::fs::remove_file(&path)?;
+                }
+            }
+        }
+    }
+
+    Ok((files, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use std::fs::{File, FileTimes};
+    use std::time::Duration;
+
+    #[test]
+    fn test_clean_dir() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("new.txt"), b"new").unwrap();
+        std::fs::write(dir.join("nested/old.txt"), b"old file").unwrap();
+
+        let old = SystemTime::now() - Duration::from_secs(60 * 60 * 24 * 60);
+        File::options()
+            .write(true)
+            .open(dir.join("nested/old.txt"))
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(old))
+            .unwrap();
+
+        let cutoff = Some(SystemTime::now() - Duration::from_secs(60 * 60 * 24 * 30));
+
+        assert_eq!(clean_dir(dir, cutoff, true, &[]).unwrap(), (1, 8));
+        assert!(dir.join("nested/old.txt").exists());
+
+        assert_eq!(clean_dir(dir, cutoff, false, &[]).unwrap(), (1, 8));
+        assert!(!dir.join("nested").exists());
+        assert!(dir.join("new.txt").exists());
+
+        assert_eq!(clean_dir(dir, None, false, &[]).unwrap(), (1, 3));
+        assert!(dir.exists());
+        assert_eq!(
+            clean_dir(&dir.join("missing"), None, false, &[]).unwrap(),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_kept_paths() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        std::fs::create_dir_all(dir.join("crash-reports")).unwrap();
+        std::fs::create_dir_all(dir.join("telemetry")).unwrap();
+        std::fs::write(dir.join("audit.ndjson"), b"{}").unwrap();
+        std::fs::write(dir.join("telemetry/events.jsonl"), b"{}").unwrap();
+        std::fs::write(dir.join("crash-reports/crash.txt"), b"crash").unwrap();
+
+        let keep = vec![dir.join("audit.ndjson"), dir.join("telemetry")];
+
+        assert_eq!(clean_dir(dir, None, false, &keep).unwrap(), (1, 5));
+        assert!(dir.join("audit.ndjson").exists());
+        assert!(dir.join("telemetry/events.jsonl").exists());
+        assert!(!dir.join("crash-reports").exists());
+
+        assert_eq!(CleanArgs::default().targets(), Target::DEFAULT.to_vec());
+        assert_eq!(
+            CleanArgs::new(vec![Target::State], None, true).targets(),
+            vec![Target::State]
+        );
+
+        let dirs = AppDirs::new("cli-helpers-clean-test").unwrap();
+        assert!(Target::State
+            .kept_paths(&dirs)
+            .contains(&dirs.state_dir().join(crate::audit::AUDIT_FILE_NAME)));
+        assert!(Target::Cache.kept_paths(&dirs).is_empty());
+    }
+
+    #[test]
+    fn test_live_run_dirs() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let own = dir.join(format!("run-{}-00000000", std::process::id()));
+        // Process IDs are never this large on any supported platform.
+        let stale = dir.join(format!("run-{}-00000000", u32::MAX - 1));
+        std::fs::create_dir_all(&own).unwrap();
+        std::fs::create_dir_all(&stale).unwrap();
+        std::fs::write(own.join("chunk-0.ndjson"), b"{}").unwrap();
+        std::fs::write(stale.join("chunk-0.ndjson"), b"{}").unwrap();
+
+        let keep = live_run_dirs(dir);
+        assert_eq!(keep, vec![own.clone()]);
+
+        assert_eq!(clean_dir(dir, None, false, &keep).unwrap(), (1, 2));
+        assert!(own.join("chunk-0.ndjson").exists());
+        assert!(!stale.exists());
+    }
+}
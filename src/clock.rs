@@ -0,0 +1,50 @@
+//! A replaceable source of the current time.
+//!
+//! [`Timestamp::now`](crate::Timestamp::now) and relative timestamp parsing (`yesterday`, `3 hours ago`) read the
+//! time from the current thread's clock, which is the system clock unless it has been replaced with [`with_clock`]
+//! (for example with a [`FixedClock`](crate::testing::FixedClock) in tests).
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<dyn Clock>>> = const { RefCell::new(None) };
+}
+
+/// The current time according to the current thread's clock.
+pub fn now() -> DateTime<Utc> {
+    CURRENT
+        .with(|current| current.borrow().clone())
+        .map(|clock| clock.now())
+        .unwrap_or_else(Utc::now)
+}
+
+/// Run the closure with the given clock installed on the current thread.
+pub fn with_clock<C: Clock + 'static, T, F: FnOnce() -> T>(clock: C, f: F) -> T {
+    struct Guard(Option<Arc<dyn Clock>>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            CURRENT.with(|current| current.replace(self.0.take()));
+        }
+    }
+
+    let _guard = Guard(CURRENT.with(|current| current.replace(Some(Arc::new(clock)))));
+
+    f()
+}
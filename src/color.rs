@@ -0,0 +1,147 @@
+//! The crate's color policy.
+//!
+//! Color is enabled automatically when the output stream is a terminal, unless `NO_COLOR` is set (to any non-empty
+//! value) or `TERM` is `dumb`. Setting `CLICOLOR_FORCE` (to any non-empty value other than `0`) enables color even when
+//! the stream is not a terminal.
+
+use std::io::IsTerminal;
+
+pub use clap::ColorChoice;
+
+/// Standard color argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorArgs {
+    /// When to use color in output
+    #[clap(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+}
+
+impl ColorArgs {
+    pub fn new(color: ColorChoice) -> Self {
+        Self { color }
+    }
+
+    pub fn choice(&self) -> ColorChoice {
+        self.color
+    }
+
+    pub fn stdout(&self) -> bool {
+        enabled(self.color, Stream::Stdout)
+    }
+
+    pub fn stderr(&self) -> bool {
+        enabled(self.color, Stream::Stderr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    pub fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdout => std::io::stdout().is_terminal(),
+            Self::Stderr => std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+/// Whether color should be used for the given stream.
+pub fn enabled(choice: ColorChoice, stream: Stream) -> bool {
+    enabled_from_env(choice, stream, |name| {
+        std::env::var_os(name).map(|value| value.to_string_lossy().into_owned())
+    })
+}
+
+fn enabled_from_env<F: Fn(&str) -> Option<String>>(
+    choice: ColorChoice,
+    stream: Stream,
+    var: F,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            // Unlike `NO_COLOR`, `CLICOLOR_FORCE=0` is conventionally treated as unset.
+            if var("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0") {
+                true
+            } else if var("NO_COLOR").is_some_and(|value| !value.is_empty())
+                || var("TERM").is_some_and(|term| term == "dumb")
+            {
+                false
+            } else {
+                stream.is_terminal()
+            }
+        }
+    }
+}
+
+/// Find the value of a `--color` flag in unparsed arguments (for use before parsing succeeds).
+pub(crate) fn choice_from_args<I: IntoIterator<Item = T>, T: AsRef<std::ffi::OsStr>>(
+    args: I,
+) -> Option<ColorChoice> {
+    let mut args = args.into_iter();
+    let mut choice = None;
+
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+
+        if arg == "--" {
+            break;
+        }
+
+        let value = if arg == "--color" {
+            args.next()
+                .and_then(|value| value.as_ref().to_str().map(|value| value.to_string()))
+        } else {
+            arg.to_str()
+                .and_then(|arg| arg.strip_prefix("--color="))
+                .map(|value| value.to_string())
+        };
+
+        if let Some(value) = value {
+            choice = clap::ValueEnum::from_str(&value, true).ok().or(choice);
+        }
+    }
+
+    choice
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_enabled_from_env() {
+        let auto = |vars| enabled_from_env(ColorChoice::Auto, Stream::Stderr, env(vars));
+
+        assert!(!auto(&[("NO_COLOR", "1")]));
+        assert!(!auto(&[("NO_COLOR", "0")]));
+        assert!(!auto(&[("TERM", "dumb")]));
+        assert!(auto(&[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")]));
+        assert!(!auto(&[("CLICOLOR_FORCE", "0"), ("NO_COLOR", "1")]));
+        assert!(!auto(&[("CLICOLOR_FORCE", ""), ("NO_COLOR", "0")]));
+
+        assert!(enabled_from_env(
+            ColorChoice::Always,
+            Stream::Stderr,
+            env(&[("NO_COLOR", "1")])
+        ));
+        assert!(!enabled_from_env(
+            ColorChoice::Never,
+            Stream::Stderr,
+            env(&[("CLICOLOR_FORCE", "1")])
+        ));
+    }
+}
@@ -0,0 +1,413 @@
+//! Git-style command aliases defined in the configuration file.
+//!
+//! Aliases are defined in the `alias` table, and are expanded before the command line is parsed, so that teams can
+//! share standard invocations:
+//!
+//! ```toml
+//! [alias]
+//! fetch-recent = "fetch --since yesterday --format ndjson"
+//! daily = "fetch-recent --output 'daily report.ndjson'"
+//! ```
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{app_dirs::AppDirs, config::alias};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(subcommand)]
+//!     command: Command,
+//! }
+//!
+//! #[derive(Debug, clap::Subcommand)]
+//! enum Command {
+//!     Fetch {
+//!         #[clap(long)]
+//!         since: Option<String>,
+//!     },
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let dirs = AppDirs::new("mytool")?;
+//! let args = alias::expand_args::<Opts, _, _>(&dirs, std::env::args_os())?;
+//! let opts = Opts::parse_from(args);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! As with Git, only the first argument that is not an option (or an option's value) is expanded, aliases may refer to
+//! other aliases (but not recursively), and an alias with the same name as a subcommand is ignored. Expansions are
+//! split into arguments with shell-style quoting. The file is read from `--config` if it is given on the command line.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::{Command, CommandFactory};
+
+use super::{ALIAS_KEY, DEFAULT_CONFIG_FILE_NAME};
+use crate::{app_dirs::AppDirs, Error};
+
+const CONFIG_OPTION: &str = "--config";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aliases {
+    aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Aliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an alias, splitting its expansion into arguments.
+    pub fn with_alias(mut self, name: &str, expansion: &str) -> Result<Self, Error> {
+        let args = split(expansion).ok_or_else(|| Error::InvalidConfig {
+            path: PathBuf::new(),
+            message: format!("unterminated quote in `{ALIAS_KEY}.{name}`"),
+        })?;
+
+        self.aliases.insert(name.to_string(), args);
+
+        Ok(self)
+    }
+
+    /// Read the aliases from a configuration file (a missing file has none).
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let invalid = |message: String| Error::InvalidConfig {
+            path: path.to_path_buf(),
+            message,
+        };
+
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut table = contents
+            .parse::<toml::Table>()
+            .map_err(|error| invalid(error.message().to_string()))?;
+
+        match table.remove(ALIAS_KEY) {
+            Some(toml::Value::Table(table)) => {
+                let mut aliases = Self::new();
+
+                for (name, expansion) in table {
+                    let toml::Value::String(expansion) = expansion else {
+                        return Err(invalid(format!("`{ALIAS_KEY}.{name}` must be a string")));
+                    };
+                    let args = split(&expansion).ok_or_else(|| {
+                        invalid(format!("unterminated quote in `{ALIAS_KEY}.{name}`"))
+                    })?;
+
+                    aliases.aliases.insert(name, args);
+                }
+
+                Ok(aliases)
+            }
+            Some(_) => Err(invalid(format!("{ALIAS_KEY} must be a table"))),
+            None => Ok(Self::new()),
+        }
+    }
+
+    /// The arguments an alias expands to (without any further expansion).
+    pub fn get(&self, name: &str) -> Option<&[String]> {
+        self.aliases.get(name).map(Vec::as_slice)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.aliases.keys().map(String::as_str)
+    }
+
+    /// Expand the first argument that is not an option (the program name is not expanded).
+    pub fn expand<I: IntoIterator<Item = A>, A: Into<OsString>>(
+        &self,
+        command: &Command,
+        args: I,
+    ) -> Result<Vec<OsString>, Error> {
+        let mut args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+        let mut expanded = vec![];
+
+        while let Some(index) = command_position(command, &args) {
+            let Some(name) = args[index].to_str().map(str::to_string) else {
+                break;
+            };
+
+            let is_subcommand = command.get_subcommands().any(|subcommand| {
+                subcommand.get_name() == name
+                    || subcommand.get_all_aliases().any(|alias| alias == name)
+            });
+
+            let Some(expansion) = self.aliases.get(&name).filter(|_| !is_subcommand) else {
+                break;
+            };
+
+            if expanded.contains(&name) {
+                return Err(Error::RecursiveAlias(name));
+            }
+
+            args.splice(index..=index, expansion.iter().map(OsString::from));
+            expanded.push(name);
+        }
+
+        Ok(args)
+    }
+}
+
+/// Expand aliases from the application's configuration file in the command line for `T`.
+pub fn expand_args<T: CommandFactory, I: IntoIterator<Item = A>, A: Into<OsString>>(
+    dirs: &AppDirs,
+    args: I,
+) -> Result<Vec<OsString>, Error> {
+    let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+    let path =
+        config_path(&args).unwrap_or_else(|| dirs.config_dir().join(DEFAULT_CONFIG_FILE_NAME));
+
+    Aliases::load_file(path)?.expand(&T::command(), args)
+}
+
+/// The value of `--config`, if it is given.
+fn config_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == CONFIG_OPTION {
+            return args.next().map(PathBuf::from);
+        } else if let Some(path) = arg
+            .to_str()
+            .and_then(|arg| arg.strip_prefix(CONFIG_OPTION))
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    None
+}
+
+/// The index of the first argument that is neither an option nor an option's value.
+fn command_position(command: &Command, args: &[OsString]) -> Option<usize> {
+    let mut index = 1;
+
+    while index < args.len() {
+        let arg = args[index].to_string_lossy();
+
+        if arg == "--" {
+            return None;
+        } else if let Some(long) = arg.strip_prefix("--") {
+            if !long.contains('=')
+                && takes_value(command, |candidate| candidate.get_long() == Some(long))
+            {
+                index += 1;
+            }
+        } else if let Some(shorts) = arg.strip_prefix('-').filter(|shorts| !shorts.is_empty()) {
+            // In a group of short flags, the value of an option is the rest of the group (or the next argument).
+            let mut chars = shorts.chars();
+
+            while let Some(short) = chars.next() {
+                if takes_value(command, |candidate| candidate.get_short() == Some(short)) {
+                    if chars.as_str().is_empty() {
+                        index += 1;
+                    }
+
+                    break;
+                }
+            }
+        } else {
+            return Some(index);
+        }
+
+        index += 1;
+    }
+
+    None
+}
+
+fn takes_value<F: Fn(&clap::Arg) -> bool>(command: &Command, f: F) -> bool {
+    command
+        .get_arguments()
+        .find(|arg| f(arg))
+        .is_some_and(|arg| arg.get_action().takes_values())
+}
+
+/// Split a string into arguments with shell-style quoting, returning `None` for an unterminated quote.
+fn split(input: &str) -> Option<Vec<String>> {
+    let mut args = vec![];
+    let mut current: Option<String> = None;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                args.extend(current.take());
+            }
+            '\'' => {
+                let current = current.get_or_insert_with(String::new);
+
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => current.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let current = current.get_or_insert_with(String::new);
+
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('"' | '\\') => current.push(c),
+                            c => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                        },
+                        c => current.push(c),
+                    }
+                }
+            }
+            '\\' => current.get_or_insert_with(String::new).push(chars.next()?),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    args.extend(current);
+
+    Some(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use clap::Parser;
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(long, global = true)]
+        config: Option<PathBuf>,
+        #[clap(short, long, global = true)]
+        verbose: bool,
+        #[clap(subcommand)]
+        command: Subcommand,
+    }
+
+    #[derive(Debug, clap::Subcommand)]
+    enum Subcommand {
+        Fetch {
+            #[clap(long)]
+            since: Option<String>,
+            #[clap(long)]
+            output: Option<PathBuf>,
+        },
+    }
+
+    fn expand(aliases: &Aliases, args: &[&str]) -> Result<Vec<String>, Error> {
+        Ok(aliases
+            .expand(&Opts::command(), args.iter().copied())?
+            .into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect())
+    }
+
+    #[test]
+    fn test_expand() {
+        let aliases = Aliases::new()
+            .with_alias("fetch-recent", "fetch --since yesterday")
+            .unwrap()
+            .with_alias("daily", "fetch-recent --output 'daily report.ndjson'")
+            .unwrap()
+            .with_alias("fetch", "fetch --since today")
+            .unwrap()
+            .with_alias("loop", "again")
+            .unwrap()
+            .with_alias("again", "-v loop")
+            .unwrap();
+
+        assert_eq!(
+            expand(
+                &aliases,
+                &["mytool", "--config", "fetch-recent", "daily", "-v"]
+            )
+            .unwrap(),
+            vec![
+                "mytool",
+                "--config",
+                "fetch-recent",
+                "fetch",
+                "--since",
+                "yesterday",
+                "--output",
+                "daily report.ndjson",
+                "-v"
+            ]
+        );
+        assert_eq!(
+            expand(&aliases, &["mytool", "fetch"]).unwrap(),
+            vec!["mytool", "fetch"]
+        );
+        assert!(matches!(
+            expand(&aliases, &["mytool", "loop"]),
+            Err(Error::RecursiveAlias(name)) if name == "loop"
+        ));
+    }
+
+    #[test]
+    fn test_load_file() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("config.toml");
+        std::fs::write(
+            &path,
+            "rate_limit = 10\n\n[alias]\nfetch-recent = \"fetch --since \\\"3 hours ago\\\"\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            Aliases::load_file(dir.join("missing.toml")).unwrap(),
+            Aliases::new()
+        );
+
+        let aliases = Aliases::load_file(&path).unwrap();
+        assert_eq!(
+            aliases.get("fetch-recent").unwrap(),
+            ["fetch", "--since", "3 hours ago"]
+        );
+
+        let args = vec![
+            OsString::from("mytool"),
+            OsString::from(format!("--config={}", path.display())),
+        ];
+        assert_eq!(config_path(&args), Some(path.clone()));
+
+        std::fs::write(&path, "[alias]\nbroken = \"fetch 'since\"\n").unwrap();
+        assert!(matches!(
+            Aliases::load_file(&path),
+            Err(Error::InvalidConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            split(r#"fetch --since "3 hours ago" --name 'a "b"' c\ d"#).unwrap(),
+            vec![
+                "fetch",
+                "--since",
+                "3 hours ago",
+                "--name",
+                r#"a "b""#,
+                "c d"
+            ]
+        );
+        assert_eq!(split("  ").unwrap(), Vec::<String>::new());
+        assert_eq!(split("''").unwrap(), vec![""]);
+        assert!(split("'unterminated").is_none());
+    }
+}
@@ -0,0 +1,347 @@
+//! Introspection of effective settings and where they come from.
+//!
+//! With `--explain-config`, an application can print every effective setting along with its source, which is the
+//! first thing to check when a setting seems to be ignored:
+//!
+//! ```text
+//! KEY         VALUE                          SOURCE
+//! base_url    "https://staging.example.com"  profile staging (/home/travis/.config/mytool/config.toml)
+//! rate_limit  20                             flag --rate-limit
+//! token       ***                            env MYTOOL_TOKEN
+//! ```
+//!
+//! Command-line options override configuration keys with the same name (so `--rate-limit` overrides `rate_limit`).
+//! Since sources are determined from [`clap::ArgMatches`], the application must parse its arguments with
+//! [`clap::Command::get_matches`] and [`clap::FromArgMatches`] to use this.
+
+use std::path::PathBuf;
+
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command};
+use serde_json::Value;
+
+use super::{read_file, to_table, AppConfig, ConfigArgs};
+use crate::output::{Column, OutputFormat, OutputRecord};
+use crate::{app_dirs::AppDirs, Error};
+
+const EXPLAIN_CONFIG_ID: &str = "explain_config";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Profile { name: String, path: PathBuf },
+    Env(String),
+    Flag(String),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::File(path) => write!(f, "file ({})", path.display()),
+            Self::Profile { name, path } => write!(f, "profile {name} ({})", path.display()),
+            Self::Env(name) => write!(f, "env {name}"),
+            Self::Flag(name) => write!(f, "flag --{name}"),
+        }
+    }
+}
+
+/// An effective setting, with its value rendered as TOML (or as given for command-line options).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+    pub source: Source,
+}
+
+impl OutputRecord for Setting {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("key"),
+            Column::new("value"),
+            Column::new("source"),
+        ]
+    }
+
+    fn row(&self) -> Vec<Value> {
+        vec![
+            self.key.clone().into(),
+            self.value.clone().into(),
+            self.source.to_string().into(),
+        ]
+    }
+}
+
+/// Determine the effective settings and their sources.
+///
+/// `command` and `matches` should be the application's full command and its matches, including subcommands.
+pub fn explain<T: AppConfig>(
+    args: &ConfigArgs,
+    dirs: &AppDirs,
+    command: &Command,
+    matches: &ArgMatches,
+) -> Result<Vec<Setting>, Error> {
+    let path = args.path(dirs);
+    let (file, profile) = if args.config.is_some() || path.exists() {
+        let (table, mut profiles) = read_file::<T>(&path)?;
+        let profile = args.profile().and_then(|name| match profiles.remove(name) {
+            Some(toml::Value::Table(profile)) => Some((name, profile)),
+            _ => None,
+        });
+
+        (Some(table), profile)
+    } else {
+        (None, None)
+    };
+
+    let config = args.load::<T>(dirs)?;
+    let mut values = vec![];
+    flatten(&to_table(&config)?, "", &mut values);
+
+    let mut settings = values
+        .into_iter()
+        .map(|(key, value)| {
+            let segments = key.split('.').collect::<Vec<_>>();
+
+            let source = match &profile {
+                Some((name, profile)) if lookup(profile, &segments) => Source::Profile {
+                    name: name.to_string(),
+                    path: path.clone(),
+                },
+                _ if file.as_ref().is_some_and(|file| lookup(file, &segments)) => {
+                    Source::File(path.clone())
+                }
+                _ => Source::Default,
+            };
+
+            Setting {
+                key,
+                value: value.to_string(),
+                source,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for setting in arg_settings(command, matches) {
+        match settings
+            .iter_mut()
+            .find(|existing| existing.key == setting.key)
+        {
+            // Clap defaults do not override configuration values.
+            Some(_) if setting.source == Source::Default => {}
+            Some(existing) => *existing = setting,
+            None => settings.push(setting),
+        }
+    }
+
+    settings.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(settings)
+}
+
+/// Print the effective settings and their sources to standard output as a table.
+pub fn print_explanation<T: AppConfig>(
+    args: &ConfigArgs,
+    dirs: &AppDirs,
+    command: &Command,
+    matches: &ArgMatches,
+) -> Result<(), Error> {
+    let settings = explain::<T>(args, dirs, command, matches)?;
+
+    OutputFormat::Table.write(std::io::stdout().lock(), &settings)
+}
+
+/// Settings from command-line options (or their environment variable fallbacks and defaults).
+fn arg_settings(command: &Command, matches: &ArgMatches) -> Vec<Setting> {
+    let mut settings = vec![];
+
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+
+        if id == EXPLAIN_CONFIG_ID
+            || matches!(
+                arg.get_action(),
+                ArgAction::Help | ArgAction::HelpShort | ArgAction::HelpLong | ArgAction::Version
+            )
+        {
+            continue;
+        }
+
+        let Some(source) = matches.value_source(id) else {
+            continue;
+        };
+
+        let long = arg.get_long().unwrap_or(id);
+        let value = matches
+            .get_raw(id)
+            .map(|values| {
+                values
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or_default();
+
+        let source = match source {
+            ValueSource::CommandLine => Source::Flag(long.to_string()),
+            ValueSource::EnvVariable => Source::Env(
+                arg.get_env()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+            ),
+            _ => Source::Default,
+        };
+
+        let value = if arg.is_hide_env_values_set() && source != Source::Default {
+            "***".to_string()
+        } else {
+            value
+        };
+
+        settings.push(Setting {
+            key: long.replace('-', "_"),
+            value,
+            source,
+        });
+    }
+
+    if let Some((name, matches)) = matches.subcommand() {
+        if let Some(subcommand) = command.find_subcommand(name) {
+            settings.extend(arg_settings(subcommand, matches));
+        }
+    }
+
+    settings
+}
+
+fn flatten(table: &toml::Table, prefix: &str, values: &mut Vec<(String, toml::Value)>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        match value {
+            toml::Value::Table(table) => flatten(table, &key, values),
+            value => values.push((key, value.clone())),
+        }
+    }
+}
+
+fn lookup(table: &toml::Table, segments: &[&str]) -> bool {
+    match segments {
+        [] => true,
+        [key, rest @ ..] => match (table.get(*key), rest.is_empty()) {
+            (Some(_), true) => true,
+            (Some(toml::Value::Table(table)), false) => lookup(table, rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+    #[serde(default)]
+    struct Config {
+        rate_limit: u32,
+        base_url: String,
+        retries: u32,
+        verbose: bool,
+    }
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(flatten)]
+        config: ConfigArgs,
+        #[clap(long)]
+        rate_limit: Option<u32>,
+        #[clap(long, env = "CLI_HELPERS_EXPLAIN_TEST_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+        #[clap(long, default_value_t = 2)]
+        retries: u32,
+    }
+
+    #[test]
+    fn test_explain() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("config.toml");
+
+        std::fs::write(
+            &path,
+            "rate_limit = 10\nbase_url = \"https://a\"\n\n[profiles.staging]\nbase_url = \"https://b\"\n",
+        )
+        .unwrap();
+
+        std::env::set_var("CLI_HELPERS_EXPLAIN_TEST_TOKEN", "abc123");
+
+        let command = Opts::command();
+        let matches = command.clone().get_matches_from([
+            "test",
+            "--config",
+            path.to_str().unwrap(),
+            "--profile",
+            "staging",
+            "--rate-limit",
+            "20",
+        ]);
+        let opts = <Opts as clap::FromArgMatches>::from_arg_matches(&matches).unwrap();
+        let dirs = AppDirs::new("cli-helpers-explain-test").unwrap();
+
+        let settings = explain::<Config>(&opts.config, &dirs, &command, &matches)
+            .unwrap()
+            .into_iter()
+            .map(|setting| (setting.key, setting.value, setting.source.to_string()))
+            .collect::<Vec<_>>();
+
+        let file = path.display();
+
+        assert_eq!(
+            settings,
+            vec![
+                (
+                    "base_url".to_string(),
+                    "\"https://b\"".to_string(),
+                    format!("profile staging ({file})")
+                ),
+                (
+                    "config".to_string(),
+                    file.to_string(),
+                    "flag --config".to_string()
+                ),
+                (
+                    "profile".to_string(),
+                    "staging".to_string(),
+                    "flag --profile".to_string()
+                ),
+                (
+                    "rate_limit".to_string(),
+                    "20".to_string(),
+                    "flag --rate-limit".to_string()
+                ),
+                (
+                    "retries".to_string(),
+                    "0".to_string(),
+                    "default".to_string()
+                ),
+                (
+                    "token".to_string(),
+                    "***".to_string(),
+                    "env CLI_HELPERS_EXPLAIN_TEST_TOKEN".to_string()
+                ),
+                (
+                    "verbose".to_string(),
+                    "false".to_string(),
+                    "default".to_string()
+                ),
+            ]
+        );
+    }
+}
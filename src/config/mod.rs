@@ -0,0 +1,498 @@
+//! TOML configuration files.
+//!
+//! An application's configuration is a struct that implements `Serialize`, `Deserialize`, `Default`, and
+//! `JsonSchema` (see [`AppConfig`]); doc comments on its fields are used to comment the generated default file. The
+//! file is read from `config.toml` in the application configuration dir, unless `--config` is given, and a
+//! missing file means the default configuration.
+//!
+//! Named profiles (selected with `--profile`) are tables under `profiles` that are merged over the top-level
+//! settings, which form the default profile:
+//!
+//! ```toml
+//! base_url = "https://api.example.com"
+//!
+//! [profiles.staging]
+//! base_url = "https://staging.example.com"
+//! ```
+//!
+//! Files are validated against the configuration's schema when they are loaded (see [`validate`]), so that unknown
+//! keys and invalid values are reported with their location.
+//!
+//! A table of [aliases](alias) for command lines can also be defined under `alias`.
+//!
+//! [`ConfigCommand`] provides reusable `config init`, `config dump`, and `config validate` subcommands, for writing a
+//! commented default file (so that users can discover the available settings), printing the effective configuration,
+//! and checking a file.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+pub use schemars;
+pub use serde;
+
+use crate::{app_dirs::AppDirs, Error};
+
+pub mod alias;
+pub mod explain;
+pub mod validate;
+
+const DEFAULT_CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The key of the table of named profiles.
+pub const PROFILES_KEY: &str = "profiles";
+
+/// The key of the table of command aliases (see [`alias`]).
+pub const ALIAS_KEY: &str = "alias";
+
+/// An application configuration.
+pub trait AppConfig: Serialize + DeserializeOwned + Default + schemars::JsonSchema {}
+
+impl<T: Serialize + DeserializeOwned + Default + schemars::JsonSchema> AppConfig for T {}
+
+/// Standard configuration file argument.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigArgs {
+    /// Configuration file path (defaults to a file in the application configuration dir)
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
+    /// Configuration profile to use
+    #[clap(long, global = true)]
+    profile: Option<String>,
+    /// Print every effective setting and where it came from
+    #[clap(long, global = true)]
+    explain_config: bool,
+}
+
+impl ConfigArgs {
+    pub fn new(config: Option<PathBuf>) -> Self {
+        Self {
+            config,
+            profile: None,
+            explain_config: false,
+        }
+    }
+
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_explain_config(mut self, explain_config: bool) -> Self {
+        self.explain_config = explain_config;
+        self
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// Whether `--explain-config` was given (see [`explain::print_explanation`]).
+    pub fn explain_config(&self) -> bool {
+        self.explain_config
+    }
+
+    /// The configuration file path (which may not exist).
+    pub fn path(&self, dirs: &AppDirs) -> PathBuf {
+        self.config
+            .clone()
+            .unwrap_or_else(|| dirs.config_dir().join(DEFAULT_CONFIG_FILE_NAME))
+    }
+
+    /// Load the configuration for the selected profile, using the default if the default file does not exist.
+    ///
+    /// A file given explicitly with `--config` must exist.
+    pub fn load<T: AppConfig>(&self, dirs: &AppDirs) -> Result<T, Error> {
+        let path = self.path(dirs);
+
+        if self.config.is_none() && !path.exists() {
+            match &self.profile {
+                Some(profile) => Err(Error::UnknownProfile {
+                    name: profile.clone(),
+                    available: vec![],
+                }),
+                None => Ok(T::default()),
+            }
+        } else {
+            load_file(path, self.profile())
+        }
+    }
+}
+
+/// Load a configuration file, merging the named profile (if any) over the top-level settings.
+///
+/// Validation warnings are logged, and the first validation error (if any) is returned.
+pub fn load_file<T: AppConfig, P: AsRef<Path>>(path: P, profile: Option<&str>) -> Result<T, Error> {
+    let path = path.as_ref();
+    let invalid = |message: String| Error::InvalidConfig {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let (mut table, profiles) = read_file::<T>(path)?;
+
+    if let Some(name) = profile {
+        match profiles.get(name) {
+            Some(toml::Value::Table(profile)) => merge(&mut table, profile.clone()),
+            Some(_) => return Err(invalid(format!("profile {name} must be a table"))),
+            None => {
+                return Err(Error::UnknownProfile {
+                    name: name.to_string(),
+                    available: profiles.keys().cloned().collect(),
+                })
+            }
+        }
+    }
+
+    table
+        .try_into()
+        .map_err(|error: toml::de::Error| invalid(error.message().to_string()))
+}
+
+/// Read and validate a configuration file, returning the top-level settings and the profiles.
+fn read_file<T: AppConfig>(path: &Path) -> Result<(toml::Table, toml::Table), Error> {
+    let invalid = |message: String| Error::InvalidConfig {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let contents = std::fs::read_to_string(path)?;
+
+    for diagnostic in validate::validate::<T>(&contents) {
+        if diagnostic.is_error() {
+            return Err(invalid(diagnostic.to_string()));
+        }
+
+        log::warn!("{}:{diagnostic}", path.display());
+    }
+
+    let mut table = contents
+        .parse::<toml::Table>()
+        .map_err(|error| invalid(error.message().to_string()))?;
+
+    let profiles = match table.remove(PROFILES_KEY) {
+        Some(toml::Value::Table(profiles)) => profiles,
+        Some(_) => return Err(invalid(format!("{PROFILES_KEY} must be a table"))),
+        None => toml::Table::new(),
+    };
+
+    // Aliases are only used before parsing the command line.
+    table.remove(ALIAS_KEY);
+
+    Ok((table, profiles))
+}
+
+/// Merge the values of `overrides` into `base`, recursively for tables.
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Reusable configuration management subcommands.
+#[derive(clap::Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// Write a commented default configuration file
+    Init {
+        /// Write to standard output instead of the configuration file
+        #[clap(long)]
+        stdout: bool,
+        /// Overwrite an existing configuration file
+        #[clap(long)]
+        force: bool,
+    },
+    /// Print the effective configuration
+    Dump,
+    /// Check the configuration file for unknown keys and invalid values
+    Validate,
+}
+
+impl ConfigCommand {
+    pub fn run<T: AppConfig>(&self, args: &ConfigArgs, dirs: &AppDirs) -> Result<(), Error> {
+        match self {
+            Self::Init { stdout: true, .. } => {
+                print!("{}", default_config::<T>()?);
+            }
+            Self::Init { force, .. } => {
+                let path = args.path(dirs);
+
+                if path.exists() && !force {
+                    return Err(Error::ConfigExists(path));
+                }
+
+                let path = AppDirs::resolve_file(
+                    Some(&path),
+                    dirs.config_dir(),
+                    DEFAULT_CONFIG_FILE_NAME,
+                )?;
+                std::fs::write(&path, default_config::<T>()?)?;
+                log::info!("Wrote default configuration to {}", path.display());
+            }
+            Self::Dump => {
+                let config = args.load::<T>(dirs)?;
+                let mut stdout = std::io::stdout().lock();
+                write!(stdout, "{}", to_table(&config)?)?;
+            }
+            Self::Validate => {
+                let path = args.path(dirs);
+                let diagnostics = validate::validate::<T>(&std::fs::read_to_string(&path)?);
+
+                for diagnostic in &diagnostics {
+                    eprintln!("{}:{diagnostic}", path.display());
+                }
+
+                let errors = diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.is_error())
+                    .count();
+
+                if errors > 0 {
+                    return Err(Error::InvalidConfig {
+                        path,
+                        message: format!(
+                            "{errors} {}",
+                            if errors == 1 { "error" } else { "errors" }
+                        ),
+                    });
+                }
+
+                log::info!("{} is valid", path.display());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Render the default configuration as TOML, with field documentation as comments.
+///
+/// Fields that are absent from the default (such as `Option` fields that default to `None`) are included as
+/// commented-out keys.
+pub fn default_config<T: AppConfig>() -> Result<String, Error> {
+    let table = to_table(&T::default())?;
+    let schema = schemars::schema_for!(T);
+    let root = schema.as_value();
+
+    let mut output = String::new();
+
+    if let Some(description) = description(root) {
+        push_comment(&mut output, description);
+        output.push('\n');
+    }
+
+    write_table(&mut output, &table, root, root, &[]);
+
+    Ok(output)
+}
+
+fn to_table<T: Serialize>(value: &T) -> Result<toml::Table, Error> {
+    toml::Table::try_from(value).map_err(|error| Error::InvalidConfig {
+        path: PathBuf::new(),
+        message: error.to_string(),
+    })
+}
+
+fn write_table(
+    output: &mut String,
+    table: &toml::Table,
+    schema: &Value,
+    root: &Value,
+    path: &[&str],
+) {
+    let properties = properties(schema, root);
+
+    // Scalar values (and arrays) must precede sub-tables.
+    for (key, property) in properties.iter().flat_map(|properties| properties.iter()) {
+        match table.get(key) {
+            Some(toml::Value::Table(_)) => {}
+            Some(value) => {
+                if let Some(description) = description(property) {
+                    push_comment(output, description);
+                }
+
+                output.push_str(&format!("{} = {value}\n", toml_key(key)));
+            }
+            None => {
+                if let Some(description) = description(property) {
+                    push_comment(output, description);
+                }
+
+                output.push_str(&format!("# {} =\n", toml_key(key)));
+            }
+        }
+    }
+
+    for (key, value) in table {
+        if let toml::Value::Table(value) = value {
+            let property = properties
+                .and_then(|properties| properties.get(key))
+                .unwrap_or(&Value::Null);
+            let mut path = path.to_vec();
+            path.push(key);
+
+            if !output.is_empty() {
+                output.push('\n');
+            }
+
+            if let Some(description) = description(property) {
+                push_comment(output, description);
+            }
+
+            let header = path.iter().map(|key| toml_key(key)).collect::<Vec<_>>();
+            output.push_str(&format!("[{}]\n", header.join(".")));
+
+            write_table(output, value, resolve(property, root), root, &path);
+        }
+    }
+}
+
+/// Follow a local `$ref` (and look through `anyOf` or `oneOf` for optional values).
+fn resolve<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+            .map_or(schema, |schema| resolve(schema, root))
+    } else if let Some(variants) = schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(Value::as_array)
+    {
+        variants
+            .iter()
+            .map(|variant| resolve(variant, root))
+            .find(|variant| variant.get("properties").is_some())
+            .unwrap_or(schema)
+    } else {
+        schema
+    }
+}
+
+fn properties<'a>(
+    schema: &'a Value,
+    root: &'a Value,
+) -> Option<&'a serde_json::Map<String, Value>> {
+    resolve(schema, root)
+        .get("properties")
+        .and_then(Value::as_object)
+}
+
+fn description(schema: &Value) -> Option<&str> {
+    schema.get("description").and_then(Value::as_str)
+}
+
+fn push_comment(output: &mut String, text: &str) {
+    for line in text.lines() {
+        if line.is_empty() {
+            output.push_str("#\n");
+        } else {
+            output.push_str(&format!("# {line}\n"));
+        }
+    }
+}
+
+/// Quote keys that are not valid bare keys.
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    /// Settings for the archive tool.
+    #[derive(
+        Debug, Default, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+    )]
+    #[serde(default)]
+    struct Config {
+        /// Maximum requests per minute
+        rate_limit: u32,
+        /// API token (prefer `--token-file`)
+        token: Option<String>,
+        /// API endpoint settings
+        api: Api,
+    }
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+    #[serde(default)]
+    struct Api {
+        /// Base URL
+        base_url: String,
+    }
+
+    impl Default for Api {
+        fn default() -> Self {
+            Self {
+                base_url: "https://api.example.com".to_string(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_default_config() {
+        let contents = default_config::<Config>().unwrap();
+
+        assert_eq!(
+            contents,
+            "# Settings for the archive tool.\n\n# Maximum requests per minute\nrate_limit = 0\n# API token (prefer `--token-file`)\n# token =\n\n# API endpoint settings\n[api]\n# Base URL\nbase_url = \"https://api.example.com\"\n"
+        );
+        assert_eq!(
+            toml::from_str::<Config>(&contents).unwrap(),
+            Config::default()
+        );
+    }
+
+    #[test]
+    fn test_profiles() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("config.toml");
+
+        std::fs::write(
+            &path,
+            "rate_limit = 10\n[api]\nbase_url = \"https://a\"\n\n[profiles.staging]\nrate_limit = 20\n\n[profiles.test.api]\nbase_url = \"https://b\"\n",
+        )
+        .unwrap();
+
+        let default = load_file::<Config, _>(&path, None).unwrap();
+        let staging = load_file::<Config, _>(&path, Some("staging")).unwrap();
+        let test = load_file::<Config, _>(&path, Some("test")).unwrap();
+
+        assert_eq!(
+            (default.rate_limit, default.api.base_url.as_str()),
+            (10, "https://a")
+        );
+        assert_eq!(
+            (staging.rate_limit, staging.api.base_url.as_str()),
+            (20, "https://a")
+        );
+        assert_eq!(
+            (test.rate_limit, test.api.base_url.as_str()),
+            (10, "https://b")
+        );
+
+        let error = load_file::<Config, _>(&path, Some("prod")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown configuration profile: prod (available: staging, test)"
+        );
+    }
+}
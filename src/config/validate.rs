@@ -0,0 +1,408 @@
+//! Configuration file validation.
+//!
+//! Files are checked against the configuration's JSON schema before they are deserialized, so that problems can be
+//! reported with their location and a suggestion where possible:
+//!
+//! ```text
+//! config.toml:3:1: warning: unknown key `rate_limt` (did you mean `rate_limit`?)
+//! config.toml:7:11: error: expected integer for `api.timeout`, found string
+//! ```
+//!
+//! Unknown keys are errors for tables that deny unknown fields and warnings otherwise. Fields marked as deprecated in
+//! the schema (with `#[deprecated]` or `#[schemars(extend("deprecated" = true))]`) are also reported as warnings.
+
+use std::ops::Range;
+
+use serde_json::Value;
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+use super::{properties, resolve, AppConfig, ALIAS_KEY, PROFILES_KEY};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem with a configuration file, with its (one-based) line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.severity, self.message
+        )?;
+
+        match &self.suggestion {
+            Some(suggestion) => write!(f, " (did you mean `{suggestion}`?)"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check the contents of a configuration file against the schema for `T`.
+///
+/// Profiles are checked against the same schema as the top-level settings.
+pub fn validate<T: AppConfig>(contents: &str) -> Vec<Diagnostic> {
+    let schema = schemars::schema_for!(T);
+    let mut validator = Validator {
+        contents,
+        root: schema.as_value(),
+        diagnostics: vec![],
+    };
+
+    match DeTable::parse(contents) {
+        Ok(table) => validator.check_table(table.get_ref(), schema.as_value(), ""),
+        Err(error) => {
+            let span = error.span().unwrap_or(0..0);
+            validator.push(Severity::Error, span, error.message().to_string(), None);
+        }
+    }
+
+    validator.diagnostics
+}
+
+struct Validator<'a> {
+    contents: &'a str,
+    root: &'a Value,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn check_profiles(&mut self, value: &Spanned<DeValue>) {
+        let DeValue::Table(profiles) = value.get_ref() else {
+            self.mismatch(value.span(), PROFILES_KEY, "table", value.get_ref());
+            return;
+        };
+
+        for (name, profile) in profiles {
+            let path = format!("{PROFILES_KEY}.{}", name.get_ref());
+
+            match profile.get_ref() {
+                DeValue::Table(table) => self.check_table(table, self.root, &path),
+                other => self.mismatch(profile.span(), &path, "table", other),
+            }
+        }
+    }
+
+    fn check_aliases(&mut self, value: &Spanned<DeValue>) {
+        let DeValue::Table(aliases) = value.get_ref() else {
+            self.mismatch(value.span(), ALIAS_KEY, "table", value.get_ref());
+            return;
+        };
+
+        for (name, expansion) in aliases {
+            if !matches!(expansion.get_ref(), DeValue::String(_)) {
+                let path = format!("{ALIAS_KEY}.{}", name.get_ref());
+                self.mismatch(expansion.span(), &path, "string", expansion.get_ref());
+            }
+        }
+    }
+
+    fn check_table(&mut self, table: &DeTable, schema: &Value, path: &str) {
+        let schema = resolve(schema, self.root);
+
+        let Some(properties) = properties(schema, self.root) else {
+            return;
+        };
+
+        let deny_unknown = schema.get("additionalProperties") == Some(&Value::Bool(false));
+
+        for (key, value) in table {
+            let name = key.get_ref().as_ref();
+            let path = if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{path}.{name}")
+            };
+
+            match properties.get(name) {
+                None if path == PROFILES_KEY => self.check_profiles(value),
+                None if path == ALIAS_KEY => self.check_aliases(value),
+                Some(property) => {
+                    if is_deprecated(property) || is_deprecated(resolve(property, self.root)) {
+                        self.push(
+                            Severity::Warning,
+                            key.span(),
+                            format!("`{path}` is deprecated"),
+                            None,
+                        );
+                    }
+
+                    self.check_value(value, property, &path);
+                }
+                None => {
+                    let severity = if deny_unknown {
+                        Severity::Error
+                    } else {
+                        Severity::Warning
+                    };
+                    let suggestion = closest(name, properties.keys().map(String::as_str));
+
+                    self.push(
+                        severity,
+                        key.span(),
+                        format!("unknown key `{path}`"),
+                        suggestion.map(str::to_string),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_value(&mut self, value: &Spanned<DeValue>, schema: &Value, path: &str) {
+        let schema = resolve(schema, self.root);
+        let expected = types(schema);
+
+        if !expected.is_empty() && !expected.iter().any(|name| matches(value.get_ref(), name)) {
+            self.mismatch(value.span(), path, &expected.join(" or "), value.get_ref());
+            return;
+        }
+
+        match value.get_ref() {
+            DeValue::String(string) => {
+                let variants = schema
+                    .get("enum")
+                    .and_then(Value::as_array)
+                    .map(|variants| {
+                        variants
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+
+                if !variants.is_empty() && !variants.contains(&string.as_ref()) {
+                    self.push(
+                        Severity::Error,
+                        value.span(),
+                        format!(
+                            "invalid value `{string}` for `{path}` (expected one of: {})",
+                            variants.join(", ")
+                        ),
+                        closest(string, variants.iter().copied()).map(str::to_string),
+                    );
+                }
+            }
+            DeValue::Integer(integer) => {
+                let parsed = i128::from_str_radix(integer.as_str(), integer.radix()).ok();
+                let minimum = schema.get("minimum").and_then(Value::as_f64);
+                let maximum = schema.get("maximum").and_then(Value::as_f64);
+
+                if let Some(parsed) = parsed {
+                    let parsed = parsed as f64;
+
+                    if minimum.is_some_and(|minimum| parsed < minimum)
+                        || maximum.is_some_and(|maximum| parsed > maximum)
+                    {
+                        self.push(
+                            Severity::Error,
+                            value.span(),
+                            format!("value {integer} is out of range for `{path}`"),
+                            None,
+                        );
+                    }
+                }
+            }
+            DeValue::Table(table) => self.check_table(table, schema, path),
+            DeValue::Array(values) => {
+                if let Some(items) = schema.get("items") {
+                    for (index, value) in values.iter().enumerate() {
+                        self.check_value(value, items, &format!("{path}[{index}]"));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn mismatch(&mut self, span: Range<usize>, path: &str, expected: &str, found: &DeValue) {
+        self.push(
+            Severity::Error,
+            span,
+            format!(
+                "expected {expected} for `{path}`, found {}",
+                found.type_str()
+            ),
+            None,
+        );
+    }
+
+    fn push(
+        &mut self,
+        severity: Severity,
+        span: Range<usize>,
+        message: String,
+        suggestion: Option<String>,
+    ) {
+        let (line, column) = location(self.contents, span.start);
+
+        self.diagnostics.push(Diagnostic {
+            severity,
+            line,
+            column,
+            message,
+            suggestion,
+        });
+    }
+}
+
+/// The JSON schema type names that a schema allows (other than `null`).
+fn types(schema: &Value) -> Vec<&str> {
+    let names = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+
+    names.into_iter().filter(|name| *name != "null").collect()
+}
+
+fn matches(value: &DeValue, name: &str) -> bool {
+    match value {
+        // Date-time values are accepted where strings are expected (for types like `chrono::NaiveDate`).
+        DeValue::String(_) | DeValue::Datetime(_) => name == "string",
+        DeValue::Integer(_) => name == "integer" || name == "number",
+        DeValue::Float(_) => name == "number",
+        DeValue::Boolean(_) => name == "boolean",
+        DeValue::Array(_) => name == "array",
+        DeValue::Table(_) => name == "object",
+    }
+}
+
+fn is_deprecated(schema: &Value) -> bool {
+    schema.get("deprecated") == Some(&Value::Bool(true))
+}
+
+/// The (one-based) line and column of a byte offset.
+fn location(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// The candidate closest to `name`, if any is close enough to be a plausible misspelling.
+fn closest<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<&'a str> {
+    let threshold = (name.chars().count() / 3).max(1);
+
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+    #[serde(default)]
+    struct Config {
+        rate_limit: u32,
+        format: Format,
+        #[schemars(extend("deprecated" = true))]
+        legacy_mode: bool,
+        api: Api,
+    }
+
+    #[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+    #[serde(default, deny_unknown_fields)]
+    struct Api {
+        base_url: String,
+        timeout: Option<u64>,
+    }
+
+    #[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+    #[serde(rename_all = "lowercase")]
+    enum Format {
+        #[default]
+        Table,
+        Json,
+    }
+
+    #[test]
+    fn test_validate() {
+        let contents = "rate_limt = 10\nformat = \"jsn\"\nlegacy_mode = true\n\n[api]\nbase_url = \"https://a\"\ntimeout = \"30s\"\nretries = 3\n\n[profiles.staging]\nrate_limit = -1\n";
+
+        let diagnostics = validate::<Config>(contents)
+            .into_iter()
+            .map(|diagnostic| diagnostic.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                "1:1: warning: unknown key `rate_limt` (did you mean `rate_limit`?)",
+                "2:10: error: invalid value `jsn` for `format` (expected one of: table, json) (did you mean `json`?)",
+                "3:1: warning: `legacy_mode` is deprecated",
+                "7:11: error: expected integer for `api.timeout`, found string",
+                "8:1: error: unknown key `api.retries`",
+                "11:14: error: value -1 is out of range for `profiles.staging.rate_limit`",
+            ]
+        );
+
+        let diagnostics =
+            validate::<Config>("[alias]\nrecent = \"fetch --since yesterday\"\nbroken = 1\n")
+                .into_iter()
+                .map(|diagnostic| diagnostic.to_string())
+                .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec!["3:10: error: expected string for `alias.broken`, found integer"]
+        );
+
+        let diagnostics = validate::<Config>("rate_limit = 1\nrate_limit = 2\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].is_error());
+        assert_eq!(diagnostics[0].line, 2);
+    }
+}
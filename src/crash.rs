@@ -0,0 +1,169 @@
+//! Crash report files for panics.
+//!
+//! When enabled with [`Builder::with_crash_reports`](crate::logging::Builder::with_crash_reports), the panic hook
+//! writes a report to the application state directory and prints its path, so that users can attach it to bug
+//! reports. Reports include the application version, the command-line arguments (with the values of secret options
+//! redacted by [`redact_args`]), the panic message and location, a backtrace, and (if a [`RingBuffer`] is configured) the most
+//! recent log records.
+//!
+//! [`RingBuffer`]: crate::logging::RingBuffer
+
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+
+use crate::redact::redact_args;
+use crate::{clock, Error, Timestamp, TimestampFormat};
+
+/// The name of the crash report directory in the application state directory.
+pub const CRASH_REPORT_DIR_NAME: &str = "crash-reports";
+
+/// The number of recent log records included in a report.
+pub const CRASH_REPORT_LOG_RECORDS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    pub name: String,
+    pub version: String,
+    pub timestamp: Timestamp,
+    pub args: Vec<String>,
+    pub thread: String,
+    pub message: String,
+    pub backtrace: String,
+    pub log: Vec<String>,
+}
+
+impl CrashReport {
+    /// Capture a report for a panic in the current thread.
+    pub fn from_panic(name: &str, version: &str, info: &PanicHookInfo) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            timestamp: Timestamp::now(),
+            args: redact_args(std::env::args_os().map(|arg| arg.to_string_lossy().into_owned())),
+            thread: thread_name(),
+            message: info.to_string(),
+            backtrace: Backtrace::force_capture().to_string(),
+            log: vec![],
+        }
+    }
+
+    /// Include the most recent of the given log records.
+    pub fn with_log(mut self, log: Vec<String>) -> Self {
+        let start = log.len().saturating_sub(CRASH_REPORT_LOG_RECORDS);
+        self.log = log[start..].to_vec();
+        self
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = format!(
+            "{} crashed\n\nVersion: {}\nTime: {}\nArguments: {}\nThread: {}\nPanic: {}\n\nBacktrace:\n{}\n",
+            self.name,
+            self.version,
+            self.timestamp
+                .format_as(TimestampFormat::Rfc3339 { use_z: true }),
+            self.args.join(" "),
+            self.thread,
+            self.message,
+            self.backtrace.trim_end()
+        );
+
+        if !self.log.is_empty() {
+            output.push_str("\nRecent log records:\n");
+
+            for line in &self.log {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+
+        output
+    }
+
+    /// Write the report to a new file in the directory, returning its path.
+    pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<PathBuf, Error> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        let path = dir.join(format!(
+            "crash-{}-{}.txt",
+            clock::now().format("%Y%m%dT%H%M%SZ"),
+            std::process::id()
+        ));
+        std::fs::write(&path, self.render())?;
+
+        Ok(path)
+    }
+}
+
+/// The current thread's name, or its ID if it is unnamed.
+pub(crate) fn thread_name() -> String {
+    let thread = std::thread::current();
+
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => {
+            let id = format!("{:?}", thread.id());
+            id.trim_start_matches("ThreadId(")
+                .trim_end_matches(')')
+                .to_string()
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{app_dirs::AppDirs, logging::Builder, temp::test_dir};
+    use log::LevelFilter;
+
+    const CHILD_VAR: &str = "CLI_HELPERS_CRASH_CHILD";
+    const SECRET: &str = "cli-helpers-crash-test-secret";
+
+    /// The panic hook is process-wide, so the panic happens in a child process, with the state directory under a
+    /// temporary home directory.
+    #[test]
+    fn test_crash_report() {
+        let temp_dir = test_dir();
+
+        // The test harness accepts `--skip <FILTER>`, which the child registers as a secret option.
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "crash::tests::child_panic", "--skip", SECRET])
+            .env(CHILD_VAR, "1")
+            .env("HOME", temp_dir.path())
+            .env("XDG_STATE_HOME", temp_dir.file("state"))
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+    }
+
+    #[test]
+    fn child_panic() {
+        if std::env::var_os(CHILD_VAR).is_none() {
+            return;
+        }
+
+        crate::redact::register_options(["skip"]);
+
+        let dirs = AppDirs::new("cli-helpers-crash-test").unwrap();
+        Builder::new(LevelFilter::Info)
+            .with_crash_reports(&dirs, "1.2.3")
+            .init()
+            .unwrap();
+
+        assert!(std::panic::catch_unwind(|| panic!("crash test")).is_err());
+
+        let reports = std::fs::read_dir(dirs.state_dir().join(CRASH_REPORT_DIR_NAME))
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].starts_with("cli-helpers-crash-test crashed\n\nVersion: 1.2.3\n"));
+        assert!(reports[0].contains("--skip ***"));
+        assert!(reports[0].contains("crash test"));
+        assert!(!reports[0].contains(SECRET));
+    }
+}
@@ -0,0 +1,247 @@
+//! Standard five-field cron expressions, for validating schedule arguments at parse time.
+//!
+//! The fields are minute, hour, day of month, month, and day of week. Each field may be `*`, a value, a range
+//! (`1-5`), a step (`*/15` or `0-30/10`), or a comma-separated list of these. Months and days of the week may also be
+//! given as three-letter English names, and Sunday may be written as either `0` or `7`. As in most cron
+//! implementations, if both the day of month and the day of week are restricted, a time matches if either matches.
+//!
+//! The macros `@yearly`, `@monthly`, `@weekly`, `@daily`, and `@hourly` are also accepted. Schedules are evaluated in
+//! UTC.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Timelike, Utc};
+
+use crate::{Error, Timestamp};
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// The number of years to search before concluding that a schedule never matches (e.g. `0 0 30 2 *`).
+const MAX_SEARCH_YEARS: i32 = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CronExpr {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl CronExpr {
+    /// Whether the schedule matches the minute containing the timestamp.
+    pub fn matches(&self, timestamp: Timestamp) -> bool {
+        let timestamp = timestamp.0;
+
+        self.matches_date(&timestamp)
+            && contains(self.hours, timestamp.hour())
+            && contains(self.minutes, timestamp.minute())
+    }
+
+    /// The first matching time strictly after the given timestamp (or `None` if the schedule never matches).
+    pub fn next_after(&self, timestamp: Timestamp) -> Option<Timestamp> {
+        let start = timestamp.0.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start.year() + MAX_SEARCH_YEARS;
+        let mut current = start;
+
+        while current.year() <= limit {
+            if !contains(self.months, current.month()) {
+                let (year, month) = if current.month() == 12 {
+                    (current.year() + 1, 1)
+                } else {
+                    (current.year(), current.month() + 1)
+                };
+
+                current = chrono::NaiveDate::from_ymd_opt(year, month, 1)?
+                    .and_time(NaiveTime::MIN)
+                    .and_utc();
+            } else if !self.matches_date(&current) {
+                current = (current.date_naive() + Duration::days(1))
+                    .and_time(NaiveTime::MIN)
+                    .and_utc();
+            } else if !contains(self.hours, current.hour()) {
+                current = current.with_minute(0)? + Duration::hours(1);
+            } else if !contains(self.minutes, current.minute()) {
+                current += Duration::minutes(1);
+            } else {
+                return Some(Timestamp(current));
+            }
+        }
+
+        None
+    }
+
+    fn matches_date(&self, timestamp: &DateTime<Utc>) -> bool {
+        let day_of_month = contains(self.days_of_month, timestamp.day());
+        let day_of_week = contains(
+            self.days_of_week,
+            timestamp.weekday().num_days_from_sunday(),
+        );
+
+        let day = if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        };
+
+        day && contains(self.months, timestamp.month())
+    }
+}
+
+impl Display for CronExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for CronExpr {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expanded = match s.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+
+        let fields = expanded.split_whitespace().collect::<Vec<_>>();
+
+        if fields.len() != 5 {
+            return Err(Error::InvalidCron(format!(
+                "expected 5 fields, found {} in {s:?}",
+                fields.len()
+            )));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES, 0)?;
+
+        // Sunday may be written as either 0 or 7.
+        if contains(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            source: s.trim().to_string(),
+            minutes: parse_field(fields[0], 0, 59, &[], 0)?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)?,
+            days_of_month: parse_field(fields[2], 1, 31, &[], 0)?,
+            months: parse_field(fields[3], 1, 12, &MONTH_NAMES, 1)?,
+            days_of_week,
+            days_of_month_restricted: !fields[2].starts_with('*'),
+            days_of_week_restricted: !fields[4].starts_with('*'),
+        })
+    }
+}
+
+fn contains(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    first_name_value: u32,
+) -> Result<u64, Error> {
+    let invalid = |message: &str| Error::InvalidCron(format!("{message} in field {field:?}"));
+
+    let parse_value = |value: &str| -> Result<u32, Error> {
+        let lowercase = value.to_lowercase();
+
+        names
+            .iter()
+            .position(|name| *name == lowercase)
+            .map(|index| index as u32 + first_name_value)
+            .or_else(|| value.parse().ok())
+            .filter(|value| (min..=max).contains(value))
+            .ok_or_else(|| invalid("invalid value"))
+    };
+
+    let mut set = 0;
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| invalid("invalid step"))?,
+            ),
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start)?, parse_value(end)?)
+        } else {
+            let value = parse_value(range)?;
+            // A single value with a step (`5/15`) runs to the end of the range.
+            (value, if step > 1 { max } else { value })
+        };
+
+        if start > end {
+            return Err(invalid("empty range"));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(expr: &str, after: &str) -> Option<String> {
+        expr.parse::<CronExpr>()
+            .unwrap()
+            .next_after(after.parse().unwrap())
+            .map(|timestamp| timestamp.format_as(crate::TimestampFormat::Rfc3339 { use_z: true }))
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let after = "2024-02-12T10:17:30Z";
+
+        assert_eq!(next("*/15 * * * *", after).unwrap(), "2024-02-12T10:30:00Z");
+        assert_eq!(
+            next("0 9 * * mon-fri", after).unwrap(),
+            "2024-02-13T09:00:00Z"
+        );
+        assert_eq!(next("@monthly", after).unwrap(), "2024-03-01T00:00:00Z");
+        assert_eq!(next("0 0 29 2 *", after).unwrap(), "2024-02-29T00:00:00Z");
+        assert_eq!(next("0 0 1 * 7", after).unwrap(), "2024-02-18T00:00:00Z");
+        assert_eq!(next("17 10 * * *", after).unwrap(), "2024-02-13T10:17:00Z");
+        assert_eq!(next("0 0 30 2 *", after), None);
+    }
+
+    #[test]
+    fn test_cron_invalid() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * foo *",
+        ] {
+            assert!(expr.parse::<CronExpr>().is_err(), "{expr}");
+        }
+    }
+}
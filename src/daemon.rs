@@ -0,0 +1,283 @@
+//! Running in the background on Unix.
+//!
+//! [`Daemon::start`] detaches the process from the terminal (forking twice, with `setsid` in between), changes to the
+//! root directory, redirects standard input to `/dev/null` and standard output and error to a log file, and acquires
+//! a [`PidFile`] (failing before detaching if another instance is running). It also installs `SIGTERM` and `SIGINT`
+//! handlers (see [`Shutdown::install_handlers`]), so that the work loop can stop cleanly when a [`Shutdown`] token is
+//! triggered:
+//!
+//! ```rust,no_run
+//! use cli_helpers::{app_dirs::AppDirs, daemon::DaemonArgs, shutdown::Shutdown};
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     daemon: DaemonArgs,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//! let dirs = AppDirs::new("mytool")?;
+//!
+//! // This must happen before any threads are started (and before the logger is initialized, so that it writes to
+//! // the log file). The PID file is removed when the guard is dropped.
+//! let _pid_file = opts.daemon.start(&dirs)?;
+//! let shutdown = Shutdown::signals();
+//!
+//! while !shutdown.is_requested() {
+//!     // ...
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::{File, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use crate::pid_file::PidFile;
+use crate::shutdown::Shutdown;
+use crate::{app_dirs::AppDirs, Error};
+
+/// Standard background mode arguments.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DaemonArgs {
+    /// Run in the background
+    #[clap(long)]
+    daemon: bool,
+    /// PID file to write in background mode (defaults to the state directory)
+    #[clap(long, requires = "daemon")]
+    pid_file: Option<PathBuf>,
+    /// Log file for background mode (defaults to the state directory)
+    #[clap(long, requires = "daemon")]
+    log_file: Option<PathBuf>,
+}
+
+impl DaemonArgs {
+    pub fn new(daemon: bool) -> Self {
+        Self {
+            daemon,
+            pid_file: None,
+            log_file: None,
+        }
+    }
+
+    pub fn is_daemon(&self) -> bool {
+        self.daemon
+    }
+
+    /// The configured daemon, with files in the application state directory by default.
+    pub fn daemon(&self, dirs: &AppDirs) -> Daemon {
+        Daemon::new()
+            .with_pid_file(
+                self.pid_file
+                    .clone()
+                    .unwrap_or_else(|| default_pid_file(dirs)),
+            )
+            .with_log_file(
+                self.log_file
+                    .clone()
+                    .unwrap_or_else(|| default_log_file(dirs)),
+            )
+    }
+
+    /// Detach if `--daemon` was given (doing nothing otherwise), returning the PID file guard.
+    pub fn start(&self, dirs: &AppDirs) -> Result<Option<PidFile>, Error> {
+        if self.daemon {
+            self.daemon(dirs).start()
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+pub(crate) fn default_pid_file(dirs: &AppDirs) -> PathBuf {
+    dirs.state_dir().join(format!("{}.pid", dirs.name()))
+}
+
+pub(crate) fn default_log_file(dirs: &AppDirs) -> PathBuf {
+    dirs.state_dir().join(format!("{}.log", dirs.name()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Daemon {
+    pid_file: Option<PathBuf>,
+    log_file: Option<PathBuf>,
+    working_dir: PathBuf,
+}
+
+impl Daemon {
+    pub fn new() -> Self {
+        Self {
+            pid_file: None,
+            log_file: None,
+            working_dir: PathBuf::from("/"),
+        }
+    }
+
+    pub fn with_pid_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.pid_file = Some(path.into());
+        self
+    }
+
+    /// Append standard output and error to the file (they are discarded by default).
+    pub fn with_log_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.log_file = Some(path.into());
+        self
+    }
+
+    /// Change to the directory instead of the root directory.
+    pub fn with_working_dir<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.working_dir = path.into();
+        self
+    }
+
+    /// Detach from the terminal, returning the PID file guard in the background process (the original process exits).
+    ///
+    /// Relative paths are resolved (and stored, for [`Daemon::reopen_log_file`]) before changing directories. This
+    /// must be called before any threads are started.
+    pub fn start(&mut self) -> Result<Option<PidFile>, Error> {
+        self.resolve_paths()?;
+
+        if let Some(path) = &self.pid_file {
+            PidFile::check(path)?;
+        }
+
+        // Open the log file before forking, so that errors are reported to the terminal.
+        let log_file = match &self.log_file {
+            Some(path) => Some(open_log_file(path)?),
+            None => None,
+        };
+
+        fork_and_exit_parent()?;
+
+        // SAFETY: `setsid` has no memory safety requirements.
+        if unsafe { libc::setsid() } == -1 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        // Fork again so that the process is not a session leader and cannot acquire a controlling terminal.
+        fork_and_exit_parent()?;
+
+        std::env::set_current_dir(&self.working_dir)?;
+
+        let null = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/null")?;
+        let output = log_file.as_ref().unwrap_or(&null);
+
+        redirect(&null, libc::STDIN_FILENO)?;
+        redirect(output, libc::STDOUT_FILENO)?;
+        redirect(output, libc::STDERR_FILENO)?;
+
+        let pid_file = self.pid_file.as_ref().map(PidFile::acquire).transpose()?;
+
+        Shutdown::install_handlers()?;
+
+        Ok(pid_file)
+    }
+
+    /// Point standard output and error at a newly opened log file (for example after it has been rotated).
+    ///
+    /// This can be registered as a [`Reload`](crate::reload::Reload) callback (after [`Daemon::start`], which resolves a
+    /// relative path before changing directories). It does nothing if there is no log file.
+    pub fn reopen_log_file(&self) -> Result<(), Error> {
+        if let Some(path) = &self.log_file {
+            let log_file = open_log_file(path)?;
+
+            redirect(&log_file, libc::STDOUT_FILENO)?;
+            redirect(&log_file, libc::STDERR_FILENO)?;
+        }
+
+        Ok(())
+    }
+
+    fn resolve_paths(&mut self) -> Result<(), Error> {
+        self.pid_file = self
+            .pid_file
+            .as_deref()
+            .map(std::path::absolute)
+            .transpose()?;
+        self.log_file = self
+            .log_file
+            .as_deref()
+            .map(std::path::absolute)
+            .transpose()?;
+
+        Ok(())
+    }
+}
+
+impl Default for Daemon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn fork_and_exit_parent() -> Result<(), Error> {
+    // SAFETY: the caller is responsible for ensuring that no other threads are running.
+    match unsafe { libc::fork() } {
+        -1 => Err(std::io::Error::last_os_error().into()),
+        0 => Ok(()),
+        // SAFETY: `_exit` skips destructors and atexit handlers, which belong to the child now.
+        _ => unsafe { libc::_exit(0) },
+    }
+}
+
+fn redirect(file: &File, fd: libc::c_int) -> Result<(), Error> {
+    // SAFETY: both file descriptors are valid for the duration of the call.
+    if unsafe { libc::dup2(file.as_raw_fd(), fd) } == -1 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(())
+    }
+}
+
+fn open_log_file(path: &Path) -> Result<File, Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daemon_args() {
+        use clap::Parser;
+
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(flatten)]
+            daemon: DaemonArgs,
+        }
+
+        let dirs = AppDirs::new("cli-helpers-daemon-test").unwrap();
+        let opts =
+            Opts::try_parse_from(["test", "--daemon", "--pid-file", "/run/test.pid"]).unwrap();
+
+        assert!(opts.daemon.is_daemon());
+        assert_eq!(
+            opts.daemon.daemon(&dirs),
+            Daemon::new()
+                .with_pid_file("/run/test.pid")
+                .with_log_file(dirs.state_dir().join("cli-helpers-daemon-test.log"))
+        );
+        assert!(Opts::try_parse_from(["test", "--pid-file", "/run/test.pid"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        let mut daemon = Daemon::new().with_log_file("test.log");
+        daemon.resolve_paths().unwrap();
+
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(daemon.log_file, Some(cwd.join("test.log")));
+        assert_eq!(daemon.pid_file, None);
+    }
+}
@@ -0,0 +1,161 @@
+//! Support for renamed flags that should continue to work with a warning.
+//!
+//! ```rust,no_run
+//! use cli_helpers::deprecation::{deprecated_alias, Deprecations};
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     verbose: Verbosity,
+//!     #[clap(long)]
+//!     timestamp: Option<Timestamp>,
+//! }
+//!
+//! fn main() -> Result<(), cli_helpers::Error> {
+//!     let deprecations = Deprecations::new([deprecated_alias("--ts", "--timestamp")]);
+//!     let opts = Opts::parse_from(deprecations.rewrite(std::env::args_os()));
+//!     opts.verbose.init_logging()?;
+//!     deprecations.warn();
+//!     Ok(())
+//! }
+//! ```
+
+use std::ffi::OsString;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedAlias {
+    old: String,
+    new: String,
+}
+
+/// Declare that the flag `old` (for example `--ts`) has been renamed to `new`.
+pub fn deprecated_alias(old: &str, new: &str) -> DeprecatedAlias {
+    DeprecatedAlias {
+        old: old.to_string(),
+        new: new.to_string(),
+    }
+}
+
+impl DeprecatedAlias {
+    fn rewrite(&self, arg: &str) -> Option<String> {
+        if arg == self.old {
+            Some(self.new.clone())
+        } else if self.old.starts_with("--") {
+            arg.strip_prefix(&self.old)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(|value| format!("{}={value}", self.new))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Deprecations {
+    aliases: Vec<DeprecatedAlias>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl Deprecations {
+    pub fn new<I: IntoIterator<Item = DeprecatedAlias>>(aliases: I) -> Self {
+        let aliases = aliases.into_iter().collect::<Vec<_>>();
+        let used = Mutex::new(vec![false; aliases.len()]);
+
+        Self { aliases, used }
+    }
+
+    /// Replace any deprecated flags with their new names (the first argument is the program name).
+    ///
+    /// Arguments after `--` are left unchanged.
+    pub fn rewrite<I: IntoIterator<Item = T>, T: Into<OsString>>(&self, args: I) -> Vec<OsString> {
+        let mut used = self.used.lock().unwrap_or_else(|error| error.into_inner());
+        let mut args = args.into_iter().map(Into::into);
+        let mut result = args.next().into_iter().collect::<Vec<_>>();
+
+        for arg in args.by_ref() {
+            if arg == "--" {
+                result.push(arg);
+                break;
+            }
+
+            let rewritten = arg.to_str().and_then(|arg| {
+                self.aliases
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, alias)| alias.rewrite(arg).map(|arg| (i, arg)))
+            });
+
+            match rewritten {
+                Some((i, rewritten)) => {
+                    used[i] = true;
+                    result.push(rewritten.into());
+                }
+                None => result.push(arg),
+            }
+        }
+
+        result.extend(args);
+        result
+    }
+
+    /// Log a warning for each deprecated flag that has been used, once.
+    ///
+    /// This should be called after logging has been initialized.
+    pub fn warn(&self) {
+        let mut used = self.used.lock().unwrap_or_else(|error| error.into_inner());
+
+        for (alias, used) in self.aliases.iter().zip(used.iter_mut()) {
+            if std::mem::take(used) {
+                log::warn!("{} is deprecated; use {} instead", alias.old, alias.new);
+            }
+        }
+    }
+
+    /// The deprecated flags that have been used but not yet warned about.
+    pub fn pending(&self) -> Vec<&DeprecatedAlias> {
+        let used = self.used.lock().unwrap_or_else(|error| error.into_inner());
+
+        self.aliases
+            .iter()
+            .zip(used.iter())
+            .filter(|(_, used)| **used)
+            .map(|(alias, _)| alias)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let deprecations = Deprecations::new([
+            deprecated_alias("--ts", "--timestamp"),
+            deprecated_alias("-t", "-T"),
+        ]);
+
+        let rewritten =
+            deprecations.rewrite(["test", "--ts", "1", "--ts=2", "--tsx", "-t", "--", "--ts"]);
+
+        assert_eq!(
+            rewritten,
+            [
+                "test",
+                "--timestamp",
+                "1",
+                "--timestamp=2",
+                "--tsx",
+                "-T",
+                "--",
+                "--ts"
+            ]
+        );
+        assert_eq!(deprecations.pending().len(), 2);
+
+        deprecations.warn();
+        assert!(deprecations.pending().is_empty());
+    }
+}
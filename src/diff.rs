@@ -0,0 +1,407 @@
+//! Line-based diffs (using the linear-space variant of Myers' algorithm).
+//!
+//! [`unified`] renders a unified diff of two strings, and [`unified_colored`] and [`unified_files`] optionally color
+//! it (removed lines in red, added lines in green), typically as determined by [`color::enabled`].
+//!
+//! [`color::enabled`]: crate::color::enabled
+
+use std::path::Path;
+
+use clap::builder::styling::{AnsiColor, Style};
+
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Compute a minimal sequence of line changes transforming `old` into `new`.
+///
+/// Within each run of changed lines, deletions come before insertions.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<Change<'a>> {
+    let a = old.lines().collect::<Vec<_>>();
+    let b = new.lines().collect::<Vec<_>>();
+    let mut changes = Vec::with_capacity(a.len().max(b.len()));
+
+    diff_range(&a, &b, &mut changes);
+
+    // Order each run of changes with deletions first (which is still minimal).
+    let mut start = 0;
+
+    while start < changes.len() {
+        let end = changes[start..]
+            .iter()
+            .position(|change| matches!(change, Change::Equal(_)))
+            .map_or(changes.len(), |offset| start + offset);
+
+        changes[start..end].sort_by_key(|change| matches!(change, Change::Insert(_)));
+        start = end + 1;
+    }
+
+    changes
+}
+
+/// Append the changes transforming `a` into `b`.
+///
+/// This is the linear-space variant of Myers' algorithm, which splits the problem at the middle snake of an optimal
+/// path instead of recording every step of the search.
+fn diff_range<'a>(a: &[&'a str], b: &[&'a str], changes: &mut Vec<Change<'a>>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    changes.extend(a[..prefix].iter().map(|line| Change::Equal(line)));
+
+    let (a_rest, b_rest) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    if a_rest.is_empty() {
+        changes.extend(b_rest.iter().map(|line| Change::Insert(line)));
+    } else if b_rest.is_empty() {
+        changes.extend(a_rest.iter().map(|line| Change::Delete(line)));
+    } else {
+        // Both are non-empty and differ at both ends, so the distance is at least two, and each half is shorter.
+        let (x, y, u, v) = middle_snake(a_rest, b_rest);
+
+        diff_range(&a_rest[..x], &b_rest[..y], changes);
+        changes.extend(a_rest[x..u].iter().map(|line| Change::Equal(line)));
+        diff_range(&a_rest[u..], &b_rest[v..], changes);
+    }
+
+    changes.extend(a[a.len() - suffix..].iter().map(|line| Change::Equal(line)));
+}
+
+/// The start and end of the middle snake of an optimal path from `(0, 0)` to `(a.len(), b.len())`.
+fn middle_snake(a: &[&str], b: &[&str]) -> (usize, usize, usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let max = (n + m + 1) / 2;
+    let index = |k: isize| (k + max + 1) as usize;
+
+    // The furthest x reached on each diagonal, from the start and (as a distance) from the end.
+    let mut forward = vec![0; 2 * max as usize + 3];
+    let mut backward = vec![0; 2 * max as usize + 3];
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let x_start = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+            let y_start = x_start - k;
+            let (mut x, mut y) = (x_start, y_start);
+
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            forward[index(k)] = x;
+
+            let reverse_k = delta - k;
+
+            if delta % 2 != 0
+                && (-(d - 1)..=d - 1).contains(&reverse_k)
+                && x + backward[index(reverse_k)] >= n
+            {
+                return (x_start as usize, y_start as usize, x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let x_start = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)])
+            {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+            let y_start = x_start - k;
+            let (mut x, mut y) = (x_start, y_start);
+
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            backward[index(k)] = x;
+
+            let forward_k = delta - k;
+
+            if delta % 2 == 0 && (-d..=d).contains(&forward_k) && x + forward[index(forward_k)] >= n
+            {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x_start) as usize,
+                    (m - y_start) as usize,
+                );
+            }
+        }
+    }
+
+    unreachable!("the forward and backward searches always overlap")
+}
+
+/// A group of changes with surrounding context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk<'a> {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub changes: Vec<Change<'a>>,
+}
+
+impl Hunk<'_> {
+    /// The unified diff hunk header (using the conventional line numbering for empty ranges).
+    pub fn header(&self) -> String {
+        let range = |start: usize, len: usize| {
+            if len == 0 {
+                format!("{},0", start.saturating_sub(1))
+            } else {
+                format!("{start},{len}")
+            }
+        };
+
+        format!(
+            "@@ -{} +{} @@",
+            range(self.old_start, self.old_len),
+            range(self.new_start, self.new_len)
+        )
+    }
+}
+
+/// Group changes into hunks with the given number of context lines.
+pub fn hunks<'a>(changes: &[Change<'a>], context: usize) -> Vec<Hunk<'a>> {
+    let changed = changes
+        .iter()
+        .enumerate()
+        .filter(|(_, change)| !matches!(change, Change::Equal(_)))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    // Merge ranges of changes whose context overlaps.
+    let mut ranges: Vec<(usize, usize)> = vec![];
+
+    for i in changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(changes.len());
+
+        match ranges.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    // Line numbers (one-based) at the start of each change.
+    let mut positions = Vec::with_capacity(changes.len());
+    let (mut old_line, mut new_line) = (1, 1);
+
+    for change in changes {
+        positions.push((old_line, new_line));
+
+        match change {
+            Change::Equal(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            Change::Delete(_) => old_line += 1,
+            Change::Insert(_) => new_line += 1,
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let changes = changes[start..end].to_vec();
+            let old_len = changes
+                .iter()
+                .filter(|change| !matches!(change, Change::Insert(_)))
+                .count();
+            let new_len = changes
+                .iter()
+                .filter(|change| !matches!(change, Change::Delete(_)))
+                .count();
+
+            Hunk {
+                old_start: positions[start].0,
+                old_len,
+                new_start: positions[start].1,
+                new_len,
+                changes,
+            }
+        })
+        .collect()
+}
+
+/// Render a unified diff (an empty string if there are no differences).
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str, context: usize) -> String {
+    unified_colored(old, new, old_name, new_name, context, false)
+}
+
+/// Render a unified diff, with color if `color` is true.
+pub fn unified_colored(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+    context: usize,
+    color: bool,
+) -> String {
+    let changes = diff_lines(old, new);
+    let hunks = hunks(&changes, context);
+
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let style = |style: Style| if color { style } else { Style::new() };
+    let header = style(Style::new().bold());
+    let range = style(AnsiColor::Cyan.on_default());
+    let delete = style(AnsiColor::Red.on_default());
+    let insert = style(AnsiColor::Green.on_default());
+
+    let mut output = format!("{header}--- {old_name}\n+++ {new_name}{header:#}\n");
+
+    for hunk in hunks {
+        output.push_str(&format!("{range}{}{range:#}\n", hunk.header()));
+
+        for change in hunk.changes {
+            let line = match change {
+                Change::Equal(line) => format!(" {line}"),
+                Change::Delete(line) => format!("{delete}-{line}{delete:#}"),
+                Change::Insert(line) => format!("{insert}+{line}{insert:#}"),
+            };
+
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+/// Render a unified diff of two files (named by their paths), with color if `color` is true.
+pub fn unified_files<P: AsRef<Path>, Q: AsRef<Path>>(
+    old: P,
+    new: Q,
+    context: usize,
+    color: bool,
+) -> Result<String, Error> {
+    let (old, new) = (old.as_ref(), new.as_ref());
+
+    Ok(unified_colored(
+        &std::fs::read_to_string(old)?,
+        &std::fs::read_to_string(new)?,
+        &old.display().to_string(),
+        &new.display().to_string(),
+        context,
+        color,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nc\nd\ne\nf\ng\nx\nh\ni\n";
+
+        assert_eq!(
+            unified(old, new, "old", "new", 1),
+            "--- old\n+++ new\n@@ -1,3 +1,2 @@\n a\n-b\n c\n@@ -7,2 +6,4 @@\n g\n+x\n h\n+i\n"
+        );
+        assert_eq!(unified(old, old, "old", "new", 3), "");
+        assert_eq!(
+            unified("", "a\n", "old", "new", 3),
+            "--- old\n+++ new\n@@ -0,0 +1,1 @@\n+a\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_minimal() {
+        // The length of the longest common subsequence, by dynamic programming.
+        fn lcs(a: &[&str], b: &[&str]) -> usize {
+            let mut row = vec![0; b.len() + 1];
+
+            for x in a {
+                let mut diagonal = 0;
+
+                for (j, y) in b.iter().enumerate() {
+                    let above = row[j + 1];
+                    row[j + 1] = if x == y {
+                        diagonal + 1
+                    } else {
+                        above.max(row[j])
+                    };
+                    diagonal = above;
+                }
+            }
+
+            row[b.len()]
+        }
+
+        // A deterministic linear congruential generator over a small alphabet.
+        let mut state = 17_u64;
+        let mut text = |len: u64| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (0..state % len)
+                .map(|_| {
+                    state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+                    ["a", "b", "c", "d"][(state >> 33) as usize % 4]
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        for _ in 0..500 {
+            let (old, new) = (text(20), text(20));
+            let changes = diff_lines(&old, &new);
+
+            let old_lines = old.lines().collect::<Vec<_>>();
+            let new_lines = new.lines().collect::<Vec<_>>();
+            let kept = changes
+                .iter()
+                .filter_map(|change| match change {
+                    Change::Equal(line) | Change::Delete(line) => Some(*line),
+                    Change::Insert(_) => None,
+                })
+                .collect::<Vec<_>>();
+            let result = changes
+                .iter()
+                .filter_map(|change| match change {
+                    Change::Equal(line) | Change::Insert(line) => Some(*line),
+                    Change::Delete(_) => None,
+                })
+                .collect::<Vec<_>>();
+
+            assert_eq!(kept, old_lines);
+            assert_eq!(result, new_lines);
+            assert_eq!(
+                changes
+                    .iter()
+                    .filter(|change| matches!(change, Change::Equal(_)))
+                    .count(),
+                lcs(&old_lines, &new_lines),
+                "{old:?} {new:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_unified_colored() {
+        assert_eq!(
+            unified_colored("a\nb\n", "a\nc\n", "old", "new", 1, true),
+            "\x1b[1m--- old\n+++ new\x1b[0m\n\x1b[36m@@ -1,2 +1,2 @@\x1b[0m\n a\n\x1b[31m-b\x1b[0m\n\x1b[32m+c\x1b[0m\n"
+        );
+    }
+}
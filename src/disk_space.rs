@@ -0,0 +1,121 @@
+//! Free disk space checks.
+//!
+//! [`ensure_free_space`] fails with [`Error::InsufficientSpace`] if the file system containing a path does not have
+//! enough space available, so that a command can stop before it starts writing rather than failing halfway through a
+//! large output file:
+//!
+//! ```rust,no_run
+//! use cli_helpers::{byte_size::ByteSize, disk_space::ensure_free_space};
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! // The path does not need to exist yet (its nearest existing ancestor is checked).
+//! ensure_free_space("out/export.ndjson", ByteSize(4 << 30))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Space is measured with `statvfs` on Unix and `GetDiskFreeSpaceExW` on Windows, and counts only the space available
+//! to unprivileged users.
+
+use std::path::{Path, PathBuf};
+
+use crate::byte_size::ByteSize;
+use crate::Error;
+
+/// The space available to the current user on the file system containing the path (or its nearest existing ancestor).
+pub fn free_space<P: AsRef<Path>>(path: P) -> Result<ByteSize, Error> {
+    available(&existing_ancestor(path.as_ref())?).map(ByteSize)
+}
+
+/// Fail with [`Error::InsufficientSpace`] if less than `required` is available for the path.
+pub fn ensure_free_space<P: AsRef<Path>>(path: P, required: ByteSize) -> Result<(), Error> {
+    let path = path.as_ref();
+    let available = free_space(path)?;
+
+    if available < required {
+        Err(Error::InsufficientSpace {
+            path: path.to_path_buf(),
+            required,
+            available,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn existing_ancestor(path: &Path) -> Result<PathBuf, Error> {
+    let path = std::path::absolute(path)?;
+
+    Ok(path
+        .ancestors()
+        .find(|ancestor| ancestor.exists())
+        .unwrap_or(&path)
+        .to_path_buf())
+}
+
+#[cfg(unix)]
+fn available(path: &Path) -> Result<u64, Error> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: the path is a valid C string, and `stats` is only read if the call succeeds.
+    if unsafe { libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) } == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    // SAFETY: `statvfs` succeeded, so the struct has been initialized.
+    let stats = unsafe { stats.assume_init() };
+
+    // The field types vary between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    Ok((stats.f_bavail as u64).saturating_mul(stats.f_frsize as u64))
+}
+
+#[cfg(windows)]
+fn available(path: &Path) -> Result<u64, Error> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect::<Vec<_>>();
+    let mut available = 0;
+
+    // SAFETY: the path is null-terminated, and the other output pointers may be null.
+    let result = unsafe {
+        GetDiskFreeSpaceExW(
+            path.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+
+    if result == 0 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(available)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ensure_free_space() {
+        let path = std::env::temp_dir().join("cli-helpers-missing/output.ndjson");
+
+        assert!(free_space(&path).unwrap() > ByteSize(0));
+        assert!(ensure_free_space(&path, ByteSize(1)).is_ok());
+        assert!(matches!(
+            ensure_free_space(&path, ByteSize(u64::MAX)),
+            Err(Error::InsufficientSpace { required, .. }) if required == ByteSize(u64::MAX)
+        ));
+    }
+}
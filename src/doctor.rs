@@ -0,0 +1,352 @@
+//! A `doctor` subcommand for self-checks.
+//!
+//! Applications register named [`Check`]s (closures also work, with [`Doctor::with_fn`]) with a [`Doctor`], and
+//! [`DoctorArgs::run`] runs them, printing a line for each with a remediation hint for warnings and failures:
+//!
+//! ```text
+//! ✔ config: /home/travis/.config/mytool/config.toml is readable
+//! ⚠ schema: schema version 2 (expected 3)
+//!   → run `mytool migrate`
+//! ✘ api: api.example.com:443 is not reachable (Connection refused (os error 111))
+//!   → check your network connection or proxy settings
+//! ```
+//!
+//! The exit code is non-zero if any check fails (or, with `--strict`, if any check warns).
+//!
+//! ```rust,no_run
+//! use cli_helpers::doctor::{checks, Doctor, DoctorArgs, Outcome};
+//! use std::path::Path;
+//!
+//! let doctor = Doctor::new()
+//!     .with_check(checks::file_readable("config", Path::new("/home/travis/.config/mytool/config.toml")))
+//!     .with_fn("schema", || Outcome::pass("schema version 3"));
+//!
+//! std::process::exit(DoctorArgs::default().run(&doctor));
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::symbols::symbols;
+
+/// The exit code when a check fails.
+pub const FAILURE_EXIT_CODE: i32 = 1;
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// The result of running a check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub status: Status,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl Outcome {
+    pub fn new<S: Into<String>>(status: Status, message: S) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub fn pass<S: Into<String>>(message: S) -> Self {
+        Self::new(Status::Pass, message)
+    }
+
+    pub fn warn<S: Into<String>>(message: S) -> Self {
+        Self::new(Status::Warn, message)
+    }
+
+    pub fn fail<S: Into<String>>(message: S) -> Self {
+        Self::new(Status::Fail, message)
+    }
+
+    /// Add a remediation hint (shown for warnings and failures).
+    pub fn with_hint<S: Into<String>>(mut self, hint: S) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+pub trait Check {
+    fn name(&self) -> &str;
+    fn run(&self) -> Outcome;
+}
+
+struct FnCheck<F> {
+    name: String,
+    f: F,
+}
+
+impl<F: Fn() -> Outcome> Check for FnCheck<F> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn run(&self) -> Outcome {
+        (self.f)()
+    }
+}
+
+/// A registry of checks.
+#[derive(Default)]
+pub struct Doctor {
+    checks: Vec<Box<dyn Check>>,
+}
+
+impl Doctor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_check<C: Check + 'static>(mut self, check: C) -> Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    pub fn with_fn<F: Fn() -> Outcome + 'static>(self, name: &str, f: F) -> Self {
+        self.with_check(FnCheck {
+            name: name.to_string(),
+            f,
+        })
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.checks.iter().map(|check| check.name()).collect()
+    }
+
+    /// Run every check, in the order they were registered.
+    pub fn run(&self) -> Diagnosis {
+        Diagnosis {
+            results: self
+                .checks
+                .iter()
+                .map(|check| (check.name().to_string(), check.run()))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Doctor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Doctor")
+            .field("checks", &self.names())
+            .finish()
+    }
+}
+
+/// The outcomes of a doctor run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub results: Vec<(String, Outcome)>,
+}
+
+impl Diagnosis {
+    /// The most severe status (passing if there are no checks).
+    pub fn status(&self) -> Status {
+        self.results
+            .iter()
+            .map(|(_, outcome)| outcome.status)
+            .max()
+            .unwrap_or(Status::Pass)
+    }
+
+    pub fn exit_code(&self, strict: bool) -> i32 {
+        match self.status() {
+            Status::Pass => 0,
+            Status::Warn if !strict => 0,
+            _ => FAILURE_EXIT_CODE,
+        }
+    }
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let symbols = symbols();
+
+        for (name, outcome) in &self.results {
+            let symbol = match outcome.status {
+                Status::Pass => symbols.check,
+                Status::Warn => symbols.warning,
+                Status::Fail => symbols.cross,
+            };
+
+            writeln!(f, "{symbol} {name}: {}", outcome.message)?;
+
+            if let Some(hint) = outcome
+                .hint
+                .as_ref()
+                .filter(|_| outcome.status != Status::Pass)
+            {
+                writeln!(f, "  {} {hint}", symbols.arrow)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Standard doctor subcommand arguments.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DoctorArgs {
+    /// Treat warnings as failures
+    #[clap(long)]
+    strict: bool,
+}
+
+impl DoctorArgs {
+    pub fn new(strict: bool) -> Self {
+        Self { strict }
+    }
+
+    /// Run the checks and print the results, returning the exit code.
+    pub fn run(&self, doctor: &Doctor) -> i32 {
+        let diagnosis = doctor.run();
+        print!("{diagnosis}");
+
+        diagnosis.exit_code(self.strict)
+    }
+}
+
+/// Common checks.
+pub mod checks {
+    use std::net::{TcpStream, ToSocketAddrs};
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{Check, FnCheck, Outcome};
+
+    /// The file exists and can be read.
+    pub fn file_readable(name: &str, path: &Path) -> impl Check {
+        let path = path.to_path_buf();
+
+        FnCheck {
+            name: name.to_string(),
+            f: move || match std::fs::read(&path) {
+                Ok(_) => Outcome::pass(format!("{} is readable", path.display())),
+                Err(error) => {
+                    Outcome::fail(format!("{} is not readable ({error})", path.display()))
+                        .with_hint("check that the file exists and its permissions")
+                }
+            },
+        }
+    }
+
+    /// A file can be created in the directory (which is created if necessary).
+    pub fn dir_writable(name: &str, path: &Path) -> impl Check {
+        let path = path.to_path_buf();
+
+        FnCheck {
+            name: name.to_string(),
+            f: move || match try_write(&path) {
+                Ok(()) => Outcome::pass(format!("{} is writable", path.display())),
+                Err(error) => {
+                    Outcome::fail(format!("{} is not writable ({error})", path.display()))
+                        .with_hint("check the directory's permissions")
+                }
+            },
+        }
+    }
+
+    /// A TCP connection to the address (such as `api.example.com:443`) can be opened within the timeout.
+    pub fn tcp_reachable(name: &str, address: &str, timeout: Duration) -> impl Check {
+        let address = address.to_string();
+
+        FnCheck {
+            name: name.to_string(),
+            f: move || match connect(&address, timeout) {
+                Ok(()) => Outcome::pass(format!("{address} is reachable")),
+                Err(error) => Outcome::fail(format!("{address} is not reachable ({error})"))
+                    .with_hint("check your network connection or proxy settings"),
+            },
+        }
+    }
+
+    /// At least `required` is available on the file system containing the path.
+    #[cfg(feature = "disk-space")]
+    pub fn free_space(name: &str, path: &Path, required: crate::byte_size::ByteSize) -> impl Check {
+        let path = path.to_path_buf();
+
+        FnCheck {
+            name: name.to_string(),
+            f: move || match crate::disk_space::free_space(&path) {
+                Ok(available) if available >= required => {
+                    Outcome::pass(format!("{available} available for {}", path.display()))
+                }
+                Ok(available) => Outcome::warn(format!(
+                    "only {available} available for {} ({required} recommended)",
+                    path.display()
+                ))
+                .with_hint("free up disk space or use a different directory"),
+                Err(error) => Outcome::fail(format!(
+                    "unable to determine free space for {} ({error})",
+                    path.display()
+                )),
+            },
+        }
+    }
+
+    fn try_write(dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+
+        let path = dir.join(format!(".doctor-{}", std::process::id()));
+        std::fs::write(&path, b"")?;
+        std::fs::remove_file(&path)
+    }
+
+    fn connect(address: &str, timeout: Duration) -> std::io::Result<()> {
+        let mut last_error = None;
+
+        for address in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, timeout) {
+                Ok(_) => return Ok(()),
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| std::io::ErrorKind::NotFound.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_doctor() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+
+        let doctor = Doctor::new()
+            .with_check(checks::dir_writable("state", dir))
+            .with_fn("schema", || {
+                Outcome::warn("schema version 2 (expected 3)").with_hint("run `mytool migrate`")
+            });
+
+        let diagnosis = doctor.run();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(doctor.names(), vec!["state", "schema"]);
+        assert_eq!(diagnosis.status(), Status::Warn);
+        assert_eq!(diagnosis.exit_code(false), 0);
+        assert_eq!(diagnosis.exit_code(true), FAILURE_EXIT_CODE);
+
+        let rendered = diagnosis.to_string();
+        assert!(rendered.contains(&format!("state: {} is writable\n", dir.display())));
+        assert!(rendered.contains("schema: schema version 2 (expected 3)\n"));
+        assert!(rendered.contains(" run `mytool migrate`\n"));
+
+        let diagnosis = Doctor::new()
+            .with_check(checks::file_readable("config", &dir.join("missing.toml")))
+            .run();
+        assert_eq!(diagnosis.status(), Status::Fail);
+        assert_eq!(diagnosis.exit_code(false), FAILURE_EXIT_CODE);
+    }
+}
@@ -0,0 +1,248 @@
+//! Loading environment variables from `.env` files.
+//!
+//! [`load_dotenv`] reads `.env` from the working directory and then from the application configuration directory.
+//! Variables that are already set are never overridden, so the precedence is: the process environment, then the
+//! working directory file, then the configuration directory file.
+//!
+//! The [`Dotenv`] middleware loads the files at the start of [`App::run`](crate::app::App::run), before the arguments
+//! are parsed, so that they can provide values for options with environment variable fallbacks.
+//!
+//! Files contain `KEY=value` lines (optionally prefixed with `export`), with `#` comments. Values may be single-quoted
+//! (taken literally) or double-quoted (with `\n`, `\t`, `\"`, and `\\` escapes).
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::app::Middleware;
+use crate::{app_dirs::AppDirs, Error};
+
+const DOTENV_FILE_NAME: &str = ".env";
+
+/// Middleware that calls [`load_dotenv`] before the arguments are parsed.
+#[derive(Debug, Clone)]
+pub struct Dotenv {
+    project_dir: PathBuf,
+    config_dir: PathBuf,
+}
+
+impl Dotenv {
+    pub fn new(dirs: AppDirs) -> Self {
+        Self {
+            project_dir: PathBuf::new(),
+            config_dir: dirs.config_dir().to_path_buf(),
+        }
+    }
+
+    /// Read the project file from this directory instead of the working directory.
+    pub fn with_project_dir<P: AsRef<Path>>(self, project_dir: P) -> Self {
+        Self {
+            project_dir: project_dir.as_ref().to_path_buf(),
+            ..self
+        }
+    }
+
+    /// Read the configuration file from this directory instead of the application configuration directory.
+    pub fn with_config_dir<P: AsRef<Path>>(self, config_dir: P) -> Self {
+        Self {
+            config_dir: config_dir.as_ref().to_path_buf(),
+            ..self
+        }
+    }
+}
+
+impl<T> Middleware<T> for Dotenv {
+    fn pre_parse(&mut self, _args: &mut Vec<OsString>) -> Result<(), Error> {
+        load_dotenv_in(&self.project_dir, &self.config_dir).map(|_| ())
+    }
+}
+
+/// Load `.env` files from the working directory and configuration directory, returning the paths that were loaded.
+pub fn load_dotenv(dirs: &AppDirs) -> Result<Vec<PathBuf>, Error> {
+    load_dotenv_in(Path::new(""), dirs.config_dir())
+}
+
+/// Load `.env` files from the given project and configuration directories (in that order of precedence).
+pub fn load_dotenv_in<P: AsRef<Path>, C: AsRef<Path>>(
+    project_dir: P,
+    config_dir: C,
+) -> Result<Vec<PathBuf>, Error> {
+    let mut loaded = vec![];
+
+    for path in [
+        project_dir.as_ref().join(DOTENV_FILE_NAME),
+        config_dir.as_ref().join(DOTENV_FILE_NAME),
+    ] {
+        if path.is_file() {
+            let count = load_file(&path)?;
+            log::debug!(
+                "Loaded {count} environment variables from {}",
+                path.display()
+            );
+            loaded.push(path);
+        }
+    }
+
+    Ok(loaded)
+}
+
+/// Set the variables in a file that are not already set, returning the number that were set.
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<usize, Error> {
+    let path = path.as_ref();
+    let variables =
+        parse(&std::fs::read_to_string(path)?).map_err(|line| Error::InvalidDotenv {
+            path: path.to_path_buf(),
+            line,
+        })?;
+    let mut count = 0;
+
+    for (key, value) in variables {
+        if std::env::var_os(&key).is_none() {
+            std::env::set_var(key, value);
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Parse the contents of a file, returning the (one-based) number of the first invalid line on failure.
+pub fn parse(contents: &str) -> Result<Vec<(String, String)>, usize> {
+    let mut variables = vec![];
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with(char::is_whitespace))
+            .unwrap_or(line);
+
+        let (key, value) = line.split_once('=').ok_or(index + 1)?;
+        let key = key.trim();
+
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        {
+            return Err(index + 1);
+        }
+
+        variables.push((key.to_string(), parse_value(value.trim()).ok_or(index + 1)?));
+    }
+
+    Ok(variables)
+}
+
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(rest) = value.strip_prefix('\'') {
+        let end = rest.find('\'')?;
+
+        Some(rest[..end].to_string())
+    } else if let Some(rest) = value.strip_prefix('"') {
+        let mut parsed = String::new();
+        let mut chars = rest.chars();
+
+        loop {
+            match chars.next()? {
+                '"' => return Some(parsed),
+                '\\' => match chars.next()? {
+                    'n' => parsed.push('\n'),
+                    't' => parsed.push('\t'),
+                    'r' => parsed.push('\r'),
+                    other => parsed.push(other),
+                },
+                other => parsed.push(other),
+            }
+        }
+    } else {
+        // Unquoted values end at an inline comment.
+        let end = value.find(" #").unwrap_or(value.len());
+
+        Some(value[..end].trim_end().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let contents = "# API settings\nAPI_TOKEN=abc123 # comment\nexport BASE_URL=\"https://example.com\\n\"\n\nLITERAL='a \\n b'\nEMPTY=\n";
+
+        assert_eq!(
+            parse(contents).unwrap(),
+            vec![
+                ("API_TOKEN".to_string(), "abc123".to_string()),
+                ("BASE_URL".to_string(), "https://example.com\n".to_string()),
+                ("LITERAL".to_string(), "a \\n b".to_string()),
+                ("EMPTY".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(parse("A=1\nnot a variable\n"), Err(2));
+        assert_eq!(parse("A=\"unterminated\n"), Err(1));
+    }
+
+    #[test]
+    fn test_dotenv_middleware() {
+        use crate::app::App;
+        use crate::temp::test_dir;
+        use clap::Parser;
+
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(long, env = "CLI_HELPERS_DOTENV_TEST_PROCESS")]
+            process: String,
+            #[clap(long, env = "CLI_HELPERS_DOTENV_TEST_PROJECT")]
+            project: String,
+            #[clap(long, env = "CLI_HELPERS_DOTENV_TEST_CONFIG")]
+            config: String,
+        }
+
+        let temp_dir = test_dir();
+        let project_dir = temp_dir.file("project");
+        let config_dir = temp_dir.file("config");
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::create_dir_all(&config_dir).unwrap();
+        std::fs::write(
+            project_dir.join(DOTENV_FILE_NAME),
+            "CLI_HELPERS_DOTENV_TEST_PROCESS=project\nCLI_HELPERS_DOTENV_TEST_PROJECT=project\n",
+        )
+        .unwrap();
+        std::fs::write(
+            config_dir.join(DOTENV_FILE_NAME),
+            "CLI_HELPERS_DOTENV_TEST_PROCESS=config\nCLI_HELPERS_DOTENV_TEST_PROJECT=config\n\
+            CLI_HELPERS_DOTENV_TEST_CONFIG=config\n",
+        )
+        .unwrap();
+
+        std::env::set_var("CLI_HELPERS_DOTENV_TEST_PROCESS", "process");
+
+        let mut values = None;
+        let exit_code = App::<Opts>::new()
+            .with_middleware(
+                Dotenv::new(AppDirs::new("cli-helpers-dotenv-test").unwrap())
+                    .with_project_dir(&project_dir)
+                    .with_config_dir(&config_dir),
+            )
+            .run_from(["test"], |opts| {
+                values = Some((opts.process, opts.project, opts.config));
+                Ok::<_, Error>(())
+            });
+
+        assert_eq!(exit_code, 0);
+        assert_eq!(
+            values,
+            Some((
+                "process".to_string(),
+                "project".to_string(),
+                "config".to_string()
+            ))
+        );
+    }
+}
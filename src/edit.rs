@@ -0,0 +1,188 @@
+//! Safe in-place file editing.
+//!
+//! [`edit_file_in_place`] streams a file through a transformation into a temporary file in the same directory, and
+//! then renames the result over the original, so that the file is never left half-written if the transformation fails
+//! or the process is interrupted:
+//!
+//! ```rust,no_run
+//! use cli_helpers::edit::edit_file_in_place;
+//! use std::io::{BufRead, Write};
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! edit_file_in_place("notes.txt", |reader, writer| {
+//!     for line in reader.lines() {
+//!         writeln!(writer, "{}", line?.trim_end())?;
+//!     }
+//!
+//!     Ok::<_, cli_helpers::Error>(())
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The new file always takes the original's permissions. [`InPlaceEdit`] can also keep the original's modification
+//! time, and make [backups](crate::backup) of previous versions.
+
+use std::fs::{File, FileTimes};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::{backup, Error};
+
+/// Transform the file's contents in place.
+pub fn edit_file_in_place<P, F, E>(path: P, f: F) -> Result<(), E>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut BufReader<File>, &mut BufWriter<File>) -> Result<(), E>,
+    E: From<Error>,
+{
+    InPlaceEdit::new(path).run(f).map(|_| ())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InPlaceEdit {
+    path: PathBuf,
+    keep_backups: usize,
+    preserve_mtime: bool,
+}
+
+impl InPlaceEdit {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            keep_backups: 0,
+            preserve_mtime: false,
+        }
+    }
+
+    /// Keep up to this many backups of previous versions (none by default).
+    pub fn with_backups(self, keep_backups: usize) -> Self {
+        Self {
+            keep_backups,
+            ..self
+        }
+    }
+
+    /// Give the new file the original's modification time.
+    pub fn with_preserve_mtime(self, preserve_mtime: bool) -> Self {
+        Self {
+            preserve_mtime,
+            ..self
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Run the transformation, returning the path of the backup, if one was made.
+    pub fn run<F, E>(&self, f: F) -> Result<Option<PathBuf>, E>
+    where
+        F: FnOnce(&mut BufReader<File>, &mut BufWriter<File>) -> Result<(), E>,
+        E: From<Error>,
+    {
+        let io = |error: std::io::Error| E::from(Error::from(error));
+
+        let original = File::open(&self.path).map_err(io)?;
+        let metadata = original.metadata().map_err(io)?;
+        let temp_path = backup::temp_sibling(&self.path)?;
+
+        let result = File::create(&temp_path).map_err(io).and_then(|temp| {
+            temp.set_permissions(metadata.permissions()).map_err(io)?;
+
+            let mut reader = BufReader::new(original);
+            let mut writer = BufWriter::new(temp);
+            f(&mut reader, &mut writer)?;
+
+            let temp = writer
+                .into_inner()
+                .map_err(|error| io(error.into_error()))?;
+
+            if self.preserve_mtime {
+                let modified = metadata.modified().map_err(io)?;
+                temp.set_times(FileTimes::new().set_modified(modified))
+                    .map_err(io)?;
+            }
+
+            temp.sync_all().map_err(io)?;
+            drop(temp);
+
+            let backup_path = backup::backup(&self.path, self.keep_backups)?;
+            std::fs::rename(&temp_path, &self.path).map_err(io)?;
+
+            Ok(backup_path)
+        });
+
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp_path);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use std::io::{BufRead, Write};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_edit_file_in_place() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("notes.txt");
+        std::fs::write(&path, "one  \ntwo\t\n").unwrap();
+
+        let old = SystemTime::now() - Duration::from_secs(3600);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(old))
+            .unwrap();
+
+        edit_file_in_place(&path, |reader, writer| {
+            for line in reader.lines() {
+                writeln!(writer, "{}", line?.trim_end())?;
+            }
+
+            Ok::<_, Error>(())
+        })
+        .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+        assert!(std::fs::metadata(&path).unwrap().modified().unwrap() > old);
+
+        let backup_path = InPlaceEdit::new(&path)
+            .with_backups(1)
+            .with_preserve_mtime(true)
+            .run(|reader, writer| {
+                std::io::copy(reader, writer)?;
+                writer.write_all(b"three\n")?;
+
+                Ok::<_, Error>(())
+            })
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "one\ntwo\n");
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().modified().unwrap(),
+            std::fs::metadata(&backup_path).unwrap().modified().unwrap()
+        );
+
+        // A failed transformation leaves the original untouched.
+        let result = edit_file_in_place(&path, |_, writer| {
+            writer.write_all(b"partial")?;
+
+            Err(Error::from(std::io::Error::other("failed")))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(std::fs::read_dir(dir).unwrap().count(), 2);
+    }
+}
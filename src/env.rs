@@ -0,0 +1,282 @@
+//! Environment variable fallbacks for command-line options.
+//!
+//! [`EnvMapping`] gives every option of a command (including those of its subcommands) an environment variable
+//! fallback named from a prefix and the option's long name, so that `--rate-limit` can also be set with
+//! `MYTOOL_RATE_LIMIT`. Options that already have an environment variable are left unchanged, and the values of
+//! options whose names suggest secrets (such as `--token`) are not shown in help output. It also adds a `--print-env`
+//! flag that lists the variable names (even if required arguments are missing).
+//!
+//! ```rust,no_run
+//! use cli_helpers::env::EnvMapping;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(long)]
+//!     rate_limit: Option<u32>,
+//! }
+//!
+//! let opts: Opts = EnvMapping::new("mytool").parse();
+//! ```
+
+use std::ffi::OsString;
+
+use clap::{Arg, ArgAction, Command, Parser};
+
+use crate::redact::looks_secret;
+
+const PRINT_ENV_ID: &str = "print-env";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvMapping {
+    prefix: String,
+    excluded: Vec<String>,
+}
+
+impl EnvMapping {
+    /// Use the given prefix (typically the application name), which is uppercased.
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: to_env_name(prefix),
+            excluded: vec![],
+        }
+    }
+
+    /// Do not add a fallback for the argument with the given ID.
+    pub fn with_excluded(mut self, id: &str) -> Self {
+        self.excluded.push(id.to_string());
+        self
+    }
+
+    /// The environment variable name for an option name.
+    pub fn name(&self, option: &str) -> String {
+        format!("{}_{}", self.prefix, to_env_name(option))
+    }
+
+    /// Add environment variable fallbacks and the `--print-env` flag to a command.
+    pub fn apply(&self, command: Command) -> Command {
+        self.apply_args(command).arg(
+            Arg::new(PRINT_ENV_ID)
+                .long(PRINT_ENV_ID)
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Print the environment variables that can be used instead of options"),
+        )
+    }
+
+    /// The environment variables for a command with fallbacks applied, as pairs of names and options.
+    pub fn variables(&self, command: &Command) -> Vec<(String, String)> {
+        let mut variables = vec![];
+        collect_variables(command, &mut variables);
+        variables.sort();
+        variables.dedup();
+        variables
+    }
+
+    /// Parse the process's arguments, exiting on error (or after printing the variables for `--print-env`).
+    pub fn parse<T: Parser>(&self) -> T {
+        self.parse_from(std::env::args_os())
+    }
+
+    pub fn parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString> + Clone>(
+        &self,
+        args: I,
+    ) -> T {
+        let mut command = self.apply(T::command());
+        let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+
+        // This is checked before parsing, so that validation errors do not prevent listing the variables.
+        if has_print_env_flag(&args) {
+            for (name, option) in self.variables(&command) {
+                println!("{name}\t--{option}");
+            }
+
+            std::process::exit(0);
+        }
+
+        let matches = command.clone().get_matches_from(args);
+
+        T::from_arg_matches(&matches).unwrap_or_else(|error| error.format(&mut command).exit())
+    }
+
+    pub fn try_parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString> + Clone>(
+        &self,
+        args: I,
+    ) -> Result<T, clap::Error> {
+        let mut command = self.apply(T::command());
+        let matches = command.try_get_matches_from_mut(args)?;
+
+        T::from_arg_matches(&matches).map_err(|error| error.format(&mut command))
+    }
+
+    fn apply_args(&self, command: Command) -> Command {
+        let ids = command
+            .get_arguments()
+            .filter(|arg| self.should_map(arg))
+            .map(|arg| arg.get_id().to_string())
+            .collect::<Vec<_>>();
+
+        let command = ids.into_iter().fold(command, |command, id| {
+            command.mut_arg(id, |arg| {
+                let option = arg.get_long().unwrap_or(arg.get_id().as_str());
+                let name = self.name(option);
+                let is_secret = looks_secret(option);
+
+                arg.env(name).hide_env_values(is_secret)
+            })
+        });
+
+        let subcommands = command
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_string())
+            .collect::<Vec<_>>();
+
+        subcommands.into_iter().fold(command, |command, name| {
+            command.mut_subcommand(name, |subcommand| self.apply_args(subcommand))
+        })
+    }
+
+    fn should_map(&self, arg: &Arg) -> bool {
+        !arg.is_positional()
+            && arg.get_env().is_none()
+            && !self.excluded.iter().any(|id| id == arg.get_id().as_str())
+            && !matches!(
+                arg.get_action(),
+                ArgAction::Help
+                    | ArgAction::HelpShort
+                    | ArgAction::HelpLong
+                    | ArgAction::Version
+                    | ArgAction::Count
+            )
+    }
+}
+
+/// Whether the arguments include `--print-env` (before any `--`).
+fn has_print_env_flag(args: &[OsString]) -> bool {
+    let flag = format!("--{PRINT_ENV_ID}");
+
+    args.iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| *arg == *flag)
+}
+
+fn collect_variables(command: &Command, variables: &mut Vec<(String, String)>) {
+    for arg in command.get_arguments() {
+        if let Some(name) = arg.get_env() {
+            let option = arg.get_long().unwrap_or(arg.get_id().as_str());
+            variables.push((name.to_string_lossy().into_owned(), option.to_string()));
+        }
+    }
+
+    for subcommand in command.get_subcommands() {
+        collect_variables(subcommand, variables);
+    }
+}
+
+fn to_env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(long)]
+        rate_limit: Option<u32>,
+        #[clap(long, env = "CUSTOM_TOKEN_VAR")]
+        token: Option<String>,
+        #[clap(short, action = ArgAction::Count)]
+        verbose: u8,
+        #[clap(subcommand)]
+        command: Option<Subcommand>,
+    }
+
+    #[derive(Debug, clap::Subcommand)]
+    enum Subcommand {
+        Import {
+            #[clap(long)]
+            dry_run: bool,
+        },
+    }
+
+    #[test]
+    fn test_env_mapping() {
+        let mapping = EnvMapping::new("cli-helpers-env-test");
+
+        assert_eq!(
+            mapping.variables(&mapping.apply(Opts::command())),
+            vec![
+                (
+                    "CLI_HELPERS_ENV_TEST_DRY_RUN".to_string(),
+                    "dry-run".to_string()
+                ),
+                (
+                    "CLI_HELPERS_ENV_TEST_RATE_LIMIT".to_string(),
+                    "rate-limit".to_string()
+                ),
+                ("CUSTOM_TOKEN_VAR".to_string(), "token".to_string()),
+            ]
+        );
+
+        std::env::set_var("CLI_HELPERS_ENV_TEST_RATE_LIMIT", "17");
+        std::env::set_var("CLI_HELPERS_ENV_TEST_DRY_RUN", "true");
+
+        let opts = mapping
+            .try_parse_from::<Opts, _, _>(["test", "import"])
+            .unwrap();
+
+        assert_eq!(opts.rate_limit, Some(17));
+        assert!(matches!(
+            opts.command,
+            Some(Subcommand::Import { dry_run: true })
+        ));
+
+        let opts = mapping
+            .try_parse_from::<Opts, _, _>(["test", "--rate-limit", "3"])
+            .unwrap();
+
+        assert_eq!(opts.rate_limit, Some(3));
+    }
+
+    #[test]
+    fn test_env_mapping_secrets() {
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(long)]
+            api_token: String,
+            #[clap(long)]
+            rate_limit: Option<u32>,
+        }
+
+        let command = EnvMapping::new("cli-helpers-env-secret-test").apply(Opts::command());
+        let hidden = |id: &str| {
+            command
+                .get_arguments()
+                .find(|arg| arg.get_id() == id)
+                .unwrap()
+                .is_hide_env_values_set()
+        };
+
+        assert!(hidden("api_token"));
+        assert!(!hidden("rate_limit"));
+
+        // The required `--api-token` is missing, but `--print-env` is still recognized.
+        let args = ["test", "--print-env"].map(OsString::from);
+        assert!(has_print_env_flag(&args));
+        assert!(!has_print_env_flag(
+            &["test", "--", "--print-env"].map(OsString::from)
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+//! A small filter expression language for JSON records.
+//!
+//! Expressions compare record fields with literals, and can be combined with `&&`, `||`, `!`, and parentheses:
+//!
+//! ```text
+//! created_at > 2023-01-01 && (lang == "en" || lang == "de") && !user.verified == true
+//! ```
+//!
+//! Fields are specified with [`JsonPath`] syntax (for example `entities.urls[0].expanded_url`, or `headers["x y"]` for
+//! keys containing spaces or other special characters).
+//!
+//! Literals are double-quoted strings, finite numbers, `true`, `false`, `null`, or unquoted timestamps in any non-numeric
+//! format accepted by [`Timestamp`] that does not contain spaces. Timestamp comparisons parse string or numeric field
+//! values as timestamps. Missing fields are treated as `null`, and ordering comparisons between incompatible values
+//! are false.
+
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::{json_path::JsonPath, Error, Timestamp};
+
+/// Standard filter argument.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq)]
+pub struct FilterArgs {
+    /// Only include records matching this expression (may be repeated)
+    #[clap(long, global = true)]
+    filter: Vec<Filter>,
+}
+
+impl FilterArgs {
+    pub fn new(filter: Vec<Filter>) -> Self {
+        Self { filter }
+    }
+
+    /// Whether the record matches all provided filters.
+    pub fn matches(&self, record: &Value) -> bool {
+        self.filter.iter().all(|filter| filter.matches(record))
+    }
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn matches(&self, record: &Value) -> bool {
+        self.expr.eval(record)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+
+        let expr = parser.parse_or()?;
+
+        match parser.peek() {
+            None => Ok(Self { expr }),
+            Some(token) => Err(Error::InvalidFilter(format!(
+                "unexpected {token:?} at position {}",
+                token.offset
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        path: JsonPath,
+        op: Op,
+        literal: Literal,
+    },
+}
+
+impl Expr {
+    pub fn eval(&self, record: &Value) -> bool {
+        match self {
+            Self::And(left, right) => left.eval(record) && right.eval(record),
+            Self::Or(left, right) => left.eval(record) || right.eval(record),
+            Self::Not(expr) => !expr.eval(record),
+            Self::Compare { path, op, literal } => {
+                let value = path.extract(record).unwrap_or(&Value::Null);
+
+                match op {
+                    Op::Eq => literal.equals(value),
+                    Op::Ne => !literal.equals(value),
+                    _ => literal
+                        .compare(value)
+                        .is_some_and(|ordering| op.accepts(ordering)),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    /// Whether the ordering of the field value relative to the literal satisfies this operator.
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering == Ordering::Equal,
+            Self::Ne => ordering != Ordering::Equal,
+            Self::Lt => ordering == Ordering::Less,
+            Self::Le => ordering != Ordering::Greater,
+            Self::Gt => ordering == Ordering::Greater,
+            Self::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Timestamp(Timestamp),
+}
+
+impl Literal {
+    fn equals(&self, value: &Value) -> bool {
+        match self {
+            Self::Null => value.is_null(),
+            Self::Bool(expected) => value.as_bool() == Some(*expected),
+            _ => self.compare(value) == Some(Ordering::Equal),
+        }
+    }
+
+    /// Compare the field value to this literal.
+    fn compare(&self, value: &Value) -> Option<Ordering> {
+        match self {
+            Self::Number(expected) => value.as_f64()?.partial_cmp(expected),
+            Self::String(expected) => Some(value.as_str()?.cmp(expected.as_str())),
+            Self::Timestamp(expected) => Some(value_to_timestamp(value)?.cmp(expected)),
+            Self::Null | Self::Bool(_) => None,
+        }
+    }
+}
+
+fn value_to_timestamp(value: &Value) -> Option<Timestamp> {
+    match value {
+        Value::String(value) => value.parse().ok(),
+        Value::Number(value) => value.as_i64()?.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Op(Op),
+    Quoted(String),
+    Word(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    offset: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let mut next_is = |expected: char| chars.next_if(|(_, c)| *c == expected).is_some();
+
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            '(' => TokenKind::Open,
+            ')' => TokenKind::Close,
+            '&' if next_is('&') => TokenKind::And,
+            '|' if next_is('|') => TokenKind::Or,
+            '=' if next_is('=') => TokenKind::Op(Op::Eq),
+            '!' if next_is('=') => TokenKind::Op(Op::Ne),
+            '!' => TokenKind::Not,
+            '<' if next_is('=') => TokenKind::Op(Op::Le),
+            '<' => TokenKind::Op(Op::Lt),
+            '>' if next_is('=') => TokenKind::Op(Op::Ge),
+            '>' => TokenKind::Op(Op::Gt),
+            '"' => {
+                let mut value = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) if chars.peek().is_some() => {
+                            value.extend(chars.next().map(|(_, c)| c));
+                        }
+                        Some((_, c)) if c != '\\' => value.push(c),
+                        _ => {
+                            return Err(Error::InvalidFilter(format!(
+                                "unterminated string at position {offset}"
+                            )))
+                        }
+                    }
+                }
+
+                TokenKind::Quoted(value)
+            }
+            c if is_word_char(c) => {
+                let mut value = c.to_string();
+
+                loop {
+                    if let Some((segment_offset, quote)) =
+                        chars.next_if(|(_, c)| *c == '"' && value.ends_with('['))
+                    {
+                        // Quoted path segments may contain any characters, and are parsed with the path.
+                        value.push(quote);
+
+                        loop {
+                            match chars.next() {
+                                Some((_, '\\')) if chars.peek().is_some() => {
+                                    value.push('\\');
+                                    value.extend(chars.next().map(|(_, c)| c));
+                                }
+                                Some((_, '"')) => {
+                                    value.push('"');
+
+                                    if chars.next_if(|(_, c)| *c == ']').is_some() {
+                                        value.push(']');
+                                        break;
+                                    }
+                                }
+                                Some((_, c)) => value.push(c),
+                                None => {
+                                    return Err(Error::InvalidFilter(format!(
+                                        "unterminated quoted field key at position {segment_offset}"
+                                    )))
+                                }
+                            }
+                        }
+                    } else if let Some((_, c)) = chars.next_if(|(_, c)| is_word_char(*c)) {
+                        value.push(c);
+                    } else {
+                        break;
+                    }
+                }
+
+                TokenKind::Word(value)
+            }
+            c => {
+                return Err(Error::InvalidFilter(format!(
+                    "unexpected character {c:?} at position {offset}"
+                )))
+            }
+        };
+
+        tokens.push(Token { kind, offset });
+    }
+
+    Ok(tokens)
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()!<>=&|\"".contains(c)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next_if(&mut self, kind: &TokenKind) -> bool {
+        let matches = self.peek().is_some_and(|token| token.kind == *kind);
+
+        if matches {
+            self.position += 1;
+        }
+
+        matches
+    }
+
+    fn next(&mut self, expected: &str) -> Result<&Token, Error> {
+        let token = self.tokens.get(self.position).ok_or_else(|| {
+            Error::InvalidFilter(format!("expected {expected} at end of expression"))
+        })?;
+        self.position += 1;
+
+        Ok(token)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_and()?;
+
+        while self.next_if(&TokenKind::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.parse_unary()?;
+
+        while self.next_if(&TokenKind::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, Error> {
+        if self.next_if(&TokenKind::Not) {
+            Ok(Expr::Not(Box::new(self.parse_unary()?)))
+        } else if self.next_if(&TokenKind::Open) {
+            let expr = self.parse_or()?;
+            let token = self.next("\")\"")?;
+
+            if token.kind == TokenKind::Close {
+                Ok(expr)
+            } else {
+                Err(unexpected(token, "\")\""))
+            }
+        } else {
+            self.parse_comparison()
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, Error> {
+        let token = self.next("field")?;
+        let path = match &token.kind {
+            TokenKind::Word(path) => path.parse().map_err(|_| {
+                Error::InvalidFilter(format!(
+                    "invalid field path {path:?} at position {}",
+                    token.offset
+                ))
+            })?,
+            _ => return Err(unexpected(token, "field")),
+        };
+
+        let token = self.next("operator")?;
+        let op = match token.kind {
+            TokenKind::Op(op) => op,
+            _ => return Err(unexpected(token, "operator")),
+        };
+
+        let token = self.next("literal")?;
+        let literal = match &token.kind {
+            TokenKind::Quoted(value) => Literal::String(value.clone()),
+            TokenKind::Word(value) => parse_literal(value).ok_or_else(|| {
+                Error::InvalidFilter(format!(
+                    "invalid literal {value:?} at position {} (strings must be quoted)",
+                    token.offset
+                ))
+            })?,
+            _ => return Err(unexpected(token, "literal")),
+        };
+
+        Ok(Expr::Compare { path, op, literal })
+    }
+}
+
+fn unexpected(token: &Token, expected: &str) -> Error {
+    Error::InvalidFilter(format!(
+        "expected {expected} at position {}, found {:?}",
+        token.offset, token.kind
+    ))
+}
+
+fn parse_literal(value: &str) -> Option<Literal> {
+    match value {
+        "null" => Some(Literal::Null),
+        "true" => Some(Literal::Bool(true)),
+        "false" => Some(Literal::Bool(false)),
+        _ => value
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .map(Literal::Number)
+            .or_else(|| value.parse().ok().map(Literal::Timestamp)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_filter() {
+        let records = [
+            json!({"id": 1, "lang": "en", "created_at": "2022-12-31T23:00:00Z", "user": {"followers": 10, "x.y": ["z"]}, "tags": ["a"]}),
+            json!({"id": 2, "lang": "en", "created_at": "2023-01-01T09:00:00+02:00", "user": {"followers": 200, "display name": "a (b)"}}),
+            json!({"id": 3, "lang": "de", "created_at": "Fri Aug 25 08:47:09 AM CEST 2023"}),
+            json!({"id": 4, "created_at": 1692946034}),
+        ];
+
+        let matching_ids = |expression: &str| {
+            let filter = expression.parse::<Filter>().unwrap();
+
+            records
+                .iter()
+                .filter(|record| filter.matches(record))
+                .map(|record| record["id"].as_i64().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matching_ids("created_at > 2023-01-01 && lang == \"en\""),
+            vec![2]
+        );
+        assert_eq!(matching_ids("created_at >= 2023-08-25"), vec![3, 4]);
+        assert_eq!(matching_ids("tags[0] == \"a\""), vec![1]);
+        assert_eq!(matching_ids("lang != \"en\""), vec![3, 4]);
+        assert_eq!(
+            matching_ids("lang == null || user.followers < 100"),
+            vec![1, 4]
+        );
+        assert_eq!(
+            matching_ids("!(lang == \"en\" || lang == \"de\") || id == 1"),
+            vec![1, 4]
+        );
+        assert_eq!(matching_ids("user[\"display name\"] == \"a (b)\""), vec![2]);
+        assert_eq!(matching_ids("user[\"x.y\"][0] == \"z\""), vec![1]);
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        for expression in [
+            "lang == en",
+            "lang == \"en",
+            "lang == \"en\\",
+            "(lang == \"en\"",
+            "lang \"en\"",
+            "lang == \"en\" &&",
+            "lang = \"en\"",
+            "tags[x] == 1",
+            "user[\"display name == 1",
+            "id == nan",
+            "id == inf",
+            "id == -infinity",
+        ] {
+            assert!(
+                matches!(expression.parse::<Filter>(), Err(Error::InvalidFilter(_))),
+                "{expression}"
+            );
+        }
+    }
+}
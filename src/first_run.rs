@@ -0,0 +1,126 @@
+//! First-run detection and setup.
+//!
+//! Whether an application has run before is recorded by a marker file in the state directory. [`FirstRun::run`]
+//! invokes the application's setup (creating a configuration file, or printing a welcome or consent message) only
+//! if the marker does not exist, and writes the marker when setup succeeds, so that failed setup is retried:
+//!
+//! ```rust,no_run
+//! use cli_helpers::{app_dirs::AppDirs, first_run::FirstRun};
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let dirs = AppDirs::new("mytool")?;
+//!
+//! FirstRun::new(&dirs).run(|| {
+//!     eprintln!("Welcome! Run `mytool config init` to create a configuration file.");
+//!     Ok::<_, cli_helpers::Error>(())
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::{app_dirs::AppDirs, Error, Timestamp, TimestampFormat};
+
+pub(crate) const MARKER_FILE_NAME: &str = "first-run";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FirstRun {
+    marker: PathBuf,
+}
+
+impl FirstRun {
+    /// Use the marker in the application state directory.
+    pub fn new(dirs: &AppDirs) -> Self {
+        Self::with_marker(dirs.state_dir().join(MARKER_FILE_NAME))
+    }
+
+    pub fn with_marker<P: AsRef<Path>>(marker: P) -> Self {
+        Self {
+            marker: marker.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn marker(&self) -> &Path {
+        &self.marker
+    }
+
+    pub fn is_first_run(&self) -> bool {
+        !self.marker.exists()
+    }
+
+    /// When the first run completed, if it has.
+    pub fn completed(&self) -> Result<Option<Timestamp>, Error> {
+        match std::fs::read_to_string(&self.marker) {
+            Ok(contents) => Ok(contents.trim().parse().ok()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Run the setup if this is the first run, returning whether it was run.
+    ///
+    /// The marker is only written if the setup succeeds.
+    pub fn run<E: From<Error>, F: FnOnce() -> Result<(), E>>(&self, setup: F) -> Result<bool, E> {
+        if self.is_first_run() {
+            setup()?;
+            self.mark_complete()?;
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Record that the first run has completed.
+    pub fn mark_complete(&self) -> Result<(), Error> {
+        if let Some(parent) = self.marker.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(
+            &self.marker,
+            format!(
+                "{}\n",
+                Timestamp::now().format_as(TimestampFormat::Rfc3339 { use_z: true })
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    /// Remove the marker, so that setup is run again.
+    pub fn reset(&self) -> Result<(), Error> {
+        match std::fs::remove_file(&self.marker) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_first_run() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let first_run = FirstRun::with_marker(dir.join(MARKER_FILE_NAME));
+
+        assert!(first_run.is_first_run());
+        assert!(first_run
+            .run(|| Err(Error::InvalidPeriod("setup".to_string())))
+            .is_err());
+        assert!(first_run.is_first_run());
+
+        assert!(first_run.run(|| Ok::<_, Error>(())).unwrap());
+        assert!(!first_run.is_first_run());
+        assert!(first_run.completed().unwrap().is_some());
+        assert!(!first_run.run(|| Ok::<_, Error>(())).unwrap());
+
+        first_run.reset().unwrap();
+        assert!(first_run.is_first_run());
+    }
+}
@@ -0,0 +1,279 @@
+//! Following growing files (`--follow`).
+//!
+//! [`follow_lines`] reads the lines of a file like `tail -f`: when it reaches the end of the file, it waits for more
+//! lines to be written instead of stopping. If the file is truncated, reading starts again from the beginning, and if
+//! it is replaced (for example by log rotation), the new file is opened. Incomplete lines are held back until they
+//! are finished (or the file is replaced). The iterator ends when the [`Shutdown`] token is triggered (by default, by
+//! Ctrl-C once the handlers are installed).
+//!
+//! [`FollowArgs`] adds a `--follow` flag that selects between this and reading the file once:
+//!
+//! ```rust,no_run
+//! use cli_helpers::follow::FollowArgs;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     follow: FollowArgs,
+//!     input: std::path::PathBuf,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//!
+//! for line in opts.follow.lines(&opts.input)? {
+//!     println!("{}", line?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::shutdown::Shutdown;
+use crate::Error;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Standard follow mode flag.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FollowArgs {
+    /// Keep reading as the input file grows
+    #[clap(long)]
+    follow: bool,
+}
+
+impl FollowArgs {
+    pub fn new(follow: bool) -> Self {
+        Self { follow }
+    }
+
+    pub fn is_follow(&self) -> bool {
+        self.follow
+    }
+
+    /// The lines of the file, either read once or followed (until interrupted).
+    pub fn lines<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Box<dyn Iterator<Item = Result<String, Error>>>, Error> {
+        if self.follow {
+            Ok(Box::new(follow_lines(path)?))
+        } else {
+            let reader = BufReader::new(File::open(path)?);
+
+            Ok(Box::new(
+                reader.lines().map(|line| line.map_err(Error::from)),
+            ))
+        }
+    }
+}
+
+/// Follow the lines of the file, starting at the beginning.
+pub fn follow_lines<P: AsRef<Path>>(path: P) -> Result<FollowLines, Error> {
+    FollowLines::open(path.as_ref())
+}
+
+/// An endless iterator over the lines of a file (see [`follow_lines`]).
+#[derive(Debug)]
+pub struct FollowLines {
+    path: PathBuf,
+    reader: BufReader<File>,
+    id: Option<u64>,
+    position: u64,
+    partial: Vec<u8>,
+    poll_interval: Duration,
+    shutdown: Shutdown,
+}
+
+impl FollowLines {
+    fn open(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        let id = file_id(&file.metadata()?);
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: BufReader::new(file),
+            id,
+            position: 0,
+            partial: Vec::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            shutdown: Shutdown::signals(),
+        })
+    }
+
+    /// How long to wait before checking the file again at the end.
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Stop when this token is triggered (instead of on `SIGTERM` or `SIGINT`).
+    pub fn with_shutdown(self, shutdown: Shutdown) -> Self {
+        Self { shutdown, ..self }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Take the buffered line, without its line ending.
+    fn take_line(&mut self) -> Result<String, Error> {
+        let mut line = std::mem::take(&mut self.partial);
+
+        if line.last() == Some(&b'\n') {
+            line.pop();
+
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        }
+
+        String::from_utf8(line)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error).into())
+    }
+
+    /// Check for truncation or replacement at the end of the file.
+    fn check_file(&mut self) -> Result<Change, Error> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // The file has been moved away and not yet replaced.
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Change::None),
+            Err(error) => return Err(error.into()),
+        };
+
+        if file_id(&metadata) != self.id {
+            let file = File::open(&self.path)?;
+
+            self.id = file_id(&file.metadata()?);
+            self.reader = BufReader::new(file);
+            self.position = 0;
+
+            Ok(Change::Replaced)
+        } else if metadata.len() < self.position {
+            self.reader.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            self.partial.clear();
+
+            Ok(Change::Truncated)
+        } else {
+            Ok(Change::None)
+        }
+    }
+}
+
+impl Iterator for FollowLines {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_until(b'\n', &mut self.partial) {
+                Ok(0) => match self.check_file() {
+                    // The unfinished last line of the old file will never be finished.
+                    Ok(Change::Replaced) if !self.partial.is_empty() => {
+                        return Some(self.take_line())
+                    }
+                    Ok(Change::Replaced | Change::Truncated) => {}
+                    Ok(Change::None) => {
+                        if !self.shutdown.sleep(self.poll_interval) {
+                            return None;
+                        }
+                    }
+                    Err(error) => return Some(Err(error)),
+                },
+                Ok(count) => {
+                    self.position += count as u64;
+
+                    if self.partial.last() == Some(&b'\n') {
+                        return Some(self.take_line());
+                    }
+                }
+                Err(error) => return Some(Err(error.into())),
+            }
+        }
+    }
+}
+
+enum Change {
+    None,
+    Truncated,
+    Replaced,
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some(metadata.ino())
+}
+
+/// Replacement cannot be detected without inode numbers.
+#[cfg(not(unix))]
+fn file_id(_metadata: &std::fs::Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use std::io::Write;
+
+    #[cfg(unix)]
+    fn append(path: &Path, contents: &str) {
+        File::options()
+            .append(true)
+            .open(path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_lines() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("input.log");
+        std::fs::write(&path, "first\r\nsecond\nthi").unwrap();
+
+        let mut lines = follow_lines(&path)
+            .unwrap()
+            .with_poll_interval(Duration::from_millis(1));
+        assert_eq!(lines.next().unwrap().unwrap(), "first");
+        assert_eq!(lines.next().unwrap().unwrap(), "second");
+
+        append(&path, "rd\nfourth\n");
+        assert_eq!(lines.next().unwrap().unwrap(), "third");
+        assert_eq!(lines.next().unwrap().unwrap(), "fourth");
+
+        // Truncation.
+        std::fs::write(&path, "fifth\n").unwrap();
+        assert_eq!(lines.next().unwrap().unwrap(), "fifth");
+
+        // Rotation.
+        append(&path, "sixth");
+        std::fs::rename(&path, dir.join("input.log.1")).unwrap();
+        std::fs::write(&path, "seventh\n").unwrap();
+        assert_eq!(lines.next().unwrap().unwrap(), "sixth");
+        assert_eq!(lines.next().unwrap().unwrap(), "seventh");
+
+        let shutdown = Shutdown::new();
+        let mut lines = lines.with_shutdown(shutdown.clone());
+        shutdown.request();
+        assert!(lines.next().is_none());
+
+        let lines = FollowArgs::new(false)
+            .lines(dir.join("input.log.1"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(lines, vec!["fifth", "sixth"]);
+    }
+}
@@ -0,0 +1,181 @@
+//! An examples section for help output.
+//!
+//! [`Examples`] renders pairs of descriptions and command lines at the end of the command's help, wrapping
+//! descriptions to the terminal width and (optionally) highlighting command lines:
+//!
+//! ```rust,no_run
+//! use clap::CommandFactory;
+//! use cli_helpers::help::examples::Examples;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! #[clap(name = "mytool")]
+//! struct Opts {
+//!     #[clap(long)]
+//!     since: Option<Timestamp>,
+//! }
+//!
+//! let command = Examples::new()
+//!     .with_example("Import everything from the last day", "mytool --since '1 day ago'")
+//!     .with_highlighting(true)
+//!     .apply(Opts::command());
+//! ```
+
+use clap::builder::styling::Styles;
+use clap::Command;
+
+use crate::color::Stream;
+use crate::i18n::{self, ids};
+
+/// The width used when the terminal width is unknown (and the maximum width).
+const DEFAULT_WIDTH: usize = 100;
+
+const INDENT: &str = "  ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Example {
+    pub description: String,
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Examples {
+    examples: Vec<Example>,
+    highlight: bool,
+}
+
+impl Examples {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_example<D: Into<String>, C: Into<String>>(
+        mut self,
+        description: D,
+        command: C,
+    ) -> Self {
+        self.examples.push(Example {
+            description: description.into(),
+            command: command.into(),
+        });
+        self
+    }
+
+    /// Highlight command lines (using clap's styles, so that this respects clap's color choice).
+    pub fn with_highlighting(self, highlight: bool) -> Self {
+        Self { highlight, ..self }
+    }
+
+    pub fn examples(&self) -> &[Example] {
+        &self.examples
+    }
+
+    /// Render the section, wrapping descriptions to the given width.
+    pub fn render(&self, width: usize) -> String {
+        let styles = Styles::default();
+        let (header, literal) = if self.highlight {
+            (*styles.get_header(), *styles.get_literal())
+        } else {
+            Default::default()
+        };
+
+        let mut output = format!(
+            "{header}{}{header:#}\n",
+            i18n::message(ids::HELP_EXAMPLES, &[])
+        );
+
+        for (i, example) in self.examples.iter().enumerate() {
+            if i > 0 {
+                output.push('\n');
+            }
+
+            for line in wrap(&example.description, width.saturating_sub(INDENT.len())) {
+                output.push_str(&format!("{INDENT}{line}\n"));
+            }
+
+            output.push_str(&format!(
+                "{INDENT}{INDENT}$ {literal}{}{literal:#}\n",
+                example.command
+            ));
+        }
+
+        output.trim_end().to_string()
+    }
+
+    /// Add the section to the end of the command's help.
+    pub fn apply(&self, command: Command) -> Command {
+        if self.examples.is_empty() {
+            return command;
+        }
+
+        let width = crate::term::width(Stream::Stdout)
+            .unwrap_or(DEFAULT_WIDTH)
+            .min(DEFAULT_WIDTH);
+
+        super::append_after_help(command, &self.render(width))
+    }
+}
+
+/// Wrap text at word boundaries (words longer than the width are not broken).
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        if !line.is_empty() {
+            line.push(' ');
+        }
+
+        line.push_str(word);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Debug, Parser)]
+    #[clap(name = "mytool", after_help = "See the README for details.")]
+    struct Opts {
+        #[clap(long)]
+        since: Option<String>,
+    }
+
+    #[test]
+    fn test_examples() {
+        let examples = Examples::new()
+            .with_example(
+                "Import everything that has changed since the previous day",
+                "mytool --since '1 day ago'",
+            )
+            .with_example("Import everything", "mytool");
+
+        assert_eq!(
+            examples.render(40),
+            "Examples:
+  Import everything that has changed
+  since the previous day
+    $ mytool --since '1 day ago'
+
+  Import everything
+    $ mytool"
+        );
+
+        let highlighted = examples.clone().with_highlighting(true).render(40);
+        assert!(highlighted.contains("$ \x1b[1mmytool"));
+
+        let help = examples.apply(Opts::command()).render_help().to_string();
+        assert!(help.contains("See the README for details.\n\nExamples:\n"));
+    }
+}
@@ -0,0 +1,211 @@
+//! Help output helpers.
+//!
+//! [`HelpTiers`] splits help into two tiers: `-h` lists only the most common options, while `--help` (or
+//! `--verbose-help`) also lists advanced options. Options can be marked as advanced either with clap's
+//! `hide_short_help` attribute or with [`HelpTiers::with_advanced`]:
+//!
+//! ```rust,no_run
+//! use cli_helpers::help::HelpTiers;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(long)]
+//!     output: Option<std::path::PathBuf>,
+//!     /// Number of concurrent requests
+//!     #[clap(long, default_value = "4", hide_short_help = true)]
+//!     concurrency: usize,
+//!     #[clap(long)]
+//!     user_agent: Option<String>,
+//! }
+//!
+//! let opts: Opts = HelpTiers::new().with_advanced("user_agent").parse();
+//! ```
+//!
+//! The [`topic`] module supports long-form help pages such as `mytool help timestamps`, and the [`examples`] module
+//! adds an examples section to the end of help.
+
+pub mod examples;
+pub mod topic;
+
+use std::ffi::OsString;
+
+use clap::{Arg, ArgAction, Command, Parser};
+
+use crate::i18n::{self, ids};
+
+const VERBOSE_HELP_ID: &str = "verbose-help";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HelpTiers {
+    advanced: Vec<String>,
+}
+
+impl HelpTiers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark the argument with the given ID as advanced (in any subcommand), so that it is omitted from `-h`.
+    pub fn with_advanced(mut self, id: &str) -> Self {
+        self.advanced.push(id.to_string());
+        self
+    }
+
+    /// Hide advanced arguments from short help, add the `--verbose-help` flag, and note the number of hidden options
+    /// in short help.
+    pub fn apply(&self, command: Command) -> Command {
+        self.apply_tiers(
+            command.arg(
+                Arg::new(VERBOSE_HELP_ID)
+                    .long(VERBOSE_HELP_ID)
+                    .action(ArgAction::HelpLong)
+                    .global(true)
+                    .help("Print help, including advanced options"),
+            ),
+        )
+    }
+
+    /// Parse the process's arguments, exiting on error (or after printing help).
+    pub fn parse<T: Parser>(&self) -> T {
+        self.parse_from(std::env::args_os())
+    }
+
+    pub fn parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString> + Clone>(
+        &self,
+        args: I,
+    ) -> T {
+        let mut command = self.apply(T::command());
+        let matches = command.clone().get_matches_from(args);
+
+        T::from_arg_matches(&matches).unwrap_or_else(|error| error.format(&mut command).exit())
+    }
+
+    pub fn try_parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString> + Clone>(
+        &self,
+        args: I,
+    ) -> Result<T, clap::Error> {
+        let mut command = self.apply(T::command());
+        let matches = command.try_get_matches_from_mut(args)?;
+
+        T::from_arg_matches(&matches).map_err(|error| error.format(&mut command))
+    }
+
+    fn apply_tiers(&self, command: Command) -> Command {
+        let ids = command
+            .get_arguments()
+            .filter(|arg| self.advanced.iter().any(|id| id == arg.get_id().as_str()))
+            .map(|arg| arg.get_id().to_string())
+            .collect::<Vec<_>>();
+
+        let command = ids.into_iter().fold(command, |command, id| {
+            command.mut_arg(id, |arg| arg.hide_short_help(true))
+        });
+
+        let hidden = command
+            .get_arguments()
+            .filter(|arg| arg.is_hide_short_help_set() && !arg.is_hide_set())
+            .count();
+
+        let command = if hidden > 0 {
+            let note = if hidden == 1 {
+                i18n::message(ids::HELP_ADVANCED_HIDDEN_ONE, &[])
+            } else {
+                i18n::message(ids::HELP_ADVANCED_HIDDEN, &[("count", &hidden)])
+            };
+
+            let after_help = command.get_after_help().map(ToString::to_string);
+            let after_long_help = command
+                .get_after_long_help()
+                .map(ToString::to_string)
+                .or_else(|| after_help.clone());
+
+            let command = command.after_help(match after_help {
+                Some(after_help) => format!("{after_help}\n\n{note}"),
+                None => note,
+            });
+
+            match after_long_help {
+                Some(after_long_help) => command.after_long_help(after_long_help),
+                // An empty long footer keeps the note out of long help.
+                None => command.after_long_help(""),
+            }
+        } else {
+            command
+        };
+
+        let subcommands = command
+            .get_subcommands()
+            .map(|subcommand| subcommand.get_name().to_string())
+            .collect::<Vec<_>>();
+
+        subcommands.into_iter().fold(command, |command, name| {
+            command.mut_subcommand(name, |subcommand| self.apply_tiers(subcommand))
+        })
+    }
+}
+
+/// Append a section to the command's footer (in both short and long help).
+fn append_after_help(command: Command, section: &str) -> Command {
+    let append = |existing: Option<String>| match existing {
+        Some(existing) if !existing.is_empty() => format!("{existing}\n\n{section}"),
+        _ => section.to_string(),
+    };
+
+    let after_help = command.get_after_help().map(ToString::to_string);
+    let after_long_help = command.get_after_long_help().map(ToString::to_string);
+    let command = command.after_help(append(after_help));
+
+    match after_long_help {
+        Some(after_long_help) => command.after_long_help(append(Some(after_long_help))),
+        None => command,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::error::ErrorKind;
+    use clap::CommandFactory;
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        /// Output file
+        #[clap(long)]
+        output: Option<String>,
+        /// Number of concurrent requests
+        #[clap(long, default_value = "4", hide_short_help = true)]
+        concurrency: usize,
+        /// User agent
+        #[clap(long)]
+        user_agent: Option<String>,
+    }
+
+    #[test]
+    fn test_help_tiers() {
+        let tiers = HelpTiers::new().with_advanced("user_agent");
+        let mut command = tiers.apply(Opts::command());
+
+        let short = command.render_help().to_string();
+        assert!(short.contains("--output"));
+        assert!(!short.contains("--concurrency"));
+        assert!(!short.contains("--user-agent"));
+        assert!(short.contains("2 advanced options are not shown"));
+
+        let long = command.render_long_help().to_string();
+        assert!(long.contains("--concurrency"));
+        assert!(long.contains("--user-agent"));
+        assert!(!long.contains("advanced options are not shown"));
+
+        let error = tiers
+            .try_parse_from::<Opts, _, _>(["test", "--verbose-help"])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::DisplayHelp);
+        assert!(error.to_string().contains("--user-agent"));
+
+        let opts = tiers
+            .try_parse_from::<Opts, _, _>(["test", "--concurrency", "8"])
+            .unwrap();
+        assert_eq!(opts.concurrency, 8);
+    }
+}
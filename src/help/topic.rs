@@ -0,0 +1,228 @@
+//! Long-form help pages (`mytool help <topic>`).
+//!
+//! [`HelpTopics`] is a registry of named pages, which are listed at the end of the command's help and printed by
+//! `mytool help <topic>`. Pages explaining the accepted [`Timestamp`](crate::Timestamp) formats and the available
+//! [`OutputFormat`]s are provided:
+//!
+//! ```rust,no_run
+//! use cli_helpers::help::topic::{HelpTopics, Topic};
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(long)]
+//!     since: Option<Timestamp>,
+//! }
+//!
+//! let opts: Opts = HelpTopics::new()
+//!     .with_topic(Topic::timestamps())
+//!     .with_topic(Topic::new("filters", "Filter expression syntax", "..."))
+//!     .parse();
+//! ```
+
+use std::ffi::OsString;
+
+use clap::{Command, Parser, ValueEnum};
+
+use crate::i18n::{self, ids};
+use crate::output::OutputFormat;
+use crate::timestamp_parser::{accepted_formats, RELATIVE_UNITS};
+
+const HELP_SUBCOMMAND: &str = "help";
+
+/// A named help page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topic {
+    pub name: String,
+    pub summary: String,
+    pub body: String,
+}
+
+impl Topic {
+    pub fn new<N: Into<String>, S: Into<String>, B: Into<String>>(
+        name: N,
+        summary: S,
+        body: B,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            summary: summary.into(),
+            body: body.into(),
+        }
+    }
+
+    /// The accepted [`Timestamp`](crate::Timestamp) formats.
+    pub fn timestamps() -> Self {
+        let body = format!(
+            "Options that take a timestamp accept any of the following formats:\n\n{}\n\
+            Relative timestamps use the units {RELATIVE_UNITS}.\n\n\
+            Options that take a local timestamp also accept a date and time without an offset (such as \
+            2023-08-25 14:00),\nwhich is interpreted in the local timezone.",
+            accepted_formats()
+        );
+
+        Self::new("timestamps", "Accepted timestamp formats", body)
+    }
+
+    /// The available [`OutputFormat`]s.
+    pub fn formats() -> Self {
+        let variants = OutputFormat::value_variants()
+            .iter()
+            .filter_map(ValueEnum::to_possible_value)
+            .collect::<Vec<_>>();
+        let width = variants
+            .iter()
+            .map(|value| value.get_name().len())
+            .max()
+            .unwrap_or_default();
+
+        let mut body =
+            "The output format can be selected with --format (the default is table):\n\n"
+                .to_string();
+
+        for value in variants {
+            let help = value
+                .get_help()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            body.push_str(&format!("  {:width$}  {help}\n", value.get_name()));
+        }
+
+        Self::new("formats", "Available output formats", body.trim_end())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HelpTopics {
+    topics: Vec<Topic>,
+}
+
+impl HelpTopics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a topic (replacing any existing topic with the same name).
+    pub fn with_topic(mut self, topic: Topic) -> Self {
+        self.topics.retain(|existing| existing.name != topic.name);
+        self.topics.push(topic);
+        self
+    }
+
+    pub fn topics(&self) -> &[Topic] {
+        &self.topics
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Topic> {
+        self.topics.iter().find(|topic| topic.name == name)
+    }
+
+    /// The topic requested by arguments of the form `mytool help <topic>`, if any.
+    pub fn requested<I: IntoIterator<Item = A>, A: Into<OsString>>(
+        &self,
+        args: I,
+    ) -> Option<&Topic> {
+        let mut args = args.into_iter().skip(1).map(Into::into);
+
+        if args.next()? != HELP_SUBCOMMAND {
+            return None;
+        }
+
+        let name = args.next()?;
+
+        match args.next() {
+            Some(_) => None,
+            None => self.get(name.to_str()?),
+        }
+    }
+
+    /// List the topics at the end of the command's help.
+    pub fn apply(&self, command: Command) -> Command {
+        if self.topics.is_empty() {
+            return command;
+        }
+
+        let width = self
+            .topics
+            .iter()
+            .map(|topic| topic.name.len())
+            .max()
+            .unwrap_or_default();
+
+        let mut listing = format!("{}\n", i18n::message(ids::HELP_TOPICS, &[]));
+
+        for topic in &self.topics {
+            listing.push_str(&format!("  {:width$}  {}\n", topic.name, topic.summary));
+        }
+
+        listing.push('\n');
+        listing.push_str(&i18n::message(
+            ids::HELP_TOPICS_USAGE,
+            &[("command", &command.get_name())],
+        ));
+
+        super::append_after_help(command, &listing)
+    }
+
+    /// Parse the process's arguments, exiting on error (or after printing a requested topic).
+    pub fn parse<T: Parser>(&self) -> T {
+        self.parse_from(std::env::args_os())
+    }
+
+    pub fn parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString> + Clone>(
+        &self,
+        args: I,
+    ) -> T {
+        let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+
+        if let Some(topic) = self.requested(&args) {
+            println!("{}", topic.body);
+            std::process::exit(0);
+        }
+
+        let mut command = self.apply(T::command());
+        let matches = command.clone().get_matches_from(args);
+
+        T::from_arg_matches(&matches).unwrap_or_else(|error| error.format(&mut command).exit())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[derive(Debug, Parser)]
+    #[clap(name = "mytool")]
+    struct Opts {
+        #[clap(long)]
+        since: Option<crate::Timestamp>,
+    }
+
+    #[test]
+    fn test_help_topics() {
+        let topics = HelpTopics::new()
+            .with_topic(Topic::timestamps())
+            .with_topic(Topic::formats());
+
+        assert_eq!(
+            topics.requested(["mytool", "help", "timestamps"]),
+            Some(&Topic::timestamps())
+        );
+        assert_eq!(topics.requested(["mytool", "help", "filters"]), None);
+        assert_eq!(topics.requested(["mytool", "--since", "now"]), None);
+
+        assert!(topics
+            .get("formats")
+            .unwrap()
+            .body
+            .contains("  csv       Comma-separated values with a header row"));
+        assert!(Topic::timestamps()
+            .body
+            .contains("\n  RFC 2822            Fri, 25 Aug 2023 08:47:09 +0200\n"));
+
+        let help = topics.apply(Opts::command()).render_help().to_string();
+        assert!(help.contains("  timestamps  Accepted timestamp formats"));
+        assert!(help.contains("Use `mytool help <topic>` to read a topic."));
+    }
+}
@@ -0,0 +1,175 @@
+//! Standard arguments and helpers for HTTP-based tools.
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use crate::{app_dirs::AppDirs, netrc, secret::Secret, Error};
+
+/// Standard API token arguments.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiAuth {
+    /// API token
+    #[clap(long, global = true, conflicts_with = "token_file")]
+    token: Option<Secret>,
+    /// File containing the API token
+    #[clap(long, global = true)]
+    token_file: Option<PathBuf>,
+}
+
+impl ApiAuth {
+    pub fn new(token: Option<Secret>, token_file: Option<PathBuf>) -> Self {
+        Self { token, token_file }
+    }
+
+    /// Resolve the token from the command-line, the token file, or the given environment variable (in that order).
+    ///
+    /// Tokens given on the command-line or in the environment may refer to the credential store (see
+    /// [`Secret::resolve`]).
+    pub fn resolve(&self, env_var: &str) -> Result<Option<Secret>, Error> {
+        if let Some(token) = &self.token {
+            Ok(Some(token.resolve()?))
+        } else if let Some(path) = &self.token_file {
+            let contents = std::fs::read_to_string(path)?;
+
+            Ok(Some(Secret::new(contents.trim())))
+        } else {
+            std::env::var(env_var)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .map(|value| Secret::new(value).resolve())
+                .transpose()
+        }
+    }
+
+    /// Resolve the token as with [`resolve`](Self::resolve), falling back to the password for the host in the
+    /// application credentials file or `.netrc`.
+    pub fn resolve_for_host(
+        &self,
+        env_var: &str,
+        dirs: &AppDirs,
+        host: &str,
+    ) -> Result<Option<Secret>, Error> {
+        match self.resolve(env_var)? {
+            Some(token) => Ok(Some(token)),
+            None => {
+                Ok(netrc::credentials(dirs, host)?.and_then(|credentials| credentials.password))
+            }
+        }
+    }
+
+    /// Resolve the token, failing if none is provided.
+    pub fn require(&self, env_var: &str) -> Result<Secret, Error> {
+        self.resolve(env_var)?
+            .ok_or_else(|| Error::MissingToken(env_var.to_string()))
+    }
+}
+
+/// A `User-Agent` header value of the form `name/version (comment; ...)`.
+///
+/// The [`user_agent`](crate::user_agent) macro creates one from the calling crate's package metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgent {
+    name: String,
+    version: String,
+    comments: Vec<String>,
+}
+
+impl UserAgent {
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, version: V) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            comments: vec![],
+        }
+    }
+
+    pub fn with_comment<C: Into<String>>(mut self, comment: C) -> Self {
+        self.comments.push(comment.into());
+        self
+    }
+}
+
+impl Display for UserAgent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.name, self.version)?;
+
+        if !self.comments.is_empty() {
+            write!(f, " ({})", self.comments.join("; "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a [`UserAgent`](crate::http::UserAgent) from the calling crate's name, version, and homepage.
+#[macro_export]
+macro_rules! user_agent {
+    () => {{
+        let user_agent =
+            $crate::http::UserAgent::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+
+        match env!("CARGO_PKG_HOMEPAGE") {
+            "" => user_agent,
+            homepage => user_agent.with_comment(format!("+{homepage}")),
+        }
+    }};
+}
+
+/// Standard user agent override argument.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserAgentArgs {
+    /// User agent to send with HTTP requests
+    #[clap(long, global = true)]
+    user_agent: Option<String>,
+}
+
+impl UserAgentArgs {
+    pub fn new(user_agent: Option<String>) -> Self {
+        Self { user_agent }
+    }
+
+    /// The user agent provided on the command line, or the given default.
+    pub fn resolve(&self, default: &UserAgent) -> String {
+        self.user_agent
+            .clone()
+            .unwrap_or_else(|| default.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_auth() {
+        use clap::Parser;
+
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(flatten)]
+            auth: ApiAuth,
+            #[clap(flatten)]
+            user_agent: UserAgentArgs,
+        }
+
+        let opts = Opts::try_parse_from(["test", "--token", "abc"]).unwrap();
+        let token = opts.auth.require("CLI_HELPERS_TEST_UNSET_TOKEN").unwrap();
+        assert_eq!(token.expose(), "abc");
+        assert_eq!(format!("{:?}", token), "Secret(<redacted>)");
+
+        let opts = Opts::try_parse_from(["test"]).unwrap();
+        assert!(matches!(
+            opts.auth.require("CLI_HELPERS_TEST_UNSET_TOKEN"),
+            Err(Error::MissingToken(_))
+        ));
+
+        assert!(Opts::try_parse_from(["test", "--token", "abc", "--token-file", "x"]).is_err());
+        assert_eq!(
+            opts.user_agent.resolve(&crate::user_agent!()),
+            format!(
+                "cli-helpers/{} (+https://github.com/travisbrown/cli-helpers)",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+}
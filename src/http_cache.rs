@@ -0,0 +1,189 @@
+//! Conditional-request caching for HTTP resources, keyed by URL.
+//!
+//! This layer is independent of any particular HTTP client: the caller provides a function that performs the request
+//! with the given validators (as `If-None-Match` and `If-Modified-Since` headers) and reports whether the server
+//! returned a new representation or `304 Not Modified`.
+
+use std::time::Duration;
+
+use crate::{cache::Cache, Error};
+
+const KEY_PREFIX: &str = "http:";
+
+/// The validators returned by the server for a cached representation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// The conditional request headers to send for these validators.
+    pub fn request_headers(&self) -> Vec<(&'static str, &str)> {
+        let mut headers = Vec::with_capacity(2);
+
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match", etag.as_str()));
+        }
+
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since", last_modified.as_str()));
+        }
+
+        headers
+    }
+}
+
+/// The outcome of a (possibly conditional) request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fetch {
+    /// A new representation, with the values of the response's `ETag` and `Last-Modified` headers.
+    Modified {
+        body: Vec<u8>,
+        validators: Validators,
+    },
+    NotModified,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    cache: Cache,
+    ttl: Duration,
+}
+
+impl HttpCache {
+    /// Create an HTTP cache that always revalidates cached responses.
+    pub fn new(cache: Cache) -> Self {
+        Self {
+            cache,
+            ttl: Duration::ZERO,
+        }
+    }
+
+    /// Return cached responses younger than the TTL without making a request.
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self { ttl, ..self }
+    }
+
+    /// Return the body for the URL, revalidating or fetching it with `fetch_fn` as necessary.
+    pub fn get<E: From<Error>, F: FnOnce(&Validators) -> Result<Fetch, E>>(
+        &self,
+        url: &str,
+        fetch_fn: F,
+    ) -> Result<Vec<u8>, E> {
+        let key = format!("{KEY_PREFIX}{url}");
+
+        let cached = match self.cache.get(&key)? {
+            Some(entry) => {
+                let is_fresh = entry.is_fresh(self.ttl);
+                decode(&entry.value).map(|(validators, body)| (validators, body, is_fresh))
+            }
+            None => None,
+        };
+
+        match cached {
+            Some((_, body, true)) => Ok(body),
+            Some((validators, body, false)) => match fetch_fn(&validators)? {
+                Fetch::Modified { body, validators } => {
+                    self.cache.insert(&key, &encode(&validators, &body))?;
+                    Ok(body)
+                }
+                Fetch::NotModified => {
+                    // Re-insert to reset the entry's age.
+                    self.cache.insert(&key, &encode(&validators, &body))?;
+                    Ok(body)
+                }
+            },
+            None => match fetch_fn(&Validators::default())? {
+                Fetch::Modified { body, validators } => {
+                    if !validators.is_empty() || !self.ttl.is_zero() {
+                        self.cache.insert(&key, &encode(&validators, &body))?;
+                    }
+                    Ok(body)
+                }
+                Fetch::NotModified => Err(Error::UnexpectedNotModified(url.to_string()).into()),
+            },
+        }
+    }
+}
+
+fn encode(validators: &Validators, body: &[u8]) -> Vec<u8> {
+    let mut value = Vec::with_capacity(body.len() + 64);
+
+    for header in [&validators.etag, &validators.last_modified] {
+        value.extend_from_slice(header.as_deref().unwrap_or_default().as_bytes());
+        value.push(b'\n');
+    }
+
+    value.extend_from_slice(body);
+    value
+}
+
+fn decode(value: &[u8]) -> Option<(Validators, Vec<u8>)> {
+    let mut parts = value.splitn(3, |byte| *byte == b'\n');
+    let etag = parts.next()?;
+    let last_modified = parts.next()?;
+    let body = parts.next()?;
+
+    let header = |bytes: &[u8]| {
+        Some(std::str::from_utf8(bytes).ok()?)
+            .filter(|value| !value.is_empty())
+            .map(|value| value.to_string())
+    };
+
+    Some((
+        Validators {
+            etag: header(etag),
+            last_modified: header(last_modified),
+        },
+        body.to_vec(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_http_cache() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let cache = HttpCache::new(Cache::open(dir).unwrap());
+        let url = "https://example.com/feed.json";
+        let requests = Cell::new(0);
+
+        let fetch = |validators: &Validators| -> Result<Fetch, Error> {
+            requests.set(requests.get() + 1);
+
+            if validators.etag.as_deref() == Some("\"abc\"") {
+                assert_eq!(
+                    validators.request_headers(),
+                    vec![("If-None-Match", "\"abc\"")]
+                );
+                Ok(Fetch::NotModified)
+            } else {
+                Ok(Fetch::Modified {
+                    body: b"[1, 2, 3]".to_vec(),
+                    validators: Validators {
+                        etag: Some("\"abc\"".to_string()),
+                        last_modified: None,
+                    },
+                })
+            }
+        };
+
+        assert_eq!(cache.get(url, fetch).unwrap(), b"[1, 2, 3]");
+        assert_eq!(cache.get(url, fetch).unwrap(), b"[1, 2, 3]");
+        assert_eq!(requests.get(), 2);
+
+        let cache = cache.with_ttl(Duration::from_secs(60));
+        assert_eq!(cache.get(url, fetch).unwrap(), b"[1, 2, 3]");
+        assert_eq!(requests.get(), 2);
+    }
+}
@@ -0,0 +1,391 @@
+//! Localization of user-facing messages.
+//!
+//! Messages are looked up by ID in a [`Catalog`], which maps locales to message templates with `{name}`
+//! placeholders. The catalog includes English defaults for the crate's own messages (see [`ids`]), and applications
+//! can add translations of these along with their own messages:
+//!
+//! ```rust,no_run
+//! use cli_helpers::i18n::{self, ids, Catalog, LocaleArgs};
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     locale: LocaleArgs,
+//! }
+//!
+//! let opts = Opts::parse();
+//! opts.locale.init();
+//!
+//! i18n::install(
+//!     Catalog::new()
+//!         .with_message("de", ids::SUMMARY_PROCESSED, "{count} {noun} in {elapsed} verarbeitet ({rate}/s)")
+//!         .with_message("de", "greeting", "Hallo, {name}!")
+//!         .with_message("en", "greeting", "Hello, {name}!"),
+//! );
+//!
+//! println!("{}", i18n::message("greeting", &[("name", &"Travis")]));
+//! ```
+//!
+//! The locale is selected with `--locale`, or from the `LC_ALL`, `LC_MESSAGES`, or `LANG` environment variables (in
+//! that order). Lookups fall back from the full locale (`de-AT`) to its language (`de`) and then to English. Error
+//! messages ([`crate::Error`]) are not localized.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::Error;
+
+/// IDs of the crate's own messages.
+pub mod ids {
+    /// `Processed {count} {noun} in {elapsed} ({rate}/s)`
+    pub const SUMMARY_PROCESSED: &str = "summary-processed";
+    /// `{count} skipped`
+    pub const SUMMARY_SKIPPED: &str = "summary-skipped";
+    /// `{count} error`
+    pub const SUMMARY_ERROR: &str = "summary-error";
+    /// `{count} errors`
+    pub const SUMMARY_ERRORS: &str = "summary-errors";
+    /// `[y/N]`
+    pub const PROMPT_DEFAULT_NO: &str = "prompt-default-no";
+    /// Comma-separated affirmative answers (`y,yes`), which are always accepted in addition to the translation.
+    pub const PROMPT_YES: &str = "prompt-yes";
+    /// `1 advanced option is not shown (use --help to show all options)`
+    pub const HELP_ADVANCED_HIDDEN_ONE: &str = "help-advanced-hidden-one";
+    /// `{count} advanced options are not shown (use --help to show all options)`
+    pub const HELP_ADVANCED_HIDDEN: &str = "help-advanced-hidden";
+    /// `Help topics:`
+    pub const HELP_TOPICS: &str = "help-topics";
+    /// ``Use `{command} help <topic>` to read a topic.``
+    pub const HELP_TOPICS_USAGE: &str = "help-topics-usage";
+    /// `Examples:`
+    pub const HELP_EXAMPLES: &str = "help-examples";
+    /// `A crash report has been written to {path} (please attach it to any bug report)`
+    pub const CRASH_REPORT_WRITTEN: &str = "crash-report-written";
+}
+
+const DEFAULT_LANGUAGE: &str = "en";
+
+const DEFAULT_MESSAGES: [(&str, &str); 12] = [
+    (
+        ids::SUMMARY_PROCESSED,
+        "Processed {count} {noun} in {elapsed} ({rate}/s)",
+    ),
+    (ids::SUMMARY_SKIPPED, "{count} skipped"),
+    (ids::SUMMARY_ERROR, "{count} error"),
+    (ids::SUMMARY_ERRORS, "{count} errors"),
+    (ids::PROMPT_DEFAULT_NO, "[y/N]"),
+    (ids::PROMPT_YES, "y,yes"),
+    (
+        ids::HELP_ADVANCED_HIDDEN_ONE,
+        "1 advanced option is not shown (use --help to show all options)",
+    ),
+    (
+        ids::HELP_ADVANCED_HIDDEN,
+        "{count} advanced options are not shown (use --help to show all options)",
+    ),
+    (ids::HELP_TOPICS, "Help topics:"),
+    (
+        ids::HELP_TOPICS_USAGE,
+        "Use `{command} help <topic>` to read a topic.",
+    ),
+    (ids::HELP_EXAMPLES, "Examples:"),
+    (
+        ids::CRASH_REPORT_WRITTEN,
+        "A crash report has been written to {path} (please attach it to any bug report)",
+    ),
+];
+
+/// Environment variables that select the locale, in order of precedence.
+const LOCALE_ENV_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+static CATALOG: RwLock<Option<Catalog>> = RwLock::new(None);
+static LOCALE: RwLock<Option<Locale>> = RwLock::new(None);
+
+/// A language tag such as `de` or `pt-BR`.
+///
+/// POSIX locale names (such as `de_DE.UTF-8`) are also accepted and normalized.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Locale(String);
+
+impl Locale {
+    /// The locale selected by the environment (`None` if it is unset or is `C` or `POSIX`).
+    pub fn from_env() -> Option<Self> {
+        Self::from_env_with(|name| std::env::var(name).ok())
+    }
+
+    fn from_env_with<F: Fn(&str) -> Option<String>>(get: F) -> Option<Self> {
+        LOCALE_ENV_VARS
+            .iter()
+            .filter_map(|name| get(name))
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// The language subtag (for example `pt` for `pt-BR`).
+    pub fn language(&self) -> &str {
+        self.0.split('-').next().unwrap_or(&self.0)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Drop any encoding (`.UTF-8`) or modifier (`@euro`).
+        let name = s.split(['.', '@']).next().unwrap_or_default();
+
+        if name.is_empty() || name == "C" || name == "POSIX" {
+            return Err(Error::InvalidLocale(s.to_string()));
+        }
+
+        let mut parts = name.split(['_', '-']);
+        let language = parts.next().unwrap_or_default();
+
+        if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return Err(Error::InvalidLocale(s.to_string()));
+        }
+
+        let mut tag = language.to_ascii_lowercase();
+
+        for part in parts {
+            if part.is_empty() || !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(Error::InvalidLocale(s.to_string()));
+            }
+
+            tag.push('-');
+
+            if part.len() == 2 {
+                tag.push_str(&part.to_ascii_uppercase());
+            } else {
+                tag.push_str(part);
+            }
+        }
+
+        Ok(Self(tag))
+    }
+}
+
+/// Message templates by locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    messages: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Catalog {
+    /// A catalog containing the English defaults for the crate's own messages.
+    pub fn new() -> Self {
+        Self {
+            messages: BTreeMap::new(),
+        }
+        .with_messages(DEFAULT_LANGUAGE, DEFAULT_MESSAGES)
+    }
+
+    /// Add a message template for a locale (or language), replacing any existing template.
+    pub fn with_message<L: AsRef<str>, I: Into<String>, T: Into<String>>(
+        mut self,
+        locale: L,
+        id: I,
+        template: T,
+    ) -> Self {
+        self.messages
+            .entry(locale.as_ref().to_string())
+            .or_default()
+            .insert(id.into(), template.into());
+        self
+    }
+
+    pub fn with_messages<L, I, K, T>(self, locale: L, messages: I) -> Self
+    where
+        L: AsRef<str>,
+        I: IntoIterator<Item = (K, T)>,
+        K: Into<String>,
+        T: Into<String>,
+    {
+        messages.into_iter().fold(self, |catalog, (id, template)| {
+            catalog.with_message(locale.as_ref(), id, template)
+        })
+    }
+
+    /// The template for a message in the given locale, falling back to its language and then to English.
+    pub fn get(&self, locale: Option<&Locale>, id: &str) -> Option<&str> {
+        let candidates = locale
+            .into_iter()
+            .flat_map(|locale| [locale.as_str(), locale.language()])
+            .chain(std::iter::once(DEFAULT_LANGUAGE));
+
+        for candidate in candidates {
+            if let Some(template) = self
+                .messages
+                .get(candidate)
+                .and_then(|messages| messages.get(id))
+            {
+                return Some(template);
+            }
+        }
+
+        None
+    }
+
+    /// Render a message, substituting `{name}` placeholders (the ID is used if the message is not found).
+    pub fn message(
+        &self,
+        locale: Option<&Locale>,
+        id: &str,
+        args: &[(&str, &dyn Display)],
+    ) -> String {
+        let mut message = self.get(locale, id).unwrap_or(id).to_string();
+
+        for (name, value) in args {
+            message = message.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+
+        message
+    }
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Standard locale argument.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocaleArgs {
+    /// Language for messages (for example `de` or `pt-BR`; the default is taken from `LANG`)
+    #[clap(long, global = true)]
+    locale: Option<Locale>,
+}
+
+impl LocaleArgs {
+    pub fn new(locale: Option<Locale>) -> Self {
+        Self { locale }
+    }
+
+    /// The selected locale, or the locale selected by the environment.
+    pub fn locale(&self) -> Option<Locale> {
+        self.locale.clone().or_else(Locale::from_env)
+    }
+
+    /// Use the selected locale for messages.
+    pub fn init(&self) {
+        if let Some(locale) = &self.locale {
+            set_locale(locale.clone());
+        }
+    }
+}
+
+/// Use the catalog for all messages.
+pub fn install(catalog: Catalog) {
+    *CATALOG.write().unwrap_or_else(|error| error.into_inner()) = Some(catalog);
+}
+
+/// Override the locale selected by the environment.
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap_or_else(|error| error.into_inner()) = Some(locale);
+}
+
+/// The current locale (set with [`set_locale`] or selected by the environment).
+pub fn locale() -> Option<Locale> {
+    LOCALE
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone()
+        .or_else(Locale::from_env)
+}
+
+/// Render a message in the current locale using the installed catalog (or the defaults).
+pub fn message(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let locale = locale();
+    let catalog = CATALOG.read().unwrap_or_else(|error| error.into_inner());
+
+    match catalog.as_ref() {
+        Some(catalog) => catalog.message(locale.as_ref(), id, args),
+        None => Catalog::new().message(locale.as_ref(), id, args),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale() {
+        let parse = |input: &str| {
+            input
+                .parse::<Locale>()
+                .map(|locale| locale.to_string())
+                .ok()
+        };
+
+        assert_eq!(parse("de_DE.UTF-8"), Some("de-DE".to_string()));
+        assert_eq!(parse("pt-br"), Some("pt-BR".to_string()));
+        assert_eq!(parse("ca_ES@valencia"), Some("ca-ES".to_string()));
+        assert_eq!(parse("de"), Some("de".to_string()));
+        assert_eq!(parse("C.UTF-8"), None);
+        assert_eq!(parse("POSIX"), None);
+        assert_eq!(parse(""), None);
+
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            Locale::from_env_with(|name| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+
+        assert_eq!(
+            env(&[("LANG", "fr_FR.UTF-8"), ("LC_ALL", "")]),
+            Some(Locale("fr-FR".to_string()))
+        );
+        assert_eq!(
+            env(&[("LANG", "fr_FR.UTF-8"), ("LC_MESSAGES", "de_AT")]),
+            Some(Locale("de-AT".to_string()))
+        );
+        assert_eq!(env(&[("LANG", "C")]), None);
+    }
+
+    #[test]
+    fn test_catalog() {
+        let catalog = Catalog::new()
+            .with_message("de", ids::SUMMARY_SKIPPED, "{count} übersprungen")
+            .with_message("de-AT", ids::SUMMARY_SKIPPED, "{count} ausgelassen");
+        let locale = |tag: &str| tag.parse::<Locale>().unwrap();
+
+        assert_eq!(
+            catalog.message(
+                Some(&locale("de-DE")),
+                ids::SUMMARY_SKIPPED,
+                &[("count", &3)]
+            ),
+            "3 übersprungen"
+        );
+        assert_eq!(
+            catalog.message(
+                Some(&locale("de-AT")),
+                ids::SUMMARY_SKIPPED,
+                &[("count", &3)]
+            ),
+            "3 ausgelassen"
+        );
+        assert_eq!(
+            catalog.message(Some(&locale("fr")), ids::SUMMARY_SKIPPED, &[("count", &3)]),
+            "3 skipped"
+        );
+        assert_eq!(catalog.message(None, "unknown-id", &[]), "unknown-id");
+    }
+}
@@ -0,0 +1,282 @@
+//! An append-only NDJSON journal that can be shared between processes.
+//!
+//! Each [`Journal::append`] takes an exclusive advisory lock on the file and writes the record as a single line, so
+//! that records from several invocations of a tool running at the same time are never interleaved:
+//!
+//! ```rust,no_run
+//! use cli_helpers::journal::{FsyncPolicy, Journal};
+//! use serde_json::json;
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let journal = Journal::open("audit.ndjson")?.with_fsync(FsyncPolicy::Always);
+//!
+//! journal.append(&json!({"event": "deleted", "id": 123}))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Locks are advisory (`flock` on Unix and `LockFileEx` on Windows), so they only protect against other writers that
+//! also use a [`Journal`]. [`read_journal`] takes a shared lock and skips any incomplete last line left by a writer
+//! that crashed.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::Error;
+
+/// When appended records are flushed to disk.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Leave flushing to the operating system.
+    #[default]
+    Never,
+    /// Flush after every append.
+    Always,
+    /// Flush after an append if the last flush was at least this long ago.
+    Interval(Duration),
+}
+
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<File>,
+    fsync: FsyncPolicy,
+    last_sync: Mutex<Instant>,
+}
+
+impl Journal {
+    /// Open the journal for appending, creating it (and its parent directory) if necessary.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let file = File::options()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            fsync: FsyncPolicy::default(),
+            last_sync: Mutex::new(Instant::now()),
+        })
+    }
+
+    pub fn with_fsync(mut self, fsync: FsyncPolicy) -> Self {
+        self.fsync = fsync;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record.
+    pub fn append(&self, record: &Value) -> Result<(), Error> {
+        self.append_all(std::slice::from_ref(record))
+    }
+
+    /// Append several records, without records from other writers between them.
+    pub fn append_all<'a, I: IntoIterator<Item = &'a Value>>(
+        &self,
+        records: I,
+    ) -> Result<(), Error> {
+        let mut buffer = Vec::new();
+
+        for record in records {
+            serde_json::to_writer(&mut buffer, record).map_err(std::io::Error::from)?;
+            buffer.push(b'\n');
+        }
+
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = self.file.lock().unwrap_or_else(|error| error.into_inner());
+
+        file.lock()?;
+        let result = Self::terminate_partial_line(&mut file)
+            .and_then(|()| file.write_all(&buffer))
+            .and_then(|()| self.sync(&file));
+        file.unlock()?;
+
+        Ok(result?)
+    }
+
+    /// Make sure that the next record starts on a new line, even if a writer crashed partway through a line.
+    fn terminate_partial_line(file: &mut File) -> std::io::Result<()> {
+        if file.metadata()?.len() == 0 {
+            return Ok(());
+        }
+
+        let mut last = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+
+        if last[0] == b'\n' {
+            Ok(())
+        } else {
+            file.write_all(b"\n")
+        }
+    }
+
+    fn sync(&self, file: &File) -> std::io::Result<()> {
+        let due = match self.fsync {
+            FsyncPolicy::Never => false,
+            FsyncPolicy::Always => true,
+            FsyncPolicy::Interval(interval) => {
+                let mut last_sync = self
+                    .last_sync
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner());
+                let now = Instant::now();
+                let due = now.duration_since(*last_sync) >= interval;
+
+                if due {
+                    *last_sync = now;
+                }
+
+                due
+            }
+        };
+
+        if due {
+            file.sync_data()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        if self.fsync != FsyncPolicy::Never {
+            let file = self
+                .file
+                .get_mut()
+                .unwrap_or_else(|error| error.into_inner());
+
+            if let Err(error) = file.sync_data() {
+                log::warn!("Unable to flush {}: {error}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Read all complete records from a journal (which may not exist).
+pub fn read_journal<P: AsRef<Path>>(path: P) -> Result<Vec<Value>, Error> {
+    let path = path.as_ref();
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error.into()),
+    };
+
+    file.lock_shared()?;
+
+    let mut records = vec![];
+    let mut reader = BufReader::new(&file);
+    let mut line = String::new();
+    let mut line_number = 0;
+
+    let result = loop {
+        line.clear();
+        line_number += 1;
+
+        match reader.read_line(&mut line) {
+            Ok(0) => break Ok(()),
+            Ok(_) if line.trim().is_empty() => {}
+            Ok(_) => match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                // An incomplete last line is expected after a crash.
+                Err(_) if !line.ends_with('\n') => break Ok(()),
+                Err(error) => {
+                    log::warn!(
+                        "Skipping invalid record at {}:{line_number}: {error}",
+                        path.display()
+                    );
+                }
+            },
+            Err(error) => break Err(error),
+        }
+    };
+
+    file.unlock()?;
+    result?;
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use serde_json::json;
+
+    #[test]
+    fn test_journal() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("audit.ndjson");
+
+        assert!(read_journal(&path).unwrap().is_empty());
+
+        let journal = Journal::open(&path)
+            .unwrap()
+            .with_fsync(FsyncPolicy::Always);
+        let other = Journal::open(&path)
+            .unwrap()
+            .with_fsync(FsyncPolicy::Interval(Duration::from_secs(60)));
+
+        std::thread::scope(|scope| {
+            for (index, journal) in [&journal, &other].into_iter().enumerate() {
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        journal
+                            .append(&json!({"writer": index, "i": i, "padding": "x".repeat(4096)}))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        // A writer that crashed partway through a record.
+        let mut file = File::options().append(true).open(&path).unwrap();
+        file.write_all(b"{\"event\": \"incomp").unwrap();
+
+        journal
+            .append_all(&[json!({"event": "first"}), json!({"event": "second"})])
+            .unwrap();
+        drop((journal, other));
+
+        file.write_all(b"{\"event\": \"incomp").unwrap();
+
+        let records = read_journal(&path).unwrap();
+        assert_eq!(records.len(), 202);
+        assert_eq!(records[201], json!({"event": "second"}));
+
+        for writer in [0, 1] {
+            let indices = records
+                .iter()
+                .filter(|record| record["writer"] == writer)
+                .map(|record| record["i"].as_u64().unwrap())
+                .collect::<Vec<_>>();
+
+            assert_eq!(indices, (0..100).collect::<Vec<_>>());
+        }
+    }
+}
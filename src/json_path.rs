@@ -0,0 +1,214 @@
+//! Simple field paths for extracting values from JSON records.
+//!
+//! Paths are sequences of keys and array indices, such as `user.entities.urls[0].expanded_url`. Negative indices count
+//! from the end of the array, and keys containing `.` or `[` can be written in quoted brackets (`headers["x.y"]`), where
+//! `"` and `\` are escaped with a backslash.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Key(String),
+    Index(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Extract the value at this path, if it exists.
+    pub fn extract<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => {
+                    let values = value.as_array()?;
+                    let index = if *index < 0 {
+                        values.len().checked_sub(index.unsigned_abs() as usize)?
+                    } else {
+                        *index as usize
+                    };
+
+                    values.get(index)
+                }
+            })
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |message: &str| Error::InvalidJsonPath(format!("{message} in {s:?}"));
+        let mut segments = vec![];
+        let mut rest = s.strip_prefix('.').unwrap_or(s);
+
+        while !rest.is_empty() {
+            if let Some(bracketed) = rest.strip_prefix('[') {
+                if let Some(quoted) = bracketed.strip_prefix('"') {
+                    let (key, next) = parse_quoted_key(quoted)
+                        .ok_or_else(|| invalid("unterminated quoted key"))?;
+
+                    segments.push(Segment::Key(key));
+                    rest = next;
+                } else {
+                    let end = bracketed
+                        .find(']')
+                        .ok_or_else(|| invalid("unterminated index"))?;
+                    let index = bracketed[..end]
+                        .trim()
+                        .parse()
+                        .map_err(|_| invalid("invalid index"))?;
+
+                    segments.push(Segment::Index(index));
+                    rest = &bracketed[end + 1..];
+                }
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+
+                if end == 0 {
+                    return Err(invalid("empty key"));
+                }
+
+                segments.push(Segment::Key(rest[..end].to_string()));
+                rest = &rest[end..];
+            }
+
+            if let Some(next) = rest.strip_prefix('.') {
+                if next.is_empty() || next.starts_with(['.', '[']) {
+                    return Err(invalid("empty key"));
+                }
+
+                rest = next;
+            } else if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid("expected \".\" or \"[\""));
+            }
+        }
+
+        if segments.is_empty() {
+            Err(invalid("empty path"))
+        } else {
+            Ok(Self { segments })
+        }
+    }
+}
+
+/// Parse the rest of a quoted key (after `["`), returning the key and the remaining input after the closing `"]`.
+fn parse_quoted_key(input: &str) -> Option<(String, &str)> {
+    let mut key = String::new();
+    let mut chars = input.char_indices();
+
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '\\' => key.push(chars.next()?.1),
+            '"' => {
+                return input[offset + 1..]
+                    .strip_prefix(']')
+                    .map(|rest| (key, rest))
+            }
+            c => key.push(c),
+        }
+    }
+
+    None
+}
+
+impl Display for JsonPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Key(key) if key.is_empty() || key.contains(['.', '[', ']', '"', '\\']) => {
+                    write!(f, "[\"")?;
+
+                    for c in key.chars() {
+                        if c == '"' || c == '\\' {
+                            write!(f, "\\")?;
+                        }
+
+                        write!(f, "{c}")?;
+                    }
+
+                    write!(f, "\"]")?
+                }
+                Segment::Key(key) if i == 0 => write!(f, "{key}")?,
+                Segment::Key(key) => write!(f, ".{key}")?,
+                Segment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path() {
+        let value = json!({
+            "user": {"entities": {"urls": [{"expanded_url": "a"}, {"expanded_url": "b"}]}},
+            "headers": {"x.y": 1, "a\"]b\\": 2}
+        });
+
+        let extract = |path: &str| path.parse::<JsonPath>().unwrap().extract(&value).cloned();
+
+        assert_eq!(
+            extract("user.entities.urls[0].expanded_url"),
+            Some(json!("a"))
+        );
+        assert_eq!(
+            extract(".user.entities.urls[-1].expanded_url"),
+            Some(json!("b"))
+        );
+        assert_eq!(extract("user.entities.urls[2]"), None);
+        assert_eq!(extract("user.entities.urls[-3]"), None);
+        assert_eq!(extract("headers[\"x.y\"]"), Some(json!(1)));
+        assert_eq!(extract("headers[\"a\\\"]b\\\\\"]"), Some(json!(2)));
+        assert_eq!(extract("user.missing"), None);
+
+        for path in [
+            "user.entities.urls[0].expanded_url",
+            "headers[\"x.y\"]",
+            "headers[\"a\\\"]b\\\\\"]",
+            "headers[\"\"]",
+        ] {
+            assert_eq!(path.parse::<JsonPath>().unwrap().to_string(), path);
+        }
+
+        let path = JsonPath {
+            segments: ["a\"]", "\\", "[x]", "\"", ""]
+                .into_iter()
+                .map(|key| Segment::Key(key.to_string()))
+                .collect(),
+        };
+        assert_eq!(path.to_string().parse::<JsonPath>().unwrap(), path);
+
+        for path in [
+            "",
+            "user..name",
+            "user.",
+            "urls[x]",
+            "urls[0",
+            "urls[0]x",
+            "headers[\"x",
+            "headers[\"x\\\"]",
+            "headers[\"x\"",
+        ] {
+            assert!(path.parse::<JsonPath>().is_err(), "{path}");
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! State about the previous successful run, for incremental processing.
+//!
+//! A [`LastRun`] records when a run started (along with an optional summary, such as [`Summary::to_json`]) in the
+//! application state directory. Recording the start time rather than the end time means that items that appear while
+//! a run is in progress are picked up by the next run.
+//!
+//! ```rust,no_run
+//! use cli_helpers::{app_dirs::AppDirs, last_run::LastRun, summary::Summary, Timestamp};
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let dirs = AppDirs::new("mytool")?;
+//! let started = Timestamp::now();
+//! let summary = Summary::new();
+//! // ...
+//! LastRun::new(started).with_summary(summary.to_json()).save(&dirs)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`SinceArgs`] provides `--since` and `--since-last-run` options that resolve to a starting timestamp.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::{app_dirs::AppDirs, Error, Timestamp, TimestampFormat};
+
+pub(crate) const LAST_RUN_FILE_NAME: &str = "last-run.json";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LastRun {
+    timestamp: Timestamp,
+    summary: Option<Value>,
+}
+
+impl LastRun {
+    pub fn new(timestamp: Timestamp) -> Self {
+        Self {
+            timestamp,
+            summary: None,
+        }
+    }
+
+    pub fn with_summary(mut self, summary: Value) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// The start of the run.
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp
+    }
+
+    pub fn summary(&self) -> Option<&Value> {
+        self.summary.as_ref()
+    }
+
+    /// The state file path (which may not exist).
+    pub fn path(dirs: &AppDirs) -> PathBuf {
+        dirs.state_dir().join(LAST_RUN_FILE_NAME)
+    }
+
+    /// Load the state of the previous successful run, if there has been one.
+    pub fn load(dirs: &AppDirs) -> Result<Option<Self>, Error> {
+        Self::load_file(Self::path(dirs))
+    }
+
+    /// Save this run as the last successful run.
+    pub fn save(&self, dirs: &AppDirs) -> Result<(), Error> {
+        let path = AppDirs::resolve_file(None, dirs.state_dir(), LAST_RUN_FILE_NAME)?;
+
+        self.save_file(path)
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Option<Self>, Error> {
+        let path = path.as_ref();
+
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let invalid = || Error::InvalidLastRun(path.to_path_buf());
+        let mut value = serde_json::from_str::<Value>(&contents).map_err(|_| invalid())?;
+
+        let timestamp = value
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|timestamp| timestamp.parse::<Timestamp>().ok())
+            .ok_or_else(invalid)?;
+        let summary = value
+            .as_object_mut()
+            .and_then(|object| object.remove("summary"));
+
+        Ok(Some(Self { timestamp, summary }))
+    }
+
+    /// Write the state file, replacing any existing file atomically.
+    pub fn save_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut value = serde_json::Map::new();
+        value.insert(
+            "timestamp".to_string(),
+            self.timestamp
+                .format_as(TimestampFormat::Rfc3339 { use_z: true })
+                .into(),
+        );
+
+        if let Some(summary) = &self.summary {
+            value.insert("summary".to_string(), summary.clone());
+        }
+
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, format!("{}\n", Value::Object(value)))?;
+        std::fs::rename(&temp_path, path)?;
+
+        Ok(())
+    }
+}
+
+/// Standard options for selecting a starting timestamp for incremental processing.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SinceArgs {
+    /// Only process items since this time
+    #[clap(long, global = true)]
+    since: Option<Timestamp>,
+    /// Only process items since the start of the last successful run
+    #[clap(long, global = true, conflicts_with = "since")]
+    since_last_run: bool,
+}
+
+impl SinceArgs {
+    pub fn new(since: Option<Timestamp>, since_last_run: bool) -> Self {
+        Self {
+            since,
+            since_last_run,
+        }
+    }
+
+    /// The starting timestamp, if any.
+    ///
+    /// With `--since-last-run`, this is `None` if there has not been a successful run yet.
+    pub fn since(&self, dirs: &AppDirs) -> Result<Option<Timestamp>, Error> {
+        if self.since_last_run {
+            let last_run = LastRun::load(dirs)?;
+
+            if last_run.is_none() {
+                log::info!("No previous run found, processing everything");
+            }
+
+            Ok(last_run.map(|last_run| last_run.timestamp()))
+        } else {
+            Ok(self.since)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_last_run() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join(LAST_RUN_FILE_NAME);
+
+        assert_eq!(LastRun::load_file(&path).unwrap(), None);
+
+        let timestamp = "2024-03-01T12:30:00Z".parse::<Timestamp>().unwrap();
+        let last_run = LastRun::new(timestamp).with_summary(serde_json::json!({"records": 17}));
+        last_run.save_file(&path).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"timestamp\":\"2024-03-01T12:30:00Z\",\"summary\":{\"records\":17}}\n"
+        );
+        assert_eq!(LastRun::load_file(&path).unwrap(), Some(last_run));
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(LastRun::load_file(&path).is_err());
+    }
+}
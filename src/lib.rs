@@ -2,7 +2,8 @@
 //!
 //! ## Example
 //!
-//! The [`prelude`] module exports a minimal subset of these two crates.
+//! The [`prelude`] module exports a minimal subset of these two crates, including clap's derivable traits (and their
+//! derive macros).
 //!
 //! ```rust,no_run
 //! use cli_helpers::prelude::*;
@@ -16,28 +17,300 @@
 //!
 //! fn main() -> Result<(), cli_helpers::Error> {
 //!     let opts: Opts = Opts::parse();
+//!     # #[cfg(feature = "logging")]
 //!     opts.verbose.init_logging()?;
 //!     Ok(())
 //! }
 //! ```
 //!
+//! Everything except the core argument types ([`Verbosity`] and [`Timestamp`]), the [`prelude`], [`clap_compat`],
+//! [`timestamp_parser`], and [`Error`] requires the `logging` feature, which is enabled by default (and which the other
+//! optional features enable). Libraries that only need the argument types and value parsers can disable default
+//! features to avoid depending on simplelog, directories, and serde_json.
+//!
+//! The crate also compiles for WASI (`wasm32-wasip1`) both with default features and without them (both are checked in
+//! CI), although platform-specific helpers such as the daemon and signal handling are not available there.
+//!
+//! ## Clap compatibility
+//!
+//! This crate depends on clap 4, and the prelude re-exports `clap` itself along with the [`Parser`](clap::Parser),
+//! [`Args`](clap::Args), [`Subcommand`](clap::Subcommand), and [`ValueEnum`](clap::ValueEnum) traits and derive
+//! macros. The derived code refers to `clap` by name, so applications that import the prelude do not need their own
+//! clap dependency, and always use the same version as this crate. A move to a new major version of clap will only
+//! happen in a breaking release of this crate. The rest of clap is re-exported from [`clap_compat`], which also
+//! exposes the clap version.
+//!
 //! [clap]: https://docs.rs/clap/latest/clap/
 //! [simplelog]: https://docs.rs/simplelog/latest/simplelog/
 
+#[cfg(feature = "logging")]
+pub mod app;
+#[cfg(feature = "logging")]
+pub mod app_dirs;
+#[cfg(feature = "logging")]
+pub mod audit;
+#[cfg(feature = "logging")]
+pub mod backup;
+#[cfg(feature = "logging")]
+pub mod batch;
+#[cfg(feature = "logging")]
+pub mod byte_size;
+#[cfg(feature = "logging")]
+pub mod cache;
+#[cfg(feature = "logging")]
+pub mod chart;
+pub mod clap_compat;
+#[cfg(feature = "logging")]
+pub mod clean;
+pub mod clock;
+#[cfg(feature = "logging")]
+pub mod color;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "logging")]
+pub mod crash;
+#[cfg(feature = "cron")]
+pub mod cron;
+#[cfg(all(unix, feature = "daemon"))]
+pub mod daemon;
+#[cfg(feature = "logging")]
+pub mod deprecation;
+#[cfg(feature = "logging")]
+pub mod diff;
+#[cfg(feature = "disk-space")]
+pub mod disk_space;
+#[cfg(feature = "logging")]
+pub mod doctor;
+#[cfg(feature = "dotenv")]
+pub mod dotenv;
+#[cfg(feature = "logging")]
+pub mod edit;
+#[cfg(feature = "logging")]
+pub mod env;
+#[cfg(feature = "logging")]
+pub mod filter;
+#[cfg(feature = "logging")]
+pub mod first_run;
+#[cfg(feature = "logging")]
+pub mod follow;
+#[cfg(feature = "logging")]
+pub mod help;
+#[cfg(feature = "logging")]
+pub mod http;
+#[cfg(feature = "http-cache")]
+pub mod http_cache;
+#[cfg(feature = "logging")]
+pub mod i18n;
+#[cfg(feature = "logging")]
+pub mod journal;
+#[cfg(feature = "logging")]
+pub mod json_path;
+#[cfg(feature = "logging")]
+pub mod last_run;
+#[cfg(feature = "logging")]
+pub mod logging;
+#[cfg(feature = "logging")]
+pub mod memory;
+#[cfg(feature = "logging")]
+pub mod multicall;
+#[cfg(feature = "logging")]
+pub mod ndjson;
+#[cfg(feature = "logging")]
+pub mod netrc;
+#[cfg(feature = "logging")]
+pub mod output;
+#[cfg(feature = "logging")]
+pub mod parse_error;
+#[cfg(feature = "logging")]
+pub mod period;
+#[cfg(feature = "logging")]
+pub mod pid_file;
+#[cfg(feature = "logging")]
+pub mod plugin;
+#[cfg(feature = "logging")]
+pub mod preset;
+#[cfg(feature = "priority")]
+pub mod priority;
+#[cfg(feature = "logging")]
+pub mod progress;
+#[cfg(feature = "logging")]
+pub mod redact;
+#[cfg(feature = "logging")]
+pub mod reload;
+#[cfg(feature = "logging")]
+pub mod report;
+#[cfg(feature = "logging")]
+pub mod rerun;
+#[cfg(feature = "logging")]
+pub mod runtime;
+#[cfg(feature = "logging")]
+pub mod scaffold;
+#[cfg(feature = "logging")]
+pub mod schedule;
+#[cfg(feature = "logging")]
+pub mod secret;
+#[cfg(feature = "logging")]
+pub mod shell;
+#[cfg(feature = "logging")]
+pub mod shutdown;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "logging")]
+pub mod summary;
+#[cfg(feature = "logging")]
+pub mod symbols;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "logging")]
+pub mod temp;
+#[cfg(feature = "logging")]
+pub mod term;
+#[cfg(feature = "logging")]
+pub mod testing;
+pub mod timestamp_parser;
+#[cfg(feature = "logging")]
+pub mod timezone;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "logging")]
+pub mod work_queue;
+
+use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
-use chrono::{DateTime, TimeZone, Utc};
-use simplelog::LevelFilter;
+use chrono::{
+    DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc,
+};
+use log::LevelFilter;
 
 const TIMESTAMP_FMT_EN_US: &str = "%a %b %e %I:%M:%S %p %z %Y";
+const DATE_FMT: &str = "%Y-%m-%d";
+const HTTP_DATE_FMT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+const GIT_LOG_FMT: &str = "%a %b %e %H:%M:%S %Y %z";
+const GIT_LOG_FMT_OUTPUT: &str = "%a %b %-d %H:%M:%S %Y %z";
+const NAIVE_DATE_TIME_FMTS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M",
+];
 const S_TO_MS_CUTOFF: i64 = 1000000000000;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
     #[error("Logger initialization error")]
     Logger(#[from] log::SetLoggerError),
     #[error("Invalid timestamp format")]
     InvalidTimestamp(String),
+    #[error("Unable to determine application directories")]
+    NoAppDirs,
+    #[error("Missing API token")]
+    MissingToken(String),
+    #[error("Invalid batch size")]
+    InvalidBatchSize(usize),
+    #[error("Invalid byte size: {0}")]
+    InvalidByteSize(String),
+    #[error("Invalid filter expression: {0}")]
+    InvalidFilter(String),
+    #[error("Invalid JSON path: {0}")]
+    InvalidJsonPath(String),
+    #[cfg(feature = "config")]
+    #[error("Invalid configuration file: {}: {message}", path.display())]
+    InvalidConfig {
+        path: std::path::PathBuf,
+        message: String,
+    },
+    #[cfg(feature = "config")]
+    #[error("Configuration file already exists: {}", .0.display())]
+    ConfigExists(std::path::PathBuf),
+    #[cfg(feature = "config")]
+    #[error("Unknown configuration profile: {name} (available: {})", if available.is_empty() { "none".to_string() } else { available.join(", ") })]
+    UnknownProfile {
+        name: String,
+        available: Vec<String>,
+    },
+    #[cfg(feature = "config")]
+    #[error("Alias expands to itself: {0}")]
+    RecursiveAlias(String),
+    #[cfg(feature = "cron")]
+    #[error("Invalid cron expression: {0}")]
+    InvalidCron(String),
+    #[cfg(feature = "priority")]
+    #[error("Invalid I/O priority: {0}")]
+    InvalidIoPriority(String),
+    #[cfg(feature = "disk-space")]
+    #[error("Not enough free space for {} ({required} required, {available} available)", path.display())]
+    InsufficientSpace {
+        path: std::path::PathBuf,
+        required: byte_size::ByteSize,
+        available: byte_size::ByteSize,
+    },
+    #[error("Invalid interval: {0}")]
+    InvalidInterval(String),
+    #[error("Invalid locale: {0}")]
+    InvalidLocale(String),
+    #[error("Already running (PID {pid} in {})", path.display())]
+    AlreadyRunning { pid: u32, path: std::path::PathBuf },
+    #[error("Invalid last run state: {}", .0.display())]
+    InvalidLastRun(std::path::PathBuf),
+    #[error("No previous run to repeat")]
+    NoPreviousRun,
+    #[error("The previous run cannot be repeated because its arguments include redacted secrets")]
+    RedactedRerun,
+    #[error("Cancelled")]
+    Cancelled,
+    #[error("File already exists: {}", .0.display())]
+    FileExists(std::path::PathBuf),
+    #[error("Invalid project name: {0}")]
+    InvalidProjectName(String),
+    #[error("Invalid netrc file: {0}")]
+    InvalidNetrc(String),
+    #[error("Invalid preset: {0}")]
+    InvalidPreset(String),
+    #[error("Unknown preset: {name} (available: {})", if available.is_empty() { "none".to_string() } else { available.join(", ") })]
+    UnknownPreset {
+        name: String,
+        available: Vec<String>,
+    },
+    #[error("Unknown command: {name} (available plugins: {})", if available.is_empty() { "none".to_string() } else { available.join(", ") })]
+    UnknownPlugin {
+        name: String,
+        available: Vec<String>,
+    },
+    #[error("Invalid period")]
+    InvalidPeriod(String),
+    #[error("Invalid timezone")]
+    InvalidTimezone(String),
+    #[error("Unable to detect the shell")]
+    UnknownShell,
+    #[cfg(feature = "logging")]
+    #[error("Unsupported log target")]
+    UnsupportedLogTarget(logging::LogTarget),
+    #[cfg(feature = "dotenv")]
+    #[error("Invalid .env file: {}:{line}", path.display())]
+    InvalidDotenv {
+        path: std::path::PathBuf,
+        line: usize,
+    },
+    #[cfg(feature = "http-cache")]
+    #[error("Unexpected 304 Not Modified response")]
+    UnexpectedNotModified(String),
+    #[cfg(feature = "keyring")]
+    #[error("Credential store error")]
+    Keyring(#[from] keyring::Error),
+    #[cfg(feature = "keyring")]
+    #[error("No credential store entry for {0}")]
+    MissingKeyringEntry(String),
+    #[cfg(feature = "store")]
+    #[error("SQLite error")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "store")]
+    #[error("Unsupported schema version")]
+    UnsupportedSchemaVersion { found: usize, expected: usize },
 }
 
 fn select_log_level_filter(verbosity: u8) -> LevelFilter {
@@ -63,21 +336,116 @@ impl Verbosity {
         Self { verbose }
     }
 
+    /// The verbosity that corresponds to the given level filter.
+    pub fn from_level_filter(level_filter: LevelFilter) -> Self {
+        Self {
+            verbose: level_filter as u8,
+        }
+    }
+
+    pub fn level_filter(&self) -> LevelFilter {
+        select_log_level_filter(self.verbose)
+    }
+
+    #[cfg(feature = "logging")]
     /// Initialize a default terminal logger with the indicated log level.
+    ///
+    /// Fails with [`Error::Logger`] if a logger has already been installed.
     pub fn init_logging(&self) -> Result<(), Error> {
-        Ok(simplelog::TermLogger::init(
-            select_log_level_filter(self.verbose),
-            simplelog::Config::default(),
-            simplelog::TerminalMode::Stderr,
-            simplelog::ColorChoice::Auto,
-        )?)
+        logging::Builder::new(self.level_filter()).init()
+    }
+
+    #[cfg(feature = "logging")]
+    /// Initialize a default terminal logger unless a logger has already been installed.
+    ///
+    /// Any other failure is reported on standard error, since there is no logger to report it.
+    pub fn init_logging_once(&self) {
+        if let Err(error) = self.try_init_logging() {
+            eprintln!("Error: {error}");
+        }
+    }
+
+    #[cfg(feature = "logging")]
+    /// Initialize a default terminal logger, returning whether it was installed (`false` if a logger already exists).
+    ///
+    /// The global maximum log level is only changed if the logger is installed.
+    pub fn try_init_logging(&self) -> Result<bool, Error> {
+        match self.init_logging() {
+            Ok(()) => Ok(true),
+            Err(Error::Logger(_)) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl From<LevelFilter> for Verbosity {
+    fn from(value: LevelFilter) -> Self {
+        Self::from_level_filter(value)
+    }
+}
+
+#[cfg(feature = "clap-verbosity-flag")]
+impl<L: clap_verbosity_flag::LogLevel> From<clap_verbosity_flag::Verbosity<L>> for Verbosity {
+    fn from(value: clap_verbosity_flag::Verbosity<L>) -> Self {
+        Self::from_level_filter(value.log_level_filter())
     }
 }
 
-/// A timestamp represented as either an epoch second or the `en_US.UTF-8` default on Linux.
+#[cfg(feature = "clap-verbosity-flag")]
+impl<L: clap_verbosity_flag::LogLevel> From<Verbosity> for clap_verbosity_flag::Verbosity<L> {
+    fn from(value: Verbosity) -> Self {
+        use clap_verbosity_flag::VerbosityFilter;
+
+        match value.level_filter() {
+            LevelFilter::Off => VerbosityFilter::Off,
+            LevelFilter::Error => VerbosityFilter::Error,
+            LevelFilter::Warn => VerbosityFilter::Warn,
+            LevelFilter::Info => VerbosityFilter::Info,
+            LevelFilter::Debug => VerbosityFilter::Debug,
+            LevelFilter::Trace => VerbosityFilter::Trace,
+        }
+        .into()
+    }
+}
+
+/// A timestamp represented as an epoch second (or millisecond), the `en_US.UTF-8` `date` default on Linux, an
+/// RFC 3339 date and time, an RFC 2822 (or HTTP) date, the default `git log` date format, or a date (which is
+/// interpreted as midnight UTC).
+///
+/// Relative values are also accepted: `now`, `today`, `yesterday`, and durations such as `3 hours ago` or `10m ago`
+/// (with units of seconds, minutes, hours, days, or weeks). These are resolved using the current [`clock`].
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Timestamp(DateTime<Utc>);
 
+impl Timestamp {
+    /// The current time according to the current [`clock`].
+    pub fn now() -> Self {
+        Self(clock::now())
+    }
+
+    /// The number of whole seconds since the epoch.
+    pub fn timestamp(&self) -> i64 {
+        self.0.timestamp()
+    }
+
+    pub fn timestamp_millis(&self) -> i64 {
+        self.0.timestamp_millis()
+    }
+
+    /// The number of nanoseconds since the epoch (`None` outside of the years 1677 to 2262).
+    pub fn timestamp_nanos(&self) -> Option<i64> {
+        self.0.timestamp_nanos_opt()
+    }
+
+    pub fn subsec_millis(&self) -> u32 {
+        self.0.timestamp_subsec_millis()
+    }
+
+    pub fn subsec_nanos(&self) -> u32 {
+        self.0.timestamp_subsec_nanos()
+    }
+}
+
 impl From<Timestamp> for DateTime<Utc> {
     fn from(value: Timestamp) -> Self {
         value.0
@@ -88,36 +456,357 @@ impl FromStr for Timestamp {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<FormattedTimestamp>()
+            .map(|formatted| formatted.timestamp())
+    }
+}
+
+/// The representation of a [`Timestamp`] in one of the accepted input formats.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum TimestampFormat {
+    EpochSeconds,
+    EpochMillis,
+    /// The `en_US.UTF-8` `date` default on Linux (with a numeric offset).
+    Date,
+    /// RFC 3339, with fractional seconds only when necessary.
+    Rfc3339 {
+        /// Whether to write a zero offset as `Z` instead of `+00:00`.
+        use_z: bool,
+    },
+    /// A date without a time (which truncates the time of day when formatting).
+    IsoDate,
+    /// An RFC 7231 HTTP date (always written in GMT).
+    HttpDate,
+    /// An RFC 2822 date with a numeric offset.
+    Rfc2822,
+    /// The default `git log` date format.
+    GitLog,
+}
+
+impl TimestampFormat {
+    fn format<Tz: TimeZone>(&self, timestamp: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        match self {
+            Self::EpochSeconds => timestamp.timestamp().to_string(),
+            Self::EpochMillis => timestamp.timestamp_millis().to_string(),
+            Self::Date => timestamp.format(TIMESTAMP_FMT_EN_US).to_string(),
+            Self::Rfc3339 { use_z } => timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, *use_z),
+            Self::IsoDate => timestamp.format(DATE_FMT).to_string(),
+            Self::HttpDate => timestamp.to_utc().format(HTTP_DATE_FMT).to_string(),
+            Self::Rfc2822 => timestamp.to_rfc2822(),
+            Self::GitLog => timestamp.format(GIT_LOG_FMT_OUTPUT).to_string(),
+        }
+    }
+}
+
+impl Timestamp {
+    /// Format the timestamp (in UTC).
+    pub fn format_as(&self, format: TimestampFormat) -> String {
+        format.format(&self.0)
+    }
+}
+
+/// A timestamp that remembers the format and offset it was parsed from, so that it can be written back in the same
+/// style.
+///
+/// Relative inputs (such as `3 hours ago`) are resolved and have the RFC 3339 format.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct FormattedTimestamp {
+    timestamp: DateTime<FixedOffset>,
+    format: TimestampFormat,
+}
+
+impl FormattedTimestamp {
+    /// Pair a timestamp with a format (using UTC for formats that include an offset).
+    pub fn new(timestamp: Timestamp, format: TimestampFormat) -> Self {
+        Self {
+            timestamp: timestamp.0.fixed_offset(),
+            format,
+        }
+    }
+
+    pub fn timestamp(&self) -> Timestamp {
+        Timestamp(self.timestamp.to_utc())
+    }
+
+    pub fn format(&self) -> TimestampFormat {
+        self.format
+    }
+
+    /// The original offset (UTC for inputs without one).
+    pub fn offset(&self) -> FixedOffset {
+        *self.timestamp.offset()
+    }
+
+    /// Replace the timestamp, keeping the original format and offset.
+    pub fn with_timestamp(self, timestamp: Timestamp) -> Self {
+        Self {
+            timestamp: timestamp.0.with_timezone(self.timestamp.offset()),
+            ..self
+        }
+    }
+}
+
+impl Display for FormattedTimestamp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.format.format(&self.timestamp))
+    }
+}
+
+impl FromStr for FormattedTimestamp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let utc = |timestamp: DateTime<Utc>, format| Self {
+            timestamp: timestamp.fixed_offset(),
+            format,
+        };
+
         s.parse::<i64>()
             .ok()
             .and_then(|timestamp_n| {
                 if timestamp_n < S_TO_MS_CUTOFF {
-                    Utc.timestamp_opt(timestamp_n, 0).single()
+                    Utc.timestamp_opt(timestamp_n, 0)
+                        .single()
+                        .map(|timestamp| utc(timestamp, TimestampFormat::EpochSeconds))
                 } else {
-                    Utc.timestamp_millis_opt(timestamp_n).single()
+                    Utc.timestamp_millis_opt(timestamp_n)
+                        .single()
+                        .map(|timestamp| utc(timestamp, TimestampFormat::EpochMillis))
                 }
             })
-            .map(Timestamp)
             .or_else(|| {
                 DateTime::parse_from_str(&tz_name_to_offset(s), TIMESTAMP_FMT_EN_US)
                     .ok()
-                    .map(|timestamp| Timestamp(timestamp.into()))
+                    .map(|timestamp| Self {
+                        timestamp,
+                        format: TimestampFormat::Date,
+                    })
+            })
+            .or_else(|| {
+                DateTime::parse_from_rfc3339(s).ok().map(|timestamp| Self {
+                    timestamp,
+                    format: TimestampFormat::Rfc3339 {
+                        use_z: s.ends_with(['Z', 'z']),
+                    },
+                })
+            })
+            .or_else(|| {
+                // Only dates in GMT are written back as HTTP dates, so that other offsets are preserved.
+                DateTime::parse_from_rfc2822(s).ok().map(|timestamp| Self {
+                    timestamp,
+                    format: if s.trim_end().ends_with("GMT") {
+                        TimestampFormat::HttpDate
+                    } else {
+                        TimestampFormat::Rfc2822
+                    },
+                })
+            })
+            .or_else(|| {
+                DateTime::parse_from_str(s, GIT_LOG_FMT)
+                    .ok()
+                    .map(|timestamp| Self {
+                        timestamp,
+                        format: TimestampFormat::GitLog,
+                    })
+            })
+            .or_else(|| {
+                NaiveDate::parse_from_str(s, DATE_FMT).ok().map(|date| {
+                    utc(
+                        date.and_time(NaiveTime::MIN).and_utc(),
+                        TimestampFormat::IsoDate,
+                    )
+                })
+            })
+            .or_else(|| {
+                parse_relative(s)
+                    .map(|timestamp| utc(timestamp, TimestampFormat::Rfc3339 { use_z: true }))
             })
             .ok_or_else(|| Error::InvalidTimestamp(s.to_string()))
     }
 }
 
+/// A [`Timestamp`] where inputs without an offset are interpreted in the local timezone.
+///
+/// In addition to a date (interpreted as local midnight), this accepts a date and time without an offset, such as
+/// `2024-03-01 14:00` or `2024-03-01T14:00:30`. Ambiguous local times (during a DST transition) resolve to the
+/// earlier instant, and nonexistent local times are rejected. All other inputs are parsed as for [`Timestamp`].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LocalTimestamp(Timestamp);
+
+impl LocalTimestamp {
+    pub fn timestamp(&self) -> Timestamp {
+        self.0
+    }
+}
+
+impl From<LocalTimestamp> for Timestamp {
+    fn from(value: LocalTimestamp) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for LocalTimestamp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match parse_naive(s) {
+            Some(naive) => chrono::Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|timestamp| Self(Timestamp(timestamp.to_utc())))
+                .ok_or_else(|| Error::InvalidTimestamp(s.to_string())),
+            None => s.parse().map(Self),
+        }
+    }
+}
+
+/// A spreadsheet serial date number, such as `45123.5`, as exported by Excel or Google Sheets.
+///
+/// Serial dates count days (with fractional times of day) since 1899-12-30, and are interpreted as UTC. Because of
+/// Excel's 1900 leap year bug, values before 1900-03-01 do not match Excel's interpretation.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SerialDate(Timestamp);
+
+impl SerialDate {
+    pub fn timestamp(&self) -> Timestamp {
+        self.0
+    }
+}
+
+impl From<SerialDate> for Timestamp {
+    fn from(value: SerialDate) -> Self {
+        value.0
+    }
+}
+
+impl FromStr for SerialDate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|days| days.is_finite() && *days >= 0.0)
+            .and_then(|days| {
+                let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_time(NaiveTime::MIN);
+                let millis = (days * 86_400_000.0).round() as i64;
+
+                epoch.checked_add_signed(chrono::Duration::try_milliseconds(millis)?)
+            })
+            .map(|timestamp| Self(Timestamp(timestamp.and_utc())))
+            .ok_or_else(|| Error::InvalidTimestamp(s.to_string()))
+    }
+}
+
+fn parse_naive(input: &str) -> Option<NaiveDateTime> {
+    NAIVE_DATE_TIME_FMTS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(input, DATE_FMT)
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN))
+        })
+}
+
+fn parse_relative(input: &str) -> Option<DateTime<Utc>> {
+    let input = input.trim().to_lowercase();
+    let now = clock::now();
+    let today = now.date_naive().and_time(NaiveTime::MIN).and_utc();
+
+    match input.as_str() {
+        "now" => Some(now),
+        "today" => Some(today),
+        "yesterday" => today.checked_sub_signed(chrono::Duration::days(1)),
+        _ => {
+            let amount = input.strip_suffix(" ago")?.trim_end();
+            let split = amount
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(amount.len());
+            let count = amount[..split].parse::<i64>().ok()?;
+
+            let duration = match amount[split..].trim_start() {
+                "s" | "sec" | "secs" | "second" | "seconds" => chrono::Duration::try_seconds(count),
+                "m" | "min" | "mins" | "minute" | "minutes" => chrono::Duration::try_minutes(count),
+                "h" | "hr" | "hrs" | "hour" | "hours" => chrono::Duration::try_hours(count),
+                "d" | "day" | "days" => chrono::Duration::try_days(count),
+                "w" | "week" | "weeks" => chrono::Duration::try_weeks(count),
+                _ => None,
+            }?;
+
+            now.checked_sub_signed(duration)
+        }
+    }
+}
+
 /// This is a very simple hack to support copy-paste from `date` for me without pulling in chrono-tz.
 fn tz_name_to_offset(input: &str) -> String {
     input.replace("CET", "+0100").replace("CEST", "+0200")
 }
 
+/// The standard prelude: the core argument types, clap's derive traits, and the log macros (in [`prelude::log`]).
+///
+/// [`prelude::minimal`] includes only the core types, and `prelude::full` adds the crate's reusable argument groups
+/// and helpers.
 pub mod prelude {
     pub use super::{Timestamp, Verbosity};
-    pub use ::clap::Parser;
+    pub use ::clap::{Args, Parser, Subcommand, ValueEnum};
     pub use clap;
     pub mod log {
-        pub use log::{error, info, warn, SetLoggerError};
+        pub use log::{debug, error, info, trace, warn, SetLoggerError};
+    }
+
+    /// Only the parser trait and the core argument types.
+    ///
+    /// This is enough to derive a parser without a direct dependency on clap:
+    ///
+    /// ```rust
+    /// use cli_helpers::prelude::minimal::*;
+    ///
+    /// #[derive(Debug, Parser)]
+    /// struct Opts {
+    ///     #[clap(flatten)]
+    ///     verbose: Verbosity,
+    ///     #[clap(long)]
+    ///     since: Option<Timestamp>,
+    /// }
+    ///
+    /// let opts = Opts::parse_from(["mytool", "-vv", "--since", "2023-08-25"]);
+    /// assert!(opts.since.is_some());
+    /// ```
+    pub mod minimal {
+        pub use crate::{Timestamp, Verbosity};
+        pub use ::clap;
+        pub use ::clap::Parser;
+    }
+
+    /// The standard prelude, the log macros, and the crate's reusable argument groups and helpers.
+    #[cfg(feature = "logging")]
+    pub mod full {
+        pub use super::{clap, log, Args, Parser, Subcommand, Timestamp, ValueEnum, Verbosity};
+        pub use ::log::{debug, error, info, trace, warn};
+
+        pub use crate::app::App;
+        pub use crate::app_dirs::AppDirs;
+        pub use crate::audit::{AuditTrail, HistoryArgs};
+        pub use crate::backup::BackupArgs;
+        pub use crate::byte_size::ByteSize;
+        pub use crate::color::ColorArgs;
+        #[cfg(feature = "config")]
+        pub use crate::config::{ConfigArgs, ConfigCommand};
+        pub use crate::doctor::DoctorArgs;
+        pub use crate::filter::FilterArgs;
+        pub use crate::logging::LogArgs;
+        pub use crate::output::{OutputArgs, OutputFormat, OutputRecord};
+        pub use crate::progress::ProgressArgs;
+        pub use crate::rerun::RerunArgs;
+        pub use crate::runtime::RuntimeLimitArgs;
+        pub use crate::shell::CompletionsArgs;
+        pub use crate::shutdown::Shutdown;
+        pub use crate::{Error, TimestampFormat};
     }
 }
 
@@ -162,4 +851,304 @@ mod tests {
 
         assert_eq!(parsed, expected);
     }
+
+    #[test]
+    fn test_prelude_derives() {
+        use super::prelude::*;
+
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(subcommand)]
+            command: Command,
+        }
+
+        #[derive(Debug, Subcommand, PartialEq, Eq)]
+        enum Command {
+            Export(ExportArgs),
+        }
+
+        #[derive(Debug, Args, PartialEq, Eq)]
+        struct ExportArgs {
+            #[clap(long, value_enum, default_value_t = Format::Json)]
+            format: Format,
+        }
+
+        #[derive(Debug, Clone, Copy, ValueEnum, PartialEq, Eq)]
+        enum Format {
+            Json,
+            Csv,
+        }
+
+        let opts = Opts::parse_from(["test", "export", "--format", "csv"]);
+        assert_eq!(
+            opts.command,
+            Command::Export(ExportArgs {
+                format: Format::Csv
+            })
+        );
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn test_tiered_preludes() {
+        mod minimal {
+            use crate::prelude::minimal::*;
+
+            #[derive(Debug, Parser)]
+            pub struct Opts {
+                #[clap(flatten)]
+                pub verbose: Verbosity,
+                #[clap(long)]
+                pub since: Option<Timestamp>,
+            }
+        }
+
+        mod full {
+            use crate::prelude::full::*;
+
+            #[derive(Debug, Parser)]
+            pub struct Opts {
+                #[clap(flatten)]
+                pub color: ColorArgs,
+                #[clap(subcommand)]
+                pub command: Command,
+            }
+
+            #[derive(Debug, Subcommand)]
+            pub enum Command {
+                Run(RunArgs),
+                Completions(CompletionsArgs),
+            }
+
+            #[derive(Debug, Args)]
+            pub struct RunArgs {
+                #[clap(long)]
+                pub limit: Option<ByteSize>,
+            }
+
+            pub fn run(opts: &Opts) {
+                debug!("{opts:?}");
+                trace!("{opts:?}");
+            }
+        }
+
+        use clap::Parser;
+
+        let opts = minimal::Opts::parse_from(["test", "-vv", "--since", "2023-08-25"]);
+        assert_eq!(opts.verbose, super::Verbosity::new(2));
+        assert!(opts.since.is_some());
+
+        let opts = full::Opts::parse_from(["test", "run", "--limit", "1MiB"]);
+        assert!(matches!(opts.command, full::Command::Run(_)));
+        full::run(&opts);
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn test_init_logging_once() {
+        use super::{Error, Verbosity};
+
+        let verbosity = Verbosity::new(2);
+
+        // Other tests may already have installed a logger.
+        verbosity.try_init_logging().unwrap();
+        assert!(!verbosity.try_init_logging().unwrap());
+        verbosity.init_logging_once();
+        assert!(matches!(verbosity.init_logging(), Err(Error::Logger(_))));
+    }
+
+    #[test]
+    fn test_verbosity_level_filter() {
+        use super::Verbosity;
+        use log::LevelFilter;
+
+        for verbose in 0..=5 {
+            let verbosity = Verbosity::new(verbose);
+            assert_eq!(Verbosity::from(verbosity.level_filter()), verbosity);
+        }
+
+        assert_eq!(Verbosity::new(9).level_filter(), LevelFilter::Trace);
+    }
+
+    #[cfg(feature = "clap-verbosity-flag")]
+    #[test]
+    fn test_clap_verbosity_flag_conversions() {
+        use super::Verbosity;
+        use clap_verbosity_flag::{ErrorLevel, InfoLevel};
+
+        let theirs = clap_verbosity_flag::Verbosity::<InfoLevel>::new(1, 0);
+        let ours = Verbosity::from(theirs);
+        assert_eq!(ours, Verbosity::new(4));
+
+        let back: clap_verbosity_flag::Verbosity<ErrorLevel> = ours.clone().into();
+        assert_eq!(back.log_level_filter(), ours.level_filter());
+    }
+
+    #[cfg(feature = "logging")]
+    #[test]
+    fn test_timestamp_relative() {
+        use super::{clock::with_clock, testing::FixedClock, Timestamp};
+        use chrono::{TimeZone, Utc};
+
+        let clock = FixedClock::new(Utc.timestamp_opt(1692946029, 0).single().unwrap());
+
+        with_clock(clock.clone(), || {
+            let parse = |input: &str| input.parse::<Timestamp>().unwrap().0.timestamp();
+
+            assert_eq!(Timestamp::now().0.timestamp(), 1692946029);
+            assert_eq!(parse("now"), 1692946029);
+            assert_eq!(parse("today"), 1692921600);
+            assert_eq!(parse("yesterday"), 1692835200);
+            assert_eq!(parse("3 hours ago"), 1692946029 - 3 * 60 * 60);
+            assert_eq!(parse("10m ago"), 1692946029 - 10 * 60);
+            assert!("3 fortnights ago".parse::<Timestamp>().is_err());
+
+            clock.advance(chrono::Duration::seconds(1));
+            assert_eq!(parse("now"), 1692946030);
+        });
+    }
+
+    #[test]
+    fn test_timestamp_iso_formats() {
+        use super::Timestamp;
+        use chrono::{TimeZone, Utc};
+
+        assert_eq!(
+            "2023-08-25T08:47:09+02:00".parse::<Timestamp>().unwrap(),
+            Timestamp(Utc.timestamp_opt(1692946029, 0).single().unwrap())
+        );
+        assert_eq!(
+            "2023-08-25".parse::<Timestamp>().unwrap(),
+            Timestamp(Utc.timestamp_opt(1692921600, 0).single().unwrap())
+        );
+        assert!("2023-13-25".parse::<Timestamp>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp_http_and_git_formats() {
+        use super::Timestamp;
+
+        let expected = "2023-08-26T06:47:09Z".parse::<Timestamp>().unwrap();
+
+        assert_eq!(
+            "Sat, 26 Aug 2023 06:47:09 GMT"
+                .parse::<Timestamp>()
+                .unwrap(),
+            expected
+        );
+        assert_eq!(
+            "Sat Aug 26 08:47:09 2023 +0200"
+                .parse::<Timestamp>()
+                .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_timestamp_subsec_precision() {
+        use super::Timestamp;
+
+        let timestamp = "1692946029123".parse::<Timestamp>().unwrap();
+        assert_eq!(timestamp.timestamp(), 1692946029);
+        assert_eq!(timestamp.timestamp_millis(), 1692946029123);
+        assert_eq!(timestamp.subsec_millis(), 123);
+
+        let timestamp = "2023-08-25T06:47:09.123456789Z"
+            .parse::<Timestamp>()
+            .unwrap();
+        assert_eq!(timestamp.subsec_millis(), 123);
+        assert_eq!(timestamp.subsec_nanos(), 123456789);
+        assert_eq!(timestamp.timestamp_nanos(), Some(1692946029123456789));
+    }
+
+    #[test]
+    fn test_serial_date() {
+        use super::{SerialDate, Timestamp};
+
+        let parse = |input: &str| input.parse::<SerialDate>().unwrap().timestamp();
+
+        assert_eq!(
+            parse("45123.5"),
+            "2023-07-16T12:00:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(parse("45123"), "2023-07-16".parse::<Timestamp>().unwrap());
+        assert!("-1".parse::<SerialDate>().is_err());
+        assert!("NaN".parse::<SerialDate>().is_err());
+    }
+
+    #[test]
+    fn test_local_timestamp() {
+        use super::{LocalTimestamp, Timestamp};
+        use chrono::{Local, NaiveDate, TimeZone};
+
+        let local = |hour, min| {
+            let naive = NaiveDate::from_ymd_opt(2024, 3, 1)
+                .unwrap()
+                .and_hms_opt(hour, min, 0)
+                .unwrap();
+            Timestamp(
+                Local
+                    .from_local_datetime(&naive)
+                    .earliest()
+                    .unwrap()
+                    .to_utc(),
+            )
+        };
+        let parse = |input: &str| input.parse::<LocalTimestamp>().unwrap().timestamp();
+
+        assert_eq!(parse("2024-03-01 14:00"), local(14, 0));
+        assert_eq!(parse("2024-03-01T14:00:00"), local(14, 0));
+        assert_eq!(parse("2024-03-01"), local(0, 0));
+        assert_eq!(
+            parse("2024-03-01T14:00:00Z"),
+            "2024-03-01T14:00:00Z".parse::<Timestamp>().unwrap()
+        );
+        assert!("2024-03-01 25:00".parse::<LocalTimestamp>().is_err());
+    }
+
+    #[test]
+    fn test_formatted_timestamp_round_trip() {
+        use super::{FormattedTimestamp, Timestamp, TimestampFormat};
+        use chrono::{TimeZone, Utc};
+
+        for input in [
+            "1692946029",
+            "1692946029123",
+            "Fri Aug 25 08:47:09 AM +0200 2023",
+            "2023-08-25T08:47:09+02:00",
+            "2023-08-25T06:47:09.123Z",
+            "2023-08-25",
+            "Sat, 26 Aug 2023 06:47:09 GMT",
+            "Sat, 26 Aug 2023 08:47:09 +0200",
+            "Sat, 26 Aug 2023 06:47:09 +0000",
+            "Sat Aug 26 06:47:09 2023 +0200",
+            "Sat Aug 5 06:47:09 2023 -0500",
+        ] {
+            assert_eq!(
+                input.parse::<FormattedTimestamp>().unwrap().to_string(),
+                input
+            );
+        }
+
+        let later = Timestamp(Utc.timestamp_opt(1692949629, 0).single().unwrap());
+        let formatted = "Fri Aug 25 08:47:09 AM +0200 2023"
+            .parse::<FormattedTimestamp>()
+            .unwrap();
+        assert_eq!(formatted.format(), TimestampFormat::Date);
+        assert_eq!(
+            "Sat, 26 Aug 2023 06:47:09 GMT"
+                .parse::<FormattedTimestamp>()
+                .unwrap()
+                .format(),
+            TimestampFormat::HttpDate
+        );
+        assert_eq!(
+            formatted.with_timestamp(later).to_string(),
+            "Fri Aug 25 09:47:09 AM +0200 2023"
+        );
+        assert_eq!(
+            later.format_as(TimestampFormat::Rfc3339 { use_z: true }),
+            "2023-08-25T07:47:09Z"
+        );
+    }
 }
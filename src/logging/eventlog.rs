@@ -0,0 +1,110 @@
+//! A logger that writes records to the Windows Event Log.
+//!
+//! Records are reported under an event source named after the executable. Sources that have not been registered (by
+//! an installer) still work, but Event Viewer will note that the event description is missing before showing the
+//! message.
+
+use std::ffi::c_void;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::EventLog::{
+    DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+    EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+};
+
+pub struct EventLogLogger {
+    level_filter: LevelFilter,
+    handle: HANDLE,
+}
+
+// Event log handles may be used from any thread.
+unsafe impl Send for EventLogLogger {}
+unsafe impl Sync for EventLogLogger {}
+
+impl EventLogLogger {
+    /// Register an event source named after the executable.
+    pub fn register(level_filter: LevelFilter) -> Result<Self, std::io::Error> {
+        Self::register_source(level_filter, &super::executable_name())
+    }
+
+    pub fn register_source(
+        level_filter: LevelFilter,
+        source: &str,
+    ) -> Result<Self, std::io::Error> {
+        let source = wide(source);
+        let handle = unsafe { RegisterEventSourceW(std::ptr::null(), source.as_ptr()) };
+
+        if handle.is_null() {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(Self {
+                level_filter,
+                handle,
+            })
+        }
+    }
+}
+
+fn event_type(level: Level) -> REPORT_EVENT_TYPE {
+    match level {
+        Level::Error => EVENTLOG_ERROR_TYPE,
+        Level::Warn => EVENTLOG_WARNING_TYPE,
+        Level::Info | Level::Debug | Level::Trace => EVENTLOG_INFORMATION_TYPE,
+    }
+}
+
+fn wide(value: &str) -> Vec<u16> {
+    value.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+impl Log for EventLogLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_filter
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let message = wide(&record.args().to_string());
+            let strings = [message.as_ptr()];
+
+            // There is nowhere to report a failure to write an event.
+            unsafe {
+                ReportEventW(
+                    self.handle,
+                    event_type(record.level()),
+                    0,
+                    0,
+                    std::ptr::null_mut(),
+                    1,
+                    0,
+                    strings.as_ptr(),
+                    std::ptr::null::<c_void>(),
+                );
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl Drop for EventLogLogger {
+    fn drop(&mut self) {
+        unsafe {
+            DeregisterEventSource(self.handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_type() {
+        assert_eq!(event_type(Level::Error), EVENTLOG_ERROR_TYPE);
+        assert_eq!(event_type(Level::Warn), EVENTLOG_WARNING_TYPE);
+        assert_eq!(event_type(Level::Trace), EVENTLOG_INFORMATION_TYPE);
+        assert_eq!(wide("ok"), vec![b'o' as u16, b'k' as u16, 0]);
+    }
+}
@@ -0,0 +1,100 @@
+//! A log file that can be reopened after rotation.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::Error;
+
+/// An appending log file writer, for use with [`Builder::with_sink`](super::Builder::with_sink).
+///
+/// Clones share the open file, so a clone kept by the application can [`reopen`](Self::reopen) the file the logger
+/// writes to (for example after logrotate has moved it away; see [`crate::reload`]).
+#[derive(Debug, Clone)]
+pub struct LogFile {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl LogFile {
+    /// Open the file for appending, creating it (and its parent directories) if necessary.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Arc::new(Mutex::new(open(path)?)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Close the current file and open the path again.
+    pub fn reopen(&self) -> Result<(), Error> {
+        let file = open(&self.path)?;
+        let mut current = self.file.lock().unwrap_or_else(|error| error.into_inner());
+
+        current.flush()?;
+        *current = file;
+
+        Ok(())
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .flush()
+    }
+}
+
+fn open(path: &Path) -> Result<File, Error> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_log_file() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("test.log");
+
+        let log_file = LogFile::open(&path).unwrap();
+        let mut writer = log_file.clone();
+        writeln!(writer, "first").unwrap();
+
+        std::fs::rename(&path, dir.join("test.log.1")).unwrap();
+        writeln!(writer, "second").unwrap();
+        log_file.reopen().unwrap();
+        writeln!(writer, "third").unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.join("test.log.1")).unwrap(),
+            "first\nsecond\n"
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "third\n");
+    }
+}
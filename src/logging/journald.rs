@@ -0,0 +1,174 @@
+//! A logger that sends records to the systemd journal using its native protocol.
+//!
+//! The record's target and key-value pairs are included as additional journal fields (with keys converted to
+//! uppercase, as the journal requires). Keys that would clash with the journal's own fields (such as `MESSAGE`,
+//! `PRIORITY`, or `CODE_LINE`) are prefixed with `KV_`.
+
+use std::os::unix::net::UnixDatagram;
+
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Value};
+
+use super::{syslog_severity, FieldVisitor};
+
+const SOCKET_PATH: &str = "/run/systemd/journal/socket";
+
+/// The prefix for key-value pairs whose names are reserved.
+const RESERVED_PREFIX: &str = "KV_";
+
+/// Fields set by this logger or given special meaning by the journal.
+const RESERVED_FIELDS: [&str; 7] = [
+    "DOCUMENTATION",
+    "ERRNO",
+    "INVOCATION_ID",
+    "PRIORITY",
+    "TARGET",
+    "TID",
+    "USER_INVOCATION_ID",
+];
+
+/// Prefixes of families of fields given special meaning by the journal.
+const RESERVED_FIELD_PREFIXES: [&str; 4] = ["CODE_", "MESSAGE", "SYSLOG_", RESERVED_PREFIX];
+
+pub struct JournaldLogger {
+    level_filter: LevelFilter,
+    identifier: String,
+    socket: UnixDatagram,
+}
+
+impl JournaldLogger {
+    /// Connect to the journal socket, identifying records with the executable name.
+    pub fn connect(level_filter: LevelFilter) -> Result<Self, std::io::Error> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(SOCKET_PATH)?;
+
+        Ok(Self {
+            level_filter,
+            identifier: super::executable_name(),
+            socket,
+        })
+    }
+
+    pub fn with_identifier(self, identifier: &str) -> Self {
+        Self {
+            identifier: identifier.to_string(),
+            ..self
+        }
+    }
+}
+
+fn encode(identifier: &str, record: &Record) -> Vec<u8> {
+    let mut fields = Map::new();
+    let _ = record.key_values().visit(&mut FieldVisitor(&mut fields));
+
+    let mut message = vec![];
+
+    add_field(&mut message, "MESSAGE", &record.args().to_string());
+    add_field(
+        &mut message,
+        "PRIORITY",
+        &syslog_severity(record.level()).to_string(),
+    );
+    add_field(&mut message, "SYSLOG_IDENTIFIER", identifier);
+    add_field(&mut message, "TARGET", record.target());
+
+    for (key, value) in fields {
+        let mut key = field_name(&key);
+
+        if is_reserved(&key) {
+            key.insert_str(0, RESERVED_PREFIX);
+        }
+
+        if !key.is_empty() {
+            match value {
+                Value::String(value) => add_field(&mut message, &key, &value),
+                value => add_field(&mut message, &key, &value.to_string()),
+            }
+        }
+    }
+
+    message
+}
+
+/// Journal field names may only contain uppercase letters, digits, and underscores, and may not start with an
+/// underscore or digit.
+fn field_name(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .skip_while(|c| *c == '_' || c.is_ascii_digit())
+        .collect()
+}
+
+/// Whether a field name would clash with a standard field (prefixed names are also reserved, so that they are never
+/// ambiguous).
+fn is_reserved(name: &str) -> bool {
+    RESERVED_FIELDS.contains(&name)
+        || RESERVED_FIELD_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+}
+
+fn add_field(message: &mut Vec<u8>, key: &str, value: &str) {
+    message.extend_from_slice(key.as_bytes());
+
+    // Values containing newlines must use the length-prefixed binary form.
+    if value.contains('\n') {
+        message.push(b'\n');
+        message.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        message.push(b'=');
+    }
+
+    message.extend_from_slice(value.as_bytes());
+    message.push(b'\n');
+}
+
+impl Log for JournaldLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_filter
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            // There is nowhere to report a failure to send a log message.
+            let _ = self.socket.send(&encode(&self.identifier, record));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let pairs: [(&str, log::kv::Value); 6] = [
+            ("record_id", 123.into()),
+            ("_note", "a\nb".into()),
+            ("message", "override".into()),
+            ("priority", 7.into()),
+            ("code_line", 1.into()),
+            ("kv_priority", 0.into()),
+        ];
+        let record = Record::builder()
+            .level(log::Level::Error)
+            .target("demo")
+            .args(format_args!("Failed"))
+            .key_values(&pairs)
+            .build();
+
+        assert_eq!(
+            encode("demo", &record),
+            b"MESSAGE=Failed\nPRIORITY=3\nSYSLOG_IDENTIFIER=demo\nTARGET=demo\nRECORD_ID=123\nNOTE\n\x03\0\0\0\0\0\0\0a\nb\n\
+            KV_MESSAGE=override\nKV_PRIORITY=7\nKV_CODE_LINE=1\nKV_KV_PRIORITY=0\n"
+        );
+    }
+}
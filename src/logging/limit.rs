@@ -0,0 +1,151 @@
+//! Deduplicated and rate-limited warnings for per-record problems in long-running loops.
+//!
+//! [`warn_once!`](crate::warn_once) logs only the first time its call site is reached, and
+//! [`warn_rate_limited!`](crate::warn_rate_limited) logs at most once per interval. Suppressed warnings are counted per
+//! call site, and [`log_suppressed_summary`] reports (and resets) the counts, typically once processing is complete:
+//!
+//! ```rust
+//! use cli_helpers::{warn_rate_limited, logging::limit::log_suppressed_summary};
+//! use std::time::Duration;
+//!
+//! for line in ["a", "b", "c"] {
+//!     warn_rate_limited!(Duration::from_secs(5), "Invalid record: {line}");
+//! }
+//!
+//! log_suppressed_summary();
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static SUPPRESSED: Mutex<Vec<&'static Callsite>> = Mutex::new(Vec::new());
+
+/// Log a warning only the first time this call site is reached.
+#[macro_export]
+macro_rules! warn_once {
+    ($($arg:tt)+) => {{
+        static CALLSITE: $crate::logging::limit::Callsite =
+            $crate::logging::limit::Callsite::new(file!(), line!());
+
+        if CALLSITE.should_log(None) {
+            $crate::prelude::log::warn!($($arg)+);
+        }
+    }};
+}
+
+/// Log a warning at most once per interval (a [`std::time::Duration`]) from this call site.
+#[macro_export]
+macro_rules! warn_rate_limited {
+    ($interval:expr, $($arg:tt)+) => {{
+        static CALLSITE: $crate::logging::limit::Callsite =
+            $crate::logging::limit::Callsite::new(file!(), line!());
+
+        if CALLSITE.should_log(Some($interval)) {
+            $crate::prelude::log::warn!($($arg)+);
+        }
+    }};
+}
+
+/// Log the number of warnings suppressed at each call site since the last summary.
+pub fn log_suppressed_summary() {
+    let callsites =
+        std::mem::take(&mut *SUPPRESSED.lock().unwrap_or_else(|error| error.into_inner()));
+
+    for callsite in callsites {
+        let count = callsite.suppressed.swap(0, Ordering::SeqCst);
+
+        if count > 0 {
+            log::warn!(
+                "Suppressed {count} repeated warning{} from {}:{}",
+                if count == 1 { "" } else { "s" },
+                callsite.file,
+                callsite.line
+            );
+        }
+    }
+}
+
+/// The state for a rate-limited call site (used by the macros).
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct Callsite {
+    file: &'static str,
+    line: u32,
+    last_logged: Mutex<Option<Instant>>,
+    suppressed: AtomicU64,
+}
+
+impl Callsite {
+    pub const fn new(file: &'static str, line: u32) -> Self {
+        Self {
+            file,
+            line,
+            last_logged: Mutex::new(None),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether to log now, given an interval (or `None` to log only once), recording a suppression if not.
+    pub fn should_log(&'static self, interval: Option<Duration>) -> bool {
+        if !log::log_enabled!(log::Level::Warn) {
+            return false;
+        }
+
+        let mut last_logged = self
+            .last_logged
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let now = Instant::now();
+
+        let should_log = match (*last_logged, interval) {
+            (None, _) => true,
+            (Some(last_logged), Some(interval)) => now.duration_since(last_logged) >= interval,
+            (Some(_), None) => false,
+        };
+
+        if should_log {
+            *last_logged = Some(now);
+        } else if self.suppressed.fetch_add(1, Ordering::SeqCst) == 0 {
+            SUPPRESSED
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .push(self);
+        }
+
+        should_log
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::capture_logs;
+
+    #[test]
+    fn test_warn_once_and_rate_limited() {
+        let records = capture_logs(|| {
+            for i in 0..5 {
+                crate::warn_once!("Invalid record {i}");
+                crate::warn_rate_limited!(Duration::from_secs(3600), "Slow record {i}");
+            }
+
+            log_suppressed_summary();
+        });
+
+        let messages = records
+            .iter()
+            .map(|record| record.message.split(" from ").next().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Invalid record 0",
+                "Slow record 0",
+                "Suppressed 4 repeated warnings",
+                "Suppressed 4 repeated warnings"
+            ]
+        );
+    }
+}
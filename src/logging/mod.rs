@@ -0,0 +1,1141 @@
+//! Logger configuration beyond the default terminal logger.
+//!
+//! [`LogArgs`] flattens [`Verbosity`] and adds `--log-format`, `--log-target`, and `--debug-dump` flags, and
+//! [`Builder`] provides the same options programmatically. The JSON format writes one object per line to standard
+//! error, including any structured key-value pairs attached to the record:
+//!
+//! ```rust
+//! use cli_helpers::prelude::log::info;
+//!
+//! info!(record_id = 123, status = "ok"; "Processed record");
+//! ```
+//!
+//! The `--log-only` and `--log-exclude` flags restrict output to records whose targets (usually module paths) start
+//! with the given prefixes.
+//!
+//! Terminal text output is colored according to the crate's [color policy](crate::color). Pass the resolved `--color`
+//! choice with [`Builder::with_color`] (for example `log_args.builder().with_color(color_args.choice())`).
+//!
+//! Thread names (or IDs) are included in each record when the verbosity is debug or higher, or always with
+//! `--log-threads`, so that interleaved output from parallel workers can be attributed.
+//!
+//! Additional output can be sent to arbitrary writers (such as an in-memory buffer or a socket) with
+//! [`Builder::with_sink`], each with its own level filter. A [`file::LogFile`] sink can be reopened after log rotation.
+//!
+//! With `--debug-dump <PATH>`, every record (regardless of verbosity) is also retained in a bounded in-memory
+//! [`RingBuffer`], which is written to the path if the program panics, or when the application calls
+//! [`write_debug_dump`] (typically on error). Note that this requires enabling all log levels globally.
+//!
+//! [`Builder::with_crash_reports`] makes the panic hook also write a crash report file (see [`crate::crash`]).
+//!
+//! On Unix, the `syslog` and `journald` features add log targets for tools that run from cron or systemd timers, and on
+//! Windows, the `eventlog` feature adds the Windows Event Log for scheduled tasks. These targets ignore the log format,
+//! since the receiving service records the time and level separately.
+//!
+//! On WebAssembly targets (such as `wasm32-wasip1`), simplelog is not used, and text output is written by the minimal
+//! [`TextLogger`] instead.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::{kv::VisitSource, LevelFilter, Log, Metadata, Record};
+use serde_json::{Map, Value};
+
+use crate::color::ColorChoice;
+use crate::crash::{self, thread_name, CrashReport};
+use crate::i18n::{self, ids};
+use crate::{app_dirs::AppDirs, clock, Error, Verbosity};
+
+#[cfg(all(windows, feature = "eventlog"))]
+pub mod eventlog;
+pub mod file;
+#[cfg(all(unix, feature = "journald"))]
+pub mod journald;
+pub mod limit;
+pub mod span;
+#[cfg(all(unix, feature = "syslog"))]
+pub mod syslog;
+
+/// Standard logging arguments.
+#[derive(clap::Args, Debug, Clone, PartialEq, Eq)]
+pub struct LogArgs {
+    #[clap(flatten)]
+    verbosity: Verbosity,
+    /// Log output format
+    #[clap(long, global = true, value_enum, default_value_t)]
+    log_format: LogFormat,
+    /// Log destination
+    #[clap(long, global = true, value_enum, default_value_t)]
+    log_target: LogTarget,
+    /// Only log records whose target starts with this prefix (may be repeated)
+    #[clap(long, global = true, value_name = "TARGET")]
+    log_only: Vec<String>,
+    /// Do not log records whose target starts with this prefix (may be repeated)
+    #[clap(long, global = true, value_name = "TARGET")]
+    log_exclude: Vec<String>,
+    /// Include thread names or IDs in every record (by default only when the verbosity is debug or higher)
+    #[clap(long, global = true)]
+    log_threads: bool,
+    /// Write all log records (at any level) to this file on panic or error
+    #[clap(long, global = true)]
+    debug_dump: Option<PathBuf>,
+}
+
+impl LogArgs {
+    pub fn new(verbosity: Verbosity, log_format: LogFormat) -> Self {
+        Self {
+            verbosity,
+            log_format,
+            log_target: LogTarget::default(),
+            log_only: vec![],
+            log_exclude: vec![],
+            log_threads: false,
+            debug_dump: None,
+        }
+    }
+
+    pub fn with_log_target(self, log_target: LogTarget) -> Self {
+        Self { log_target, ..self }
+    }
+
+    pub fn with_debug_dump<P: AsRef<Path>>(self, path: P) -> Self {
+        Self {
+            debug_dump: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
+    pub fn verbosity(&self) -> &Verbosity {
+        &self.verbosity
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    pub fn log_target(&self) -> LogTarget {
+        self.log_target
+    }
+
+    pub fn builder(&self) -> Builder {
+        let builder = Builder::new(self.verbosity.level_filter())
+            .with_format(self.log_format)
+            .with_target(self.log_target);
+
+        let builder = if self.log_threads {
+            builder.with_thread_info(ThreadInfo::Always)
+        } else {
+            builder
+        };
+
+        let builder = self.log_only.iter().fold(builder, |builder, target| {
+            builder.with_allowed_target(target)
+        });
+
+        let builder = self.log_exclude.iter().fold(builder, |builder, target| {
+            builder.with_ignored_target(target)
+        });
+
+        match &self.debug_dump {
+            Some(path) => builder.with_debug_dump(path),
+            None => builder,
+        }
+    }
+
+    /// Initialize the configured logger.
+    ///
+    /// Fails with [`Error::Logger`] if a logger has already been installed.
+    pub fn init_logging(&self) -> Result<(), Error> {
+        self.builder().init()
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable terminal output
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+/// The destination for log records.
+///
+/// All targets are always listed, but selecting one that is not supported by the build fails at initialization.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogTarget {
+    /// Standard error
+    #[default]
+    Stderr,
+    /// The local syslog daemon (requires the `syslog` feature)
+    Syslog,
+    /// The systemd journal (requires the `journald` feature)
+    Journald,
+    /// The Windows Event Log (requires the `eventlog` feature)
+    Eventlog,
+}
+
+/// When to include the current thread's name (or ID, for unnamed threads) in log records.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThreadInfo {
+    /// In every record if the level filter is debug or more verbose, and otherwise never.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// The number of records retained for debug dumps.
+pub const DEFAULT_RING_BUFFER_CAPACITY: usize = 10_000;
+
+static DEBUG_DUMP: Mutex<Option<(RingBuffer, PathBuf)>> = Mutex::new(None);
+
+/// Write the retained log records to the `--debug-dump` path, returning `false` if no dump was configured.
+pub fn write_debug_dump() -> Result<bool, Error> {
+    let debug_dump = DEBUG_DUMP
+        .lock()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone();
+
+    match debug_dump {
+        Some((buffer, path)) => {
+            buffer.dump(path)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Builder {
+    level_filter: LevelFilter,
+    format: LogFormat,
+    target: LogTarget,
+    ring_buffer: Option<RingBuffer>,
+    debug_dump: Option<PathBuf>,
+    crash_reports: Option<CrashReports>,
+    sinks: Vec<Sink>,
+    target_filter: TargetFilter,
+    thread_info: ThreadInfo,
+    // The minimal text logger used on WebAssembly never colors output.
+    #[cfg_attr(target_family = "wasm", allow(dead_code))]
+    color: ColorChoice,
+}
+
+impl Builder {
+    pub fn new(level_filter: LevelFilter) -> Self {
+        Self {
+            level_filter,
+            format: LogFormat::default(),
+            target: LogTarget::default(),
+            ring_buffer: None,
+            debug_dump: None,
+            crash_reports: None,
+            sinks: vec![],
+            target_filter: TargetFilter::default(),
+            thread_info: ThreadInfo::default(),
+            color: ColorChoice::Auto,
+        }
+    }
+
+    pub fn with_format(self, format: LogFormat) -> Self {
+        Self { format, ..self }
+    }
+
+    pub fn with_target(self, target: LogTarget) -> Self {
+        Self { target, ..self }
+    }
+
+    pub fn with_thread_info(self, thread_info: ThreadInfo) -> Self {
+        Self {
+            thread_info,
+            ..self
+        }
+    }
+
+    /// When to color terminal text output (by default color is detected automatically).
+    pub fn with_color(self, color: ColorChoice) -> Self {
+        Self { color, ..self }
+    }
+
+    /// Only log records whose target (usually a module path) starts with one of the allowed prefixes.
+    ///
+    /// This applies to every logger except the ring buffer, which always retains all records.
+    pub fn with_allowed_target(mut self, prefix: &str) -> Self {
+        self.target_filter.allow.push(prefix.to_string());
+        self
+    }
+
+    /// Do not log records whose target starts with the prefix.
+    pub fn with_ignored_target(mut self, prefix: &str) -> Self {
+        self.target_filter.ignore.push(prefix.to_string());
+        self
+    }
+
+    /// Also retain all records (regardless of the level filter) in the given buffer.
+    pub fn with_ring_buffer(self, ring_buffer: RingBuffer) -> Self {
+        Self {
+            ring_buffer: Some(ring_buffer),
+            ..self
+        }
+    }
+
+    /// Retain all records and write them to the path on panic or on [`write_debug_dump`].
+    pub fn with_debug_dump<P: AsRef<Path>>(self, path: P) -> Self {
+        let ring_buffer = self
+            .ring_buffer
+            .clone()
+            .unwrap_or_else(|| RingBuffer::new(DEFAULT_RING_BUFFER_CAPACITY));
+
+        Self {
+            ring_buffer: Some(ring_buffer),
+            debug_dump: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
+    /// Write a crash report to the application state directory on panic (see [`crate::crash`]).
+    ///
+    /// Reports include recent log records only if a ring buffer is configured.
+    pub fn with_crash_reports(self, dirs: &AppDirs, version: &str) -> Self {
+        Self {
+            crash_reports: Some(CrashReports {
+                dir: dirs.state_dir().join(crash::CRASH_REPORT_DIR_NAME),
+                name: dirs.name().to_string(),
+                version: version.to_string(),
+            }),
+            ..self
+        }
+    }
+
+    /// Also write records at or above the given level to the writer, in the configured format.
+    pub fn with_sink(mut self, level_filter: LevelFilter, writer: Box<dyn Write + Send>) -> Self {
+        self.sinks.push(Sink {
+            level_filter,
+            writer: Arc::new(Mutex::new(writer)),
+        });
+        self
+    }
+
+    pub fn with_sinks<I: IntoIterator<Item = (LevelFilter, Box<dyn Write + Send>)>>(
+        self,
+        sinks: I,
+    ) -> Self {
+        sinks
+            .into_iter()
+            .fold(self, |builder, (level_filter, writer)| {
+                builder.with_sink(level_filter, writer)
+            })
+    }
+
+    /// Build the logger without installing it.
+    ///
+    /// Fails with [`Error::UnsupportedLogTarget`] if the target is not available in this build, or with an I/O error
+    /// if the target's socket cannot be opened.
+    pub fn build(&self) -> Result<Box<dyn Log>, Error> {
+        let mut loggers = vec![self.build_primary()?];
+
+        for sink in &self.sinks {
+            loggers.push(match self.format {
+                LogFormat::Text => self.text_logger(sink.level_filter, sink.clone()),
+                LogFormat::Json => self.target_filter.wrap(Box::new(
+                    JsonLogger::new(sink.level_filter, sink.clone())
+                        .with_thread_level(self.thread_level()),
+                )),
+            });
+        }
+
+        if let Some(ring_buffer) = &self.ring_buffer {
+            loggers.push(Box::new(ring_buffer.clone()));
+        }
+
+        Ok(if loggers.len() == 1 {
+            loggers.remove(0)
+        } else {
+            Box::new(Multi(loggers))
+        })
+    }
+
+    fn build_primary(&self) -> Result<Box<dyn Log>, Error> {
+        let logger: Box<dyn Log> = match (self.target, self.format) {
+            // simplelog applies the target filters itself.
+            #[cfg(not(target_family = "wasm"))]
+            (LogTarget::Stderr, LogFormat::Text) => {
+                return Ok(simplelog::TermLogger::new(
+                    self.level_filter,
+                    self.config(),
+                    simplelog::TerminalMode::Stderr,
+                    self.terminal_color(),
+                ))
+            }
+            #[cfg(target_family = "wasm")]
+            (LogTarget::Stderr, LogFormat::Text) => {
+                return Ok(self.text_logger(self.level_filter, std::io::stderr()))
+            }
+            (LogTarget::Stderr, LogFormat::Json) => Box::new(
+                JsonLogger::new(self.level_filter, std::io::stderr())
+                    .with_thread_level(self.thread_level()),
+            ),
+            #[cfg(all(unix, feature = "syslog"))]
+            (LogTarget::Syslog, _) => Box::new(syslog::SyslogLogger::connect(self.level_filter)?),
+            #[cfg(all(unix, feature = "journald"))]
+            (LogTarget::Journald, _) => {
+                Box::new(journald::JournaldLogger::connect(self.level_filter)?)
+            }
+            #[cfg(all(windows, feature = "eventlog"))]
+            (LogTarget::Eventlog, _) => {
+                Box::new(eventlog::EventLogLogger::register(self.level_filter)?)
+            }
+            #[allow(unreachable_patterns)]
+            (target, _) => return Err(Error::UnsupportedLogTarget(target)),
+        };
+
+        Ok(self.target_filter.wrap(logger))
+    }
+
+    /// Thread information is included for records at this level or more verbose (as in simplelog).
+    fn thread_level(&self) -> LevelFilter {
+        let always = match self.thread_info {
+            ThreadInfo::Auto => self.level_filter >= LevelFilter::Debug,
+            ThreadInfo::Always => true,
+            ThreadInfo::Never => false,
+        };
+
+        if always {
+            LevelFilter::Error
+        } else {
+            LevelFilter::Off
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn text_logger<W: Write + Send + 'static>(
+        &self,
+        level_filter: LevelFilter,
+        writer: W,
+    ) -> Box<dyn Log> {
+        simplelog::WriteLogger::new(level_filter, self.config(), writer)
+    }
+
+    /// simplelog is not available on WebAssembly, so text output uses the minimal [`TextLogger`].
+    #[cfg(target_family = "wasm")]
+    fn text_logger<W: Write + Send + 'static>(
+        &self,
+        level_filter: LevelFilter,
+        writer: W,
+    ) -> Box<dyn Log> {
+        self.target_filter.wrap(Box::new(
+            TextLogger::new(level_filter, writer).with_thread_level(self.thread_level()),
+        ))
+    }
+
+    /// Resolve the color choice for simplelog, which would otherwise ignore `NO_COLOR` and related variables.
+    #[cfg(not(target_family = "wasm"))]
+    fn terminal_color(&self) -> simplelog::ColorChoice {
+        if crate::color::enabled(self.color, crate::color::Stream::Stderr) {
+            simplelog::ColorChoice::Always
+        } else {
+            simplelog::ColorChoice::Never
+        }
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    fn config(&self) -> simplelog::Config {
+        let mut builder = simplelog::ConfigBuilder::new();
+
+        builder
+            .set_thread_level(self.thread_level())
+            .set_thread_mode(simplelog::ThreadLogMode::Both);
+
+        for prefix in &self.target_filter.allow {
+            builder.add_filter_allow(prefix.clone());
+        }
+
+        for prefix in &self.target_filter.ignore {
+            builder.add_filter_ignore(prefix.clone());
+        }
+
+        builder.build()
+    }
+
+    /// Install the logger.
+    ///
+    /// Fails with [`Error::Logger`] if a logger has already been installed.
+    pub fn init(&self) -> Result<(), Error> {
+        let max_level = if self.ring_buffer.is_some() {
+            LevelFilter::Trace
+        } else {
+            self.sinks
+                .iter()
+                .map(|sink| sink.level_filter)
+                .fold(self.level_filter, Ord::max)
+        };
+
+        #[cfg(any(test, feature = "testing"))]
+        crate::testing::install_logger(Some(self.build()?), max_level)?;
+
+        #[cfg(not(any(test, feature = "testing")))]
+        {
+            log::set_boxed_logger(self.build()?)?;
+            log::set_max_level(max_level);
+        }
+
+        if let (Some(ring_buffer), Some(path)) = (&self.ring_buffer, &self.debug_dump) {
+            *DEBUG_DUMP.lock().unwrap_or_else(|error| error.into_inner()) =
+                Some((ring_buffer.clone(), path.clone()));
+        }
+
+        if self.debug_dump.is_some() || self.crash_reports.is_some() {
+            let ring_buffer = self.ring_buffer.clone();
+            let crash_reports = self.crash_reports.clone();
+            let previous_hook = std::panic::take_hook();
+
+            std::panic::set_hook(Box::new(move |info| {
+                log::error!("{info}");
+                let _ = write_debug_dump();
+
+                let report = crash_reports.as_ref().and_then(|crash_reports| {
+                    let log = ring_buffer
+                        .as_ref()
+                        .map(RingBuffer::lines)
+                        .unwrap_or_default();
+
+                    CrashReport::from_panic(&crash_reports.name, &crash_reports.version, info)
+                        .with_log(log)
+                        .write_to_dir(&crash_reports.dir)
+                        .ok()
+                });
+
+                previous_hook(info);
+
+                if let Some(path) = report {
+                    eprintln!(
+                        "{}",
+                        i18n::message(ids::CRASH_REPORT_WRITTEN, &[("path", &path.display())])
+                    );
+                }
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+/// The syslog severity for a log level (which the journal also uses for priorities).
+#[cfg(all(unix, any(feature = "syslog", feature = "journald")))]
+fn syslog_severity(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 3,
+        log::Level::Warn => 4,
+        log::Level::Info => 6,
+        log::Level::Debug | log::Level::Trace => 7,
+    }
+}
+
+#[cfg(any(
+    all(unix, any(feature = "syslog", feature = "journald")),
+    all(windows, feature = "eventlog")
+))]
+fn executable_name() -> String {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[derive(Debug, Clone)]
+struct CrashReports {
+    dir: PathBuf,
+    name: String,
+    version: String,
+}
+
+/// Allow and ignore lists of target prefixes (with the same semantics as simplelog's filters).
+#[derive(Debug, Clone, Default)]
+struct TargetFilter {
+    allow: Vec<String>,
+    ignore: Vec<String>,
+}
+
+impl TargetFilter {
+    fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.ignore.is_empty()
+    }
+
+    fn skips(&self, target: &str) -> bool {
+        (!self.allow.is_empty() && !self.allow.iter().any(|prefix| target.starts_with(prefix)))
+            || self.ignore.iter().any(|prefix| target.starts_with(prefix))
+    }
+
+    fn wrap(&self, logger: Box<dyn Log>) -> Box<dyn Log> {
+        if self.is_empty() {
+            logger
+        } else {
+            Box::new(Filtered {
+                filter: self.clone(),
+                logger,
+            })
+        }
+    }
+}
+
+struct Filtered {
+    filter: TargetFilter,
+    logger: Box<dyn Log>,
+}
+
+impl Log for Filtered {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        !self.filter.skips(metadata.target()) && self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.filter.skips(record.target()) {
+            self.logger.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.logger.flush();
+    }
+}
+
+/// A shared writer for additional log output.
+#[derive(Clone)]
+struct Sink {
+    level_filter: LevelFilter,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl std::fmt::Debug for Sink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sink")
+            .field("level_filter", &self.level_filter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .flush()
+    }
+}
+
+/// Dispatch records to several loggers.
+struct Multi(Vec<Box<dyn Log>>);
+
+impl Log for Multi {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.iter().any(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        for logger in &self.0 {
+            if logger.enabled(record.metadata()) {
+                logger.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        for logger in &self.0 {
+            logger.flush();
+        }
+    }
+}
+
+/// A logger that retains the most recent records (at every level) as formatted lines.
+#[derive(Debug, Clone)]
+pub struct RingBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity.min(1024)))),
+            capacity,
+        }
+    }
+
+    /// The retained lines, oldest first.
+    pub fn lines(&self) -> Vec<String> {
+        self.lock().iter().cloned().collect()
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        for line in self.lock().iter() {
+            writeln!(writer, "{line}")?;
+        }
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn dump<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.write_to(std::io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<String>> {
+        self.lines.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Log for RingBuffer {
+    fn enabled(&self, _: &Metadata) -> bool {
+        self.capacity > 0
+    }
+
+    fn log(&self, record: &Record) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut line = format!(
+            "{} {:<5} [{}] {}",
+            clock::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        let mut fields = Map::new();
+        let _ = record.key_values().visit(&mut FieldVisitor(&mut fields));
+
+        for (key, value) in fields {
+            line.push_str(&format!(" {key}={value}"));
+        }
+
+        let mut lines = self.lock();
+
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+
+        lines.push_back(line);
+    }
+
+    fn flush(&self) {}
+}
+
+/// A logger that writes each record as a JSON object on its own line.
+///
+/// Objects have `timestamp`, `level`, `target`, and `message` fields, followed by the record's key-value pairs.
+pub struct JsonLogger {
+    level_filter: LevelFilter,
+    thread_level: LevelFilter,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLogger {
+    pub fn new<W: Write + Send + 'static>(level_filter: LevelFilter, writer: W) -> Self {
+        Self {
+            level_filter,
+            thread_level: LevelFilter::Off,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Include a `thread` field for records at this level or more verbose.
+    pub fn with_thread_level(self, thread_level: LevelFilter) -> Self {
+        Self {
+            thread_level,
+            ..self
+        }
+    }
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_filter
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut fields = record_json(record);
+
+        if self.thread_level != LevelFilter::Off && self.thread_level <= record.level() {
+            fields.insert("thread".to_string(), thread_name().into());
+        }
+
+        let mut line = serde_json::to_string(&fields).unwrap_or_default();
+        line.push('\n');
+
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        // There is nowhere to report a failure to write a log line.
+        let _ = writer.write_all(line.as_bytes());
+    }
+
+    fn flush(&self) {
+        let _ = self
+            .writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .flush();
+    }
+}
+
+/// A minimal logger that writes each record as a line of text (in a format similar to simplelog's).
+///
+/// This is the text logger on WebAssembly, where simplelog is not available.
+pub struct TextLogger {
+    level_filter: LevelFilter,
+    thread_level: LevelFilter,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl TextLogger {
+    pub fn new<W: Write + Send + 'static>(level_filter: LevelFilter, writer: W) -> Self {
+        Self {
+            level_filter,
+            thread_level: LevelFilter::Off,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Include the thread name for records at this level or more verbose.
+    pub fn with_thread_level(self, thread_level: LevelFilter) -> Self {
+        Self {
+            thread_level,
+            ..self
+        }
+    }
+}
+
+impl Log for TextLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_filter
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let mut line = format!("{} [{}] ", clock::now().format("%H:%M:%S"), record.level());
+
+        if self.thread_level != LevelFilter::Off && self.thread_level <= record.level() {
+            line.push_str(&format!("({}) ", thread_name()));
+        }
+
+        line.push_str(&format!("{}\n", record.args()));
+
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        // There is nowhere to report a failure to write a log line.
+        let _ = writer.write_all(line.as_bytes());
+    }
+
+    fn flush(&self) {
+        let _ = self
+            .writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .flush();
+    }
+}
+
+fn record_json(record: &Record) -> Map<String, Value> {
+    let mut fields = Map::new();
+
+    fields.insert(
+        "timestamp".to_string(),
+        clock::now()
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            .into(),
+    );
+    fields.insert("level".to_string(), record.level().as_str().into());
+    fields.insert("target".to_string(), record.target().into());
+    fields.insert("message".to_string(), record.args().to_string().into());
+
+    let _ = record.key_values().visit(&mut FieldVisitor(&mut fields));
+
+    fields
+}
+
+struct FieldVisitor<'a>(&'a mut Map<String, Value>);
+
+impl<'kvs> VisitSource<'kvs> for FieldVisitor<'_> {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        let value = if let Some(value) = value.to_bool() {
+            value.into()
+        } else if let Some(value) = value.to_i64() {
+            value.into()
+        } else if let Some(value) = value.to_u64() {
+            value.into()
+        } else if let Some(value) = value.to_f64() {
+            serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number)
+        } else {
+            value.to_string().into()
+        };
+
+        self.0.insert(key.as_str().to_string(), value);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clock::with_clock, testing::FixedClock};
+    use chrono::{TimeZone, Utc};
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_color() {
+        for (color, expected) in [
+            (ColorChoice::Always, simplelog::ColorChoice::Always),
+            (ColorChoice::Never, simplelog::ColorChoice::Never),
+        ] {
+            assert_eq!(
+                Builder::new(LevelFilter::Info)
+                    .with_color(color)
+                    .terminal_color(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_sinks() {
+        let info = Buffer::default();
+        let debug = Buffer::default();
+        let logger = Builder::new(LevelFilter::Off)
+            .with_format(LogFormat::Json)
+            .with_sinks([
+                (
+                    LevelFilter::Info,
+                    Box::new(info.clone()) as Box<dyn Write + Send>,
+                ),
+                (LevelFilter::Debug, Box::new(debug.clone())),
+            ])
+            .build()
+            .unwrap();
+
+        for level in [log::Level::Info, log::Level::Debug] {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .args(format_args!("{level}"))
+                    .build(),
+            );
+        }
+
+        let lines = |buffer: &Buffer| {
+            String::from_utf8(buffer.0.lock().unwrap().clone())
+                .unwrap()
+                .lines()
+                .count()
+        };
+
+        assert_eq!(lines(&info), 1);
+        assert_eq!(lines(&debug), 2);
+    }
+
+    #[test]
+    fn test_target_filters() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Opts {
+            #[clap(flatten)]
+            log: LogArgs,
+        }
+
+        let opts = Opts::parse_from([
+            "demo",
+            "--log-format",
+            "json",
+            "--log-only",
+            "app::index",
+            "--log-exclude",
+            "app::index::noisy",
+            "-vvvvv",
+        ]);
+
+        let buffer = Buffer::default();
+        let logger = opts
+            .log
+            .builder()
+            .with_sink(LevelFilter::Trace, Box::new(buffer.clone()))
+            .build()
+            .unwrap();
+
+        for target in ["app::index::files", "app::index::noisy", "app::fetch"] {
+            logger.log(
+                &Record::builder()
+                    .level(log::Level::Info)
+                    .target(target)
+                    .args(format_args!("{target}"))
+                    .build(),
+            );
+        }
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        assert_eq!(output.lines().count(), 1);
+        assert!(output.contains("\"target\":\"app::index::files\""));
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let buffer = RingBuffer::new(2);
+        let clock = FixedClock::new(Utc.timestamp_opt(1692946029, 0).single().unwrap());
+        let pairs: [(&str, log::kv::Value); 1] = [("record_id", 123.into())];
+
+        with_clock(clock, || {
+            for (level, message) in [
+                (log::Level::Info, "first"),
+                (log::Level::Trace, "second"),
+                (log::Level::Debug, "third"),
+            ] {
+                buffer.log(
+                    &Record::builder()
+                        .level(level)
+                        .target("demo")
+                        .args(format_args!("{message}"))
+                        .key_values(&pairs)
+                        .build(),
+                );
+            }
+        });
+
+        assert_eq!(
+            buffer.lines(),
+            vec![
+                "2023-08-25T06:47:09.000Z TRACE [demo] second record_id=123",
+                "2023-08-25T06:47:09.000Z DEBUG [demo] third record_id=123"
+            ]
+        );
+
+        let mut output = vec![];
+        buffer.write_to(&mut output).unwrap();
+        assert_eq!(output.iter().filter(|byte| **byte == b'\n').count(), 2);
+    }
+
+    #[test]
+    fn test_json_logger_thread() {
+        let buffer = Buffer::default();
+        let logger = Arc::new(
+            JsonLogger::new(LevelFilter::Info, buffer.clone())
+                .with_thread_level(LevelFilter::Error),
+        );
+
+        let thread_logger = logger.clone();
+        std::thread::Builder::new()
+            .name("worker-1".to_string())
+            .spawn(move || {
+                thread_logger.log(
+                    &Record::builder()
+                        .level(log::Level::Info)
+                        .args(format_args!("Working"))
+                        .build(),
+                );
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.ends_with(",\"thread\":\"worker-1\"}\n"));
+    }
+
+    #[test]
+    fn test_json_logger() {
+        let buffer = Buffer::default();
+        let logger = JsonLogger::new(LevelFilter::Info, buffer.clone());
+        let clock = FixedClock::new(Utc.timestamp_opt(1692946029, 0).single().unwrap());
+        let pairs: [(&str, log::kv::Value); 2] =
+            [("record_id", 123.into()), ("status", "ok".into())];
+
+        with_clock(clock, || {
+            logger.log(
+                &Record::builder()
+                    .level(log::Level::Info)
+                    .target("demo")
+                    .args(format_args!("Processed record"))
+                    .key_values(&pairs)
+                    .build(),
+            );
+            logger.log(
+                &Record::builder()
+                    .level(log::Level::Debug)
+                    .args(format_args!("Ignored"))
+                    .build(),
+            );
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        assert_eq!(
+            output,
+            "{\"timestamp\":\"2023-08-25T06:47:09.000Z\",\"level\":\"INFO\",\"target\":\"demo\",\
+            \"message\":\"Processed record\",\"record_id\":123,\"status\":\"ok\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_text_logger() {
+        let buffer = Buffer::default();
+        let logger =
+            TextLogger::new(LevelFilter::Info, buffer.clone()).with_thread_level(LevelFilter::Info);
+        let clock = FixedClock::new(Utc.timestamp_opt(1692946029, 0).single().unwrap());
+
+        with_clock(clock, || {
+            for level in [log::Level::Warn, log::Level::Info, log::Level::Debug] {
+                logger.log(
+                    &Record::builder()
+                        .level(level)
+                        .args(format_args!("{level}"))
+                        .build(),
+                );
+            }
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let thread = thread_name();
+
+        assert_eq!(
+            output,
+            format!("06:47:09 [WARN] WARN\n06:47:09 [INFO] ({thread}) INFO\n")
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Lightweight timing spans.
+//!
+//! A [`LogSpan`] logs its name at debug level when it is created and again at info level with the elapsed time when it
+//! is dropped. Spans opened while another span is active on the same thread are indented, so nested work reads as a
+//! tree:
+//!
+//! ```rust
+//! use cli_helpers::timed;
+//!
+//! let path = "data/records.ndjson";
+//! let _span = timed!("indexing {path}");
+//! ```
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+thread_local! {
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+const INDENT: &str = "  ";
+
+/// Create a [`LogSpan`] with a formatted name and the calling module as the log target.
+#[macro_export]
+macro_rules! timed {
+    ($($arg:tt)+) => {
+        $crate::logging::span::LogSpan::with_target(module_path!(), format!($($arg)+))
+    };
+}
+
+#[derive(Debug)]
+#[must_use = "the span ends when it is dropped"]
+pub struct LogSpan {
+    target: &'static str,
+    name: String,
+    depth: usize,
+    start: Instant,
+}
+
+impl LogSpan {
+    /// Create a span with this module as the log target (the [`timed!`](crate::timed) macro uses the caller's).
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self::with_target(module_path!(), name)
+    }
+
+    pub fn with_target<S: Into<String>>(target: &'static str, name: S) -> Self {
+        let name = name.into();
+        let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+
+        log::debug!(target: target, "{}> {name}", INDENT.repeat(depth));
+
+        Self {
+            target,
+            name,
+            depth,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl Drop for LogSpan {
+    fn drop(&mut self) {
+        DEPTH.with(|depth| depth.set(self.depth));
+
+        log::info!(
+            target: self.target,
+            "{}< {} ({:.2?})",
+            INDENT.repeat(self.depth),
+            self.name,
+            self.elapsed()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::capture_logs;
+    use log::Level;
+
+    #[test]
+    fn test_log_span() {
+        let records = capture_logs(|| {
+            let _outer = timed!("indexing {}", "a.ndjson");
+            let _inner = timed!("parsing");
+        });
+
+        let messages = records
+            .iter()
+            .map(|record| (record.level, record.message.split(" (").next().unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec![
+                (Level::Debug, "> indexing a.ndjson"),
+                (Level::Debug, "  > parsing"),
+                (Level::Info, "  < parsing"),
+                (Level::Info, "< indexing a.ndjson"),
+            ]
+        );
+        assert!(records
+            .iter()
+            .all(|record| record.target == "cli_helpers::logging::span::tests"));
+    }
+}
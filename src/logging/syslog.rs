@@ -0,0 +1,93 @@
+//! A logger that sends records to the local syslog daemon.
+
+use std::os::unix::net::UnixDatagram;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use super::syslog_severity;
+
+/// The socket paths to try, in order (the second is used on macOS).
+const SOCKET_PATHS: [&str; 2] = ["/dev/log", "/var/run/syslog"];
+
+/// The `user` facility.
+const FACILITY: u8 = 1;
+
+pub struct SyslogLogger {
+    level_filter: LevelFilter,
+    identifier: String,
+    socket: UnixDatagram,
+}
+
+impl SyslogLogger {
+    /// Connect to the local syslog socket, identifying records with the executable name.
+    pub fn connect(level_filter: LevelFilter) -> Result<Self, std::io::Error> {
+        let socket = UnixDatagram::unbound()?;
+        let mut result = Err(std::io::ErrorKind::NotFound.into());
+
+        for path in SOCKET_PATHS {
+            result = socket.connect(path);
+
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result?;
+
+        Ok(Self {
+            level_filter,
+            identifier: super::executable_name(),
+            socket,
+        })
+    }
+
+    pub fn with_identifier(self, identifier: &str) -> Self {
+        Self {
+            identifier: identifier.to_string(),
+            ..self
+        }
+    }
+}
+
+fn format_message(identifier: &str, pid: u32, record: &Record) -> String {
+    format!(
+        "<{}>{identifier}[{pid}]: {}",
+        FACILITY * 8 + syslog_severity(record.level()),
+        record.args()
+    )
+}
+
+impl Log for SyslogLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_filter
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let message = format_message(&self.identifier, std::process::id(), record);
+            // There is nowhere to report a failure to send a log message.
+            let _ = self.socket.send(message.as_bytes());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_format_message() {
+        let record = Record::builder()
+            .level(Level::Warn)
+            .args(format_args!("Disk almost full"))
+            .build();
+
+        assert_eq!(
+            format_message("demo", 123, &record),
+            "<12>demo[123]: Disk almost full"
+        );
+    }
+}
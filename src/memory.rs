@@ -0,0 +1,266 @@
+//! A memory usage guard (`--max-memory`).
+//!
+//! A [`MemoryGuard`] samples the process's resident set size on a background thread. It logs a warning when usage
+//! first reaches 80% of the limit, and when the limit is exceeded, it either calls an application callback (for
+//! example to spill an in-memory set to disk) or, by default, triggers a graceful [`Shutdown`]. Warnings and the
+//! callback fire again only after usage has dropped back below the threshold.
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{memory::MemoryLimitArgs, shutdown::Shutdown};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     memory: MemoryLimitArgs,
+//! }
+//!
+//! let opts = Opts::parse();
+//! let shutdown = Shutdown::signals();
+//! // The guard stops sampling when dropped.
+//! let _guard = opts.memory.guard(shutdown.clone());
+//!
+//! while !shutdown.is_requested() {
+//!     // ...
+//! }
+//! ```
+//!
+//! Resident memory is read from `/proc/self/status`, so the guard only has an effect on Linux (elsewhere it logs a
+//! warning and does nothing).
+
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::byte_size::ByteSize;
+use crate::shutdown::Shutdown;
+
+const DEFAULT_WARN_RATIO: f64 = 0.8;
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Standard memory limit argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryLimitArgs {
+    /// Stop (or spill to disk) when resident memory exceeds this (for example `4GiB`)
+    #[clap(long)]
+    max_memory: Option<ByteSize>,
+}
+
+impl MemoryLimitArgs {
+    pub fn new(max_memory: Option<ByteSize>) -> Self {
+        Self { max_memory }
+    }
+
+    pub fn max_memory(&self) -> Option<ByteSize> {
+        self.max_memory
+    }
+
+    /// The configured limit, which requests shutdown from the token when exceeded.
+    pub fn limit(&self, shutdown: Shutdown) -> Option<MemoryLimit> {
+        self.max_memory
+            .map(|max_memory| MemoryLimit::new(max_memory).with_shutdown(shutdown))
+    }
+
+    /// Start the guard if `--max-memory` was given.
+    pub fn guard(&self, shutdown: Shutdown) -> Option<MemoryGuard> {
+        self.limit(shutdown).map(MemoryLimit::spawn)
+    }
+}
+
+type Callback = Box<dyn FnMut(ByteSize) + Send>;
+
+pub struct MemoryLimit {
+    max_memory: ByteSize,
+    warn_ratio: f64,
+    interval: Duration,
+    shutdown: Shutdown,
+    on_exceeded: Option<Callback>,
+}
+
+impl MemoryLimit {
+    pub fn new(max_memory: ByteSize) -> Self {
+        Self {
+            max_memory,
+            warn_ratio: DEFAULT_WARN_RATIO,
+            interval: DEFAULT_INTERVAL,
+            shutdown: Shutdown::signals(),
+            on_exceeded: None,
+        }
+    }
+
+    /// Warn when usage reaches this fraction of the limit (0.8 by default).
+    pub fn with_warn_ratio(self, warn_ratio: f64) -> Self {
+        Self { warn_ratio, ..self }
+    }
+
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self { interval, ..self }
+    }
+
+    /// Request shutdown from this token when the limit is exceeded (if there is no callback).
+    pub fn with_shutdown(self, shutdown: Shutdown) -> Self {
+        Self { shutdown, ..self }
+    }
+
+    /// Call this with the current usage when the limit is exceeded, instead of requesting shutdown.
+    pub fn on_exceeded<F: FnMut(ByteSize) + Send + 'static>(self, f: F) -> Self {
+        Self {
+            on_exceeded: Some(Box::new(f)),
+            ..self
+        }
+    }
+
+    /// Start sampling on a background thread.
+    pub fn spawn(mut self) -> MemoryGuard {
+        let stop = Shutdown::new();
+        let handle = if resident_memory().is_some() {
+            let stop = stop.clone();
+
+            Some(std::thread::spawn(move || {
+                let mut level = Level::Normal;
+
+                while stop.sleep(self.interval) {
+                    if let Some(usage) = resident_memory() {
+                        level = self.check(level, usage);
+                    }
+                }
+            }))
+        } else {
+            log::warn!("Unable to measure memory usage on this platform; --max-memory is ignored");
+            None
+        };
+
+        MemoryGuard { stop, handle }
+    }
+
+    /// Act on a sample, returning the new level.
+    fn check(&mut self, previous: Level, usage: ByteSize) -> Level {
+        let level = self.level(usage);
+
+        if level > previous {
+            match level {
+                Level::Normal => {}
+                Level::Warning => log::warn!(
+                    "Memory usage is {usage} ({:.0}% of the {} limit)",
+                    usage.0 as f64 / self.max_memory.0 as f64 * 100.0,
+                    self.max_memory
+                ),
+                Level::Exceeded => match &mut self.on_exceeded {
+                    Some(on_exceeded) => on_exceeded(usage),
+                    None => {
+                        log::error!(
+                            "Memory usage is {usage}, which exceeds the {} limit; shutting down",
+                            self.max_memory
+                        );
+                        self.shutdown.request();
+                    }
+                },
+            }
+        }
+
+        level
+    }
+
+    fn level(&self, usage: ByteSize) -> Level {
+        if usage > self.max_memory {
+            Level::Exceeded
+        } else if usage.0 as f64 >= self.max_memory.0 as f64 * self.warn_ratio {
+            Level::Warning
+        } else {
+            Level::Normal
+        }
+    }
+}
+
+impl std::fmt::Debug for MemoryLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryLimit")
+            .field("max_memory", &self.max_memory)
+            .field("warn_ratio", &self.warn_ratio)
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Stops sampling when dropped.
+#[derive(Debug)]
+pub struct MemoryGuard {
+    stop: Shutdown,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for MemoryGuard {
+    fn drop(&mut self) {
+        self.stop.request();
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Level {
+    Normal,
+    Warning,
+    Exceeded,
+}
+
+/// The resident set size of the current process (if it can be determined on this platform).
+pub fn resident_memory() -> Option<ByteSize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+    status.lines().find_map(|line| {
+        let kibibytes = line
+            .strip_prefix("VmRSS:")?
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+
+        Some(ByteSize(kibibytes * 1024))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_memory_limit() {
+        let shutdown = Shutdown::new();
+        let mut limit = MemoryLimit::new(ByteSize(1000)).with_shutdown(shutdown.clone());
+
+        let level = limit.check(Level::Normal, ByteSize(500));
+        assert_eq!(level, Level::Normal);
+        let level = limit.check(level, ByteSize(800));
+        assert_eq!(level, Level::Warning);
+        assert!(!shutdown.is_requested());
+        let level = limit.check(level, ByteSize(1001));
+        assert_eq!(level, Level::Exceeded);
+        assert!(shutdown.is_requested());
+
+        let exceeded = Arc::new(Mutex::new(vec![]));
+        let mut limit = MemoryLimit::new(ByteSize(1000)).on_exceeded({
+            let exceeded = exceeded.clone();
+            move |usage| exceeded.lock().unwrap().push(usage)
+        });
+
+        let mut level = Level::Normal;
+        for usage in [1200, 1300, 100, 1100] {
+            level = limit.check(level, ByteSize(usage));
+        }
+
+        assert_eq!(
+            *exceeded.lock().unwrap(),
+            vec![ByteSize(1200), ByteSize(1100)]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_resident_memory() {
+        assert!(resident_memory().unwrap() > ByteSize(0));
+    }
+}
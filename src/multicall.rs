@@ -0,0 +1,258 @@
+//! Multi-call (busybox-style) binaries.
+//!
+//! A suite of small related tools can ship as a single binary with a subcommand for each tool, and a hard link to the
+//! binary for each subcommand. When the binary is invoked through one of these links, [`dispatch_args`] selects the
+//! subcommand from the program name, so that `mytool-fetch --since yesterday` is parsed as
+//! `mytool mytool-fetch --since yesterday`:
+//!
+//! ```rust,no_run
+//! use cli_helpers::multicall;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! #[clap(name = "mytool")]
+//! struct Opts {
+//!     #[clap(subcommand)]
+//!     command: Command,
+//! }
+//!
+//! #[derive(Debug, clap::Subcommand)]
+//! enum Command {
+//!     #[clap(name = "mytool-fetch")]
+//!     Fetch {
+//!         #[clap(long)]
+//!         since: Option<String>,
+//!     },
+//! }
+//!
+//! let opts: Opts = multicall::parse();
+//! ```
+//!
+//! [`install_links`] and [`uninstall_links`] manage the links (for example from an `install-links` subcommand or an
+//! installation script).
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::{Command, Parser};
+
+use crate::Error;
+
+/// The names of the subcommands that can be selected by the program name.
+pub fn applet_names(command: &Command) -> Vec<String> {
+    command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .filter(|name| name != "help")
+        .collect()
+}
+
+/// Insert the subcommand selected by the program name (if any) into the arguments.
+///
+/// The program name's directory and any executable suffix are ignored, and aliases of subcommands are also matched.
+pub fn dispatch_args<I: IntoIterator<Item = A>, A: Into<OsString>>(
+    command: &Command,
+    args: I,
+) -> Vec<OsString> {
+    let mut args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+
+    let applet = args
+        .first()
+        .and_then(|program| program_name(Path::new(program)));
+
+    if let Some(applet) = applet.filter(|applet| applet != command.get_name()) {
+        let subcommand = command.get_subcommands().find(|subcommand| {
+            subcommand.get_name() == applet
+                || subcommand.get_all_aliases().any(|alias| alias == applet)
+        });
+
+        if let Some(subcommand) = subcommand {
+            args[0] = command.get_name().into();
+            args.insert(1, subcommand.get_name().into());
+        }
+    }
+
+    args
+}
+
+/// Parse the process's arguments, selecting the subcommand from the program name.
+pub fn parse<T: Parser>() -> T {
+    parse_from(std::env::args_os())
+}
+
+pub fn parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString>>(args: I) -> T {
+    T::parse_from(dispatch_args(&T::command(), args))
+}
+
+/// Create a hard link to the executable in the directory for each name, returning the paths of the new links.
+///
+/// Existing links to the executable are left in place. Fails with [`Error::FileExists`] if any other file would be
+/// replaced (in which case no links are created).
+pub fn install_links<P: AsRef<Path>, Q: AsRef<Path>, S: AsRef<str>>(
+    dir: P,
+    exe: Q,
+    names: &[S],
+) -> Result<Vec<PathBuf>, Error> {
+    let exe = exe.as_ref();
+    let mut paths = vec![];
+
+    for name in names {
+        let path = link_path(dir.as_ref(), name.as_ref());
+
+        if path.exists() {
+            if !is_same_file(&path, exe)? {
+                return Err(Error::FileExists(path));
+            }
+        } else {
+            paths.push(path);
+        }
+    }
+
+    std::fs::create_dir_all(dir.as_ref())?;
+
+    for path in &paths {
+        std::fs::hard_link(exe, path)?;
+    }
+
+    Ok(paths)
+}
+
+/// Remove the links to the executable from the directory, returning the paths of the removed links.
+///
+/// Files with these names that are not links to the executable are left in place (with a warning).
+pub fn uninstall_links<P: AsRef<Path>, Q: AsRef<Path>, S: AsRef<str>>(
+    dir: P,
+    exe: Q,
+    names: &[S],
+) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = vec![];
+
+    for name in names {
+        let path = link_path(dir.as_ref(), name.as_ref());
+
+        if !path.exists() {
+            continue;
+        }
+
+        if is_same_file(&path, exe.as_ref())? {
+            std::fs::remove_file(&path)?;
+            paths.push(path);
+        } else {
+            log::warn!(
+                "Not removing {}, which is not a link to the executable",
+                path.display()
+            );
+        }
+    }
+
+    Ok(paths)
+}
+
+fn link_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}{}", std::env::consts::EXE_SUFFIX))
+}
+
+fn program_name(program: &Path) -> Option<String> {
+    let name = program.file_name()?.to_str()?;
+    let suffix = std::env::consts::EXE_SUFFIX;
+
+    Some(
+        name.strip_suffix(suffix)
+            .filter(|_| !suffix.is_empty())
+            .unwrap_or(name)
+            .to_string(),
+    )
+}
+
+#[cfg(unix)]
+fn is_same_file(a: &Path, b: &Path) -> Result<bool, Error> {
+    use std::os::unix::fs::MetadataExt;
+
+    let a = std::fs::metadata(a)?;
+    let b = std::fs::metadata(b)?;
+
+    Ok(a.dev() == b.dev() && a.ino() == b.ino())
+}
+
+#[cfg(not(unix))]
+fn is_same_file(a: &Path, b: &Path) -> Result<bool, Error> {
+    Ok(std::fs::read(a)? == std::fs::read(b)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use clap::CommandFactory;
+
+    #[derive(Debug, Parser, PartialEq, Eq)]
+    #[clap(name = "mytool")]
+    struct Opts {
+        #[clap(long, global = true)]
+        verbose: bool,
+        #[clap(subcommand)]
+        command: Subcommand,
+    }
+
+    #[derive(Debug, clap::Subcommand, PartialEq, Eq)]
+    enum Subcommand {
+        #[clap(name = "mytool-fetch", alias = "fetch")]
+        Fetch {
+            #[clap(long)]
+            since: Option<String>,
+        },
+        #[clap(name = "mytool-export")]
+        Export,
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let fetch = Subcommand::Fetch {
+            since: Some("yesterday".to_string()),
+        };
+
+        let opts: Opts = parse_from(["/usr/local/bin/mytool-fetch", "--since", "yesterday"]);
+        assert_eq!(opts.command, fetch);
+
+        let opts: Opts = parse_from(["fetch", "--verbose", "--since", "yesterday"]);
+        assert_eq!(opts.command, fetch);
+        assert!(opts.verbose);
+
+        let opts: Opts = parse_from(["./mytool", "mytool-export"]);
+        assert_eq!(opts.command, Subcommand::Export);
+
+        assert_eq!(
+            applet_names(&Opts::command()),
+            vec!["mytool-fetch", "mytool-export"]
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+
+        let exe = dir.join("mytool");
+        std::fs::write(&exe, "binary").unwrap();
+        let bin = dir.join("bin");
+        let names = applet_names(&Opts::command());
+
+        let paths = install_links(&bin, &exe, &names).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(std::fs::read_to_string(&paths[0]).unwrap(), "binary");
+        assert!(install_links(&bin, &exe, &names).unwrap().is_empty());
+
+        std::fs::remove_file(&paths[1]).unwrap();
+        std::fs::write(&paths[1], "other").unwrap();
+        assert!(matches!(
+            install_links(&bin, &exe, &names),
+            Err(Error::FileExists(path)) if path == paths[1]
+        ));
+
+        assert_eq!(
+            uninstall_links(&bin, &exe, &names).unwrap(),
+            vec![paths[0].clone()]
+        );
+        assert!(paths[1].exists());
+    }
+}
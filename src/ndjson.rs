@@ -0,0 +1,145 @@
+//! Helpers for working with newline-delimited JSON files.
+
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+use crate::{Error, Timestamp};
+
+/// Position the reader at the start of the first record with a timestamp greater than or equal to `timestamp`.
+///
+/// The input must be sorted chronologically. Lines for which `extract_ts_fn` returns `None` (blank or malformed
+/// lines, for example) are skipped. The returned offset is the byte position the reader is left at, which is the end
+/// of the input if no record matches.
+pub fn seek_to_timestamp<R: Read + Seek, F: Fn(&[u8]) -> Option<Timestamp>>(
+    reader: &mut R,
+    timestamp: Timestamp,
+    extract_ts_fn: F,
+) -> Result<u64, Error> {
+    let len = reader.seek(SeekFrom::End(0))?;
+
+    let (mut lo, mut hi) = (0, len);
+    // Invariant: `offset` is the start of the first record at or after `hi` (or the end of the input).
+    let mut offset = len;
+
+    {
+        let mut buffered = BufReader::new(&mut *reader);
+        let mut line = Vec::new();
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+
+            match next_record(&mut buffered, mid, &mut line, &extract_ts_fn)? {
+                Some((start, record_timestamp)) if record_timestamp < timestamp => {
+                    lo = start + line.len() as u64;
+                }
+                Some((start, _)) => {
+                    offset = start;
+                    hi = mid;
+                }
+                None => {
+                    offset = len;
+                    hi = mid;
+                }
+            }
+        }
+    }
+
+    reader.seek(SeekFrom::Start(offset))?;
+
+    Ok(offset)
+}
+
+/// Find the first record with a timestamp that starts at or after `position`.
+fn next_record<R: BufRead + Seek, F: Fn(&[u8]) -> Option<Timestamp>>(
+    reader: &mut R,
+    position: u64,
+    line: &mut Vec<u8>,
+    extract_ts_fn: &F,
+) -> Result<Option<(u64, Timestamp)>, Error> {
+    let mut start = if position == 0 {
+        reader.seek(SeekFrom::Start(0))?;
+        0
+    } else {
+        // Skip the remainder of the line containing the byte before `position`.
+        reader.seek(SeekFrom::Start(position - 1))?;
+        line.clear();
+        position - 1 + reader.read_until(b'\n', line)? as u64
+    };
+
+    loop {
+        line.clear();
+
+        if reader.read_until(b'\n', line)? == 0 {
+            return Ok(None);
+        }
+
+        if let Some(timestamp) = extract_ts_fn(trim_newline(line)) {
+            return Ok(Some((start, timestamp)));
+        }
+
+        start += line.len() as u64;
+    }
+}
+
+fn trim_newline(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::io::Cursor;
+
+    fn extract(line: &[u8]) -> Option<Timestamp> {
+        std::str::from_utf8(line)
+            .ok()?
+            .strip_prefix("{\"ts\":")?
+            .strip_suffix('}')?
+            .parse()
+            .ok()
+    }
+
+    fn ts(value: i64) -> Timestamp {
+        Timestamp(Utc.timestamp_opt(value, 0).single().unwrap())
+    }
+
+    #[test]
+    fn test_seek_to_timestamp() {
+        let mut contents = String::new();
+        let mut offsets = Vec::new();
+
+        for i in 0..100 {
+            offsets.push(contents.len() as u64);
+            contents.push_str(&format!("{{\"ts\":{}}}\n", 1692946000 + i * 10));
+
+            if i % 7 == 0 {
+                contents.push('\n');
+            }
+        }
+
+        let mut reader = Cursor::new(contents.as_bytes());
+
+        for (i, expected) in offsets.iter().enumerate() {
+            let target = 1692946000 + i as i64 * 10;
+
+            assert_eq!(
+                seek_to_timestamp(&mut reader, ts(target), extract).unwrap(),
+                *expected
+            );
+            assert_eq!(
+                seek_to_timestamp(&mut reader, ts(target - 5), extract).unwrap(),
+                *expected
+            );
+        }
+
+        let end = seek_to_timestamp(&mut reader, ts(1692947000), extract).unwrap();
+        assert_eq!(end, contents.len() as u64);
+
+        let mut rest = String::new();
+        reader.seek(SeekFrom::Start(offsets[42])).unwrap();
+        seek_to_timestamp(&mut reader, ts(1692946420), extract).unwrap();
+        reader.read_to_string(&mut rest).unwrap();
+        assert!(rest.starts_with("{\"ts\":1692946420}\n"));
+    }
+}
@@ -0,0 +1,183 @@
+//! Host credentials from `.netrc`-style files.
+//!
+//! Credentials are read from an application-specific `credentials` file in the configuration directory and then from
+//! the user's `.netrc` (or the file named by `NETRC`; `_netrc` on Windows), both in the standard format:
+//!
+//! ```text
+//! machine api.example.com login travis password abc123
+//! default login anonymous password guest
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::{app_dirs::AppDirs, secret::Secret, Error};
+
+const CREDENTIALS_FILE_NAME: &str = "credentials";
+
+#[cfg(windows)]
+const NETRC_FILE_NAME: &str = "_netrc";
+#[cfg(not(windows))]
+const NETRC_FILE_NAME: &str = ".netrc";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    pub login: Option<String>,
+    pub password: Option<Secret>,
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Netrc {
+    machines: Vec<(String, Credentials)>,
+    default: Option<Credentials>,
+}
+
+impl Netrc {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// The credentials for the host (or the default entry if there is none for the host).
+    pub fn credentials(&self, host: &str) -> Option<&Credentials> {
+        self.machines
+            .iter()
+            .find(|(machine, _)| machine.eq_ignore_ascii_case(host))
+            .map(|(_, credentials)| credentials)
+            .or(self.default.as_ref())
+    }
+
+    fn push(&mut self, entry: Option<(Option<String>, Credentials)>) {
+        match entry {
+            Some((Some(machine), credentials)) => self.machines.push((machine, credentials)),
+            Some((None, credentials)) => self.default = Some(credentials),
+            None => {}
+        }
+    }
+}
+
+impl std::str::FromStr for Netrc {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut netrc = Self::default();
+        let mut current: Option<(Option<String>, Credentials)> = None;
+        let mut lines = s.lines();
+
+        while let Some(line) = lines.next() {
+            let mut tokens = line.split_whitespace();
+
+            while let Some(token) = tokens.next() {
+                if token.starts_with('#') {
+                    break;
+                }
+
+                let mut value = |keyword: &str| {
+                    tokens
+                        .next()
+                        .map(|value| value.to_string())
+                        .ok_or_else(|| Error::InvalidNetrc(format!("missing value for {keyword}")))
+                };
+
+                match token {
+                    "machine" | "default" => {
+                        let machine = if token == "machine" {
+                            Some(value(token)?)
+                        } else {
+                            None
+                        };
+
+                        netrc.push(current.take());
+                        current = Some((machine, Credentials::default()));
+                    }
+                    "login" | "password" | "account" => {
+                        let value = value(token)?;
+                        let (_, credentials) = current.as_mut().ok_or_else(|| {
+                            Error::InvalidNetrc(format!("{token} outside of an entry"))
+                        })?;
+
+                        match token {
+                            "login" => credentials.login = Some(value),
+                            "password" => credentials.password = Some(Secret::new(value)),
+                            _ => credentials.account = Some(value),
+                        }
+                    }
+                    // Macro definitions continue until the next blank line.
+                    "macdef" => {
+                        for line in lines.by_ref() {
+                            if line.trim().is_empty() {
+                                break;
+                            }
+                        }
+
+                        break;
+                    }
+                    other => return Err(Error::InvalidNetrc(format!("unexpected token {other}"))),
+                }
+            }
+        }
+
+        netrc.push(current);
+
+        Ok(netrc)
+    }
+}
+
+/// The user's `.netrc` file (which may not exist).
+pub fn netrc_path() -> Option<PathBuf> {
+    std::env::var_os("NETRC")
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| directories::BaseDirs::new().map(|dirs| dirs.home_dir().join(NETRC_FILE_NAME)))
+}
+
+/// Find credentials for the host in the application credentials file or the user's `.netrc` (in that order).
+pub fn credentials(dirs: &AppDirs, host: &str) -> Result<Option<Credentials>, Error> {
+    let paths = std::iter::once(dirs.config_dir().join(CREDENTIALS_FILE_NAME)).chain(netrc_path());
+
+    for path in paths {
+        if path.is_file() {
+            if let Some(credentials) = Netrc::load(&path)?.credentials(host) {
+                log::debug!("Using credentials for {host} from {}", path.display());
+                return Ok(Some(credentials.clone()));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let netrc = "# Archive API\nmachine api.example.com\n  login travis\n  password abc123\n\nmacdef init\ncd /pub\n\nmachine other.example.com login x password y account z\ndefault login anonymous password guest\n"
+            .parse::<Netrc>()
+            .unwrap();
+
+        let credentials = netrc.credentials("API.example.com").unwrap();
+        assert_eq!(credentials.login.as_deref(), Some("travis"));
+        assert_eq!(credentials.password, Some(Secret::new("abc123")));
+
+        assert_eq!(
+            netrc
+                .credentials("other.example.com")
+                .unwrap()
+                .account
+                .as_deref(),
+            Some("z")
+        );
+        assert_eq!(
+            netrc
+                .credentials("unknown.example.com")
+                .unwrap()
+                .login
+                .as_deref(),
+            Some("anonymous")
+        );
+
+        assert!("login travis".parse::<Netrc>().is_err());
+        assert!("machine".parse::<Netrc>().is_err());
+    }
+}
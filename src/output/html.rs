@@ -0,0 +1,58 @@
+//! HTML tables.
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use super::{cell, Column};
+
+pub(super) fn write<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+) -> std::io::Result<()> {
+    write!(writer, "<table>\n  <thead>\n    <tr>")?;
+
+    for column in columns {
+        write!(writer, "<th>{}</th>", escape(&column.name))?;
+    }
+
+    writeln!(writer, "</tr>\n  </thead>\n  <tbody>")?;
+
+    for row in rows {
+        write!(writer, "    <tr>")?;
+
+        for value in row {
+            // Numbers are marked so that stylesheets can right-align them.
+            if value.is_number() {
+                write!(
+                    writer,
+                    "<td class=\"numeric\">{}</td>",
+                    escape(&cell(value))
+                )?;
+            } else {
+                write!(writer, "<td>{}</td>", escape(&cell(value)))?;
+            }
+        }
+
+        writeln!(writer, "</tr>")?;
+    }
+
+    writeln!(writer, "  </tbody>\n</table>")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,48 @@
+//! GitHub-flavored Markdown tables.
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use super::{cell, is_numeric, Column};
+
+pub(super) fn write<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+) -> std::io::Result<()> {
+    let header = columns
+        .iter()
+        .map(|column| escape(&column.name))
+        .collect::<Vec<_>>();
+    writeln!(writer, "| {} |", header.join(" | "))?;
+
+    // Numeric columns are right-aligned.
+    let separators = (0..columns.len())
+        .map(|index| {
+            if is_numeric(rows, index) {
+                "---:"
+            } else {
+                "---"
+            }
+        })
+        .collect::<Vec<_>>();
+    writeln!(writer, "| {} |", separators.join(" | "))?;
+
+    for row in rows {
+        let cells = row
+            .iter()
+            .map(|value| escape(&cell(value)))
+            .collect::<Vec<_>>();
+        writeln!(writer, "| {} |", cells.join(" | "))?;
+    }
+
+    Ok(())
+}
+
+/// Escape pipes (which would end the cell) and line breaks (which would end the row).
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
@@ -0,0 +1,489 @@
+//! Rendering lists of records in a user-selected format.
+//!
+//! Types implement [`OutputRecord`] by describing their columns and providing a row of values for each record, and
+//! [`OutputFormat`] renders a slice of records as an aligned table, CSV, JSON, a Markdown table (for pasting into
+//! GitHub issues), or an HTML table (for static reports). With the `yaml` and `toml` features, records can also be
+//! rendered as YAML or TOML, for subcommands that generate configuration. [`OutputArgs`] provides a standard
+//! `--format`/`-o` flag.
+//!
+//! When a table is printed to a terminal that is too narrow for it, columns are hidden in order of their
+//! [priority](Column::with_priority) until it fits (unless `--wide` or `-o wide` is given).
+//!
+//! Records can render their timestamps in a [`TimezoneArg`] (see [`OutputRecord::row_in`]), which is UTC unless one
+//! is passed to [`OutputArgs::with_timezone`] or [`OutputFormat::write_in`].
+
+mod html;
+mod markdown;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use crate::timezone::TimezoneArg;
+use crate::Error;
+
+/// A column in the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    name: String,
+    priority: u8,
+}
+
+impl Column {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            priority: 0,
+        }
+    }
+
+    /// Allow the column to be hidden in narrow tables (columns with higher values are hidden first, and columns with
+    /// priority zero, the default, are never hidden).
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+}
+
+/// A record that can be rendered in any output format.
+pub trait OutputRecord {
+    fn columns() -> Vec<Column>;
+
+    /// The values for this record, in the order of the columns.
+    fn row(&self) -> Vec<Value>;
+
+    /// The values for this record, with any timestamps rendered in the given timezone (by default the same as
+    /// [`row`](Self::row)).
+    fn row_in(&self, tz: TimezoneArg) -> Vec<Value> {
+        let _ = tz;
+        self.row()
+    }
+}
+
+/// Standard output format argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputArgs {
+    /// Output format
+    #[clap(long, short = 'o', global = true, value_enum, default_value_t)]
+    format: OutputFormat,
+    /// Show all table columns, even if the terminal is too narrow
+    #[clap(long, global = true)]
+    wide: bool,
+    #[clap(skip)]
+    tz: TimezoneArg,
+}
+
+impl OutputArgs {
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            wide: false,
+            tz: TimezoneArg::Utc,
+        }
+    }
+
+    pub fn with_wide(mut self, wide: bool) -> Self {
+        self.wide = wide;
+        self
+    }
+
+    /// Render timestamps in the given timezone (typically from [`TimezoneArgs`](crate::timezone::TimezoneArgs)).
+    pub fn with_timezone(mut self, tz: TimezoneArg) -> Self {
+        self.tz = tz;
+        self
+    }
+
+    /// The selected format (where `--wide` selects [`OutputFormat::Wide`] instead of [`OutputFormat::Table`]).
+    pub fn format(&self) -> OutputFormat {
+        match self.format {
+            OutputFormat::Table if self.wide => OutputFormat::Wide,
+            format => format,
+        }
+    }
+
+    /// Write the records to standard output in the selected format, fitting tables to the terminal width.
+    pub fn print<R: OutputRecord>(&self, records: &[R]) -> Result<(), Error> {
+        self.format().write_records(
+            std::io::stdout().lock(),
+            records,
+            self.tz,
+            crate::term::width(crate::color::Stream::Stdout),
+        )
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// An aligned table
+    #[default]
+    Table,
+    /// An aligned table with all columns
+    Wide,
+    /// Comma-separated values with a header row
+    Csv,
+    /// A JSON array of objects
+    Json,
+    /// A Markdown table
+    Markdown,
+    /// An HTML table
+    Html,
+    /// A YAML sequence of mappings
+    #[cfg(feature = "yaml")]
+    Yaml,
+    /// A TOML array of tables (named `records`)
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl OutputFormat {
+    pub fn write<W: Write, R: OutputRecord>(self, writer: W, records: &[R]) -> Result<(), Error> {
+        self.write_records(writer, records, TimezoneArg::Utc, None)
+    }
+
+    /// Write the records, rendering timestamps in the given timezone.
+    pub fn write_in<W: Write, R: OutputRecord>(
+        self,
+        writer: W,
+        records: &[R],
+        tz: TimezoneArg,
+    ) -> Result<(), Error> {
+        self.write_records(writer, records, tz, None)
+    }
+
+    /// Write the records, hiding table columns by priority as necessary to fit within `max_width`.
+    pub fn write_with_max_width<W: Write, R: OutputRecord>(
+        self,
+        writer: W,
+        records: &[R],
+        max_width: Option<usize>,
+    ) -> Result<(), Error> {
+        self.write_records(writer, records, TimezoneArg::Utc, max_width)
+    }
+
+    /// Render the records as a string.
+    pub fn render<R: OutputRecord>(self, records: &[R]) -> String {
+        self.render_in(records, TimezoneArg::Utc)
+    }
+
+    /// Render the records as a string, rendering timestamps in the given timezone.
+    pub fn render_in<R: OutputRecord>(self, records: &[R], tz: TimezoneArg) -> String {
+        let mut output = vec![];
+
+        // Writing to a vector can only fail if serialization fails, which it cannot for these values.
+        let _ = self.write_in(&mut output, records, tz);
+
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    fn write_records<W: Write, R: OutputRecord>(
+        self,
+        mut writer: W,
+        records: &[R],
+        tz: TimezoneArg,
+        max_width: Option<usize>,
+    ) -> Result<(), Error> {
+        let columns = R::columns();
+        let rows = records
+            .iter()
+            .map(|record| record.row_in(tz))
+            .collect::<Vec<_>>();
+
+        match self {
+            Self::Table => write_table(&mut writer, &columns, &rows, max_width)?,
+            Self::Wide => write_table(&mut writer, &columns, &rows, None)?,
+            Self::Csv => write_csv(&mut writer, &columns, &rows)?,
+            Self::Json => {
+                let objects = rows
+                    .into_iter()
+                    .map(|row| Value::Object(to_object(&columns, row)))
+                    .collect::<Vec<_>>();
+
+                serde_json::to_writer_pretty(&mut writer, &objects)
+                    .map_err(std::io::Error::from)?;
+                writeln!(writer)?;
+            }
+            Self::Markdown => markdown::write(&mut writer, &columns, &rows)?,
+            Self::Html => html::write(&mut writer, &columns, &rows)?,
+            #[cfg(feature = "yaml")]
+            Self::Yaml => yaml::write(&mut writer, &columns, rows)?,
+            #[cfg(feature = "toml")]
+            Self::Toml => toml::write(&mut writer, &columns, rows)?,
+        }
+
+        Ok(writer.flush()?)
+    }
+}
+
+/// The text of a value in a cell (strings are not quoted, and nulls are empty).
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Whether all non-null values in a column are numbers (and should be right-aligned).
+fn is_numeric(rows: &[Vec<Value>], index: usize) -> bool {
+    let mut values = rows.iter().filter_map(|row| row.get(index)).peekable();
+
+    values.peek().is_some() && values.all(|value| matches!(value, Value::Number(_) | Value::Null))
+}
+
+fn to_object(columns: &[Column], row: Vec<Value>) -> serde_json::Map<String, Value> {
+    columns
+        .iter()
+        .map(|column| column.name.clone())
+        .zip(row)
+        .collect()
+}
+
+fn write_table<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+    max_width: Option<usize>,
+) -> std::io::Result<()> {
+    let headers = columns
+        .iter()
+        .map(|column| column.name.to_uppercase())
+        .collect::<Vec<_>>();
+    let cells = rows
+        .iter()
+        .map(|row| row.iter().map(cell).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    let widths = (0..columns.len())
+        .map(|index| {
+            cells
+                .iter()
+                .filter_map(|row| row.get(index))
+                .chain(std::iter::once(&headers[index]))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let numeric = (0..columns.len())
+        .map(|index| is_numeric(rows, index))
+        .collect::<Vec<_>>();
+    let visible = visible_columns(columns, &widths, max_width);
+
+    for row in std::iter::once(&headers).chain(&cells) {
+        let mut line = String::new();
+
+        for (index, cell) in row.iter().enumerate().filter(|(index, _)| visible[*index]) {
+            if !line.is_empty() {
+                line.push_str("  ");
+            }
+
+            let padding = " ".repeat(widths[index].saturating_sub(cell.chars().count()));
+
+            if numeric[index] {
+                line.push_str(&padding);
+                line.push_str(cell);
+            } else {
+                line.push_str(cell);
+                line.push_str(&padding);
+            }
+        }
+
+        writeln!(writer, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+}
+
+/// Choose the columns to show, hiding the highest-priority (and then rightmost) hideable columns until the table fits.
+fn visible_columns(columns: &[Column], widths: &[usize], max_width: Option<usize>) -> Vec<bool> {
+    let mut visible = vec![true; columns.len()];
+
+    if let Some(max_width) = max_width {
+        let total = |visible: &[bool]| {
+            let shown = widths
+                .iter()
+                .zip(visible)
+                .filter(|(_, visible)| **visible)
+                .map(|(width, _)| *width)
+                .collect::<Vec<_>>();
+
+            shown.iter().sum::<usize>() + 2 * shown.len().saturating_sub(1)
+        };
+
+        while total(&visible) > max_width {
+            let hidden = (0..columns.len())
+                .filter(|index| visible[*index] && columns[*index].priority > 0)
+                .max_by_key(|index| (columns[*index].priority, *index));
+
+            match hidden {
+                Some(index) => visible[index] = false,
+                None => break,
+            }
+        }
+    }
+
+    visible
+}
+
+fn write_csv<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    rows: &[Vec<Value>],
+) -> std::io::Result<()> {
+    let escape = |field: &str| {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    };
+
+    let header = columns
+        .iter()
+        .map(|column| escape(&column.name))
+        .collect::<Vec<_>>();
+    writeln!(writer, "{}", header.join(","))?;
+
+    for row in rows {
+        let fields = row
+            .iter()
+            .map(|value| escape(&cell(value)))
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Account {
+        screen_name: &'static str,
+        followers: u64,
+    }
+
+    impl OutputRecord for Account {
+        fn columns() -> Vec<Column> {
+            vec![Column::new("screen_name"), Column::new("followers")]
+        }
+
+        fn row(&self) -> Vec<Value> {
+            vec![self.screen_name.into(), self.followers.into()]
+        }
+    }
+
+    fn accounts() -> Vec<Account> {
+        vec![
+            Account {
+                screen_name: "travisbrown",
+                followers: 1204,
+            },
+            Account {
+                screen_name: "a|b",
+                followers: 17,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_formats() {
+        let accounts = accounts();
+
+        assert_eq!(
+            OutputFormat::Table.render(&accounts),
+            "SCREEN_NAME  FOLLOWERS\ntravisbrown       1204\na|b                 17\n"
+        );
+        assert_eq!(
+            OutputFormat::Csv.render(&accounts),
+            "screen_name,followers\ntravisbrown,1204\na|b,17\n"
+        );
+        assert_eq!(
+            OutputFormat::Json.render(&accounts[1..]),
+            "[\n  {\n    \"screen_name\": \"a|b\",\n    \"followers\": 17\n  }\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_narrow_table() {
+        struct Tweet(&'static str, &'static str, &'static str);
+
+        impl OutputRecord for Tweet {
+            fn columns() -> Vec<Column> {
+                vec![
+                    Column::new("id"),
+                    Column::new("source").with_priority(2),
+                    Column::new("text").with_priority(1),
+                ]
+            }
+
+            fn row(&self) -> Vec<Value> {
+                vec![self.0.into(), self.1.into(), self.2.into()]
+            }
+        }
+
+        let tweets = [Tweet("1", "Twitter Web App", "hello world")];
+        let render = |format: OutputFormat, max_width| {
+            let mut output = vec![];
+            format
+                .write_with_max_width(&mut output, &tweets, max_width)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        assert_eq!(
+            render(OutputFormat::Table, Some(20)),
+            "ID  TEXT\n1   hello world\n"
+        );
+        assert_eq!(render(OutputFormat::Table, Some(5)), "ID\n1\n");
+        assert_eq!(
+            render(OutputFormat::Wide, Some(5)),
+            "ID  SOURCE           TEXT\n1   Twitter Web App  hello world\n"
+        );
+    }
+
+    #[test]
+    fn test_markup_formats() {
+        let accounts = accounts();
+
+        assert_eq!(
+            OutputFormat::Markdown.render(&accounts),
+            "| screen_name | followers |\n| --- | ---: |\n| travisbrown | 1204 |\n| a\\|b | 17 |\n"
+        );
+        assert_eq!(
+            OutputFormat::Html.render(&accounts[..1]),
+            "<table>\n  <thead>\n    <tr><th>screen_name</th><th>followers</th></tr>\n  </thead>\n  <tbody>\n    <tr><td>travisbrown</td><td class=\"numeric\">1204</td></tr>\n  </tbody>\n</table>\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml() {
+        assert_eq!(
+            OutputFormat::Yaml.render(&accounts()),
+            "- screen_name: travisbrown\n  followers: 1204\n- screen_name: \"a|b\"\n  followers: 17\n"
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml() {
+        assert_eq!(
+            OutputFormat::Toml.render(&accounts()),
+            "[[records]]\nscreen_name = \"travisbrown\"\nfollowers = 1204\n\n[[records]]\nscreen_name = \"a|b\"\nfollowers = 17\n"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! TOML arrays of tables.
+//!
+//! TOML documents must be tables, so records are written as a `records` array of tables. TOML has no null value, so
+//! null fields are omitted.
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use super::{to_object, Column};
+
+/// The key of the array of records.
+const RECORDS_KEY: &str = "records";
+
+pub(super) fn write<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    rows: Vec<Vec<Value>>,
+) -> std::io::Result<()> {
+    let records = rows
+        .into_iter()
+        .map(|row| {
+            ::toml::Value::Table(
+                to_object(columns, row)
+                    .into_iter()
+                    .filter_map(|(key, value)| convert(value).map(|value| (key, value)))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let mut document = ::toml::Table::new();
+    document.insert(RECORDS_KEY.to_string(), ::toml::Value::Array(records));
+
+    write!(writer, "{document}")
+}
+
+fn convert(value: Value) -> Option<::toml::Value> {
+    match value {
+        Value::Null => None,
+        Value::Bool(value) => Some(value.into()),
+        Value::Number(value) => Some(match value.as_i64() {
+            Some(value) => value.into(),
+            // Integers beyond the range of TOML integers are written as floats.
+            None => value.as_f64().unwrap_or(f64::NAN).into(),
+        }),
+        Value::String(value) => Some(value.into()),
+        Value::Array(values) => Some(::toml::Value::Array(
+            values.into_iter().filter_map(convert).collect(),
+        )),
+        Value::Object(fields) => Some(::toml::Value::Table(
+            fields
+                .into_iter()
+                .filter_map(|(key, value)| convert(value).map(|value| (key, value)))
+                .collect(),
+        )),
+    }
+}
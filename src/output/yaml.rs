@@ -0,0 +1,62 @@
+//! YAML sequences of mappings.
+//!
+//! Scalars are written in plain style when that is unambiguous and otherwise double-quoted, and nested values are
+//! written in flow style (as JSON, which is valid YAML).
+
+use std::io::Write;
+
+use serde_json::Value;
+
+use super::{to_object, Column};
+
+pub(super) fn write<W: Write>(
+    writer: &mut W,
+    columns: &[Column],
+    rows: Vec<Vec<Value>>,
+) -> std::io::Result<()> {
+    if rows.is_empty() {
+        return writeln!(writer, "[]");
+    }
+
+    for row in rows {
+        for (index, (key, value)) in to_object(columns, row).into_iter().enumerate() {
+            let indent = if index == 0 { "- " } else { "  " };
+
+            writeln!(writer, "{indent}{}: {}", scalar(&key), node(&value))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn node(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(value) => scalar(value),
+        other => other.to_string(),
+    }
+}
+
+/// A string scalar, quoted if the plain form could be read as something else.
+fn scalar(value: &str) -> String {
+    let reserved = matches!(
+        value.to_ascii_lowercase().as_str(),
+        "" | "~" | "null" | "true" | "false" | "yes" | "no" | "on" | "off" | "y" | "n"
+    );
+    let plain = !reserved
+        && value
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '/')
+        && !value.ends_with(' ')
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '_' | '-' | '.' | '/' | '@' | '+'))
+        && !value.contains(" #");
+
+    if plain {
+        value.to_string()
+    } else {
+        Value::String(value.to_string()).to_string()
+    }
+}
@@ -0,0 +1,146 @@
+//! Consistent handling of command-line parsing errors.
+//!
+//! ```rust,no_run
+//! use cli_helpers::parse_error::ErrorHandler;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(long)]
+//!     since: Timestamp,
+//! }
+//!
+//! let opts: Opts = ErrorHandler::new()
+//!     .with_hint("see `mytool help timestamps` for accepted formats")
+//!     .parse();
+//! ```
+
+use std::ffi::OsString;
+
+use clap::builder::styling::{AnsiColor, Style};
+use clap::error::ErrorKind;
+use clap::Parser;
+
+use crate::color::{self, ColorChoice, Stream};
+
+/// The conventional exit code for command-line usage errors (`EX_USAGE` in `sysexits.h`).
+pub const USAGE_EXIT_CODE: i32 = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct ErrorHandler {
+    hints: Vec<String>,
+    color: Option<ColorChoice>,
+}
+
+impl ErrorHandler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a hint to be printed after any parsing error.
+    pub fn with_hint<S: Into<String>>(mut self, hint: S) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
+
+    /// Set the color choice (by default any `--color` argument is used, or color is detected automatically).
+    pub fn with_color(self, color: ColorChoice) -> Self {
+        Self {
+            color: Some(color),
+            ..self
+        }
+    }
+
+    /// Parse the process's arguments, exiting on error.
+    pub fn parse<T: Parser>(&self) -> T {
+        self.parse_from(std::env::args_os())
+    }
+
+    pub fn parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString>>(
+        &self,
+        args: I,
+    ) -> T {
+        let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+
+        T::try_parse_from(&args).unwrap_or_else(|error| {
+            let color = self
+                .color
+                .or_else(|| color::choice_from_args(&args))
+                .unwrap_or(ColorChoice::Auto);
+
+            self.exit(error, color::enabled(color, Stream::Stderr))
+        })
+    }
+
+    /// Render a parsing error with the configured hints.
+    pub fn render(&self, error: &clap::Error, color: bool) -> String {
+        let rendered = error.render();
+        let mut output = if color {
+            rendered.ansi().to_string()
+        } else {
+            rendered.to_string()
+        };
+
+        let style = if color {
+            AnsiColor::Cyan.on_default().bold()
+        } else {
+            Style::new()
+        };
+
+        for hint in &self.hints {
+            if !output.ends_with('\n') {
+                output.push('\n');
+            }
+
+            output.push_str(&format!("{style}hint:{style:#} {hint}\n"));
+        }
+
+        output
+    }
+
+    /// Print the error and exit with [`USAGE_EXIT_CODE`] (or successfully for help and version requests).
+    pub fn exit(&self, error: clap::Error, color: bool) -> ! {
+        match error.kind() {
+            ErrorKind::DisplayHelp
+            | ErrorKind::DisplayVersion
+            | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand => error.exit(),
+            _ => {
+                eprint!("{}", self.render(&error, color));
+                std::process::exit(USAGE_EXIT_CODE)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        #[derive(Debug, Parser)]
+        #[clap(name = "test")]
+        struct Opts {
+            #[clap(long)]
+            since: crate::Timestamp,
+        }
+
+        let error = Opts::try_parse_from(["test", "--since", "foo"]).unwrap_err();
+        let handler = ErrorHandler::new().with_hint("see `test help timestamps`");
+
+        let plain = handler.render(&error, false);
+        assert!(plain.contains("invalid value 'foo'"));
+        assert!(plain.ends_with("\nhint: see `test help timestamps`\n"));
+        assert!(!plain.contains('\x1b'));
+        assert!(handler.render(&error, true).contains('\x1b'));
+
+        assert_eq!(
+            color::choice_from_args(["test", "--color", "never", "--since", "foo"]),
+            Some(ColorChoice::Never)
+        );
+        assert_eq!(
+            color::choice_from_args(["test", "--color=always"]),
+            Some(ColorChoice::Always)
+        );
+    }
+}
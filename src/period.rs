@@ -0,0 +1,239 @@
+//! Calendar periods for commands that operate on weekly or monthly buckets.
+//!
+//! Periods are interpreted in UTC, and expand into a half-open range of timestamps: the start is the first instant of
+//! the period, and the end is the first instant of the next period.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+
+use crate::{Error, Timestamp};
+
+/// An ISO 8601 week, such as `2024-W07`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IsoWeek {
+    year: i32,
+    week: u32,
+}
+
+impl IsoWeek {
+    pub fn new(year: i32, week: u32) -> Option<Self> {
+        NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).map(|_| Self { year, week })
+    }
+
+    /// The week containing the given timestamp.
+    pub fn containing(timestamp: Timestamp) -> Self {
+        Self::containing_date(timestamp.0.date_naive())
+    }
+
+    /// The week containing the given date.
+    pub fn containing_date(date: NaiveDate) -> Self {
+        let week = date.iso_week();
+
+        Self {
+            year: week.year(),
+            week: week.week(),
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn week(&self) -> u32 {
+        self.week
+    }
+
+    /// The Monday of the week.
+    pub fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_isoywd_opt(self.year, self.week, Weekday::Mon).expect("validated week")
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        NaiveDate::from_isoywd_opt(self.year, self.week, Weekday::Sun).expect("validated week")
+    }
+
+    pub fn start(&self) -> Timestamp {
+        midnight(self.first_day())
+    }
+
+    /// The start of the following week.
+    pub fn end(&self) -> Timestamp {
+        midnight(self.first_day() + chrono::Duration::days(7))
+    }
+
+    pub fn next(&self) -> Self {
+        Self::containing(self.end())
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        self.first_day().iter_days().take(7)
+    }
+}
+
+impl Display for IsoWeek {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-W{:02}", self.year, self.week)
+    }
+}
+
+impl FromStr for IsoWeek {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once("-W")
+            .filter(|(_, week)| week.len() == 2 && week.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|(year, week)| Self::new(year.parse().ok()?, week.parse().ok()?))
+            .ok_or_else(|| Error::InvalidPeriod(s.to_string()))
+    }
+}
+
+/// A calendar month, such as `2024-02`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Month {
+    year: i32,
+    month: u32,
+}
+
+impl Month {
+    pub fn new(year: i32, month: u32) -> Option<Self> {
+        NaiveDate::from_ymd_opt(year, month, 1).map(|_| Self { year, month })
+    }
+
+    /// The month containing the given timestamp.
+    pub fn containing(timestamp: Timestamp) -> Self {
+        Self::containing_date(timestamp.0.date_naive())
+    }
+
+    /// The month containing the given date.
+    pub fn containing_date(date: NaiveDate) -> Self {
+        Self {
+            year: date.year(),
+            month: date.month(),
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u32 {
+        self.month
+    }
+
+    pub fn first_day(&self) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.year, self.month, 1).expect("validated month")
+    }
+
+    pub fn last_day(&self) -> NaiveDate {
+        self.next().first_day().pred_opt().expect("valid date")
+    }
+
+    pub fn start(&self) -> Timestamp {
+        midnight(self.first_day())
+    }
+
+    /// The start of the following month.
+    pub fn end(&self) -> Timestamp {
+        self.next().start()
+    }
+
+    pub fn next(&self) -> Self {
+        if self.month == 12 {
+            Self {
+                year: self.year + 1,
+                month: 1,
+            }
+        } else {
+            Self {
+                year: self.year,
+                month: self.month + 1,
+            }
+        }
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let month = self.month;
+
+        self.first_day()
+            .iter_days()
+            .take_while(move |day| day.month() == month)
+    }
+}
+
+impl Display for Month {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+impl FromStr for Month {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_once('-')
+            .filter(|(_, month)| {
+                month.len() == 2 && month.bytes().all(|byte| byte.is_ascii_digit())
+            })
+            .and_then(|(year, month)| Self::new(year.parse().ok()?, month.parse().ok()?))
+            .ok_or_else(|| Error::InvalidPeriod(s.to_string()))
+    }
+}
+
+fn midnight(date: NaiveDate) -> Timestamp {
+    Timestamp(date.and_time(NaiveTime::MIN).and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso_week() {
+        let week = "2024-W07".parse::<IsoWeek>().unwrap();
+
+        assert_eq!(week.start(), "2024-02-12".parse().unwrap());
+        assert_eq!(week.end(), "2024-02-19".parse().unwrap());
+        assert_eq!(week.days().count(), 7);
+        assert_eq!(week.to_string(), "2024-W07");
+        assert_eq!(week.next().to_string(), "2024-W08");
+        assert_eq!(IsoWeek::containing(week.end()), week.next());
+        assert_eq!(
+            "2020-W53".parse::<IsoWeek>().unwrap().next().to_string(),
+            "2021-W01"
+        );
+
+        for input in [
+            "2024-W54",
+            "2024-07",
+            "2024W07",
+            "2024-W7",
+            "2024-W007",
+            "2024-W+7",
+        ] {
+            assert!(input.parse::<IsoWeek>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_month() {
+        let month = "2024-02".parse::<Month>().unwrap();
+
+        assert_eq!(month.start(), "2024-02-01".parse().unwrap());
+        assert_eq!(month.end(), "2024-03-01".parse().unwrap());
+        assert_eq!(
+            month.last_day(),
+            NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+        assert_eq!(month.days().count(), 29);
+        assert_eq!(
+            "2024-12".parse::<Month>().unwrap().next().to_string(),
+            "2025-01"
+        );
+
+        for input in ["2024-13", "2024-2", "2024-+2", "2024-W07"] {
+            assert!(input.parse::<Month>().is_err(), "{input}");
+        }
+    }
+}
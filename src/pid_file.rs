@@ -0,0 +1,289 @@
+//! PID files with stale file detection.
+//!
+//! [`PidFile::acquire`] writes the current process ID to a temporary file, locks it, and links it into place, failing
+//! with [`Error::AlreadyRunning`] if another process holds the lock on an existing file. The lock is held until the
+//! [`PidFile`] is dropped (or the process exits), so files left behind by processes that have exited are unlocked, and
+//! are replaced. The file is removed when the [`PidFile`] is dropped, so it should be kept alive for the lifetime of the
+//! process:
+//!
+//! ```rust,no_run
+//! use cli_helpers::pid_file::PidFile;
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let _pid_file = PidFile::acquire("/run/user/1000/mytool.pid")?;
+//! // ...
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Locks are advisory (`flock` on Unix and `LockFileEx` on Windows), as with [`Journal`](crate::journal::Journal).
+
+use std::fs::{File, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{backup, Error};
+
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    pid: u32,
+    // The lock is released when the file is closed.
+    _file: File,
+}
+
+impl PidFile {
+    /// Create the PID file for the current process, replacing any stale file.
+    pub fn acquire<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let pid = std::process::id();
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // If the file exists but is stale, it is removed and creation is retried.
+        for _ in 0..3 {
+            match File::open(path) {
+                Ok(existing) => {
+                    Self::remove_if_stale(path, existing)?;
+                    continue;
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+
+            // The file is complete and locked before it appears at the path.
+            let temp_path = backup::temp_sibling(path)?;
+            let result = Self::write_locked(&temp_path, pid)
+                .and_then(|file| std::fs::hard_link(&temp_path, path).map(|()| file));
+            let _ = std::fs::remove_file(&temp_path);
+
+            match result {
+                Ok(file) => {
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                        pid,
+                        _file: file,
+                    });
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Err(std::io::Error::from(std::io::ErrorKind::AlreadyExists).into())
+    }
+
+    /// Fail with [`Error::AlreadyRunning`] if the file exists and is held by another process.
+    pub fn check<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+
+        match file.try_lock_shared() {
+            Ok(()) => Ok(()),
+            Err(TryLockError::WouldBlock) => match Self::read(path)? {
+                Some(pid) if pid == std::process::id() => Ok(()),
+                pid => Err(Error::AlreadyRunning {
+                    pid: pid.unwrap_or_default(),
+                    path: path.to_path_buf(),
+                }),
+            },
+            Err(TryLockError::Error(error)) => Err(error.into()),
+        }
+    }
+
+    /// The process ID recorded in the file (`None` if it does not exist or does not contain a valid process ID).
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Option<u32>, Error> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(contents.trim().parse().ok().filter(|pid| *pid != 0)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    fn write_locked(path: &Path, pid: u32) -> std::io::Result<File> {
+        let mut file = File::create(path)?;
+        file.lock()?;
+        writeln!(file, "{pid}")?;
+        file.sync_all()?;
+
+        Ok(file)
+    }
+
+    /// Remove the existing file if no process holds its lock.
+    fn remove_if_stale(path: &Path, existing: File) -> Result<(), Error> {
+        match existing.try_lock() {
+            // Another process may already have replaced the stale file (in which case it is left alone).
+            Ok(()) => {
+                if is_same_file(&existing, path)? {
+                    match std::fs::remove_file(path) {
+                        Ok(()) => {}
+                        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(error) => return Err(error.into()),
+                    }
+                }
+
+                Ok(())
+            }
+            Err(TryLockError::WouldBlock) => Err(Error::AlreadyRunning {
+                pid: Self::read(path)?.unwrap_or_default(),
+                path: path.to_path_buf(),
+            }),
+            Err(TryLockError::Error(error)) => Err(error.into()),
+        }
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Another process may have replaced the file if it was removed by hand.
+        if matches!(Self::read(&self.path), Ok(Some(pid)) if pid == self.pid) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> Result<bool, Error> {
+    use std::os::unix::fs::MetadataExt;
+
+    let a = file.metadata()?;
+
+    match std::fs::metadata(path) {
+        Ok(b) => Ok(a.dev() == b.dev() && a.ino() == b.ino()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(not(unix))]
+fn is_same_file(_file: &File, path: &Path) -> Result<bool, Error> {
+    Ok(path.try_exists()?)
+}
+
+/// Whether a process with the given ID exists (assumed to be true if this cannot be determined).
+#[cfg(unix)]
+pub(crate) fn process_exists(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+
+    // Zero would refer to the current process group.
+    if pid == 0 {
+        return false;
+    }
+
+    // SAFETY: signal zero only checks whether the process can be signalled.
+    let result = unsafe { libc::kill(pid, 0) };
+
+    // The process may exist but belong to another user.
+    result == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+#[cfg(windows)]
+pub(crate) fn process_exists(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_INVALID_PARAMETER, STILL_ACTIVE,
+    };
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is checked before use and closed afterwards.
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+
+        if handle.is_null() {
+            // Any other error (such as access being denied) means that the process exists.
+            return GetLastError() != ERROR_INVALID_PARAMETER;
+        }
+
+        let mut exit_code = 0;
+        let exists =
+            GetExitCodeProcess(handle, &mut exit_code) == 0 || exit_code == STILL_ACTIVE as u32;
+        CloseHandle(handle);
+
+        exists
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn process_exists(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_pid_file() {
+        let temp_dir = test_dir();
+        let path = temp_dir.file("run").join("test.pid");
+
+        let pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(PidFile::read(&path).unwrap(), Some(std::process::id()));
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // A file left behind by a process that has exited is replaced.
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let child_pid = child.id();
+        child.wait().unwrap();
+
+        std::fs::write(&path, format!("{child_pid}\n")).unwrap();
+        let pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(PidFile::read(&path).unwrap(), Some(std::process::id()));
+        drop(pid_file);
+
+        std::fs::write(&path, "not a pid\n").unwrap();
+        assert!(PidFile::acquire(&path).is_ok());
+
+        // A file that was left behind by a live process that did not lock it is also replaced.
+        std::fs::write(&path, format!("{}\n", std::process::id())).unwrap();
+        assert!(PidFile::acquire(&path).is_ok());
+    }
+
+    #[test]
+    fn test_pid_file_already_running() {
+        let temp_dir = test_dir();
+        let path = temp_dir.file("test.pid");
+
+        let pid_file = PidFile::acquire(&path).unwrap();
+
+        // The lock is held by the first file handle, so a second acquisition fails even within the same process.
+        assert!(matches!(
+            PidFile::acquire(&path),
+            Err(Error::AlreadyRunning { pid, .. }) if pid == std::process::id()
+        ));
+        assert!(PidFile::check(&path).is_ok());
+        drop(pid_file);
+        assert!(!path.exists());
+
+        std::fs::write(&path, "0\n").unwrap();
+        assert_eq!(PidFile::read(&path).unwrap(), None);
+        drop(PidFile::acquire(&path).unwrap());
+    }
+}
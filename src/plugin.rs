@@ -0,0 +1,282 @@
+//! External subcommand plugins (`mytool foo` runs `mytool-foo`).
+//!
+//! As with Git and Cargo, an unknown subcommand can be resolved to an executable named `PREFIX-<name>` on `PATH`, so
+//! that other tools can extend the application without recompiling it. The plugin receives the remaining arguments,
+//! and the application's settings in environment variables named after the prefix (for example `MYTOOL_LOG_LEVEL`,
+//! `MYTOOL_COLOR`, and `MYTOOL_CONFIG`):
+//!
+//! ```rust,no_run
+//! use std::ffi::OsString;
+//!
+//! use cli_helpers::plugin::Plugins;
+//! use cli_helpers::prelude::*;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     verbose: Verbosity,
+//!     #[clap(subcommand)]
+//!     command: Command,
+//! }
+//!
+//! #[derive(Debug, clap::Subcommand)]
+//! enum Command {
+//!     Fetch,
+//!     #[clap(external_subcommand)]
+//!     External(Vec<OsString>),
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//!
+//! match opts.command {
+//!     Command::Fetch => {}
+//!     Command::External(args) => {
+//!         let exit_code = Plugins::new("mytool").with_verbosity(&opts.verbose).run(&args)?;
+//!         std::process::exit(exit_code);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! On Unix the plugin replaces the current process.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use clap::{ColorChoice, ValueEnum};
+
+use crate::{Error, Verbosity};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugins {
+    prefix: String,
+    search_path: Option<Vec<PathBuf>>,
+    env: Vec<(String, OsString)>,
+}
+
+impl Plugins {
+    /// Plugins are executables named `PREFIX-<name>` (typically the application name).
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            search_path: None,
+            env: vec![],
+        }
+    }
+
+    /// Search these directories instead of `PATH`.
+    pub fn with_search_path<I: IntoIterator<Item = P>, P: AsRef<Path>>(self, dirs: I) -> Self {
+        Self {
+            search_path: Some(
+                dirs.into_iter()
+                    .map(|dir| dir.as_ref().to_path_buf())
+                    .collect(),
+            ),
+            ..self
+        }
+    }
+
+    /// Pass the log level as `PREFIX_LOG_LEVEL` (for example `info`).
+    pub fn with_verbosity(self, verbosity: &Verbosity) -> Self {
+        let level = verbosity.level_filter().as_str().to_lowercase();
+
+        self.with_env("LOG_LEVEL", level)
+    }
+
+    /// Pass the color choice as `PREFIX_COLOR` (`auto`, `always`, or `never`).
+    pub fn with_color(self, choice: ColorChoice) -> Self {
+        match choice.to_possible_value() {
+            Some(value) => {
+                let name = value.get_name().to_string();
+                self.with_env("COLOR", name)
+            }
+            None => self,
+        }
+    }
+
+    /// Pass the configuration file path as `PREFIX_CONFIG`.
+    pub fn with_config_path<P: AsRef<Path>>(self, path: P) -> Self {
+        self.with_env("CONFIG", path.as_ref())
+    }
+
+    /// Pass a value as `PREFIX_<NAME>`.
+    pub fn with_env<V: Into<OsString>>(mut self, name: &str, value: V) -> Self {
+        let name = format!("{}_{name}", self.env_prefix());
+        self.env.retain(|(existing, _)| *existing != name);
+        self.env.push((name, value.into()));
+        self
+    }
+
+    /// The environment variables passed to plugins.
+    pub fn env(&self) -> &[(String, OsString)] {
+        &self.env
+    }
+
+    /// The path of the plugin's executable, if it is installed.
+    pub fn find(&self, name: &str) -> Option<PathBuf> {
+        let file_name = format!("{}-{name}{}", self.prefix, std::env::consts::EXE_SUFFIX);
+
+        self.dirs()
+            .into_iter()
+            .map(|dir| dir.join(&file_name))
+            .find(|path| is_executable(path))
+    }
+
+    /// The names of the installed plugins.
+    pub fn names(&self) -> Vec<String> {
+        let prefix = format!("{}-", self.prefix);
+        let mut names = vec![];
+
+        for dir in self.dirs() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let file_name = entry.file_name();
+                let name = file_name
+                    .to_str()
+                    .and_then(|file_name| file_name.strip_prefix(&prefix))
+                    .and_then(|name| name.strip_suffix(std::env::consts::EXE_SUFFIX));
+
+                if let Some(name) = name.filter(|name| !name.is_empty()) {
+                    if is_executable(&entry.path()) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// A command for running the plugin named by the first argument with the remaining arguments.
+    ///
+    /// Fails with [`Error::UnknownPlugin`] if the plugin is not installed.
+    pub fn command(&self, args: &[OsString]) -> Result<Command, Error> {
+        let (name, args) = args.split_first().ok_or_else(|| Error::UnknownPlugin {
+            name: String::new(),
+            available: self.names(),
+        })?;
+        let name = name.to_string_lossy();
+
+        let path = self.find(&name).ok_or_else(|| Error::UnknownPlugin {
+            name: name.to_string(),
+            available: self.names(),
+        })?;
+
+        let mut command = Command::new(path);
+        command.args(args).envs(self.env.clone());
+
+        Ok(command)
+    }
+
+    /// Run the plugin named by the first argument, returning its exit code.
+    ///
+    /// On Unix the plugin replaces the current process, so this only returns if it could not be started.
+    pub fn run(&self, args: &[OsString]) -> Result<i32, Error> {
+        let mut command = self.command(args)?;
+        log::debug!("Running plugin {:?}", command.get_program());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            Err(command.exec().into())
+        }
+
+        #[cfg(not(unix))]
+        {
+            Ok(command
+                .status()?
+                .code()
+                .unwrap_or(crate::app::FAILURE_EXIT_CODE))
+        }
+    }
+
+    fn env_prefix(&self) -> String {
+        self.prefix
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    fn dirs(&self) -> Vec<PathBuf> {
+        match &self.search_path {
+            Some(dirs) => dirs.clone(),
+            None => std::env::var_os("PATH")
+                .map(|path| std::env::split_paths(&path).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_plugins() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+
+        let script = dir.join("my-tool-hello");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nprintf '%s %s %s' \"$MY_TOOL_LOG_LEVEL\" \"$MY_TOOL_COLOR\" \"$1\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::write(dir.join("my-tool-disabled"), "").unwrap();
+
+        let plugins = Plugins::new("my-tool")
+            .with_search_path([dir.join("missing"), dir.to_path_buf()])
+            .with_verbosity(&Verbosity::new(3))
+            .with_color(ColorChoice::Never);
+
+        assert_eq!(plugins.names(), vec!["hello"]);
+        assert_eq!(plugins.find("hello"), Some(script));
+
+        let output = plugins
+            .command(&["hello".into(), "world".into()])
+            .unwrap()
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "info never world"
+        );
+
+        assert!(matches!(
+            plugins.command(&["disabled".into()]),
+            Err(Error::UnknownPlugin { name, available }) if name == "disabled" && available == vec!["hello"]
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+//! Named presets of command-line options (`--save-preset`, `--preset`).
+//!
+//! [`Presets`] adds two options to a command. `--save-preset NAME` saves the options given on the command line to a
+//! file in the application configuration directory, and `--preset NAME` loads them as defaults for the current run,
+//! so that options given explicitly still take precedence:
+//!
+//! ```text
+//! $ mytool export --since yesterday --format ndjson --fields id,text --save-preset daily
+//! $ mytool export --preset daily --format csv
+//! ```
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{app_dirs::AppDirs, preset::Presets};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(long)]
+//!     since: Option<String>,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts: Opts = Presets::new(&AppDirs::new("mytool")?).parse()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only top-level options are saved (not positional arguments or subcommand options). Since preset values are applied
+//! as defaults, environment variables also take precedence over them.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command, Parser};
+use serde_json::Value;
+
+use crate::{app_dirs::AppDirs, Error};
+
+const PRESET_ID: &str = "preset";
+const SAVE_PRESET_ID: &str = "save-preset";
+const PRESETS_DIR_NAME: &str = "presets";
+const PRESET_FILE_EXTENSION: &str = "json";
+
+/// Option values, as given on the command line, by argument ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preset {
+    values: BTreeMap<String, Vec<String>>,
+}
+
+impl Preset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_value<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        id: &str,
+        values: I,
+    ) -> Self {
+        self.values
+            .insert(id.to_string(), values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    pub fn values(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.values
+    }
+
+    /// The options that were given on the command line.
+    pub fn from_matches(command: &Command, matches: &ArgMatches) -> Self {
+        let values = command
+            .get_arguments()
+            .filter(|arg| is_presettable(arg))
+            .filter(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+            .filter_map(|arg| {
+                let id = arg.get_id().as_str();
+                let values = matches
+                    .get_raw(id)?
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .collect();
+
+                Some((id.to_string(), values))
+            })
+            .collect();
+
+        Self { values }
+    }
+
+    /// Add the values of another preset, replacing any for the same options.
+    pub fn merge(mut self, other: Self) -> Self {
+        self.values.extend(other.values);
+        self
+    }
+
+    /// Use the preset's values as the command's defaults.
+    ///
+    /// Values for options that the command does not have (for example because they have been renamed) are ignored.
+    pub fn apply_defaults(&self, command: Command) -> Command {
+        self.values.iter().fold(command, |command, (id, values)| {
+            if command.get_arguments().any(|arg| arg.get_id() == id) {
+                command.mut_arg(id, |arg| arg.required(false).default_values(values))
+            } else {
+                log::warn!("Ignoring unknown option in preset: {id}");
+                command
+            }
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        Value::Object(
+            self.values
+                .iter()
+                .map(|(id, values)| (id.clone(), values.clone().into()))
+                .collect(),
+        )
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let values = value
+            .as_object()?
+            .iter()
+            .map(|(id, values)| {
+                let values = values
+                    .as_array()?
+                    .iter()
+                    .map(|value| value.as_str().map(str::to_string))
+                    .collect::<Option<_>>()?;
+
+                Some((id.clone(), values))
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self { values })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Presets {
+    dir: PathBuf,
+}
+
+impl Presets {
+    /// Store presets in the application configuration directory.
+    pub fn new(dirs: &AppDirs) -> Self {
+        Self::with_dir(dirs.config_dir().join(PRESETS_DIR_NAME))
+    }
+
+    pub fn with_dir<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The names of the saved presets.
+    pub fn names(&self) -> Result<Vec<String>, Error> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut names = vec![];
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == PRESET_FILE_EXTENSION)
+            {
+                if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+
+        names.sort();
+
+        Ok(names)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Preset, Error> {
+        let path = self.path(name)?;
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Err(Error::UnknownPreset {
+                    name: name.to_string(),
+                    available: self.names()?,
+                })
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        serde_json::from_str(&contents)
+            .ok()
+            .as_ref()
+            .and_then(Preset::from_json)
+            .ok_or_else(|| Error::InvalidPreset(name.to_string()))
+    }
+
+    /// Save a preset, replacing any existing preset with the same name, and return its path.
+    pub fn save(&self, name: &str, preset: &Preset) -> Result<PathBuf, Error> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, format!("{:#}\n", preset.to_json()))?;
+
+        Ok(path)
+    }
+
+    /// Add the `--preset` and `--save-preset` options to a command.
+    pub fn apply(&self, command: Command) -> Command {
+        command
+            .arg(
+                Arg::new(PRESET_ID)
+                    .long(PRESET_ID)
+                    .value_name("NAME")
+                    .help("Use the options saved in a preset as defaults"),
+            )
+            .arg(
+                Arg::new(SAVE_PRESET_ID)
+                    .long(SAVE_PRESET_ID)
+                    .value_name("NAME")
+                    .help("Save the options given on the command line as a preset"),
+            )
+    }
+
+    /// Parse the process's arguments, exiting on a parsing error.
+    pub fn parse<T: Parser>(&self) -> Result<T, Error> {
+        self.parse_from(std::env::args_os())
+    }
+
+    pub fn parse_from<T: Parser, I: IntoIterator<Item = A>, A: Into<OsString> + Clone>(
+        &self,
+        args: I,
+    ) -> Result<T, Error> {
+        let args = args.into_iter().map(Into::into).collect::<Vec<OsString>>();
+        let mut command = self.apply(T::command());
+        let mut matches = command.clone().get_matches_from(&args);
+        let mut preset = Preset::new();
+
+        if let Some(name) = matches.get_one::<String>(PRESET_ID) {
+            preset = self.load(name)?;
+            command = preset.apply_defaults(command);
+            matches = command.clone().get_matches_from(&args);
+        }
+
+        if let Some(name) = matches.get_one::<String>(SAVE_PRESET_ID) {
+            let path = self.save(
+                name,
+                &preset.merge(Preset::from_matches(&command, &matches)),
+            )?;
+            log::info!("Saved preset {name} to {}", path.display());
+        }
+
+        Ok(T::from_arg_matches(&matches).unwrap_or_else(|error| error.format(&mut command).exit()))
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, Error> {
+        let is_valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if is_valid {
+            Ok(self.dir.join(format!("{name}.{PRESET_FILE_EXTENSION}")))
+        } else {
+            Err(Error::InvalidPreset(name.to_string()))
+        }
+    }
+}
+
+fn is_presettable(arg: &Arg) -> bool {
+    !arg.is_positional()
+        && !matches!(arg.get_id().as_str(), PRESET_ID | SAVE_PRESET_ID)
+        && !matches!(
+            arg.get_action(),
+            ArgAction::Help | ArgAction::HelpShort | ArgAction::HelpLong | ArgAction::Version
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(long)]
+        since: Option<String>,
+        #[clap(long, default_value = "table")]
+        format: String,
+        #[clap(long, value_delimiter = ',')]
+        fields: Vec<String>,
+        #[clap(long)]
+        wide: bool,
+        #[clap(short, action = clap::ArgAction::Count)]
+        verbose: u8,
+    }
+
+    #[test]
+    fn test_presets() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let presets = Presets::with_dir(dir);
+
+        let opts: Opts = presets
+            .parse_from([
+                "mytool",
+                "--since",
+                "yesterday",
+                "--fields",
+                "id,text",
+                "--wide",
+                "-vv",
+                "--save-preset",
+                "daily",
+            ])
+            .unwrap();
+        assert_eq!(opts.since.as_deref(), Some("yesterday"));
+        assert_eq!(presets.names().unwrap(), vec!["daily"]);
+
+        let opts: Opts = presets
+            .parse_from(["mytool", "--preset", "daily", "--format", "csv"])
+            .unwrap();
+        assert_eq!(opts.since.as_deref(), Some("yesterday"));
+        assert_eq!(opts.format, "csv");
+        assert_eq!(opts.fields, vec!["id", "text"]);
+        assert!(opts.wide);
+        assert_eq!(opts.verbose, 2);
+
+        let opts: Opts = presets
+            .parse_from(["mytool", "--preset", "daily", "--since", "today"])
+            .unwrap();
+        assert_eq!(opts.since.as_deref(), Some("today"));
+        assert_eq!(opts.format, "table");
+
+        assert!(matches!(
+            presets.load("weekly"),
+            Err(Error::UnknownPreset { available, .. }) if available == vec!["daily"]
+        ));
+        assert!(matches!(
+            presets.load("../daily"),
+            Err(Error::InvalidPreset(_))
+        ));
+    }
+}
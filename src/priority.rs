@@ -0,0 +1,208 @@
+//! CPU and I/O scheduling priority flags (`--nice`, `--ionice`).
+//!
+//! [`PriorityArgs::apply`] sets the process's niceness with `setpriority` and its I/O scheduling class with
+//! `ioprio_set`, so that long batch jobs can run politely on shared machines:
+//!
+//! ```text
+//! $ mytool --nice 10 --ionice idle import data/
+//! ```
+//!
+//! Priorities should be applied at startup, before any threads are started (on Linux, threads that already exist keep
+//! their old priorities). I/O priorities are only supported on Linux, and neither flag has any effect on Windows.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::Error;
+
+/// The highest I/O priority level within a class (levels range from 0, the highest priority, to 7).
+const MAX_IO_LEVEL: u8 = 7;
+
+/// Standard priority arguments.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PriorityArgs {
+    /// Scheduling niceness, from -20 (highest priority) to 19 (lowest)
+    #[clap(long, allow_negative_numbers = true, value_parser = clap::value_parser!(i32).range(-20..=19))]
+    nice: Option<i32>,
+    /// I/O scheduling class (`idle`, `best-effort[:LEVEL]`, or `realtime[:LEVEL]`, with levels from 0 to 7)
+    #[clap(long)]
+    ionice: Option<IoPriority>,
+}
+
+impl PriorityArgs {
+    pub fn new(nice: Option<i32>, ionice: Option<IoPriority>) -> Self {
+        Self { nice, ionice }
+    }
+
+    pub fn nice(&self) -> Option<i32> {
+        self.nice
+    }
+
+    pub fn ionice(&self) -> Option<IoPriority> {
+        self.ionice
+    }
+
+    /// Apply the requested priorities to the current process.
+    pub fn apply(&self) -> Result<(), Error> {
+        if let Some(nice) = self.nice {
+            set_nice(nice)?;
+        }
+
+        if let Some(ionice) = self.ionice {
+            set_io_priority(ionice)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum IoPriority {
+    /// Only use the disk when no other process needs it.
+    Idle,
+    BestEffort(u8),
+    /// Usually requires elevated privileges.
+    Realtime(u8),
+}
+
+impl IoPriority {
+    /// The Linux `ioprio` value.
+    fn value(&self) -> i32 {
+        let (class, level) = match self {
+            Self::Realtime(level) => (1, *level),
+            Self::BestEffort(level) => (2, *level),
+            Self::Idle => (3, 0),
+        };
+
+        (class << 13) | i32::from(level)
+    }
+}
+
+impl FromStr for IoPriority {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (class, level) = match s.split_once(':') {
+            Some((class, level)) => (class, Some(level)),
+            None => (s, None),
+        };
+
+        let level = match level {
+            Some(level) => match level.parse::<u8>() {
+                Ok(level) if level <= MAX_IO_LEVEL => Some(level),
+                _ => return Err(Error::InvalidIoPriority(s.to_string())),
+            },
+            None => None,
+        };
+
+        match (class, level) {
+            ("idle", None) => Ok(Self::Idle),
+            ("best-effort", level) => Ok(Self::BestEffort(level.unwrap_or(4))),
+            ("realtime", level) => Ok(Self::Realtime(level.unwrap_or(4))),
+            _ => Err(Error::InvalidIoPriority(s.to_string())),
+        }
+    }
+}
+
+impl Display for IoPriority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Idle => write!(f, "idle"),
+            Self::BestEffort(level) => write!(f, "best-effort:{level}"),
+            Self::Realtime(level) => write!(f, "realtime:{level}"),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_nice(nice: i32) -> Result<(), Error> {
+    // SAFETY: `setpriority` has no memory safety requirements.
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS as _, 0, nice) } == -1 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+fn set_nice(_nice: i32) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(priority: IoPriority) -> Result<(), Error> {
+    /// Apply to a single process (identified by PID, where 0 is the current process).
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+    // SAFETY: `ioprio_set` takes only integer arguments.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            priority.value(),
+        )
+    };
+
+    if result == -1 {
+        Err(std::io::Error::last_os_error().into())
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn set_io_priority(priority: IoPriority) -> Result<(), Error> {
+    log::warn!("I/O priorities are not supported on this platform; ignoring --ionice {priority}");
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_io_priority(_priority: IoPriority) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_priority() {
+        assert_eq!("idle".parse::<IoPriority>().unwrap(), IoPriority::Idle);
+        assert_eq!(
+            "best-effort".parse::<IoPriority>().unwrap(),
+            IoPriority::BestEffort(4)
+        );
+        assert_eq!(
+            "realtime:0".parse::<IoPriority>().unwrap(),
+            IoPriority::Realtime(0)
+        );
+        assert_eq!(IoPriority::BestEffort(7).to_string(), "best-effort:7");
+        assert_eq!(IoPriority::Idle.value(), 3 << 13);
+        assert!("idle:3".parse::<IoPriority>().is_err());
+        assert!("best-effort:8".parse::<IoPriority>().is_err());
+        assert!("low".parse::<IoPriority>().is_err());
+    }
+
+    #[test]
+    fn test_priority_args() {
+        use clap::Parser;
+
+        #[derive(Debug, Parser)]
+        struct Opts {
+            #[clap(flatten)]
+            priority: PriorityArgs,
+        }
+
+        let opts = Opts::try_parse_from(["test", "--nice", "-5", "--ionice", "idle"]).unwrap();
+        assert_eq!(opts.priority.nice(), Some(-5));
+        assert_eq!(opts.priority.ionice(), Some(IoPriority::Idle));
+        assert!(Opts::try_parse_from(["test", "--nice", "20"]).is_err());
+
+        // Lowering the priority never requires privileges.
+        #[cfg(target_os = "linux")]
+        PriorityArgs::new(None, Some(IoPriority::BestEffort(7)))
+            .apply()
+            .unwrap();
+    }
+}
@@ -0,0 +1,602 @@
+//! Progress reporting that is independent of the frontend.
+//!
+//! Library code reports progress through the [`Progress`] trait, and the application chooses an implementation:
+//! a terminal bar, periodic log lines, JSON events, or nothing. [`auto`] selects a terminal bar when standard error is
+//! an interactive terminal, log lines when it is not (and info-level logging is enabled), and otherwise nothing.
+//! [`ProgressArgs`] provides a standard `--progress` flag for overriding this choice (for example, `--progress json`
+//! for GUI wrappers and CI systems that parse progress events from standard error).
+//!
+//! For concurrent workers, [`multi::MultiProgress`] draws a bar per worker above an aggregate bar, and for operations
+//! of unknown duration, [`spinner::spinner`] shows an animated spinner.
+
+pub mod multi;
+pub mod rate;
+pub mod spinner;
+
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use rate::RateEstimator;
+
+/// The minimum time between terminal bar redraws.
+pub const BAR_INTERVAL: Duration = Duration::from_millis(100);
+/// The minimum time between progress log lines.
+pub const LOG_INTERVAL: Duration = Duration::from_secs(10);
+/// The minimum time between JSON progress events.
+pub const JSON_INTERVAL: Duration = Duration::from_secs(1);
+
+const BAR_WIDTH: usize = 30;
+
+pub trait Progress: Send + Sync {
+    /// Set the total number of items (if known).
+    fn set_length(&self, length: u64);
+    /// Record that `delta` more items have been processed.
+    fn inc(&self, delta: u64);
+    fn set_message(&self, message: &str);
+    /// Report the final state.
+    fn finish(&self);
+
+    /// Record that one more item has been processed.
+    fn tick(&self) {
+        self.inc(1);
+    }
+}
+
+/// Standard progress argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgressArgs {
+    /// How to report progress on standard error
+    #[clap(long, global = true, value_enum, default_value_t)]
+    progress: ProgressMode,
+}
+
+impl ProgressArgs {
+    pub fn new(progress: ProgressMode) -> Self {
+        Self { progress }
+    }
+
+    pub fn mode(&self) -> ProgressMode {
+        self.progress
+    }
+
+    /// Create the selected progress frontend.
+    pub fn progress(&self) -> Box<dyn Progress> {
+        match self.progress {
+            ProgressMode::Auto => auto(),
+            ProgressMode::Bar => Box::new(BarProgress::stderr()),
+            ProgressMode::Log => Box::new(LogProgress::new()),
+            ProgressMode::Json => Box::new(JsonProgress::new(std::io::stderr())),
+            ProgressMode::None => Box::new(NoProgress),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// A bar on terminals, and log lines otherwise
+    #[default]
+    Auto,
+    /// A progress bar
+    Bar,
+    /// Periodic log lines
+    Log,
+    /// Periodic JSON events
+    Json,
+    /// No progress reporting
+    None,
+}
+
+/// Choose a progress frontend for standard error.
+pub fn auto() -> Box<dyn Progress> {
+    if is_interactive() {
+        Box::new(BarProgress::stderr())
+    } else if log::log_enabled!(log::Level::Info) {
+        Box::new(LogProgress::new())
+    } else {
+        Box::new(NoProgress)
+    }
+}
+
+/// Whether standard error is an interactive terminal (and we do not appear to be running in CI).
+pub fn is_interactive() -> bool {
+    std::io::stderr().is_terminal() && !crate::term::is_dumb() && std::env::var_os("CI").is_none()
+}
+
+/// A progress implementation that does nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn set_length(&self, _: u64) {}
+    fn inc(&self, _: u64) {}
+    fn set_message(&self, _: &str) {}
+    fn finish(&self) {}
+}
+
+/// The state shared by the reporting implementations.
+#[derive(Debug)]
+struct State {
+    done: AtomicU64,
+    /// Zero if the length is unknown.
+    length: AtomicU64,
+    message: Mutex<String>,
+    interval: Duration,
+    start: Instant,
+    last_report: Mutex<Option<Instant>>,
+    estimator: Mutex<RateEstimator>,
+}
+
+/// A snapshot of the state at the time of a report.
+struct Snapshot {
+    done: u64,
+    length: Option<u64>,
+    message: String,
+    elapsed: Duration,
+    /// The rate and ETA (while incomplete).
+    estimate: Option<String>,
+    rate: Option<f64>,
+}
+
+impl Snapshot {
+    /// The smoothed rate, or the average number of items per second if there is no estimate yet.
+    fn rate(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+
+        self.rate.unwrap_or(if seconds > 0.0 {
+            self.done as f64 / seconds
+        } else {
+            0.0
+        })
+    }
+
+    fn is_complete(&self) -> bool {
+        self.length.is_some_and(|length| self.done >= length)
+    }
+}
+
+impl State {
+    fn new(interval: Duration) -> Self {
+        Self {
+            done: AtomicU64::new(0),
+            length: AtomicU64::new(0),
+            message: Mutex::new(String::new()),
+            interval,
+            start: Instant::now(),
+            last_report: Mutex::new(None),
+            estimator: Mutex::new(RateEstimator::default()),
+        }
+    }
+
+    /// Return a snapshot if a report is due (or forced), updating the time of the last report.
+    fn report(&self, force: bool) -> Option<Snapshot> {
+        let mut last_report = self
+            .last_report
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let now = Instant::now();
+
+        if force
+            || last_report
+                .is_none_or(|last_report| now.duration_since(last_report) >= self.interval)
+        {
+            *last_report = Some(now);
+
+            Some(self.snapshot())
+        } else {
+            None
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let length = self.length.load(Ordering::Relaxed);
+        let length = (length > 0).then_some(length);
+        let done = self.done.load(Ordering::Relaxed);
+
+        let mut estimator = self
+            .estimator
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        estimator.record(done);
+
+        Snapshot {
+            done,
+            length,
+            estimate: estimator.render(done, length),
+            rate: estimator.rate(),
+            message: self
+                .message
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .clone(),
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    fn set_length(&self, length: u64) {
+        self.length.store(length, Ordering::Relaxed);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.done.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    fn set_message(&self, message: &str) {
+        *self
+            .message
+            .lock()
+            .unwrap_or_else(|error| error.into_inner()) = message.to_string();
+    }
+}
+
+/// A progress bar redrawn in place on a terminal.
+pub struct BarProgress {
+    state: State,
+    writer: Mutex<Box<dyn Write + Send>>,
+    max_width: Option<usize>,
+}
+
+impl BarProgress {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::with_interval(writer, BAR_INTERVAL)
+    }
+
+    pub fn with_interval<W: Write + Send + 'static>(writer: W, interval: Duration) -> Self {
+        Self {
+            state: State::new(interval),
+            writer: Mutex::new(Box::new(writer)),
+            max_width: None,
+        }
+    }
+
+    /// Draw to standard error, fitting lines to the terminal width.
+    pub fn stderr() -> Self {
+        Self::new(std::io::stderr())
+            .with_max_width(crate::term::width(crate::color::Stream::Stderr))
+    }
+
+    /// Truncate lines to the given width (so that they do not wrap and break redrawing).
+    pub fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    fn render(&self, force: bool, finished: bool) {
+        if let Some(snapshot) = self.state.report(force) {
+            let mut line = format!("\r\x1b[2K{}", fit(bar_line(&snapshot), self.max_width));
+
+            if finished {
+                line.push('\n');
+            }
+
+            let mut writer = self
+                .writer
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            let _ = writer.write_all(line.as_bytes());
+            let _ = writer.flush();
+        }
+    }
+}
+
+fn bar_line(snapshot: &Snapshot) -> String {
+    let mut line = String::new();
+
+    if !snapshot.message.is_empty() {
+        line.push_str(&snapshot.message);
+        line.push(' ');
+    }
+
+    match snapshot.length {
+        Some(length) => {
+            let ratio = (snapshot.done as f64 / length as f64).min(1.0);
+            let filled = (ratio * BAR_WIDTH as f64).round() as usize;
+
+            line.push_str(&format!(
+                "[{}{}] {}/{} ({:.0}%)",
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                snapshot.done,
+                length,
+                ratio * 100.0
+            ));
+        }
+        None => line.push_str(&snapshot.done.to_string()),
+    }
+
+    if let Some(estimate) = snapshot
+        .estimate
+        .as_ref()
+        .filter(|_| snapshot.length.is_some() && !snapshot.is_complete())
+    {
+        line.push_str(", ");
+        line.push_str(estimate);
+    }
+
+    line
+}
+
+/// Truncate a line to less than the terminal width, since writing to the last column may wrap.
+fn fit(mut line: String, width: Option<usize>) -> String {
+    if let Some((index, _)) =
+        width.and_then(|width| line.char_indices().nth(width.saturating_sub(1)))
+    {
+        line.truncate(index);
+    }
+
+    line
+}
+
+impl Progress for BarProgress {
+    fn set_length(&self, length: u64) {
+        self.state.set_length(length);
+        self.render(false, false);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.state.inc(delta);
+        self.render(false, false);
+    }
+
+    fn set_message(&self, message: &str) {
+        self.state.set_message(message);
+        self.render(true, false);
+    }
+
+    fn finish(&self) {
+        self.render(true, true);
+    }
+}
+
+/// Progress reported as periodic info-level log lines.
+pub struct LogProgress {
+    state: State,
+}
+
+impl LogProgress {
+    pub fn new() -> Self {
+        Self::with_interval(LOG_INTERVAL)
+    }
+
+    pub fn with_interval(interval: Duration) -> Self {
+        Self {
+            state: State::new(interval),
+        }
+    }
+
+    fn report(&self, force: bool) {
+        if let Some(snapshot) = self.state.report(force) {
+            let message = if snapshot.message.is_empty() {
+                "Progress"
+            } else {
+                &snapshot.message
+            };
+
+            let estimate = match &snapshot.estimate {
+                Some(estimate) if !snapshot.is_complete() => format!(", {estimate}"),
+                _ => String::new(),
+            };
+
+            match snapshot.length {
+                Some(length) => log::info!(
+                    "{message}: {}/{length} ({:.0}%){estimate}",
+                    snapshot.done,
+                    (snapshot.done as f64 / length as f64).min(1.0) * 100.0
+                ),
+                None => log::info!("{message}: {}{estimate}", snapshot.done),
+            }
+        }
+    }
+}
+
+impl Default for LogProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Progress for LogProgress {
+    fn set_length(&self, length: u64) {
+        self.state.set_length(length);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.state.inc(delta);
+        self.report(false);
+    }
+
+    fn set_message(&self, message: &str) {
+        self.state.set_message(message);
+    }
+
+    fn finish(&self) {
+        self.report(true);
+    }
+}
+
+/// Progress reported as periodic JSON objects, one per line.
+///
+/// Events have `done`, `total` (`null` if unknown), and `rate` (smoothed items per second) fields, and optionally `message`. The
+/// last event has `"finished": true`.
+pub struct JsonProgress {
+    state: State,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonProgress {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::with_interval(writer, JSON_INTERVAL)
+    }
+
+    pub fn with_interval<W: Write + Send + 'static>(writer: W, interval: Duration) -> Self {
+        Self {
+            state: State::new(interval),
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    fn report(&self, force: bool, finished: bool) {
+        if let Some(snapshot) = self.state.report(force) {
+            let mut event = serde_json::Map::new();
+            event.insert("done".to_string(), snapshot.done.into());
+            event.insert("total".to_string(), snapshot.length.into());
+            event.insert(
+                "rate".to_string(),
+                serde_json::Number::from_f64((snapshot.rate() * 10.0).round() / 10.0)
+                    .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            );
+
+            if !snapshot.message.is_empty() {
+                event.insert("message".to_string(), snapshot.message.into());
+            }
+
+            if finished {
+                event.insert("finished".to_string(), true.into());
+            }
+
+            let mut line = serde_json::Value::Object(event).to_string();
+            line.push('\n');
+
+            let mut writer = self
+                .writer
+                .lock()
+                .unwrap_or_else(|error| error.into_inner());
+            let _ = writer.write_all(line.as_bytes());
+            let _ = writer.flush();
+        }
+    }
+}
+
+impl Progress for JsonProgress {
+    fn set_length(&self, length: u64) {
+        self.state.set_length(length);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.state.inc(delta);
+        self.report(false, false);
+    }
+
+    fn set_message(&self, message: &str) {
+        self.state.set_message(message);
+    }
+
+    fn finish(&self) {
+        self.report(true, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    fn run(progress: &dyn Progress) {
+        progress.set_length(4);
+        progress.set_message("Indexing");
+
+        for _ in 0..4 {
+            progress.tick();
+        }
+
+        progress.finish();
+    }
+
+    #[test]
+    fn test_bar_progress() {
+        let buffer = Buffer::default();
+        run(&BarProgress::with_interval(
+            buffer.clone(),
+            Duration::from_secs(3600),
+        ));
+
+        let contents = buffer.contents();
+        let last = contents.rsplit('\r').next().unwrap();
+
+        assert_eq!(
+            last,
+            format!("\x1b[2KIndexing [{}] 4/4 (100%)\n", "#".repeat(BAR_WIDTH))
+        );
+    }
+
+    #[test]
+    fn test_json_progress() {
+        let buffer = Buffer::default();
+        run(&JsonProgress::with_interval(buffer.clone(), Duration::ZERO));
+
+        let lines = buffer.contents();
+        let lines = lines.lines().collect::<Vec<_>>();
+
+        let events = lines
+            .iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0]["done"], 1);
+        assert_eq!(events[0]["total"], 4);
+        assert!(events[0]["rate"].is_number());
+        assert_eq!(events[4]["done"], 4);
+        assert_eq!(events[4]["message"], "Indexing");
+        assert_eq!(events[4]["finished"], true);
+    }
+
+    #[test]
+    fn test_progress_args() {
+        use clap::Parser;
+
+        #[derive(Parser)]
+        struct Opts {
+            #[clap(flatten)]
+            progress: ProgressArgs,
+        }
+
+        assert_eq!(
+            Opts::parse_from(["demo"]).progress.mode(),
+            ProgressMode::Auto
+        );
+        assert_eq!(
+            Opts::parse_from(["demo", "--progress", "json"])
+                .progress
+                .mode(),
+            ProgressMode::Json
+        );
+    }
+
+    #[test]
+    fn test_log_progress() {
+        let records = crate::testing::capture_logs(|| {
+            run(&LogProgress::with_interval(Duration::from_secs(3600)))
+        });
+
+        let messages = records
+            .iter()
+            .map(|record| record.message.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            messages,
+            vec!["Indexing: 1/4 (25%)", "Indexing: 4/4 (100%)"]
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Progress for concurrent workers, drawn as one bar per active worker above an aggregate bar.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{bar_line, fit, is_interactive, Progress, State, BAR_INTERVAL};
+
+/// A set of worker progress bars with an aggregate bar.
+///
+/// The aggregate counts every item reported by any worker (as well as items reported directly to the
+/// `MultiProgress`). Its length is unknown unless set explicitly. Each worker's bar is removed when the worker
+/// finishes or is dropped.
+pub struct MultiProgress {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    aggregate: State,
+    workers: Mutex<Vec<(u64, Arc<State>)>>,
+    next_id: AtomicU64,
+    /// `None` if drawing is disabled.
+    writer: Option<Mutex<Output>>,
+}
+
+struct Output {
+    writer: Box<dyn Write + Send>,
+    /// The number of lines drawn by the last render.
+    lines: usize,
+    max_width: Option<usize>,
+}
+
+impl MultiProgress {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self::with_interval(writer, BAR_INTERVAL)
+    }
+
+    pub fn with_interval<W: Write + Send + 'static>(writer: W, interval: Duration) -> Self {
+        Self::build(
+            Some(Output {
+                writer: Box::new(writer),
+                lines: 0,
+                max_width: None,
+            }),
+            interval,
+        )
+    }
+
+    /// Draw to standard error if it is an interactive terminal, and otherwise only track progress.
+    pub fn stderr() -> Self {
+        if is_interactive() {
+            Self::new(std::io::stderr())
+                .with_max_width(crate::term::width(crate::color::Stream::Stderr))
+        } else {
+            Self::build(None, BAR_INTERVAL)
+        }
+    }
+
+    /// Truncate lines to the given width (so that they do not wrap and break redrawing).
+    pub fn with_max_width(self, max_width: Option<usize>) -> Self {
+        if let Some(output) = &self.shared.writer {
+            output
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .max_width = max_width;
+        }
+
+        self
+    }
+
+    fn build(output: Option<Output>, interval: Duration) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                aggregate: State::new(interval),
+                workers: Mutex::new(vec![]),
+                next_id: AtomicU64::new(0),
+                writer: output.map(Mutex::new),
+            }),
+        }
+    }
+
+    /// Add a bar for a worker.
+    pub fn add(&self, message: &str) -> WorkerProgress {
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let state = Arc::new(State::new(Duration::ZERO));
+        state.set_message(message);
+
+        self.shared.lock_workers().push((id, state.clone()));
+        self.shared.render(true);
+
+        WorkerProgress {
+            id,
+            state,
+            shared: self.shared.clone(),
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    /// The number of workers that have not finished.
+    pub fn active(&self) -> usize {
+        self.shared.lock_workers().len()
+    }
+}
+
+impl Progress for MultiProgress {
+    fn set_length(&self, length: u64) {
+        self.shared.aggregate.set_length(length);
+        self.shared.render(false);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.shared.aggregate.inc(delta);
+        self.shared.render(false);
+    }
+
+    fn set_message(&self, message: &str) {
+        self.shared.aggregate.set_message(message);
+        self.shared.render(true);
+    }
+
+    fn finish(&self) {
+        self.shared.lock_workers().clear();
+        self.shared.render(true);
+
+        // Leave the final state on the screen.
+        if let Some(output) = &self.shared.writer {
+            output
+                .lock()
+                .unwrap_or_else(|error| error.into_inner())
+                .lines = 0;
+        }
+    }
+}
+
+impl Shared {
+    fn lock_workers(&self) -> std::sync::MutexGuard<'_, Vec<(u64, Arc<State>)>> {
+        self.workers
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn render(&self, force: bool) {
+        let Some(output) = &self.writer else {
+            return;
+        };
+
+        if let Some(aggregate) = self.aggregate.report(force) {
+            let mut lines = self
+                .lock_workers()
+                .iter()
+                .map(|(_, state)| bar_line(&state.snapshot()))
+                .collect::<Vec<_>>();
+            lines.push(bar_line(&aggregate));
+
+            let mut output = output.lock().unwrap_or_else(|error| error.into_inner());
+            let mut contents = String::from("\r");
+
+            // Move back to the start of the previous render.
+            if output.lines > 0 {
+                contents.push_str(&format!("\x1b[{}A", output.lines));
+            }
+
+            for line in lines.iter().cloned() {
+                contents.push_str("\x1b[2K");
+                contents.push_str(&fit(line, output.max_width));
+                contents.push('\n');
+            }
+
+            // Clear any lines left over from a taller previous render.
+            contents.push_str("\x1b[J");
+
+            let _ = output.writer.write_all(contents.as_bytes());
+            let _ = output.writer.flush();
+            output.lines = lines.len();
+        }
+    }
+}
+
+/// The progress of a single worker in a [`MultiProgress`].
+pub struct WorkerProgress {
+    id: u64,
+    state: Arc<State>,
+    shared: Arc<Shared>,
+    finished: AtomicBool,
+}
+
+impl Progress for WorkerProgress {
+    fn set_length(&self, length: u64) {
+        self.state.set_length(length);
+        self.shared.render(false);
+    }
+
+    fn inc(&self, delta: u64) {
+        self.state.inc(delta);
+        self.shared.aggregate.inc(delta);
+        self.shared.render(false);
+    }
+
+    fn set_message(&self, message: &str) {
+        self.state.set_message(message);
+        self.shared.render(false);
+    }
+
+    /// Remove the worker's bar.
+    fn finish(&self) {
+        if !self.finished.swap(true, Ordering::SeqCst) {
+            self.shared.lock_workers().retain(|(id, _)| *id != self.id);
+            self.shared.render(true);
+        }
+    }
+}
+
+impl Drop for WorkerProgress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_multi_progress() {
+        let buffer = Buffer::default();
+        let multi = MultiProgress::with_interval(buffer.clone(), Duration::from_secs(3600));
+        multi.set_message("Total");
+
+        std::thread::scope(|scope| {
+            for name in ["a", "b"] {
+                let worker = multi.add(name);
+
+                scope.spawn(move || {
+                    worker.set_length(10);
+
+                    for _ in 0..10 {
+                        worker.tick();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(multi.active(), 0);
+        multi.finish();
+
+        let contents = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let last = contents.rsplit('\r').next().unwrap();
+
+        // After the workers finish, only the aggregate bar remains.
+        assert_eq!(last, "\x1b[1A\x1b[2KTotal 20\n\x1b[J");
+    }
+}
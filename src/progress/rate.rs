@@ -0,0 +1,165 @@
+//! Throughput and ETA estimation.
+//!
+//! [`RateEstimator`] measures the rate over a sliding window of recent samples (so that it follows changes in
+//! throughput without reflecting the whole run), and smooths it exponentially, so that estimates do not jump with
+//! every sample.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// The default sliding window.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(10);
+
+/// The weight of each new window rate in the smoothed rate.
+const SMOOTHING: f64 = 0.3;
+
+#[derive(Debug, Clone)]
+pub struct RateEstimator {
+    window: Duration,
+    samples: VecDeque<(Instant, u64)>,
+    smoothed: Option<f64>,
+}
+
+impl RateEstimator {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: VecDeque::new(),
+            smoothed: None,
+        }
+    }
+
+    /// Record the cumulative number of items done at the current time.
+    pub fn record(&mut self, done: u64) {
+        self.record_at(Instant::now(), done);
+    }
+
+    /// Record the cumulative number of items done at the given time.
+    pub fn record_at(&mut self, now: Instant, done: u64) {
+        if let Some((last, _)) = self.samples.back() {
+            if now < *last {
+                return;
+            }
+        }
+
+        self.samples.push_back((now, done));
+
+        // Keep one sample at or beyond the start of the window, so that the window is always covered.
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= self.window {
+            self.samples.pop_front();
+        }
+
+        if let Some(rate) = self.window_rate() {
+            self.smoothed = Some(match self.smoothed {
+                Some(smoothed) => smoothed + SMOOTHING * (rate - smoothed),
+                None => rate,
+            });
+        }
+    }
+
+    /// The smoothed number of items per second (`None` until there are samples spanning some time).
+    pub fn rate(&self) -> Option<f64> {
+        self.smoothed
+    }
+
+    /// The estimated time to complete the remaining items at the smoothed rate.
+    pub fn eta(&self, remaining: u64) -> Option<Duration> {
+        self.smoothed
+            .filter(|rate| *rate > 0.0)
+            .and_then(|rate| Duration::try_from_secs_f64(remaining as f64 / rate).ok())
+    }
+
+    /// A human-readable summary such as `340.5/s, ETA 3m 05s` (or just the rate if the total is unknown).
+    pub fn render(&self, done: u64, total: Option<u64>) -> Option<String> {
+        let rate = self.rate()?;
+
+        Some(
+            match total.and_then(|total| self.eta(total.saturating_sub(done))) {
+                Some(eta) => format!("{}, ETA {}", format_rate(rate), format_duration(eta)),
+                None => format_rate(rate),
+            },
+        )
+    }
+
+    fn window_rate(&self) -> Option<f64> {
+        let (first_time, first_done) = self.samples.front()?;
+        let (last_time, last_done) = self.samples.back()?;
+        let seconds = last_time.duration_since(*first_time).as_secs_f64();
+
+        (seconds > 0.0).then(|| last_done.saturating_sub(*first_done) as f64 / seconds)
+    }
+}
+
+impl Default for RateEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW)
+    }
+}
+
+/// Format a rate in items per second, such as `340.5/s` or `12.3k/s`.
+pub fn format_rate(rate: f64) -> String {
+    if rate >= 1_000_000.0 {
+        format!("{:.1}M/s", rate / 1_000_000.0)
+    } else if rate >= 10_000.0 {
+        format!("{:.1}k/s", rate / 1_000.0)
+    } else {
+        format!("{rate:.1}/s")
+    }
+}
+
+/// Format a duration to the second, such as `12s`, `3m 05s`, or `1h 02m`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64().round() as u64;
+
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, (seconds % 3600) / 60)
+    } else if seconds >= 60 {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_estimator() {
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut estimator = RateEstimator::new(Duration::from_secs(10));
+
+        estimator.record_at(at(0), 0);
+        assert_eq!(estimator.rate(), None);
+
+        estimator.record_at(at(1), 100);
+        assert_eq!(estimator.rate(), Some(100.0));
+        assert_eq!(estimator.eta(1000), Some(Duration::from_secs(10)));
+
+        // A sudden change in throughput moves the estimate only partway.
+        estimator.record_at(at(2), 400);
+        let rate = estimator.rate().unwrap();
+        assert!(rate > 100.0 && rate < 200.0);
+
+        for seconds in 3..=30 {
+            estimator.record_at(at(seconds), 400 + (seconds - 2) * 50);
+        }
+
+        // Once the window has moved past the burst, the estimate settles on the current rate.
+        assert!((estimator.rate().unwrap() - 50.0).abs() < 1.0);
+        assert_eq!(
+            estimator.render(1800, Some(4800)).unwrap(),
+            "50.0/s, ETA 1m 00s"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_rate(340.54), "340.5/s");
+        assert_eq!(format_rate(12_345.0), "12.3k/s");
+        assert_eq!(format_duration(Duration::from_secs(12)), "12s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h 02m");
+    }
+}
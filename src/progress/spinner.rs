@@ -0,0 +1,195 @@
+//! A spinner for operations of unknown duration.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::is_interactive;
+
+const FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const ASCII_FRAMES: [&str; 4] = ["|", "/", "-", "\\"];
+
+/// The time between spinner frames.
+pub const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+
+/// Show a spinner on standard error if it is an interactive terminal, and otherwise log start and finish lines.
+pub fn spinner(label: &str) -> Spinner {
+    if is_interactive() {
+        let frames: &'static [&'static str] = if crate::term::supports_unicode() {
+            &FRAMES
+        } else {
+            &ASCII_FRAMES
+        };
+
+        Spinner::animate(label, std::io::stderr(), SPINNER_INTERVAL, frames)
+    } else {
+        Spinner::logged(label)
+    }
+}
+
+/// A guard that shows a spinner (or logs) until it is finished or dropped.
+#[must_use = "the spinner stops when it is dropped"]
+pub struct Spinner {
+    label: String,
+    start: Instant,
+    animation: Option<Animation>,
+    finished: bool,
+}
+
+struct Animation {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl Spinner {
+    /// Animate a spinner on the given writer.
+    pub fn new<W: Write + Send + 'static>(label: &str, writer: W, interval: Duration) -> Self {
+        Self::animate(label, writer, interval, &FRAMES)
+    }
+
+    fn animate<W: Write + Send + 'static>(
+        label: &str,
+        writer: W,
+        interval: Duration,
+        frames: &'static [&'static str],
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let writer: Arc<Mutex<Box<dyn Write + Send>>> = Arc::new(Mutex::new(Box::new(writer)));
+
+        let handle = {
+            let stop = stop.clone();
+            let writer = writer.clone();
+            let label = label.to_string();
+
+            std::thread::spawn(move || {
+                for frame in frames.iter().cycle() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    let mut writer = writer.lock().unwrap_or_else(|error| error.into_inner());
+                    let _ = write!(writer, "\r\x1b[2K{frame} {label}");
+                    let _ = writer.flush();
+                    drop(writer);
+
+                    std::thread::park_timeout(interval);
+                }
+            })
+        };
+
+        Self {
+            label: label.to_string(),
+            start: Instant::now(),
+            animation: Some(Animation {
+                stop,
+                handle,
+                writer,
+            }),
+            finished: false,
+        }
+    }
+
+    /// Log the start and finish of the operation instead of animating.
+    pub fn logged(label: &str) -> Self {
+        log::info!("{label}...");
+
+        Self {
+            label: label.to_string(),
+            start: Instant::now(),
+            animation: None,
+            finished: false,
+        }
+    }
+
+    pub fn finish(mut self) {
+        self.stop(None);
+    }
+
+    /// Stop the spinner, replacing the label with a final message.
+    pub fn finish_with_message(mut self, message: &str) {
+        self.stop(Some(message));
+    }
+
+    fn stop(&mut self, message: Option<&str>) {
+        if self.finished {
+            return;
+        }
+
+        self.finished = true;
+
+        let message = message.unwrap_or(&self.label);
+        let elapsed = self.start.elapsed();
+
+        match self.animation.take() {
+            Some(animation) => {
+                animation.stop.store(true, Ordering::SeqCst);
+                animation.handle.thread().unpark();
+                let _ = animation.handle.join();
+
+                let mut writer = animation
+                    .writer
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner());
+                let _ = writeln!(writer, "\r\x1b[2K{message} ({elapsed:.2?})");
+                let _ = writer.flush();
+            }
+            None => log::info!("{message} ({elapsed:.2?})"),
+        }
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_spinner() {
+        let buffer = Buffer::default();
+        let spinner = Spinner::new("Waiting", buffer.clone(), Duration::from_millis(1));
+        std::thread::sleep(Duration::from_millis(20));
+        spinner.finish_with_message("Done");
+
+        let contents = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+
+        assert!(contents.starts_with("\r\x1b[2K⠋ Waiting"));
+        assert!(contents.contains("\r\x1b[2K⠙ Waiting"));
+        assert!(contents
+            .rsplit('\r')
+            .next()
+            .unwrap()
+            .starts_with("\x1b[2KDone ("));
+    }
+
+    #[test]
+    fn test_logged_spinner() {
+        let records = crate::testing::capture_logs(|| {
+            let _spinner = Spinner::logged("Waiting for API");
+        });
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].message, "Waiting for API...");
+        assert!(records[1].message.starts_with("Waiting for API ("));
+    }
+}
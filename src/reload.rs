@@ -0,0 +1,184 @@
+//! Reopening log files and reloading configuration on `SIGHUP`.
+//!
+//! A [`Reload`] collects [`LogFile`]s to reopen (for compatibility with logrotate's default `create` mode) and
+//! callbacks to run (typically to reload configuration). Signal handlers cannot do either safely, so the handler
+//! installed by [`Reload::install_handler`] only records the signal, and the work happens on the next
+//! [`poll`](Reload::poll), either from the application's own loop or from a background thread started with
+//! [`spawn`](Reload::spawn):
+//!
+//! ```rust,no_run
+//! use cli_helpers::logging::file::LogFile;
+//! use cli_helpers::{reload::Reload, shutdown::Shutdown};
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let log_file = LogFile::open("/var/log/mytool.log")?;
+//! // Pass `Box::new(log_file.clone())` to `logging::Builder::with_sink`.
+//!
+//! #[cfg(feature = "daemon")]
+//! Reload::install_handler()?;
+//! Reload::new()
+//!     .with_log_file(log_file)
+//!     .with_callback(|| {
+//!         // Read the configuration again.
+//!         Ok(())
+//!     })
+//!     .spawn(Shutdown::signals());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The handler requires the `daemon` feature on Unix. On Windows, which has no `SIGHUP`, installing it does nothing,
+//! but reloads can still be triggered with [`Reload::request`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::logging::file::LogFile;
+use crate::shutdown::Shutdown;
+use crate::Error;
+
+/// Set by the signal handler (or [`Reload::request`]).
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// How often the background thread checks for reload requests.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type Callback = Box<dyn FnMut() -> Result<(), Error> + Send>;
+
+#[derive(Default)]
+pub struct Reload {
+    log_files: Vec<LogFile>,
+    callbacks: Vec<Callback>,
+}
+
+impl Reload {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log_file(mut self, log_file: LogFile) -> Self {
+        self.log_files.push(log_file);
+        self
+    }
+
+    /// Run the callback on every reload (after the log files have been reopened).
+    pub fn with_callback<F: FnMut() -> Result<(), Error> + Send + 'static>(mut self, f: F) -> Self {
+        self.callbacks.push(Box::new(f));
+        self
+    }
+
+    /// Record `SIGHUP` as a reload request instead of terminating.
+    #[cfg(all(unix, feature = "daemon"))]
+    pub fn install_handler() -> Result<(), Error> {
+        // SAFETY: the handler is async-signal-safe (it only stores to an atomic).
+        let previous = unsafe {
+            libc::signal(
+                libc::SIGHUP,
+                handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+            )
+        };
+
+        if previous == libc::SIG_ERR {
+            Err(std::io::Error::last_os_error().into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Does nothing, since there is no `SIGHUP` on this platform.
+    #[cfg(not(unix))]
+    pub fn install_handler() -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Request a reload on the next poll (as if `SIGHUP` had been received).
+    pub fn request() {
+        REQUESTED.store(true, Ordering::SeqCst);
+    }
+
+    /// Reload if requested since the last poll, returning whether a reload happened.
+    pub fn poll(&mut self) -> bool {
+        let requested = REQUESTED.swap(false, Ordering::SeqCst);
+
+        if requested {
+            self.reload();
+        }
+
+        requested
+    }
+
+    /// Reopen the log files and run the callbacks now, logging any errors.
+    pub fn reload(&mut self) {
+        for log_file in &self.log_files {
+            if let Err(error) = log_file.reopen() {
+                log::error!("Unable to reopen {}: {error}", log_file.path().display());
+            }
+        }
+
+        for callback in &mut self.callbacks {
+            if let Err(error) = callback() {
+                log::error!("Reload failed: {error}");
+            }
+        }
+
+        log::info!("Reloaded");
+    }
+
+    /// Poll on a background thread until the token is triggered.
+    pub fn spawn(mut self, shutdown: Shutdown) -> JoinHandle<()> {
+        std::thread::spawn(move || {
+            while shutdown.sleep(POLL_INTERVAL) {
+                self.poll();
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for Reload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("Reload");
+        debug.field("log_files", &self.log_files);
+        debug.field("callbacks", &self.callbacks.len()).finish()
+    }
+}
+
+#[cfg(all(unix, feature = "daemon"))]
+extern "C" fn handle_signal(_signal: libc::c_int) {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_reload() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let mut reload = Reload::new().with_callback({
+            let count = count.clone();
+            move || {
+                count.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        });
+
+        Reload::request();
+        assert!(reload.poll());
+        assert!(!reload.poll());
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        let shutdown = Shutdown::new();
+        let handle = reload.spawn(shutdown.clone());
+        Reload::request();
+
+        while count.load(Ordering::SeqCst) < 2 {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        shutdown.request();
+        handle.join().unwrap();
+    }
+}
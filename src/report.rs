@@ -0,0 +1,286 @@
+//! Bucketed counts and sums of timestamped values.
+//!
+//! A [`Report`] aggregates `(Timestamp, value)` pairs into days, ISO weeks, or months. Buckets are calendar periods in
+//! UTC by default, or in the timezone given to [`Report::with_timezone`] (such as the `--tz` value from
+//! [`TimezoneArgs`](crate::timezone::TimezoneArgs)). Its [`rows`](Report::rows) cover every bucket from the first to the last, so
+//! that gaps appear as zero rows, and implement [`OutputRecord`] for rendering as a table, CSV, or JSON. Sums can
+//! also be shown as a [histogram](Report::histogram) or [sparkline](Report::sparkline).
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use crate::chart;
+use crate::output::{Column, OutputFormat, OutputRecord};
+use crate::period::{IsoWeek, Month};
+use crate::timezone::TimezoneArg;
+use crate::{Error, Timestamp};
+
+/// Standard report bucketing argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReportArgs {
+    /// The period to group results by
+    #[clap(long, global = true, value_enum, default_value_t)]
+    by: Granularity,
+}
+
+impl ReportArgs {
+    pub fn new(by: Granularity) -> Self {
+        Self { by }
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.by
+    }
+
+    pub fn report(&self) -> Report {
+        Report::new(self.by)
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Granularity {
+    Day,
+    Week,
+    #[default]
+    Month,
+}
+
+/// A day, ISO week, or month.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bucket {
+    Day(NaiveDate),
+    Week(IsoWeek),
+    Month(Month),
+}
+
+impl Bucket {
+    /// The bucket of the given granularity containing the timestamp (in UTC).
+    pub fn containing(granularity: Granularity, timestamp: Timestamp) -> Self {
+        Self::containing_in(granularity, timestamp, TimezoneArg::Utc)
+    }
+
+    /// The bucket of the given granularity containing the timestamp in the given timezone.
+    pub fn containing_in(granularity: Granularity, timestamp: Timestamp, tz: TimezoneArg) -> Self {
+        let date = timestamp
+            .0
+            .with_timezone(&tz.offset_at(timestamp))
+            .date_naive();
+
+        match granularity {
+            Granularity::Day => Self::Day(date),
+            Granularity::Week => Self::Week(IsoWeek::containing_date(date)),
+            Granularity::Month => Self::Month(Month::containing_date(date)),
+        }
+    }
+
+    /// The first instant of the bucket in UTC.
+    pub fn start(&self) -> Timestamp {
+        match self {
+            Self::Day(date) => Timestamp(date.and_time(chrono::NaiveTime::MIN).and_utc()),
+            Self::Week(week) => week.start(),
+            Self::Month(month) => month.start(),
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Day(date) => Self::Day(date.succ_opt().expect("valid date")),
+            Self::Week(week) => Self::Week(week.next()),
+            Self::Month(month) => Self::Month(month.next()),
+        }
+    }
+}
+
+impl Display for Bucket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Day(date) => write!(f, "{}", date.format(crate::DATE_FMT)),
+            Self::Week(week) => week.fmt(f),
+            Self::Month(month) => month.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    granularity: Granularity,
+    tz: TimezoneArg,
+    buckets: BTreeMap<Bucket, (u64, f64)>,
+}
+
+impl Report {
+    pub fn new(granularity: Granularity) -> Self {
+        Self {
+            granularity,
+            tz: TimezoneArg::Utc,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Bucket timestamps by calendar periods in the given timezone (this should be set before values are added).
+    pub fn with_timezone(mut self, tz: TimezoneArg) -> Self {
+        self.tz = tz;
+        self
+    }
+
+    pub fn from_values<I: IntoIterator<Item = (Timestamp, V)>, V: Into<f64>>(
+        granularity: Granularity,
+        values: I,
+    ) -> Self {
+        let mut report = Self::new(granularity);
+        report.extend(values);
+        report
+    }
+
+    pub fn granularity(&self) -> Granularity {
+        self.granularity
+    }
+
+    pub fn timezone(&self) -> TimezoneArg {
+        self.tz
+    }
+
+    pub fn add<V: Into<f64>>(&mut self, timestamp: Timestamp, value: V) {
+        let totals = self
+            .buckets
+            .entry(Bucket::containing_in(self.granularity, timestamp, self.tz))
+            .or_default();
+
+        totals.0 += 1;
+        totals.1 += value.into();
+    }
+
+    /// A row for every bucket from the first to the last (inclusive), in order.
+    pub fn rows(&self) -> Vec<ReportRow> {
+        let (Some(first), Some(last)) = (self.buckets.keys().next(), self.buckets.keys().last())
+        else {
+            return vec![];
+        };
+
+        let mut rows = vec![];
+        let mut bucket = *first;
+
+        while bucket <= *last {
+            let (count, sum) = self.buckets.get(&bucket).copied().unwrap_or_default();
+
+            rows.push(ReportRow { bucket, count, sum });
+            bucket = bucket.next();
+        }
+
+        rows
+    }
+
+    pub fn write<W: Write>(&self, format: OutputFormat, writer: W) -> Result<(), Error> {
+        format.write(writer, &self.rows())
+    }
+
+    /// A histogram of the sum for each bucket.
+    pub fn histogram(&self) -> String {
+        chart::histogram(self.rows().into_iter().map(|row| (row.bucket, row.sum)))
+    }
+
+    /// A sparkline of the sum for each bucket.
+    pub fn sparkline(&self) -> String {
+        chart::sparkline(self.rows().into_iter().map(|row| row.sum))
+    }
+}
+
+impl<V: Into<f64>> Extend<(Timestamp, V)> for Report {
+    fn extend<I: IntoIterator<Item = (Timestamp, V)>>(&mut self, values: I) {
+        for (timestamp, value) in values {
+            self.add(timestamp, value);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReportRow {
+    pub bucket: Bucket,
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl OutputRecord for ReportRow {
+    fn columns() -> Vec<Column> {
+        vec![
+            Column::new("period"),
+            Column::new("count"),
+            Column::new("sum"),
+        ]
+    }
+
+    fn row(&self) -> Vec<Value> {
+        // Integral sums (such as sums of counts) are shown without a fractional part.
+        let sum = if self.sum.fract() == 0.0 && self.sum.abs() < (1u64 << 53) as f64 {
+            Value::from(self.sum as i64)
+        } else {
+            Value::from(self.sum)
+        };
+
+        vec![self.bucket.to_string().into(), self.count.into(), sum]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let values = [
+            ("2024-02-28T10:00:00Z", 2),
+            ("2024-02-28T23:59:59Z", 3),
+            ("2024-03-01T00:00:00Z", 1),
+            ("2024-04-15", 10),
+        ]
+        .into_iter()
+        .map(|(timestamp, value)| (timestamp.parse::<Timestamp>().unwrap(), value));
+
+        let report = Report::from_values(Granularity::Month, values.clone());
+
+        assert_eq!(
+            OutputFormat::Csv.render(&report.rows()),
+            "period,count,sum\n2024-02,2,5\n2024-03,1,1\n2024-04,1,10\n"
+        );
+
+        let report = Report::from_values(Granularity::Day, values.clone().take(3));
+
+        assert_eq!(
+            OutputFormat::Table.render(&report.rows()),
+            "PERIOD      COUNT  SUM\n2024-02-28      2    5\n2024-02-29      0    0\n2024-03-01      1    1\n"
+        );
+
+        let report = Report::from_values(Granularity::Week, values);
+
+        assert_eq!(report.rows().len(), 8);
+        assert_eq!(report.rows()[0].bucket.to_string(), "2024-W09");
+    }
+
+    #[test]
+    fn test_report_timezone() {
+        let tz = "+02:00".parse::<TimezoneArg>().unwrap();
+        let values = [("2024-02-28T10:00:00Z", 2), ("2024-02-29T23:00:00Z", 3)]
+            .into_iter()
+            .map(|(timestamp, value)| (timestamp.parse::<Timestamp>().unwrap(), value));
+
+        let mut report = Report::new(Granularity::Day).with_timezone(tz);
+        report.extend(values.clone());
+
+        assert_eq!(
+            OutputFormat::Csv.render(&report.rows()),
+            "period,count,sum\n2024-02-28,1,2\n2024-02-29,0,0\n2024-03-01,1,3\n"
+        );
+
+        let mut report = Report::new(Granularity::Month).with_timezone(tz);
+        report.extend(values);
+
+        assert_eq!(
+            OutputFormat::Csv.render(&report.rows()),
+            "period,count,sum\n2024-02,1,2\n2024-03,1,3\n"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Repeating the previous invocation (`--rerun-last`).
+//!
+//! [`RerunArgs::last_args`] finds the most recent run in the [audit trail](crate::audit), shows its command line, and
+//! asks for confirmation, so that the application can parse the previous arguments in place of the current ones:
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{app_dirs::AppDirs, audit::AuditTrail, rerun::RerunArgs};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     rerun: RerunArgs,
+//!     #[clap(long)]
+//!     since: Option<String>,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let dirs = AppDirs::new("mytool")?;
+//! let audit = AuditTrail::start(&dirs, env!("CARGO_PKG_VERSION"));
+//!
+//! let opts = Opts::parse();
+//! let opts = match opts.rerun.last_args(&dirs)? {
+//!     Some(args) => Opts::parse_from(args),
+//!     None => opts,
+//! };
+//! // ...
+//! audit.finish(0)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Runs that were themselves started with `--rerun-last` are skipped. A run whose arguments included secret values
+//! cannot be repeated, since only the redacted arguments are recorded.
+
+use std::path::Path;
+
+use crate::audit::{read_history, AuditRecord, AuditTrail};
+use crate::redact::is_redacted;
+use crate::{app_dirs::AppDirs, term, Error};
+
+const RERUN_LAST_FLAG: &str = "--rerun-last";
+
+/// Standard rerun argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RerunArgs {
+    /// Run the previous command again (after confirmation)
+    #[clap(long)]
+    rerun_last: bool,
+}
+
+impl RerunArgs {
+    pub fn new(rerun_last: bool) -> Self {
+        Self { rerun_last }
+    }
+
+    pub fn is_rerun_last(&self) -> bool {
+        self.rerun_last
+    }
+
+    /// The previous run's arguments (including the program name) if `--rerun-last` was given and the user confirms.
+    ///
+    /// Fails with [`Error::Cancelled`] if the user declines (or cannot be asked, because standard input is not a
+    /// terminal).
+    pub fn last_args(&self, dirs: &AppDirs) -> Result<Option<Vec<String>>, Error> {
+        if !self.rerun_last {
+            return Ok(None);
+        }
+
+        let args = previous_args(AuditTrail::path(dirs))?;
+
+        match term::confirm(&format!("Run `{}` again?", display_command(&args)))? {
+            Some(true) => Ok(Some(args)),
+            Some(false) | None => Err(Error::Cancelled),
+        }
+    }
+}
+
+/// The arguments of the most recent run in the audit file that was not itself a rerun.
+pub fn previous_args<P: AsRef<Path>>(path: P) -> Result<Vec<String>, Error> {
+    let record = read_history(path)?
+        .into_iter()
+        .rev()
+        .find(|record| !is_rerun(record))
+        .ok_or(Error::NoPreviousRun)?;
+
+    if record.args.iter().any(|arg| is_redacted(arg)) {
+        Err(Error::RedactedRerun)
+    } else {
+        Ok(record.args)
+    }
+}
+
+fn is_rerun(record: &AuditRecord) -> bool {
+    record.args.iter().skip(1).any(|arg| arg == RERUN_LAST_FLAG)
+}
+
+/// Quote arguments for display where necessary (using POSIX shell quoting).
+fn display_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let is_plain = !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || "-_./:=,@%+".contains(c));
+
+            if is_plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AUDIT_FILE_NAME;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_previous_args() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join(AUDIT_FILE_NAME);
+
+        assert!(matches!(previous_args(&path), Err(Error::NoPreviousRun)));
+
+        let record = |args: &[&str]| {
+            AuditTrail::start_in(&path, "1.0.0")
+                .with_args(args.iter().copied())
+                .finish(0)
+                .unwrap();
+        };
+
+        record(&["mytool", "import", "--since", "3 hours ago"]);
+        record(&["mytool", "--rerun-last"]);
+        assert_eq!(
+            previous_args(&path).unwrap(),
+            vec!["mytool", "import", "--since", "3 hours ago"]
+        );
+        assert_eq!(
+            display_command(&previous_args(&path).unwrap()),
+            "mytool import --since '3 hours ago'"
+        );
+
+        record(&["mytool", "export", "--api-key=abc123"]);
+        assert!(matches!(previous_args(&path), Err(Error::RedactedRerun)));
+    }
+}
@@ -0,0 +1,200 @@
+//! A wall-clock runtime limit (`--max-runtime`).
+//!
+//! A [`Watchdog`] waits on a background thread until the limit has passed, and then triggers a graceful [`Shutdown`].
+//! If the process is still running after a grace period, it exits immediately with [`TIMEOUT_EXIT_CODE`] (the code
+//! used by `timeout(1)`), so that a tool stuck in a long operation still finishes before an outer time limit (such as
+//! a CI job's) kills it without any cleanup at all.
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{runtime::RuntimeLimitArgs, shutdown::Shutdown};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     runtime: RuntimeLimitArgs,
+//! }
+//!
+//! let opts = Opts::parse();
+//! let shutdown = Shutdown::signals();
+//! // The watchdog is disarmed when dropped.
+//! let _watchdog = opts.runtime.watchdog(shutdown.clone());
+//!
+//! while !shutdown.is_requested() {
+//!     // ...
+//! }
+//! ```
+
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::schedule::Interval;
+use crate::shutdown::Shutdown;
+
+/// The exit code after the grace period (as in `timeout(1)`).
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Standard runtime limit arguments.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeLimitArgs {
+    /// Stop gracefully after running for this long (for example `50m`)
+    #[clap(long)]
+    max_runtime: Option<Interval>,
+    /// How long to wait after stopping gracefully before exiting immediately (30s by default)
+    #[clap(long, requires = "max_runtime")]
+    shutdown_grace: Option<Interval>,
+}
+
+impl RuntimeLimitArgs {
+    pub fn new(max_runtime: Option<Interval>, shutdown_grace: Option<Interval>) -> Self {
+        Self {
+            max_runtime,
+            shutdown_grace,
+        }
+    }
+
+    pub fn max_runtime(&self) -> Option<Interval> {
+        self.max_runtime
+    }
+
+    /// Arm the watchdog if `--max-runtime` was given (the limit is measured from now).
+    pub fn watchdog(&self, shutdown: Shutdown) -> Option<WatchdogGuard> {
+        self.max_runtime.map(|max_runtime| {
+            Watchdog::new(max_runtime.0)
+                .with_grace_period(
+                    self.shutdown_grace
+                        .map(|grace| grace.0)
+                        .unwrap_or(DEFAULT_GRACE_PERIOD),
+                )
+                .with_shutdown(shutdown)
+                .arm()
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    limit: Duration,
+    grace_period: Duration,
+    shutdown: Shutdown,
+    exit: fn(i32),
+}
+
+impl Watchdog {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            limit,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            shutdown: Shutdown::signals(),
+            exit: process_exit,
+        }
+    }
+
+    pub fn with_grace_period(self, grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            ..self
+        }
+    }
+
+    /// Request shutdown from this token when the limit is reached.
+    pub fn with_shutdown(self, shutdown: Shutdown) -> Self {
+        Self { shutdown, ..self }
+    }
+
+    /// Start the timer on a background thread.
+    pub fn arm(self) -> WatchdogGuard {
+        let start = Instant::now();
+        let disarmed = Shutdown::new();
+
+        let handle = {
+            let disarmed = disarmed.clone();
+
+            std::thread::spawn(move || {
+                if !disarmed.sleep_until(start + self.limit) {
+                    return;
+                }
+
+                log::warn!(
+                    "Maximum runtime of {} reached; shutting down",
+                    Interval(self.limit)
+                );
+                self.shutdown.request();
+
+                if disarmed.sleep(self.grace_period) {
+                    log::error!(
+                        "Still running {} after the maximum runtime; exiting",
+                        Interval(self.grace_period)
+                    );
+                    (self.exit)(TIMEOUT_EXIT_CODE);
+                }
+            })
+        };
+
+        WatchdogGuard {
+            disarmed,
+            handle: Some(handle),
+        }
+    }
+}
+
+/// Disarms the watchdog when dropped.
+#[derive(Debug)]
+pub struct WatchdogGuard {
+    disarmed: Shutdown,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for WatchdogGuard {
+    fn drop(&mut self) {
+        self.disarmed.request();
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn process_exit(code: i32) {
+    std::process::exit(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
+
+    #[test]
+    fn test_watchdog() {
+        let shutdown = Shutdown::new();
+        let watchdog = Watchdog {
+            exit: |code| EXIT_CODE.store(code, Ordering::SeqCst),
+            ..Watchdog::new(Duration::from_millis(10))
+                .with_grace_period(Duration::from_millis(10))
+                .with_shutdown(shutdown.clone())
+        };
+
+        // Disarming before the limit means that shutdown is never requested.
+        let unused = Shutdown::new();
+        drop(
+            Watchdog::new(Duration::from_secs(60))
+                .with_shutdown(unused.clone())
+                .arm(),
+        );
+        assert!(!unused.is_requested());
+
+        let guard = watchdog.arm();
+
+        while EXIT_CODE.load(Ordering::SeqCst) == 0 {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        assert!(shutdown.is_requested());
+        assert_eq!(EXIT_CODE.load(Ordering::SeqCst), TIMEOUT_EXIT_CODE);
+        drop(guard);
+    }
+}
@@ -0,0 +1,269 @@
+//! A generator for new application projects.
+//!
+//! [`Scaffold`] produces a `Cargo.toml` and a starter `src/main.rs` with an `Opts` struct that is wired to
+//! [`Verbosity`](crate::Verbosity), the [`App`](crate::app::App) bootstrap, and (optionally) the configuration and
+//! completion subcommands, so that new tools start from the same layout:
+//!
+//! ```rust,no_run
+//! use cli_helpers::scaffold::Scaffold;
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let paths = Scaffold::new("mytool")?
+//!     .with_config(true)
+//!     .with_completions(true)
+//!     .write("mytool")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The configuration subcommands require the `config` feature in the generated project (which is enabled in its
+//! manifest), and the completions subcommand adds a dependency on `clap_complete`.
+
+use std::path::{Path, PathBuf};
+
+use crate::Error;
+
+const CLI_HELPERS_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scaffold {
+    name: String,
+    config: bool,
+    completions: bool,
+}
+
+impl Scaffold {
+    /// Fails with [`Error::InvalidProjectName`] unless the name is a valid package name.
+    pub fn new(name: &str) -> Result<Self, Error> {
+        let is_valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        if is_valid {
+            Ok(Self {
+                name: name.to_string(),
+                config: false,
+                completions: false,
+            })
+        } else {
+            Err(Error::InvalidProjectName(name.to_string()))
+        }
+    }
+
+    /// Include a configuration file and the `config` subcommands.
+    pub fn with_config(self, config: bool) -> Self {
+        Self { config, ..self }
+    }
+
+    /// Include the `completions` subcommand.
+    pub fn with_completions(self, completions: bool) -> Self {
+        Self {
+            completions,
+            ..self
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The generated files, with paths relative to the project directory.
+    pub fn files(&self) -> Vec<(PathBuf, String)> {
+        vec![
+            (PathBuf::from("Cargo.toml"), self.manifest()),
+            (PathBuf::from("src").join("main.rs"), self.main()),
+        ]
+    }
+
+    /// Write the project to a directory (which is created if necessary), returning the paths of the new files.
+    ///
+    /// Fails with [`Error::FileExists`] without writing anything if any of the files already exist.
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>, Error> {
+        let files = self
+            .files()
+            .into_iter()
+            .map(|(path, contents)| (dir.as_ref().join(path), contents))
+            .collect::<Vec<_>>();
+
+        if let Some((path, _)) = files.iter().find(|(path, _)| path.exists()) {
+            return Err(Error::FileExists(path.clone()));
+        }
+
+        let mut paths = Vec::with_capacity(files.len());
+
+        for (path, contents) in files {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+
+            std::fs::write(&path, contents)?;
+            paths.push(path);
+        }
+
+        Ok(paths)
+    }
+
+    fn manifest(&self) -> String {
+        let mut manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+            self.name
+        );
+
+        if self.config {
+            manifest.push_str(&format!(
+                "cli-helpers = {{ version = \"{CLI_HELPERS_VERSION}\", features = [\"config\"] }}\n"
+            ));
+        } else {
+            manifest.push_str(&format!("cli-helpers = \"{CLI_HELPERS_VERSION}\"\n"));
+        }
+
+        if self.completions {
+            manifest.push_str("clap_complete = \"4\"\n");
+        }
+
+        if self.config {
+            manifest.push_str(
+                "schemars = \"1\"\nserde = { version = \"1\", features = [\"derive\"] }\n",
+            );
+        }
+
+        manifest
+    }
+
+    fn main(&self) -> String {
+        let mut imports = vec![
+            "use cli_helpers::app::App;",
+            "use cli_helpers::app_dirs::AppDirs;",
+        ];
+        let mut args = String::new();
+        let mut commands = String::new();
+        let mut arms = String::new();
+
+        if self.config {
+            imports.push("use cli_helpers::config::{ConfigArgs, ConfigCommand};");
+            args.push_str("    #[clap(flatten)]\n    config: ConfigArgs,\n");
+            commands.push_str(
+                "    /// Manage the configuration file\n    #[clap(subcommand)]\n    Config(ConfigCommand),\n",
+            );
+            arms.push_str(
+                "        Command::Config(command) => command.run::<Config>(&opts.config, &dirs),\n",
+            );
+        }
+
+        imports.push("use cli_helpers::prelude::*;");
+
+        if self.completions {
+            imports.push("use cli_helpers::shell::CompletionsArgs;");
+            imports.push("use clap::CommandFactory;");
+            commands.push_str(
+                "    /// Print or install shell completions\n    Completions(CompletionsArgs),\n",
+            );
+            arms.push_str(concat!(
+                "        Command::Completions(args) => args.run(NAME, |shell, writer| {\n",
+                "            let shell = shell.name().parse::<clap_complete::Shell>().unwrap();\n",
+                "            clap_complete::generate(shell, &mut Opts::command(), NAME, writer);\n",
+                "        }),\n",
+            ));
+        }
+
+        let run_arm = if self.config {
+            concat!(
+                "        Command::Run => {\n",
+                "            let config = opts.config.load::<Config>(&dirs)?;\n",
+                "            log::info!(\"Running with {config:?}\");\n",
+                "            Ok(())\n",
+                "        }\n",
+            )
+        } else {
+            concat!(
+                "        Command::Run => {\n",
+                "            log::info!(\"Running with {}\", dirs.config_dir().display());\n",
+                "            Ok(())\n",
+                "        }\n",
+            )
+        };
+
+        let config = if self.config {
+            concat!(
+                "\n/// Application configuration.\n",
+                "#[derive(Debug, Default, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]\n",
+                "#[serde(default)]\n",
+                "struct Config {}\n",
+            )
+        } else {
+            ""
+        };
+
+        format!(
+            r#"{imports}
+
+const NAME: &str = "{name}";
+
+#[derive(Debug, Parser)]
+#[clap(name = NAME, version, about)]
+struct Opts {{
+    #[clap(flatten)]
+    verbose: Verbosity,
+{args}    #[clap(subcommand)]
+    command: Command,
+}}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {{
+    /// Run the application
+    Run,
+{commands}}}
+{config}
+fn main() {{
+    std::process::exit(App::new().run(run));
+}}
+
+fn run(opts: Opts) -> Result<(), cli_helpers::Error> {{
+    opts.verbose.init_logging()?;
+    let dirs = AppDirs::new(NAME)?;
+
+    match opts.command {{
+{run_arm}{arms}    }}
+}}
+"#,
+            imports = imports.join("\n"),
+            name = self.name,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_scaffold() {
+        assert!(matches!(
+            Scaffold::new("1tool"),
+            Err(Error::InvalidProjectName(_))
+        ));
+
+        let scaffold = Scaffold::new("mytool").unwrap().with_completions(true);
+        let files = scaffold.files();
+        assert!(files[0].1.contains("clap_complete = \"4\""));
+        assert!(!files[0].1.contains("features = [\"config\"]"));
+        assert!(files[1].1.contains("const NAME: &str = \"mytool\";"));
+        assert!(files[1].1.contains("Completions(CompletionsArgs),"));
+        assert!(!files[1].1.contains("ConfigArgs"));
+
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+
+        let paths = scaffold.with_config(true).write(dir).unwrap();
+        assert_eq!(paths, vec![dir.join("Cargo.toml"), dir.join("src/main.rs")]);
+        assert!(std::fs::read_to_string(&paths[1])
+            .unwrap()
+            .contains("Config(ConfigCommand),"));
+        assert!(matches!(
+            Scaffold::new("mytool").unwrap().write(dir),
+            Err(Error::FileExists(path)) if path == dir.join("Cargo.toml")
+        ));
+    }
+}
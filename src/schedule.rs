@@ -0,0 +1,299 @@
+//! Periodic execution in the current process (`--every 5m`).
+//!
+//! [`run_every`] runs a closure on a fixed interval, optionally delaying each run by a random amount up to a jitter
+//! limit (so that many instances don't all run at once). Errors are logged and do not stop the schedule. When a run
+//! takes longer than the interval, the [`Missed`] policy decides whether the missed runs are skipped (the default),
+//! made up immediately, or whether the schedule restarts from the end of the slow run.
+//!
+//! [`ScheduleArgs`] adds `--every`, `--jitter`, and `--missed` options, and runs the closure once if `--every` is not
+//! given:
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::schedule::ScheduleArgs;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     schedule: ScheduleArgs,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//!
+//! opts.schedule.run(|| -> Result<(), cli_helpers::Error> {
+//!     // ...
+//!     Ok(())
+//! })
+//! # }
+//! ```
+//!
+//! The schedule stops (after any current run) when the [`Shutdown`] token is triggered (by default, by Ctrl-C once the
+//! handlers are installed).
+
+use std::collections::hash_map::RandomState;
+use std::fmt::{Display, Formatter};
+use std::hash::BuildHasher;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::shutdown::Shutdown;
+use crate::Error;
+
+const UNITS: [(&str, u64); 4] = [("d", 60 * 60 * 24), ("h", 60 * 60), ("m", 60), ("s", 1)];
+
+/// A positive number of seconds, minutes, hours, or days, such as `30s` or `5m`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Interval(pub Duration);
+
+impl Interval {
+    pub fn as_duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl FromStr for Interval {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim().to_lowercase();
+        let split = input
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len());
+        let count = input[..split]
+            .parse::<u64>()
+            .ok()
+            .filter(|count| *count > 0);
+
+        let seconds = match input[split..].trim_start() {
+            "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+            "m" | "min" | "mins" | "minute" | "minutes" => Some(60),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Some(60 * 60),
+            "d" | "day" | "days" => Some(60 * 60 * 24),
+            _ => None,
+        };
+
+        count
+            .zip(seconds)
+            .and_then(|(count, seconds)| count.checked_mul(seconds))
+            .map(|seconds| Self(Duration::from_secs(seconds)))
+            .ok_or_else(|| Error::InvalidInterval(s.to_string()))
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.0.as_secs();
+
+        match UNITS
+            .iter()
+            .find(|(_, unit)| seconds > 0 && seconds.is_multiple_of(*unit))
+        {
+            Some((name, unit)) => write!(f, "{}{name}", seconds / unit),
+            None => write!(f, "{}s", self.0.as_secs_f64()),
+        }
+    }
+}
+
+impl From<Duration> for Interval {
+    fn from(duration: Duration) -> Self {
+        Self(duration)
+    }
+}
+
+/// What to do when a run takes longer than the interval.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum Missed {
+    /// Skip the missed runs and continue on the original schedule
+    #[default]
+    Skip,
+    /// Make up each missed run immediately
+    Burst,
+    /// Restart the schedule from the end of the slow run
+    Delay,
+}
+
+/// Standard scheduling options.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScheduleArgs {
+    /// Run repeatedly on this interval (for example `5m`)
+    #[clap(long)]
+    every: Option<Interval>,
+    /// Delay each run by a random amount up to this limit
+    #[clap(long, requires = "every")]
+    jitter: Option<Interval>,
+    /// What to do when a run takes longer than the interval
+    #[clap(long, value_enum, default_value_t, requires = "every")]
+    missed: Missed,
+}
+
+impl ScheduleArgs {
+    pub fn new(every: Option<Interval>, jitter: Option<Interval>, missed: Missed) -> Self {
+        Self {
+            every,
+            jitter,
+            missed,
+        }
+    }
+
+    pub fn schedule(&self) -> Option<Schedule> {
+        self.every.map(|every| {
+            Schedule::new(every.0)
+                .with_jitter(self.jitter.map(|jitter| jitter.0).unwrap_or_default())
+                .with_missed(self.missed)
+        })
+    }
+
+    /// Run the closure once, or with `--every`, run it on the schedule (until interrupted).
+    pub fn run<E: Display, F: FnMut() -> Result<(), E>>(&self, f: F) -> Result<(), E> {
+        match self.schedule() {
+            Some(schedule) => {
+                schedule.run(f);
+                Ok(())
+            }
+            None => {
+                let mut f = f;
+                f()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    every: Duration,
+    jitter: Duration,
+    missed: Missed,
+    shutdown: Shutdown,
+}
+
+impl Schedule {
+    pub fn new(every: Duration) -> Self {
+        Self {
+            every,
+            jitter: Duration::ZERO,
+            missed: Missed::default(),
+            shutdown: Shutdown::signals(),
+        }
+    }
+
+    /// Delay each run by a random amount less than this.
+    pub fn with_jitter(self, jitter: Duration) -> Self {
+        Self { jitter, ..self }
+    }
+
+    pub fn with_missed(self, missed: Missed) -> Self {
+        Self { missed, ..self }
+    }
+
+    /// Stop when this token is triggered (instead of on `SIGTERM` or `SIGINT`).
+    pub fn with_shutdown(self, shutdown: Shutdown) -> Self {
+        Self { shutdown, ..self }
+    }
+
+    /// Run the closure immediately and then on the schedule, logging any errors (until interrupted).
+    pub fn run<E: Display, F: FnMut() -> Result<(), E>>(&self, mut f: F) {
+        let mut tick = Instant::now();
+
+        loop {
+            if let Err(error) = f() {
+                log::error!("{error}");
+            }
+
+            let (next, skipped) = next_tick(tick, Instant::now(), self.every, self.missed);
+
+            if skipped > 0 {
+                log::warn!("Skipped {skipped} scheduled run(s) because the last run was too slow");
+            }
+
+            tick = next;
+
+            if !self.shutdown.sleep_until(tick + random_jitter(self.jitter)) {
+                return;
+            }
+        }
+    }
+}
+
+/// Run the closure every `every`, with a random delay less than `jitter`, until interrupted.
+pub fn run_every<E: Display, F: FnMut() -> Result<(), E>>(every: Duration, jitter: Duration, f: F) {
+    Schedule::new(every).with_jitter(jitter).run(f)
+}
+
+/// The time of the next run after the run scheduled at `tick` finished at `now`, with the number of runs skipped.
+fn next_tick(tick: Instant, now: Instant, every: Duration, missed: Missed) -> (Instant, u32) {
+    match missed {
+        Missed::Skip => {
+            let mut next = tick + every;
+            let mut skipped = 0;
+
+            while next <= now {
+                next += every;
+                skipped += 1;
+            }
+
+            (next, skipped)
+        }
+        Missed::Burst => (tick + every, 0),
+        Missed::Delay if now > tick + every => (now + every, 0),
+        Missed::Delay => (tick + every, 0),
+    }
+}
+
+fn random_jitter(jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        Duration::ZERO
+    } else {
+        let nanos = u64::try_from(jitter.as_nanos()).unwrap_or(u64::MAX);
+
+        Duration::from_nanos(RandomState::new().hash_one(Instant::now()) % nanos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval() {
+        assert_eq!(
+            "5m".parse::<Interval>().unwrap(),
+            Interval(Duration::from_secs(300))
+        );
+        assert_eq!(
+            "2 hours".parse::<Interval>().unwrap(),
+            Interval(Duration::from_secs(7200))
+        );
+        assert_eq!("90s".parse::<Interval>().unwrap().to_string(), "90s");
+        assert_eq!("120s".parse::<Interval>().unwrap().to_string(), "2m");
+        assert_eq!("1d".parse::<Interval>().unwrap().to_string(), "1d");
+        assert!("0s".parse::<Interval>().is_err());
+        assert!("5".parse::<Interval>().is_err());
+        assert!("m".parse::<Interval>().is_err());
+    }
+
+    #[test]
+    fn test_next_tick() {
+        let every = Duration::from_secs(10);
+        let start = Instant::now();
+        let fast = start + Duration::from_secs(1);
+        let slow = start + Duration::from_secs(35);
+
+        for missed in [Missed::Skip, Missed::Burst, Missed::Delay] {
+            assert_eq!(next_tick(start, fast, every, missed), (start + every, 0));
+        }
+
+        assert_eq!(
+            next_tick(start, slow, every, Missed::Skip),
+            (start + Duration::from_secs(40), 3)
+        );
+        assert_eq!(
+            next_tick(start, slow, every, Missed::Burst),
+            (start + every, 0)
+        );
+        assert_eq!(
+            next_tick(start, slow, every, Missed::Delay),
+            (slow + every, 0)
+        );
+    }
+}
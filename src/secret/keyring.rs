@@ -0,0 +1,260 @@
+//! Secrets stored in the operating system's credential store.
+//!
+//! A secret given as `keyring:<service>` (or `keyring:<service>/<user>`) is read from the credential store (the
+//! Keychain on macOS, the Credential Manager on Windows, and the Secret Service on Linux). [`SecretCommand`] provides
+//! reusable `secret set` and `secret delete` subcommands for managing entries.
+//!
+//! `secret set` reads the secret from standard input, with echo disabled if it is a terminal.
+
+use std::io::{BufRead, IsTerminal};
+
+use super::Secret;
+use crate::Error;
+
+/// The prefix of secret values that refer to the credential store.
+pub const KEYRING_PREFIX: &str = "keyring:";
+
+/// The user name for entries that do not specify one.
+pub const DEFAULT_USER: &str = "default";
+
+/// A credential store entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyringEntry {
+    service: String,
+    user: String,
+}
+
+impl KeyringEntry {
+    pub fn new(service: &str, user: Option<&str>) -> Self {
+        Self {
+            service: service.to_string(),
+            user: user.unwrap_or(DEFAULT_USER).to_string(),
+        }
+    }
+
+    /// Parse a `keyring:<service>[/<user>]` reference (`None` if the value does not have the prefix).
+    pub fn parse_reference(value: &str) -> Option<Self> {
+        let reference = value.strip_prefix(KEYRING_PREFIX)?;
+
+        Some(match reference.split_once('/') {
+            Some((service, user)) => Self::new(service, Some(user)),
+            None => Self::new(reference, None),
+        })
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    pub fn get(&self) -> Result<Secret, Error> {
+        match self.entry()?.get_password() {
+            Ok(password) => Ok(Secret::new(password)),
+            Err(keyring::Error::NoEntry) => Err(Error::MissingKeyringEntry(self.to_string())),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn set(&self, secret: &Secret) -> Result<(), Error> {
+        Ok(self.entry()?.set_password(secret.expose())?)
+    }
+
+    pub fn delete(&self) -> Result<(), Error> {
+        match self.entry()?.delete_credential() {
+            Ok(()) => Ok(()),
+            Err(keyring::Error::NoEntry) => Err(Error::MissingKeyringEntry(self.to_string())),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn entry(&self) -> Result<keyring::Entry, Error> {
+        Ok(keyring::Entry::new(&self.service, &self.user)?)
+    }
+}
+
+impl std::fmt::Display for KeyringEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{KEYRING_PREFIX}{}/{}", self.service, self.user)
+    }
+}
+
+/// Reusable credential store management subcommands.
+#[derive(clap::Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum SecretCommand {
+    /// Store a secret (read from standard input without echo) in the credential store
+    Set {
+        /// Service name
+        service: String,
+        /// User name
+        #[clap(long)]
+        user: Option<String>,
+    },
+    /// Delete a secret from the credential store
+    Delete {
+        /// Service name
+        service: String,
+        /// User name
+        #[clap(long)]
+        user: Option<String>,
+    },
+}
+
+impl SecretCommand {
+    pub fn run(&self) -> Result<(), Error> {
+        match self {
+            Self::Set { service, user } => {
+                let entry = KeyringEntry::new(service, user.as_deref());
+
+                eprint!("Enter the secret for {entry}: ");
+
+                let line = read_secret_line()?;
+                entry.set(&Secret::new(line))?;
+
+                log::info!("Stored {entry}");
+            }
+            Self::Delete { service, user } => {
+                let entry = KeyringEntry::new(service, user.as_deref());
+                entry.delete()?;
+
+                log::info!("Deleted {entry}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a line from standard input, without echoing it if standard input is a terminal.
+fn read_secret_line() -> Result<String, Error> {
+    let stdin = std::io::stdin();
+    let echo_guard = if stdin.is_terminal() {
+        Some(EchoGuard::disable()?)
+    } else {
+        None
+    };
+
+    let mut line = String::new();
+    let result = stdin.lock().read_line(&mut line);
+
+    // The newline was not echoed either.
+    if echo_guard.is_some() {
+        drop(echo_guard);
+        eprintln!();
+    }
+
+    result?;
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Disables echo on the terminal attached to standard input until it is dropped.
+#[cfg(unix)]
+struct EchoGuard {
+    original: libc::termios,
+}
+
+#[cfg(unix)]
+impl EchoGuard {
+    fn disable() -> Result<Self, Error> {
+        let mut original = std::mem::MaybeUninit::<libc::termios>::uninit();
+
+        // SAFETY: the pointer is valid for writing a `termios` struct.
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, original.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        // SAFETY: `tcgetattr` succeeded, so the struct is initialized.
+        let original = unsafe { original.assume_init() };
+        let mut attributes = original;
+        attributes.c_lflag &= !libc::ECHO;
+
+        // SAFETY: the pointer refers to a valid `termios` struct.
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &attributes) } != 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(Self { original })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        // SAFETY: the pointer refers to a valid `termios` struct.
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original) };
+    }
+}
+
+#[cfg(windows)]
+struct EchoGuard {
+    original: u32,
+}
+
+#[cfg(windows)]
+impl EchoGuard {
+    fn disable() -> Result<Self, Error> {
+        use windows_sys::Win32::System::Console::{
+            GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_ECHO_INPUT, STD_INPUT_HANDLE,
+        };
+
+        // SAFETY: `GetStdHandle` has no memory safety requirements.
+        let handle = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        let mut original = 0;
+
+        // SAFETY: the handle is the standard input handle, and the mode pointer is valid for writing.
+        if unsafe { GetConsoleMode(handle, &mut original) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        // SAFETY: the handle is the standard input handle.
+        if unsafe { SetConsoleMode(handle, original & !ENABLE_ECHO_INPUT) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(Self { original })
+    }
+}
+
+#[cfg(windows)]
+impl Drop for EchoGuard {
+    fn drop(&mut self) {
+        use windows_sys::Win32::System::Console::{GetStdHandle, SetConsoleMode, STD_INPUT_HANDLE};
+
+        // SAFETY: the handle is the standard input handle.
+        unsafe { SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), self.original) };
+    }
+}
+
+/// Echo cannot be disabled on other platforms.
+#[cfg(not(any(unix, windows)))]
+struct EchoGuard;
+
+#[cfg(not(any(unix, windows)))]
+impl EchoGuard {
+    fn disable() -> Result<Self, Error> {
+        Ok(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            KeyringEntry::parse_reference("keyring:archive-tool"),
+            Some(KeyringEntry::new("archive-tool", None))
+        );
+        assert_eq!(
+            KeyringEntry::parse_reference("keyring:archive-tool/travis")
+                .unwrap()
+                .to_string(),
+            "keyring:archive-tool/travis"
+        );
+        assert_eq!(KeyringEntry::parse_reference("abc123"), None);
+    }
+}
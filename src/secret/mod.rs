@@ -0,0 +1,52 @@
+//! A wrapper for sensitive values such as API tokens.
+//!
+//! With the `keyring` feature, secrets can also refer to entries in the operating system's credential store (see
+//! [`keyring`]), which are looked up by [`Secret::resolve`].
+
+#[cfg(feature = "keyring")]
+pub mod keyring;
+
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::str::FromStr;
+
+/// A secret string that is redacted in debug output.
+///
+/// This type intentionally does not implement `Display`, so the value must be accessed explicitly with
+/// [`Secret::expose`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Resolve references to the credential store (with the `keyring` feature), returning other values unchanged.
+    pub fn resolve(&self) -> Result<Self, crate::Error> {
+        #[cfg(feature = "keyring")]
+        if let Some(entry) = keyring::KeyringEntry::parse_reference(&self.0) {
+            return entry.get();
+        }
+
+        Ok(self.clone())
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+impl FromStr for Secret {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s))
+    }
+}
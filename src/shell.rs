@@ -0,0 +1,239 @@
+//! Shell detection and completion script installation.
+//!
+//! [`detect_shell`] determines the user's shell from the parent process (on Linux), the `SHELL` environment
+//! variable, or (on Windows) the presence of PowerShell, and [`completions_install_path`] gives the per-user location
+//! where that shell looks for completion scripts.
+//!
+//! [`CompletionsArgs`] provides a `completions` subcommand that prints or installs a script generated by the
+//! application (typically with `clap_complete`):
+//!
+//! ```rust,ignore
+//! use cli_helpers::shell::CompletionsArgs;
+//!
+//! args.run("mytool", |shell, writer| {
+//!     let shell = shell.name().parse::<clap_complete::Shell>().unwrap();
+//!     clap_complete::generate(shell, &mut Opts::command(), "mytool", writer);
+//! })?;
+//! ```
+//!
+//! Bash (with bash-completion 2) and fish load installed scripts automatically. For zsh, the directory must be in
+//! `fpath`, and for PowerShell and Elvish, the script must be loaded from the shell's profile.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use directories::{BaseDirs, UserDirs};
+
+use crate::Error;
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    #[clap(name = "powershell")]
+    PowerShell,
+    Elvish,
+}
+
+impl Shell {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+            Self::PowerShell => "powershell",
+            Self::Elvish => "elvish",
+        }
+    }
+
+    /// Identify a shell from an executable path or process name (such as `/bin/zsh`, `-bash`, or `pwsh.exe`).
+    pub fn from_program(program: &str) -> Option<Self> {
+        let name = Path::new(program.trim())
+            .file_name()?
+            .to_str()?
+            .trim_start_matches('-')
+            .to_ascii_lowercase();
+        let name = name.strip_suffix(".exe").unwrap_or(&name);
+
+        match name {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            "pwsh" | "powershell" => Some(Self::PowerShell),
+            "elvish" => Some(Self::Elvish),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Detect the user's shell (`None` if it cannot be determined or is not supported).
+pub fn detect_shell() -> Option<Shell> {
+    parent_process_shell()
+        .or_else(|| {
+            std::env::var("SHELL")
+                .ok()
+                .and_then(|shell| Shell::from_program(&shell))
+        })
+        .or_else(windows_shell)
+}
+
+#[cfg(target_os = "linux")]
+fn parent_process_shell() -> Option<Shell> {
+    let parent_id = std::os::unix::process::parent_id();
+    let name = std::fs::read_to_string(format!("/proc/{parent_id}/comm")).ok()?;
+
+    Shell::from_program(&name)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parent_process_shell() -> Option<Shell> {
+    None
+}
+
+/// On Windows, `PSModulePath` is only set in PowerShell sessions.
+fn windows_shell() -> Option<Shell> {
+    (cfg!(windows) && std::env::var_os("PSModulePath").is_some()).then_some(Shell::PowerShell)
+}
+
+/// The per-user location for the named application's completion script (`None` if the home directory is unknown).
+pub fn completions_install_path(shell: Shell, name: &str) -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new()?;
+    let home = base_dirs.home_dir();
+
+    let path = if cfg!(windows) {
+        match shell {
+            Shell::PowerShell => UserDirs::new()?
+                .document_dir()?
+                .join("PowerShell")
+                .join("Completions")
+                .join(format!("{name}.ps1")),
+            Shell::Elvish => base_dirs
+                .config_dir()
+                .join("elvish")
+                .join("lib")
+                .join(format!("{name}.elv")),
+            _ => return None,
+        }
+    } else {
+        // Shells use the XDG directories on all Unix platforms (including macOS).
+        let data_dir = xdg_dir("XDG_DATA_HOME").unwrap_or_else(|| home.join(".local/share"));
+        let config_dir = xdg_dir("XDG_CONFIG_HOME").unwrap_or_else(|| home.join(".config"));
+
+        match shell {
+            Shell::Bash => data_dir.join("bash-completion/completions").join(name),
+            Shell::Zsh => home.join(".zfunc").join(format!("_{name}")),
+            Shell::Fish => config_dir
+                .join("fish/completions")
+                .join(format!("{name}.fish")),
+            Shell::PowerShell => config_dir
+                .join("powershell/Completions")
+                .join(format!("{name}.ps1")),
+            Shell::Elvish => config_dir.join("elvish/lib").join(format!("{name}.elv")),
+        }
+    };
+
+    Some(path)
+}
+
+fn xdg_dir(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+/// Write a completion script to its per-user location, returning the path.
+pub fn install_completions(shell: Shell, name: &str, script: &[u8]) -> Result<PathBuf, Error> {
+    let path = completions_install_path(shell, name).ok_or(Error::NoAppDirs)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(&path, script)?;
+
+    Ok(path)
+}
+
+/// Standard completion subcommand arguments.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for (detected by default)
+    #[clap(value_enum)]
+    shell: Option<Shell>,
+    /// Install the completion script for the current user instead of printing it
+    #[clap(long)]
+    install: bool,
+}
+
+impl CompletionsArgs {
+    pub fn new(shell: Option<Shell>, install: bool) -> Self {
+        Self { shell, install }
+    }
+
+    /// The selected or detected shell.
+    pub fn shell(&self) -> Result<Shell, Error> {
+        self.shell.or_else(detect_shell).ok_or(Error::UnknownShell)
+    }
+
+    /// Print or install the script produced by the generator.
+    pub fn run<F: FnOnce(Shell, &mut dyn Write)>(
+        &self,
+        name: &str,
+        generate: F,
+    ) -> Result<(), Error> {
+        let shell = self.shell()?;
+
+        if self.install {
+            let mut script = vec![];
+            generate(shell, &mut script);
+
+            let path = install_completions(shell, name, &script)?;
+            log::info!("Installed {shell} completions to {}", path.display());
+        } else {
+            generate(shell, &mut std::io::stdout().lock());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_from_program() {
+        assert_eq!(Shell::from_program("/bin/zsh"), Some(Shell::Zsh));
+        assert_eq!(Shell::from_program("-bash\n"), Some(Shell::Bash));
+        assert_eq!(
+            Shell::from_program("/usr/local/bin/fish"),
+            Some(Shell::Fish)
+        );
+        assert_eq!(Shell::from_program("pwsh.exe"), Some(Shell::PowerShell));
+        assert_eq!(Shell::from_program("cargo"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_completions_install_path() {
+        let home = BaseDirs::new().unwrap().home_dir().to_path_buf();
+
+        assert_eq!(
+            completions_install_path(Shell::Zsh, "mytool"),
+            Some(home.join(".zfunc/_mytool"))
+        );
+        assert!(completions_install_path(Shell::Bash, "mytool")
+            .unwrap()
+            .ends_with("bash-completion/completions/mytool"));
+        assert!(completions_install_path(Shell::Fish, "mytool")
+            .unwrap()
+            .ends_with("fish/completions/mytool.fish"));
+    }
+}
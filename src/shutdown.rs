@@ -0,0 +1,239 @@
+//! Cooperative cancellation.
+//!
+//! A [`Shutdown`] token is a cheaply cloneable flag that long-running helpers check between units of work: the
+//! [`WorkQueue`](crate::work_queue::WorkQueue) stops starting new jobs, the [scheduler](crate::schedule) and watch
+//! mode stop waiting for the next run, and [followed files](crate::follow) stop waiting for more lines. Work that has
+//! already started is allowed to finish.
+//!
+//! [`Shutdown::signals`] returns a token that is also triggered by `SIGTERM` and `SIGINT` (or Ctrl-C, Ctrl-Break, and
+//! closing the console on Windows) once the handlers have been installed with [`Shutdown::install_handlers`] (which
+//! [`Daemon::start`](crate::daemon::Daemon::start) also does). This is the default token for every helper, so a single
+//! Ctrl-C stops all of them:
+//!
+//! ```rust,no_run
+//! use cli_helpers::shutdown::Shutdown;
+//!
+//! // Or `Shutdown::install_handlers()?` to install the handlers at the same time.
+//! let shutdown = Shutdown::signals();
+//!
+//! while !shutdown.is_requested() {
+//!     // ...
+//! }
+//! ```
+//!
+//! Without the handlers, Ctrl-C terminates the process as usual, but tokens can still be triggered with
+//! [`Shutdown::request`] (for example from another thread, or in tests). Requesting shutdown from any signals token
+//! triggers every signals token in the process, just as a signal would.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Set by the signal handlers (or by requesting shutdown from any signals token).
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+/// The longest time to sleep before checking the token again.
+const SLEEP_SLICE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    signals: bool,
+}
+
+impl Shutdown {
+    /// A token that is only triggered by [`request`](Self::request).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that is also triggered by `SIGTERM` and `SIGINT` (if the handlers are installed).
+    pub fn signals() -> Self {
+        Self {
+            requested: Arc::default(),
+            signals: true,
+        }
+    }
+
+    /// Record `SIGTERM` and `SIGINT` for [`signals`](Self::signals) tokens instead of terminating.
+    #[cfg(unix)]
+    pub fn install_handlers() -> Result<Self, crate::Error> {
+        for signal in [libc::SIGTERM, libc::SIGINT] {
+            // SAFETY: the handler is async-signal-safe (it only stores to an atomic).
+            let previous = unsafe {
+                libc::signal(
+                    signal,
+                    handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+                )
+            };
+
+            if previous == libc::SIG_ERR {
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+
+        Ok(Self::signals())
+    }
+
+    /// Record Ctrl-C, Ctrl-Break, and closing the console for [`signals`](Self::signals) tokens instead of terminating.
+    ///
+    /// Windows terminates the process shortly after the console is closed, whether or not the work loop has stopped.
+    #[cfg(windows)]
+    pub fn install_handlers() -> Result<Self, crate::Error> {
+        use windows_sys::Win32::System::Console::SetConsoleCtrlHandler;
+
+        // SAFETY: the handler only stores to an atomic.
+        if unsafe { SetConsoleCtrlHandler(Some(handle_console_event), 1) } == 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        Ok(Self::signals())
+    }
+
+    /// There are no signals to handle on other platforms (such as WebAssembly), so this only returns a signals token.
+    #[cfg(not(any(unix, windows)))]
+    pub fn install_handlers() -> Result<Self, crate::Error> {
+        Ok(Self::signals())
+    }
+
+    /// Trigger this token and its clones (or every signals token, if this is one).
+    pub fn request(&self) {
+        if self.signals {
+            SIGNALLED.store(true, Ordering::SeqCst);
+        } else {
+            self.requested.store(true, Ordering::SeqCst);
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst) || (self.signals && SIGNALLED.load(Ordering::SeqCst))
+    }
+
+    /// Sleep for the duration, returning `false` if interrupted by a shutdown request.
+    pub fn sleep(&self, duration: Duration) -> bool {
+        self.sleep_until(Instant::now() + duration)
+    }
+
+    /// Sleep until the deadline, returning `false` if interrupted by a shutdown request.
+    pub fn sleep_until(&self, deadline: Instant) -> bool {
+        loop {
+            if self.is_requested() {
+                return false;
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                return true;
+            }
+
+            std::thread::sleep((deadline - now).min(SLEEP_SLICE));
+        }
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn handle_signal(_signal: libc::c_int) {
+    SIGNALLED.store(true, Ordering::SeqCst);
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn handle_console_event(event: u32) -> windows_sys::core::BOOL {
+    use windows_sys::Win32::System::Console::{CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT};
+
+    match event {
+        CTRL_C_EVENT | CTRL_BREAK_EVENT | CTRL_CLOSE_EVENT => {
+            SIGNALLED.store(true, Ordering::SeqCst);
+            1
+        }
+        // Let the next handler (by default the one that terminates the process) deal with other events.
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shutdown() {
+        let shutdown = Shutdown::new();
+        let clone = shutdown.clone();
+        assert!(!clone.is_requested());
+        assert!(clone.sleep(Duration::from_millis(1)));
+
+        let requester = {
+            let shutdown = shutdown.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(20));
+                shutdown.request();
+            })
+        };
+
+        assert!(!clone.sleep(Duration::from_secs(60)));
+        requester.join().unwrap();
+        assert!(shutdown.is_requested());
+        assert!(!Shutdown::new().is_requested());
+    }
+
+    const CHILD_VAR: &str = "CLI_HELPERS_SHUTDOWN_CHILD";
+
+    /// Signals tokens share process-wide state, so these tests run in a child process.
+    fn run_child(name: &str) {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                &format!("shutdown::tests::{name}"),
+                "--nocapture",
+            ])
+            .env(CHILD_VAR, "1")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("1 passed"));
+    }
+
+    #[test]
+    fn test_signals_shared() {
+        run_child("child_request");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_handlers() {
+        run_child("child_signal");
+    }
+
+    #[test]
+    fn child_request() {
+        if std::env::var_os(CHILD_VAR).is_none() {
+            return;
+        }
+
+        let first = Shutdown::signals();
+        let second = Shutdown::signals();
+        let unrelated = Shutdown::new();
+        assert!(!second.is_requested());
+
+        first.request();
+        assert!(second.is_requested());
+        assert!(!unrelated.is_requested());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn child_signal() {
+        if std::env::var_os(CHILD_VAR).is_none() {
+            return;
+        }
+
+        let shutdown = Shutdown::install_handlers().unwrap();
+        assert!(!shutdown.is_requested());
+
+        // SAFETY: the handler has been installed, so this does not terminate the process.
+        unsafe { libc::raise(libc::SIGTERM) };
+        assert!(shutdown.is_requested());
+        assert!(!Shutdown::new().is_requested());
+    }
+}
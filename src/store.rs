@@ -0,0 +1,180 @@
+//! A SQLite-backed store for persistent application state.
+//!
+//! The store provides a simple key-value table managed by this crate, and applies application-provided migrations
+//! for any additional tables, tracking the schema version with SQLite's `user_version` pragma.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{app_dirs::AppDirs, Error};
+
+pub(crate) const DEFAULT_DB_FILE_NAME: &str = "store.db";
+
+const KV_TABLE_SCHEMA: &str =
+    "CREATE TABLE IF NOT EXISTS cli_helpers_kv (key TEXT PRIMARY KEY NOT NULL, value BLOB NOT NULL)";
+
+/// Standard store location argument.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbArgs {
+    /// Database file path (defaults to a file in the application data directory)
+    #[clap(long)]
+    db: Option<PathBuf>,
+}
+
+impl DbArgs {
+    pub fn new(db: Option<PathBuf>) -> Self {
+        Self { db }
+    }
+
+    /// The database path, creating the parent directory if necessary.
+    pub fn path(&self, dirs: &AppDirs) -> Result<PathBuf, Error> {
+        AppDirs::resolve_file(self.db.as_deref(), dirs.data_dir(), DEFAULT_DB_FILE_NAME)
+    }
+
+    /// Open the store at the indicated path, applying any pending migrations.
+    pub fn open(&self, dirs: &AppDirs, migrations: &[&str]) -> Result<Store, Error> {
+        Store::open(self.path(dirs)?, migrations)
+    }
+}
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Open a store, applying any migrations with an index greater than or equal to the current schema version.
+    ///
+    /// Migrations are SQL batches that must only ever be appended to, since their position determines the version.
+    pub fn open<P: AsRef<Path>>(path: P, migrations: &[&str]) -> Result<Self, Error> {
+        Self::init(Connection::open(path)?, migrations)
+    }
+
+    pub fn open_in_memory(migrations: &[&str]) -> Result<Self, Error> {
+        Self::init(Connection::open_in_memory()?, migrations)
+    }
+
+    fn init(mut connection: Connection, migrations: &[&str]) -> Result<Self, Error> {
+        connection.execute(KV_TABLE_SCHEMA, [])?;
+
+        let version = Self::user_version(&connection)?;
+
+        if version > migrations.len() {
+            return Err(Error::UnsupportedSchemaVersion {
+                found: version,
+                expected: migrations.len(),
+            });
+        }
+
+        for (index, migration) in migrations.iter().enumerate().skip(version) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", index as u32 + 1)?;
+            transaction.commit()?;
+        }
+
+        Ok(Self { connection })
+    }
+
+    /// The current schema version (the number of migrations that have been applied).
+    pub fn schema_version(&self) -> Result<usize, Error> {
+        Self::user_version(&self.connection)
+    }
+
+    fn user_version(connection: &Connection) -> Result<usize, Error> {
+        let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+        Ok(version as usize)
+    }
+
+    /// Direct access to the underlying connection for application-managed tables.
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT value FROM cli_helpers_kv WHERE key = ?",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Insert or replace a value, returning `true` if the key was not already present.
+    pub fn insert(&self, key: &str, value: &[u8]) -> Result<bool, Error> {
+        let is_new = !self.contains(key)?;
+
+        self.connection.execute(
+            "INSERT OR REPLACE INTO cli_helpers_kv (key, value) VALUES (?, ?)",
+            params![key, value],
+        )?;
+
+        Ok(is_new)
+    }
+
+    pub fn contains(&self, key: &str) -> Result<bool, Error> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT 1 FROM cli_helpers_kv WHERE key = ?",
+                params![key],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some())
+    }
+
+    /// Remove a value, returning `true` if the key was present.
+    pub fn remove(&self, key: &str) -> Result<bool, Error> {
+        Ok(self
+            .connection
+            .execute("DELETE FROM cli_helpers_kv WHERE key = ?", params![key])?
+            > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    const MIGRATIONS: [&str; 2] = [
+        "CREATE TABLE seen (id INTEGER PRIMARY KEY)",
+        "ALTER TABLE seen ADD COLUMN first_seen INTEGER",
+    ];
+
+    #[test]
+    fn test_store() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let path = dir.join("test.db");
+
+        let store = Store::open(&path, &MIGRATIONS[..1]).unwrap();
+        assert_eq!(store.schema_version().unwrap(), 1);
+        assert!(store.insert("last", b"123").unwrap());
+        assert!(!store.insert("last", b"456").unwrap());
+        drop(store);
+
+        let store = Store::open(&path, &MIGRATIONS).unwrap();
+        assert_eq!(store.schema_version().unwrap(), 2);
+        assert_eq!(store.get("last").unwrap(), Some(b"456".to_vec()));
+        store
+            .connection()
+            .execute("INSERT INTO seen (id, first_seen) VALUES (1, 2)", [])
+            .unwrap();
+        assert!(store.remove("last").unwrap());
+        assert!(!store.contains("last").unwrap());
+        drop(store);
+
+        assert!(matches!(
+            Store::open(&path, &MIGRATIONS[..1]),
+            Err(Error::UnsupportedSchemaVersion {
+                found: 2,
+                expected: 1
+            })
+        ));
+    }
+}
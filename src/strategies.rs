@@ -0,0 +1,141 @@
+//! [`proptest`](https://docs.rs/proptest) strategies for this crate's types.
+//!
+//! These are useful for testing parsing and round-trip logic in downstream crates. For example,
+//! [`timestamp_input`] generates strings in each of the formats accepted by [`Timestamp`] along with the
+//! expected parsed value.
+
+use std::time::Duration;
+
+use chrono::{FixedOffset, SecondsFormat, TimeZone, Utc};
+use proptest::prelude::*;
+
+use crate::byte_size::ByteSize;
+use crate::schedule::Interval;
+use crate::{batch::BatchSize, Timestamp, Verbosity, S_TO_MS_CUTOFF, TIMESTAMP_FMT_EN_US};
+
+/// The latest generated instant (the start of 2100).
+const MAX_EPOCH_SECOND: i64 = 4102444800;
+
+/// Timestamps between 1970 and 2100 with millisecond precision.
+pub fn timestamp() -> impl Strategy<Value = Timestamp> {
+    (0..MAX_EPOCH_SECOND * 1000)
+        .prop_map(|millis| Timestamp(Utc.timestamp_millis_opt(millis).single().expect("in range")))
+}
+
+/// Timestamps between 1970 and 2100 with second precision.
+pub fn timestamp_seconds() -> impl Strategy<Value = Timestamp> {
+    (0..MAX_EPOCH_SECOND)
+        .prop_map(|seconds| Timestamp(Utc.timestamp_opt(seconds, 0).single().expect("in range")))
+}
+
+/// A string in one of the accepted timestamp formats, along with the timestamp it represents.
+pub fn timestamp_input() -> impl Strategy<Value = (String, Timestamp)> {
+    let offset = (-12 * 60..=14 * 60)
+        .prop_map(|minutes| FixedOffset::east_opt(minutes * 60).expect("valid offset"));
+
+    prop_oneof![
+        timestamp_seconds().prop_map(|timestamp| (timestamp.0.timestamp().to_string(), timestamp)),
+        timestamp()
+            .prop_filter("parsed as seconds", |timestamp| timestamp
+                .0
+                .timestamp_millis()
+                >= S_TO_MS_CUTOFF)
+            .prop_map(|timestamp| (timestamp.0.timestamp_millis().to_string(), timestamp)),
+        (timestamp(), offset.clone()).prop_map(|(timestamp, offset)| (
+            timestamp
+                .0
+                .with_timezone(&offset)
+                .to_rfc3339_opts(SecondsFormat::Millis, false),
+            timestamp
+        )),
+        (timestamp_seconds(), offset).prop_map(|(timestamp, offset)| (
+            timestamp
+                .0
+                .with_timezone(&offset)
+                .format(TIMESTAMP_FMT_EN_US)
+                .to_string(),
+            timestamp
+        )),
+    ]
+}
+
+/// Any valid batch size.
+pub fn batch_size() -> impl Strategy<Value = BatchSize> {
+    (1..=crate::batch::MAX_BATCH_SIZE)
+        .prop_map(|batch_size| BatchSize::new(batch_size).expect("in range"))
+}
+
+/// Byte sizes up to a pebibyte.
+pub fn byte_size() -> impl Strategy<Value = ByteSize> {
+    (0..=1u64 << 50).prop_map(ByteSize)
+}
+
+/// A string with a count and one of the accepted byte size units, along with the size it represents.
+pub fn byte_size_input() -> impl Strategy<Value = (String, ByteSize)> {
+    let unit = prop::sample::select(vec![
+        ("", 1),
+        ("b", 1),
+        ("k", 1 << 10),
+        ("KiB", 1 << 10),
+        ("M", 1 << 20),
+        ("mib", 1 << 20),
+        ("g", 1 << 30),
+        ("GiB", 1 << 30),
+        ("T", 1 << 40),
+        ("TiB", 1 << 40),
+        ("kB", 1_000),
+        ("MB", 1_000_000),
+        ("gb", 1_000_000_000),
+        ("TB", 1_000_000_000_000),
+    ]);
+
+    (0..=1_000_000u64, unit, any::<bool>()).prop_map(|(count, (unit, multiplier), space)| {
+        let separator = if space && !unit.is_empty() { " " } else { "" };
+
+        (
+            format!("{count}{separator}{unit}"),
+            ByteSize(count * multiplier),
+        )
+    })
+}
+
+/// Intervals from a second to a year, in whole seconds.
+pub fn interval() -> impl Strategy<Value = Interval> {
+    (1..=365 * 24 * 60 * 60u64).prop_map(|seconds| Interval(Duration::from_secs(seconds)))
+}
+
+/// Verbosity levels from errors only to trace.
+pub fn verbosity() -> impl Strategy<Value = Verbosity> {
+    (0..=5u8).prop_map(Verbosity::new)
+}
+
+impl Arbitrary for Timestamp {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        timestamp().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn timestamp_input_round_trip((input, expected) in timestamp_input()) {
+            prop_assert_eq!(input.parse::<Timestamp>().unwrap(), expected);
+        }
+
+        #[test]
+        fn byte_size_input_round_trip((input, expected) in byte_size_input()) {
+            prop_assert_eq!(input.parse::<ByteSize>().unwrap(), expected);
+        }
+
+        #[test]
+        fn interval_round_trip(interval in interval()) {
+            prop_assert_eq!(interval.to_string().parse::<Interval>().unwrap(), interval);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! A uniform end-of-run summary.
+//!
+//! A [`Summary`] is shared by the code doing the work (counts are atomic, so it can be updated from several threads)
+//! and printed to standard error at the end:
+//!
+//! ```text
+//! Processed 1,204,133 records in 3m12s (6,261/s); 17 errors
+//! ```
+//!
+//! With [`OutputFormat::Json`], the summary is printed as a single JSON object instead.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::i18n::{self, ids};
+use crate::output::OutputFormat;
+
+#[derive(Debug)]
+pub struct Summary {
+    noun: String,
+    start: Instant,
+    records: AtomicU64,
+    skipped: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Self {
+            noun: "records".to_string(),
+            start: Instant::now(),
+            records: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the plural noun used in the text summary (the default is `records`).
+    pub fn with_noun(mut self, noun: &str) -> Self {
+        self.noun = noun.to_string();
+        self
+    }
+
+    /// Record that one more item has been processed.
+    pub fn record(&self) {
+        self.add_records(1);
+    }
+
+    pub fn add_records(&self, count: u64) {
+        self.records.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn skip(&self) {
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn records(&self) -> u64 {
+        self.records.load(Ordering::Relaxed)
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Render the summary as text, or as a JSON object for [`OutputFormat::Json`].
+    pub fn render(&self, format: OutputFormat) -> String {
+        self.render_with_elapsed(format, self.elapsed())
+    }
+
+    /// Print the summary to standard error.
+    pub fn print(&self, format: OutputFormat) {
+        eprintln!("{}", self.render(format));
+    }
+
+    /// The summary as a JSON object (with the elapsed time in seconds).
+    pub fn to_json(&self) -> serde_json::Value {
+        self.json_with_elapsed(self.elapsed())
+    }
+
+    fn json_with_elapsed(&self, elapsed: Duration) -> serde_json::Value {
+        let seconds = elapsed.as_secs_f64();
+        let rate = if seconds > 0.0 {
+            self.records() as f64 / seconds
+        } else {
+            0.0
+        };
+
+        let mut summary = serde_json::Map::new();
+        summary.insert("records".to_string(), self.records().into());
+        summary.insert("skipped".to_string(), self.skipped().into());
+        summary.insert("errors".to_string(), self.errors().into());
+        summary.insert(
+            "elapsed".to_string(),
+            ((seconds * 1000.0).round() / 1000.0).into(),
+        );
+        summary.insert("rate".to_string(), ((rate * 10.0).round() / 10.0).into());
+
+        serde_json::Value::Object(summary)
+    }
+
+    fn render_with_elapsed(&self, format: OutputFormat, elapsed: Duration) -> String {
+        let (records, skipped, errors) = (self.records(), self.skipped(), self.errors());
+        let seconds = elapsed.as_secs_f64();
+        let rate = if seconds > 0.0 {
+            records as f64 / seconds
+        } else {
+            0.0
+        };
+
+        if format == OutputFormat::Json {
+            self.json_with_elapsed(elapsed).to_string()
+        } else {
+            let mut line = i18n::message(
+                ids::SUMMARY_PROCESSED,
+                &[
+                    ("count", &format_count(records)),
+                    ("noun", &self.noun),
+                    ("elapsed", &format_elapsed(elapsed)),
+                    ("rate", &format_count(rate.round() as u64)),
+                ],
+            );
+
+            if skipped > 0 {
+                line.push_str("; ");
+                line.push_str(&i18n::message(
+                    ids::SUMMARY_SKIPPED,
+                    &[("count", &format_count(skipped))],
+                ));
+            }
+
+            if errors > 0 {
+                let id = if errors == 1 {
+                    ids::SUMMARY_ERROR
+                } else {
+                    ids::SUMMARY_ERRORS
+                };
+
+                line.push_str("; ");
+                line.push_str(&i18n::message(id, &[("count", &format_count(errors))]));
+            }
+
+            line
+        }
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Format a count with comma thousands separators.
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            formatted.push(',');
+        }
+
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+/// Format a size in bytes with binary units, such as `512 B`, `3.4 KiB`, or `1.2 GiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Format an elapsed time compactly, such as `850ms`, `42s`, `3m12s`, or `1h05m`.
+pub(crate) fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+
+    if seconds == 0 {
+        format!("{}ms", elapsed.as_millis())
+    } else if seconds < 60 {
+        format!("{seconds}s")
+    } else if seconds < 3600 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h{:02}m", seconds / 3600, (seconds % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::new();
+        summary.add_records(1_204_133);
+
+        for _ in 0..17 {
+            summary.error();
+        }
+
+        let elapsed = Duration::from_secs(192);
+
+        assert_eq!(
+            summary.render_with_elapsed(OutputFormat::Table, elapsed),
+            "Processed 1,204,133 records in 3m12s (6,272/s); 17 errors"
+        );
+        assert_eq!(
+            summary.render_with_elapsed(OutputFormat::Json, elapsed),
+            r#"{"records":1204133,"skipped":0,"errors":17,"elapsed":192.0,"rate":6271.5}"#
+        );
+
+        let summary = Summary::new().with_noun("tweets");
+        summary.record();
+        summary.record();
+        summary.skip();
+
+        assert_eq!(
+            summary.render_with_elapsed(OutputFormat::Csv, Duration::from_millis(500)),
+            "Processed 2 tweets in 500ms (4/s); 1 skipped"
+        );
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3_500), "3.4 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_bytes(1_300_000_000), "1.2 GiB");
+    }
+}
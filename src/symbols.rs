@@ -0,0 +1,54 @@
+//! Status symbols with an ASCII fallback.
+//!
+//! [`symbols`] returns Unicode glyphs when the terminal supports them (see [`term::supports_unicode`]), and ASCII
+//! replacements otherwise (including on legacy Windows consoles, which do not reliably render these glyphs).
+//!
+//! [`term::supports_unicode`]: crate::term::supports_unicode
+
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbols {
+    pub check: &'static str,
+    pub cross: &'static str,
+    pub warning: &'static str,
+    pub info: &'static str,
+    pub arrow: &'static str,
+    pub bullet: &'static str,
+}
+
+impl Symbols {
+    pub const UNICODE: Self = Self {
+        check: "✔",
+        cross: "✘",
+        warning: "⚠",
+        info: "ℹ",
+        arrow: "→",
+        bullet: "•",
+    };
+
+    pub const ASCII: Self = Self {
+        check: "[ok]",
+        cross: "[x]",
+        warning: "[!]",
+        info: "[i]",
+        arrow: "->",
+        bullet: "*",
+    };
+
+    /// The Unicode or ASCII set.
+    pub fn new(unicode: bool) -> Self {
+        if unicode {
+            Self::UNICODE
+        } else {
+            Self::ASCII
+        }
+    }
+}
+
+/// The symbol set for the current terminal (detected once).
+pub fn symbols() -> &'static Symbols {
+    static SYMBOLS: OnceLock<Symbols> = OnceLock::new();
+
+    SYMBOLS.get_or_init(|| Symbols::new(crate::term::supports_unicode()))
+}
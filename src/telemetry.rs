@@ -0,0 +1,480 @@
+//! Strictly opt-in anonymous usage telemetry.
+//!
+//! Nothing is recorded unless all of the following hold:
+//!
+//! * the user has explicitly consented (with [`Telemetry::prompt_consent`] or `telemetry enable`),
+//! * an endpoint has been configured (there is no default),
+//! * it has not been disabled in the configuration file, with `--disable-telemetry`, or with `DO_NOT_TRACK`.
+//!
+//! Events contain only what the application records (no user or machine identifiers are added), and are queued in a
+//! [`Journal`] in the application state directory until [`Telemetry::flush`] sends them in batches. Like [`http_cache`](crate::http_cache),
+//! this is independent of any HTTP client: the caller provides a function that posts a batch to the endpoint.
+//!
+//! [`TelemetryCommand`] provides reusable `telemetry status`, `telemetry enable`, and `telemetry disable`
+//! subcommands.
+
+use std::fs::{File, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::journal::{read_journal, Journal};
+use crate::term;
+use crate::{app_dirs::AppDirs, Error, Timestamp, TimestampFormat};
+
+pub(crate) const TELEMETRY_DIR_NAME: &str = "telemetry";
+const CONSENT_FILE_NAME: &str = "consent";
+const EVENTS_FILE_NAME: &str = "events.jsonl";
+const FLUSH_LOCK_FILE_NAME: &str = "flush.lock";
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Standard telemetry flag.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TelemetryArgs {
+    /// Disable usage telemetry for this run
+    #[clap(long, global = true)]
+    disable_telemetry: bool,
+}
+
+impl TelemetryArgs {
+    pub fn new(disable_telemetry: bool) -> Self {
+        Self { disable_telemetry }
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disable_telemetry
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consent {
+    Granted,
+    Denied,
+}
+
+/// Why telemetry is disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disabled {
+    /// The user has not been asked (or did not answer).
+    NoConsent,
+    Denied,
+    NoEndpoint,
+    Config,
+    Flag,
+    /// The `DO_NOT_TRACK` environment variable is set.
+    DoNotTrack,
+}
+
+impl std::fmt::Display for Disabled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoConsent => write!(f, "not enabled"),
+            Self::Denied => write!(f, "declined"),
+            Self::NoEndpoint => write!(f, "no endpoint configured"),
+            Self::Config => write!(f, "disabled in configuration"),
+            Self::Flag => write!(f, "disabled with --disable-telemetry"),
+            Self::DoNotTrack => write!(f, "DO_NOT_TRACK is set"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Telemetry {
+    dir: PathBuf,
+    endpoint: Option<String>,
+    config_enabled: Option<bool>,
+    flag_disabled: bool,
+    batch_size: usize,
+}
+
+impl Telemetry {
+    /// Use the telemetry directory in the application state directory.
+    pub fn new(dirs: &AppDirs) -> Self {
+        Self::with_dir(dirs.state_dir().join(TELEMETRY_DIR_NAME))
+    }
+
+    pub fn with_dir<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            endpoint: None,
+            config_enabled: None,
+            flag_disabled: false,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Set the user-configured endpoint that events are sent to.
+    pub fn with_endpoint(mut self, endpoint: Option<String>) -> Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Apply the configuration file setting (`Some(false)` disables telemetry).
+    pub fn with_config_enabled(mut self, enabled: Option<bool>) -> Self {
+        self.config_enabled = enabled;
+        self
+    }
+
+    pub fn with_args(mut self, args: &TelemetryArgs) -> Self {
+        self.flag_disabled = args.is_disabled();
+        self
+    }
+
+    /// Set the maximum number of events sent in a single request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    /// The recorded consent, if the user has been asked.
+    pub fn consent(&self) -> Result<Option<Consent>, Error> {
+        match std::fs::read_to_string(self.dir.join(CONSENT_FILE_NAME)) {
+            Ok(contents) => Ok(match contents.trim() {
+                "granted" => Some(Consent::Granted),
+                "denied" => Some(Consent::Denied),
+                _ => None,
+            }),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Record the user's decision (denying consent also discards any queued events).
+    pub fn set_consent(&self, consent: Consent) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let value = match consent {
+            Consent::Granted => "granted",
+            Consent::Denied => {
+                self.clear()?;
+                "denied"
+            }
+        };
+
+        std::fs::write(self.dir.join(CONSENT_FILE_NAME), format!("{value}\n"))?;
+
+        Ok(())
+    }
+
+    /// Ask the user for consent if they have not been asked yet (typically on the first run).
+    ///
+    /// The user is only asked if standard input and standard error are terminals, and the default answer is no.
+    pub fn prompt_consent(&self, message: &str) -> Result<Option<Consent>, Error> {
+        if let Some(consent) = self.consent()? {
+            return Ok(Some(consent));
+        }
+
+        let consent = match term::confirm(message)? {
+            Some(true) => Consent::Granted,
+            Some(false) => Consent::Denied,
+            None => return Ok(None),
+        };
+
+        self.set_consent(consent)?;
+
+        Ok(Some(consent))
+    }
+
+    /// Whether telemetry is enabled, or the reason it is not.
+    pub fn status(&self) -> Result<Result<(), Disabled>, Error> {
+        self.status_from_env(|name| std::env::var(name).ok())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.status(), Ok(Ok(())))
+    }
+
+    /// Queue an event with the given properties (doing nothing if telemetry is not enabled).
+    pub fn record(&self, event: &str, properties: Value) -> Result<(), Error> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let mut line = serde_json::Map::new();
+        line.insert("event".to_string(), event.into());
+        line.insert(
+            "timestamp".to_string(),
+            Timestamp::now()
+                .format_as(TimestampFormat::Rfc3339 { use_z: true })
+                .into(),
+        );
+        line.insert("properties".to_string(), properties);
+
+        Journal::open(self.dir.join(EVENTS_FILE_NAME))?.append(&Value::Object(line))
+    }
+
+    /// The number of queued events.
+    pub fn pending(&self) -> Result<usize, Error> {
+        Ok(self.events()?.len())
+    }
+
+    /// Send queued events to the endpoint in batches, returning the number sent.
+    ///
+    /// If telemetry is no longer enabled, queued events are discarded instead. Each batch is removed from the queue as
+    /// soon as it has been sent, so if sending fails, only the unsent events remain queued. If another process is
+    /// already flushing the queue, this returns zero immediately.
+    pub fn flush<E: From<Error>, F: FnMut(&str, &[Value]) -> Result<(), E>>(
+        &self,
+        mut send: F,
+    ) -> Result<usize, E> {
+        match (self.status()?, self.endpoint()) {
+            (Ok(()), Some(endpoint)) => {
+                let Some(_flush_lock) = self.try_lock_flush()? else {
+                    return Ok(0);
+                };
+
+                let events = self.events()?;
+
+                for batch in events.chunks(self.batch_size) {
+                    send(endpoint, batch)?;
+                    self.remove_sent(batch.len())?;
+                }
+
+                Ok(events.len())
+            }
+            (Ok(()), None) => Ok(0),
+            (Err(_), _) => {
+                self.clear()?;
+
+                Ok(0)
+            }
+        }
+    }
+
+    /// Discard any queued events.
+    pub fn clear(&self) -> Result<(), Error> {
+        self.rewrite_events(|_| &[])
+    }
+
+    fn events(&self) -> Result<Vec<Value>, Error> {
+        read_journal(self.dir.join(EVENTS_FILE_NAME))
+    }
+
+    /// Remove the first `count` queued events, keeping any that were recorded after they were read.
+    fn remove_sent(&self, count: usize) -> Result<(), Error> {
+        self.rewrite_events(|contents| {
+            let mut remaining = count;
+            let mut offset = 0;
+
+            for line in contents.split_inclusive(|byte| *byte == b'\n') {
+                if remaining == 0 {
+                    break;
+                }
+
+                // Invalid lines are skipped when reading, so they are not counted.
+                if serde_json::from_slice::<Value>(line).is_ok() {
+                    remaining -= 1;
+                }
+
+                offset += line.len();
+            }
+
+            &contents[offset..]
+        })
+    }
+
+    /// Replace the queue in place, holding the lock that [`Journal::append`] takes.
+    fn rewrite_events<F: FnOnce(&[u8]) -> &[u8]>(&self, rewrite: F) -> Result<(), Error> {
+        let mut file = match File::options()
+            .read(true)
+            .write(true)
+            .open(self.dir.join(EVENTS_FILE_NAME))
+        {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error.into()),
+        };
+
+        file.lock()?;
+
+        let mut contents = vec![];
+        let result = file
+            .read_to_end(&mut contents)
+            .and_then(|_| file.set_len(0))
+            .and_then(|()| file.seek(SeekFrom::Start(0)))
+            .and_then(|_| file.write_all(rewrite(&contents)));
+        file.unlock()?;
+
+        Ok(result?)
+    }
+
+    /// Take the lock that prevents concurrent flushes (`None` if another process holds it).
+    fn try_lock_flush(&self) -> Result<Option<File>, Error> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        let file = File::create(self.dir.join(FLUSH_LOCK_FILE_NAME))?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(Some(file)),
+            Err(TryLockError::WouldBlock) => Ok(None),
+            Err(TryLockError::Error(error)) => Err(error.into()),
+        }
+    }
+
+    fn status_from_env<F: Fn(&str) -> Option<String>>(
+        &self,
+        var: F,
+    ) -> Result<Result<(), Disabled>, Error> {
+        Ok(
+            if var("DO_NOT_TRACK").is_some_and(|value| !value.is_empty() && value != "0") {
+                Err(Disabled::DoNotTrack)
+            } else if self.flag_disabled {
+                Err(Disabled::Flag)
+            } else if self.config_enabled == Some(false) {
+                Err(Disabled::Config)
+            } else if self.endpoint.is_none() {
+                Err(Disabled::NoEndpoint)
+            } else {
+                match self.consent()? {
+                    Some(Consent::Granted) => Ok(()),
+                    Some(Consent::Denied) => Err(Disabled::Denied),
+                    None => Err(Disabled::NoConsent),
+                }
+            },
+        )
+    }
+}
+
+/// Reusable telemetry management subcommands.
+#[derive(clap::Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum TelemetryCommand {
+    /// Show whether usage telemetry is enabled
+    Status,
+    /// Allow anonymous usage telemetry
+    Enable,
+    /// Disallow usage telemetry and discard queued events
+    Disable,
+}
+
+impl TelemetryCommand {
+    pub fn run(&self, telemetry: &Telemetry) -> Result<(), Error> {
+        match self {
+            Self::Status => {
+                match telemetry.status()? {
+                    Ok(()) => println!(
+                        "Telemetry is enabled (endpoint: {})",
+                        telemetry.endpoint().unwrap_or_default()
+                    ),
+                    Err(reason) => println!("Telemetry is disabled ({reason})"),
+                }
+
+                println!("Queued events: {}", telemetry.pending()?);
+            }
+            Self::Enable => {
+                telemetry.set_consent(Consent::Granted)?;
+
+                if let Err(reason) = telemetry.status()? {
+                    log::warn!("Telemetry is allowed but still disabled ({reason})");
+                }
+            }
+            Self::Disable => {
+                telemetry.set_consent(Consent::Denied)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_telemetry() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let telemetry = Telemetry::with_dir(dir)
+            .with_endpoint(Some("https://telemetry.example.com".to_string()))
+            .with_batch_size(2);
+        let no_env = |_: &str| None;
+
+        assert_eq!(
+            telemetry.status_from_env(no_env).unwrap(),
+            Err(Disabled::NoConsent)
+        );
+
+        telemetry.set_consent(Consent::Granted).unwrap();
+        assert_eq!(telemetry.status_from_env(no_env).unwrap(), Ok(()));
+        assert_eq!(
+            telemetry
+                .status_from_env(|name| (name == "DO_NOT_TRACK").then(|| "1".to_string()))
+                .unwrap(),
+            Err(Disabled::DoNotTrack)
+        );
+        assert_eq!(
+            telemetry
+                .clone()
+                .with_config_enabled(Some(false))
+                .status_from_env(no_env)
+                .unwrap(),
+            Err(Disabled::Config)
+        );
+
+        if telemetry.is_enabled() {
+            for command in ["import", "export", "import"] {
+                telemetry
+                    .record("command", serde_json::json!({ "name": command }))
+                    .unwrap();
+            }
+
+            assert_eq!(telemetry.pending().unwrap(), 3);
+
+            let mut batches = vec![];
+            let sent = telemetry
+                .flush(|endpoint, batch| {
+                    batches.push((endpoint.to_string(), batch.len()));
+                    Ok::<_, Error>(())
+                })
+                .unwrap();
+
+            assert_eq!(sent, 3);
+            assert_eq!(
+                batches,
+                vec![
+                    ("https://telemetry.example.com".to_string(), 2),
+                    ("https://telemetry.example.com".to_string(), 1)
+                ]
+            );
+            assert_eq!(telemetry.pending().unwrap(), 0);
+
+            // Batches that were sent before a failure are not sent again.
+            for command in ["import", "export", "import"] {
+                telemetry
+                    .record("command", serde_json::json!({ "name": command }))
+                    .unwrap();
+            }
+
+            let mut attempts = 0;
+            let result = telemetry.flush(|_, _| {
+                attempts += 1;
+
+                if attempts == 1 {
+                    Ok(())
+                } else {
+                    Err(Error::from(std::io::Error::other("unavailable")))
+                }
+            });
+
+            assert!(result.is_err());
+
+            let events = telemetry.events().unwrap();
+            assert_eq!(events.len(), 1);
+            assert_eq!(events[0]["properties"]["name"], "import");
+
+            assert_eq!(telemetry.flush(|_, _| Ok::<_, Error>(())).unwrap(), 1);
+            assert_eq!(telemetry.pending().unwrap(), 0);
+        }
+
+        TelemetryCommand::Disable.run(&telemetry).unwrap();
+        assert_eq!(telemetry.consent().unwrap(), Some(Consent::Denied));
+        telemetry.record("command", Value::Null).unwrap();
+        assert_eq!(telemetry.pending().unwrap(), 0);
+    }
+}
@@ -0,0 +1,308 @@
+//! A temporary directory scoped to the current run.
+//!
+//! [`RunTempDir`] creates a uniquely-named directory (by default under the application's directory in the system
+//! temporary directory, which respects `TMPDIR`, or under `--temp-dir`), hands out paths inside it, and removes it
+//! when dropped, unless `--keep-temp` was given (which is useful for inspecting intermediate files while debugging).
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{app_dirs::AppDirs, shutdown::Shutdown, temp::TempArgs};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     temp: TempArgs,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//! let shutdown = Shutdown::install_handlers()?;
+//! let temp_dir = opts
+//!     .temp
+//!     .create(&AppDirs::new("mytool")?)?
+//!     .with_shutdown(&shutdown);
+//!
+//! std::fs::write(temp_dir.unique_file("chunk", "ndjson"), b"{}\n")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Since removal happens on drop, a run interrupted by Ctrl-C only cleans up if the [`Shutdown`] handlers are installed
+//! (so that the application can return normally), or if the directory is removed as soon as shutdown is requested with
+//! [`RunTempDir::with_shutdown`] (even if the application then exits without dropping it). Directories left behind by
+//! killed runs can be removed with the [`clean`](crate::clean) subcommand.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{app_dirs::AppDirs, shutdown::Shutdown, Error};
+
+/// The number of names to try before giving up on creating a unique directory.
+const MAX_ATTEMPTS: u32 = 16;
+
+/// Run directories are named `run-<PID>-<SUFFIX>`.
+pub(crate) const RUN_DIR_PREFIX: &str = "run-";
+
+/// How often to check for a shutdown request.
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Standard temporary directory arguments.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TempArgs {
+    /// Directory in which to create temporary files (defaults to the system temporary directory)
+    #[clap(long, global = true)]
+    temp_dir: Option<PathBuf>,
+    /// Keep temporary files after the run (for debugging)
+    #[clap(long, global = true)]
+    keep_temp: bool,
+}
+
+impl TempArgs {
+    pub fn new(temp_dir: Option<PathBuf>, keep_temp: bool) -> Self {
+        Self {
+            temp_dir,
+            keep_temp,
+        }
+    }
+
+    /// The directory in which run directories are created.
+    pub fn base_dir(&self, dirs: &AppDirs) -> PathBuf {
+        self.temp_dir.clone().unwrap_or_else(|| dirs.temp_dir())
+    }
+
+    pub fn create(&self, dirs: &AppDirs) -> Result<RunTempDir, Error> {
+        Ok(RunTempDir::create_in(self.base_dir(dirs))?.with_keep(self.keep_temp))
+    }
+}
+
+#[derive(Debug)]
+pub struct RunTempDir {
+    path: PathBuf,
+    keep: bool,
+    next_file: AtomicU64,
+    closed: bool,
+    watcher: Option<(Shutdown, JoinHandle<()>)>,
+}
+
+impl RunTempDir {
+    /// Create a new directory under the given directory (which is created if necessary).
+    pub fn create_in<P: AsRef<Path>>(base_dir: P) -> Result<Self, Error> {
+        let base_dir = base_dir.as_ref();
+        std::fs::create_dir_all(base_dir)?;
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or_default();
+
+        for attempt in 0..MAX_ATTEMPTS {
+            let path = base_dir.join(format!(
+                "{RUN_DIR_PREFIX}{}-{:08x}",
+                std::process::id(),
+                nanos.wrapping_add(attempt)
+            ));
+
+            match std::fs::create_dir(&path) {
+                Ok(()) => {
+                    return Ok(Self {
+                        path,
+                        keep: false,
+                        next_file: AtomicU64::new(0),
+                        closed: false,
+                        watcher: None,
+                    })
+                }
+                Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Err(std::io::Error::from(std::io::ErrorKind::AlreadyExists).into())
+    }
+
+    /// Keep the directory when dropped.
+    pub fn with_keep(mut self, keep: bool) -> Self {
+        self.keep = keep;
+
+        if keep {
+            self.stop_watcher();
+        }
+
+        self
+    }
+
+    /// Also remove the directory (unless it is being kept) as soon as shutdown is requested from the token.
+    ///
+    /// Work that writes to the directory should stop when the token is triggered, since the directory may be removed
+    /// while it is still running.
+    pub fn with_shutdown(mut self, shutdown: &Shutdown) -> Self {
+        self.stop_watcher();
+
+        if !self.keep {
+            let stop = Shutdown::new();
+            let handle = std::thread::spawn({
+                let shutdown = shutdown.clone();
+                let stop = stop.clone();
+                let path = self.path.clone();
+
+                move || loop {
+                    if shutdown.is_requested() {
+                        if let Err(error) = remove_dir(&path) {
+                            log::warn!("Unable to remove {}: {error}", path.display());
+                        }
+
+                        break;
+                    }
+
+                    if !stop.sleep(SHUTDOWN_POLL_INTERVAL) {
+                        break;
+                    }
+                }
+            });
+
+            self.watcher = Some((stop, handle));
+        }
+
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A path with the given name in the directory.
+    pub fn file<P: AsRef<Path>>(&self, name: P) -> PathBuf {
+        self.path.join(name)
+    }
+
+    /// A path that has not been handed out before, such as `chunk-3.ndjson`.
+    pub fn unique_file(&self, prefix: &str, extension: &str) -> PathBuf {
+        let index = self.next_file.fetch_add(1, Ordering::Relaxed);
+
+        if extension.is_empty() {
+            self.path.join(format!("{prefix}-{index}"))
+        } else {
+            self.path.join(format!("{prefix}-{index}.{extension}"))
+        }
+    }
+
+    /// Remove the directory now (unless it is being kept), reporting any error.
+    pub fn close(mut self) -> Result<(), Error> {
+        self.closed = true;
+        self.stop_watcher();
+        self.remove()
+    }
+
+    fn remove(&self) -> Result<(), Error> {
+        if self.keep {
+            log::info!("Keeping temporary files in {}", self.path.display());
+            Ok(())
+        } else {
+            remove_dir(&self.path)
+        }
+    }
+
+    fn stop_watcher(&mut self) {
+        if let Some((stop, handle)) = self.watcher.take() {
+            stop.request();
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The ID of the process that created a run directory with the given name.
+pub(crate) fn run_dir_pid(name: &str) -> Option<u32> {
+    name.strip_prefix(RUN_DIR_PREFIX)?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+fn remove_dir(path: &Path) -> Result<(), Error> {
+    match std::fs::remove_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+impl Drop for RunTempDir {
+    fn drop(&mut self) {
+        self.stop_watcher();
+
+        if !self.closed {
+            if let Err(error) = self.remove() {
+                log::warn!("Unable to remove {}: {error}", self.path.display());
+            }
+        }
+    }
+}
+
+/// A directory for a test, which is removed when dropped (even if the test panics).
+#[cfg(test)]
+pub(crate) fn test_dir() -> RunTempDir {
+    RunTempDir::create_in(std::env::temp_dir().join("cli-helpers-tests"))
+        .expect("test directory can be created")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_temp_dir() {
+        let base_dir = test_dir();
+
+        let temp_dir = RunTempDir::create_in(base_dir.path()).unwrap();
+        let other = RunTempDir::create_in(base_dir.path()).unwrap();
+        assert_ne!(temp_dir.path(), other.path());
+
+        let path = temp_dir.path().to_path_buf();
+        assert_eq!(
+            temp_dir.unique_file("chunk", "ndjson"),
+            path.join("chunk-0.ndjson")
+        );
+        assert_eq!(temp_dir.unique_file("chunk", ""), path.join("chunk-1"));
+        std::fs::write(temp_dir.file("data.txt"), b"data").unwrap();
+
+        drop(temp_dir);
+        assert!(!path.exists());
+
+        let kept = other.with_keep(true);
+        let path = kept.path().to_path_buf();
+        kept.close().unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_run_temp_dir_shutdown() {
+        let base_dir = test_dir();
+        let shutdown = Shutdown::new();
+
+        let temp_dir = RunTempDir::create_in(base_dir.path())
+            .unwrap()
+            .with_shutdown(&shutdown);
+        let kept = RunTempDir::create_in(base_dir.path())
+            .unwrap()
+            .with_shutdown(&shutdown)
+            .with_keep(true);
+        std::fs::write(temp_dir.file("data.txt"), b"data").unwrap();
+
+        shutdown.request();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+
+        while temp_dir.path().exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert!(!temp_dir.path().exists());
+        assert!(kept.path().exists());
+
+        // Dropping after the directory has already been removed is not an error.
+        drop(temp_dir);
+    }
+}
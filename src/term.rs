@@ -0,0 +1,236 @@
+//! Terminal capability detection.
+//!
+//! Detection is based on the environment, with the usual overrides: `COLUMNS` and `LINES` take precedence over the
+//! size reported by the terminal, and `TERM=dumb` disables everything beyond plain text. `FORCE_HYPERLINK` (set to
+//! `0` or `1`) overrides hyperlink detection.
+//!
+//! [`hyperlink`] renders a clickable link if standard output supports it, and otherwise falls back to plain text.
+
+use std::io::{IsTerminal, Write};
+
+use crate::color::Stream;
+use crate::i18n::{self, ids};
+use crate::Error;
+
+/// Capabilities of the terminal attached to a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    pub width: Option<usize>,
+    pub height: Option<usize>,
+    pub unicode: bool,
+    pub hyperlinks: bool,
+    pub truecolor: bool,
+}
+
+impl Capabilities {
+    pub fn detect(stream: Stream) -> Self {
+        Self {
+            width: width(stream),
+            height: height(stream),
+            unicode: supports_unicode(),
+            hyperlinks: supports_hyperlinks(stream),
+            truecolor: supports_truecolor(),
+        }
+    }
+}
+
+/// The width of the terminal in columns (`None` if the stream is not a terminal and `COLUMNS` is not set).
+pub fn width(stream: Stream) -> Option<usize> {
+    env_size("COLUMNS").or_else(|| size(stream).map(|(width, _)| width))
+}
+
+/// The height of the terminal in lines (`None` if the stream is not a terminal and `LINES` is not set).
+pub fn height(stream: Stream) -> Option<usize> {
+    env_size("LINES").or_else(|| size(stream).map(|(_, height)| height))
+}
+
+/// Whether `TERM` is `dumb`.
+pub fn is_dumb() -> bool {
+    std::env::var_os("TERM").is_some_and(|term| term == "dumb")
+}
+
+/// Whether the terminal can be expected to display non-ASCII characters.
+pub fn supports_unicode() -> bool {
+    unicode_from_env(env_var)
+}
+
+/// Whether the terminal attached to the stream supports OSC 8 hyperlinks.
+pub fn supports_hyperlinks(stream: Stream) -> bool {
+    match env_var("FORCE_HYPERLINK") {
+        Some(value) => value != "0",
+        None => stream.is_terminal() && hyperlinks_from_env(env_var),
+    }
+}
+
+/// Whether the terminal supports 24-bit color.
+pub fn supports_truecolor() -> bool {
+    truecolor_from_env(env_var)
+}
+
+/// Render a link to standard output, as an OSC 8 hyperlink if supported and otherwise as `text (url)`.
+pub fn hyperlink(url: &str, text: &str) -> String {
+    format_hyperlink(url, text, supports_hyperlinks(Stream::Stdout))
+}
+
+/// Render a link as an OSC 8 hyperlink if `enabled` and otherwise as `text (url)` (or only the URL if they are the
+/// same).
+pub fn format_hyperlink(url: &str, text: &str, enabled: bool) -> String {
+    if enabled {
+        format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+    } else if text == url || text.is_empty() {
+        url.to_string()
+    } else {
+        format!("{text} ({url})")
+    }
+}
+
+/// Ask a yes-or-no question on standard error, where the default answer is no.
+///
+/// Returns `None` without asking if standard input or standard error is not a terminal.
+pub fn confirm(message: &str) -> Result<Option<bool>, Error> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Ok(None);
+    }
+
+    eprint!("{message} {} ", i18n::message(ids::PROMPT_DEFAULT_NO, &[]));
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(Some(is_affirmative(&answer)))
+}
+
+fn is_affirmative(answer: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+    let affirmative = i18n::message(ids::PROMPT_YES, &[]);
+
+    matches!(answer.as_str(), "y" | "yes") || affirmative.split(',').any(|yes| yes.trim() == answer)
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn size(stream: Stream) -> Option<(usize, usize)> {
+    let (terminal_size::Width(width), terminal_size::Height(height)) = match stream {
+        Stream::Stdout => terminal_size::terminal_size_of(std::io::stdout()),
+        Stream::Stderr => terminal_size::terminal_size_of(std::io::stderr()),
+    }?;
+
+    Some((width as usize, height as usize))
+}
+
+/// The size is only available from the environment on WebAssembly.
+#[cfg(target_family = "wasm")]
+fn size(_stream: Stream) -> Option<(usize, usize)> {
+    None
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn env_size(name: &str) -> Option<usize> {
+    env_var(name)
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|size| *size > 0)
+}
+
+fn unicode_from_env<F: Fn(&str) -> Option<String>>(var: F) -> bool {
+    if var("TERM").is_some_and(|term| term == "dumb") {
+        return false;
+    }
+
+    // The first of these that is set determines the character encoding.
+    match ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| var(name))
+    {
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+        // Windows does not set locale variables, but modern terminals there handle Unicode.
+        None => cfg!(windows) && (var("WT_SESSION").is_some() || var("TERM_PROGRAM").is_some()),
+    }
+}
+
+fn hyperlinks_from_env<F: Fn(&str) -> Option<String>>(var: F) -> bool {
+    if var("TERM").is_some_and(|term| term == "dumb") {
+        return false;
+    }
+
+    var("TERM_PROGRAM").is_some_and(|program| {
+        matches!(
+            program.as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty"
+        )
+    }) || var("TERM").is_some_and(|term| {
+        matches!(
+            term.as_str(),
+            "xterm-kitty" | "alacritty" | "foot" | "xterm-ghostty"
+        )
+    }) || var("VTE_VERSION")
+        .and_then(|version| version.parse::<u32>().ok())
+        .is_some_and(|version| version >= 5000)
+        || ["WT_SESSION", "KONSOLE_VERSION", "DOMTERM"]
+            .iter()
+            .any(|name| var(name).is_some())
+}
+
+fn truecolor_from_env<F: Fn(&str) -> Option<String>>(var: F) -> bool {
+    var("TERM").is_none_or(|term| term != "dumb")
+        && (var("COLORTERM").is_some_and(|value| value == "truecolor" || value == "24bit")
+            || var("TERM").is_some_and(|term| term.ends_with("-direct"))
+            || var("WT_SESSION").is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn test_detection() {
+        assert!(unicode_from_env(env(&[("LANG", "en_US.UTF-8")])));
+        assert!(!unicode_from_env(env(&[
+            ("LC_ALL", "C"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+        assert!(!unicode_from_env(env(&[
+            ("TERM", "dumb"),
+            ("LANG", "en_US.UTF-8")
+        ])));
+
+        assert!(hyperlinks_from_env(env(&[("TERM_PROGRAM", "WezTerm")])));
+        assert!(hyperlinks_from_env(env(&[("VTE_VERSION", "6800")])));
+        assert!(!hyperlinks_from_env(env(&[("VTE_VERSION", "4600")])));
+        assert!(!hyperlinks_from_env(env(&[
+            ("TERM", "dumb"),
+            ("WT_SESSION", "1")
+        ])));
+
+        assert!(truecolor_from_env(env(&[("COLORTERM", "truecolor")])));
+        assert!(!truecolor_from_env(env(&[("TERM", "xterm-256color")])));
+    }
+
+    #[test]
+    fn test_format_hyperlink() {
+        let url = "https://x.com/i/status/1";
+
+        assert_eq!(
+            format_hyperlink(url, "tweet", true),
+            "\x1b]8;;https://x.com/i/status/1\x1b\\tweet\x1b]8;;\x1b\\"
+        );
+        assert_eq!(
+            format_hyperlink(url, "tweet", false),
+            "tweet (https://x.com/i/status/1)"
+        );
+        assert_eq!(format_hyperlink(url, url, false), url);
+    }
+}
@@ -0,0 +1,170 @@
+//! Log capture for tests.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<CapturedRecord>>> = const { RefCell::new(None) };
+}
+
+/// Whether the installed global logger was installed by this crate (and therefore supports capture).
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// The number of active captures and the maximum level to restore when the last one finishes.
+static ACTIVE: Mutex<(usize, LevelFilter)> = Mutex::new((0, LevelFilter::Off));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Run the closure, returning all log records emitted on the current thread while it runs.
+///
+/// Records are captured regardless of the configured verbosity (the global maximum level is raised to `Trace` while
+/// any capture is active). This works if no logger has been installed yet or if the logger was installed by this crate
+/// with the `testing` feature enabled; it panics if some other logger has been installed.
+pub fn capture_logs<F: FnOnce()>(f: F) -> Vec<CapturedRecord> {
+    if !INSTALLED.load(Ordering::SeqCst) {
+        let _ = install_logger(None, LevelFilter::Off);
+
+        assert!(
+            INSTALLED.load(Ordering::SeqCst),
+            "a logger that does not support capture has already been installed"
+        );
+    }
+
+    {
+        let mut active = ACTIVE.lock().unwrap_or_else(|error| error.into_inner());
+
+        if active.0 == 0 {
+            active.1 = log::max_level();
+        }
+
+        active.0 += 1;
+        log::set_max_level(LevelFilter::Trace);
+    }
+
+    // Restore state even if the closure panics.
+    struct Guard(Option<Vec<CapturedRecord>>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            CAPTURED.with(|captured| captured.replace(self.0.take()));
+
+            let mut active = ACTIVE.lock().unwrap_or_else(|error| error.into_inner());
+            active.0 -= 1;
+
+            if active.0 == 0 {
+                log::set_max_level(active.1);
+            }
+        }
+    }
+
+    let previous = CAPTURED.with(|captured| captured.replace(Some(vec![])));
+    let guard = Guard(previous);
+
+    f();
+
+    let records = CAPTURED.with(|captured| captured.replace(None));
+    drop(guard);
+
+    records.unwrap_or_default()
+}
+
+/// Install a global logger that supports capture, delegating to the given logger.
+pub(crate) fn install_logger(
+    logger: Option<Box<dyn Log>>,
+    level_filter: LevelFilter,
+) -> Result<(), log::SetLoggerError> {
+    log::set_boxed_logger(Box::new(CaptureLogger { logger }))?;
+    log::set_max_level(level_filter);
+    INSTALLED.store(true, Ordering::SeqCst);
+
+    Ok(())
+}
+
+struct CaptureLogger {
+    logger: Option<Box<dyn Log>>,
+}
+
+impl CaptureLogger {
+    fn is_capturing() -> bool {
+        CAPTURED
+            .try_with(|captured| captured.borrow().is_some())
+            .unwrap_or(false)
+    }
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        Self::is_capturing()
+            || self
+                .logger
+                .as_ref()
+                .is_some_and(|logger| logger.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        let captured = CAPTURED
+            .try_with(|captured| {
+                captured.borrow_mut().as_mut().map(|records| {
+                    records.push(CapturedRecord {
+                        level: record.level(),
+                        target: record.target().to_string(),
+                        message: record.args().to_string(),
+                    })
+                })
+            })
+            .ok()
+            .flatten()
+            .is_some();
+
+        if !captured {
+            if let Some(logger) = &self.logger {
+                logger.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(logger) = &self.logger {
+            logger.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_logs() {
+        let records = capture_logs(|| {
+            log::warn!("malformed line: {}", 42);
+            log::trace!(target: "custom", "details");
+        });
+
+        assert_eq!(
+            records,
+            vec![
+                CapturedRecord {
+                    level: Level::Warn,
+                    target: "cli_helpers::testing::capture::tests".to_string(),
+                    message: "malformed line: 42".to_string()
+                },
+                CapturedRecord {
+                    level: Level::Trace,
+                    target: "custom".to_string(),
+                    message: "details".to_string()
+                }
+            ]
+        );
+
+        assert!(capture_logs(|| {}).is_empty());
+    }
+}
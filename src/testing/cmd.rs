@@ -0,0 +1,224 @@
+//! An integration-test harness for running command-line applications.
+//!
+//! Output is available both raw and normalized (with ANSI escape sequences removed and RFC 3339 timestamps replaced),
+//! and the assertion methods operate on the normalized output.
+//!
+//! ```rust,ignore
+//! let output = cli_helpers::cargo_bin_cmd!("mytool")
+//!     .args(["--since", "2023-01-01"])
+//!     .stdin("{\"id\":1}\n")
+//!     .run()
+//!     .unwrap();
+//!
+//! output.assert_success().assert_stdout_contains("1 records");
+//! ```
+
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::Error;
+
+use super::normalize;
+
+/// Create a [`Cmd`](crate::testing::cmd::Cmd) for a binary in the current package (in integration tests only).
+#[macro_export]
+macro_rules! cargo_bin_cmd {
+    ($name:literal) => {
+        $crate::testing::cmd::Cmd::new(env!(concat!("CARGO_BIN_EXE_", $name)))
+    };
+}
+
+#[derive(Debug)]
+pub struct Cmd {
+    command: Command,
+    stdin: Option<Vec<u8>>,
+}
+
+impl Cmd {
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Self {
+        Self {
+            command: Command::new(program),
+            stdin: None,
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.command.arg(arg);
+        self
+    }
+
+    pub fn args<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(&mut self, args: I) -> &mut Self {
+        self.command.args(args);
+        self
+    }
+
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) -> &mut Self {
+        self.command.env(key, value);
+        self
+    }
+
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Self {
+        self.command.env_remove(key);
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.command.current_dir(dir);
+        self
+    }
+
+    pub fn stdin<B: Into<Vec<u8>>>(&mut self, stdin: B) -> &mut Self {
+        self.stdin = Some(stdin.into());
+        self
+    }
+
+    /// Run the command to completion, capturing its output.
+    pub fn run(&mut self) -> Result<Output, Error> {
+        let mut child = self
+            .command
+            .stdin(if self.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(stdin) = &self.stdin {
+            // Write from another thread to avoid deadlocking on a full output pipe.
+            let mut child_stdin = child.stdin.take().expect("stdin is piped");
+            let stdin = stdin.clone();
+            let writer = std::thread::spawn(move || child_stdin.write_all(&stdin));
+
+            let output = child.wait_with_output()?;
+            writer.join().expect("stdin writer panicked")?;
+
+            Ok(Output::new(output))
+        } else {
+            Ok(Output::new(child.wait_with_output()?))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Output {
+    status: ExitStatus,
+    raw_stdout: Vec<u8>,
+    raw_stderr: Vec<u8>,
+    stdout: String,
+    stderr: String,
+}
+
+impl Output {
+    fn new(output: std::process::Output) -> Self {
+        let normalize = |bytes: &[u8]| {
+            normalize::rfc3339_timestamps(&normalize::strip_ansi(&String::from_utf8_lossy(bytes)))
+        };
+
+        Self {
+            status: output.status,
+            stdout: normalize(&output.stdout),
+            stderr: normalize(&output.stderr),
+            raw_stdout: output.stdout,
+            raw_stderr: output.stderr,
+        }
+    }
+
+    pub fn status(&self) -> ExitStatus {
+        self.status
+    }
+
+    /// The exit code (`None` if the process was terminated by a signal).
+    pub fn code(&self) -> Option<i32> {
+        self.status.code()
+    }
+
+    /// The normalized standard output.
+    pub fn stdout(&self) -> &str {
+        &self.stdout
+    }
+
+    /// The normalized standard error.
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+
+    pub fn raw_stdout(&self) -> &[u8] {
+        &self.raw_stdout
+    }
+
+    pub fn raw_stderr(&self) -> &[u8] {
+        &self.raw_stderr
+    }
+
+    pub fn assert_success(&self) -> &Self {
+        if !self.status.success() {
+            self.fail(&format!("expected success, got {}", self.status));
+        }
+        self
+    }
+
+    pub fn assert_code(&self, code: i32) -> &Self {
+        if self.code() != Some(code) {
+            self.fail(&format!("expected exit code {code}, got {}", self.status));
+        }
+        self
+    }
+
+    pub fn assert_stdout_eq(&self, expected: &str) -> &Self {
+        if self.stdout != expected {
+            self.fail(&format!("expected stdout:\n{expected}"));
+        }
+        self
+    }
+
+    pub fn assert_stdout_contains(&self, pattern: &str) -> &Self {
+        if !self.stdout.contains(pattern) {
+            self.fail(&format!("expected stdout to contain {pattern:?}"));
+        }
+        self
+    }
+
+    pub fn assert_stderr_contains(&self, pattern: &str) -> &Self {
+        if !self.stderr.contains(pattern) {
+            self.fail(&format!("expected stderr to contain {pattern:?}"));
+        }
+        self
+    }
+
+    fn fail(&self, message: &str) -> ! {
+        panic!(
+            "{message}\n--- stdout ---\n{}\n--- stderr ---\n{}",
+            self.stdout, self.stderr
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmd() {
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+
+        Cmd::new(&cargo)
+            .arg("--version")
+            .run()
+            .unwrap()
+            .assert_success()
+            .assert_stdout_contains("cargo ");
+
+        let output = Cmd::new(&cargo)
+            .arg("--no-such-flag")
+            .env("NO_COLOR", "1")
+            .run()
+            .unwrap();
+
+        assert!(!output.status().success());
+        assert!(output.stderr().contains("--no-such-flag"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Golden (snapshot) file assertions.
+//!
+//! Run tests with `UPDATE_GOLDEN=1` to write the actual output to the golden files instead of comparing.
+
+use std::path::{Path, PathBuf};
+
+use crate::diff;
+
+pub const UPDATE_ENV_VAR: &str = "UPDATE_GOLDEN";
+
+const DEFAULT_CONTEXT: usize = 3;
+
+type Normalizer = Box<dyn Fn(&str) -> String>;
+
+/// Assert that the output matches the contents of the golden file at the path.
+pub fn assert_matches_golden<P: AsRef<Path>>(path: P, actual: &str) {
+    Golden::new(path).assert(actual);
+}
+
+pub struct Golden {
+    path: PathBuf,
+    normalizers: Vec<Normalizer>,
+    context: usize,
+}
+
+impl Golden {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            normalizers: vec![],
+            context: DEFAULT_CONTEXT,
+        }
+    }
+
+    /// Add a normalizer (such as [`rfc3339_timestamps`](super::normalize::rfc3339_timestamps)) to apply to the
+    /// actual output before comparison.
+    pub fn with_normalizer<F: Fn(&str) -> String + 'static>(mut self, normalizer: F) -> Self {
+        self.normalizers.push(Box::new(normalizer));
+        self
+    }
+
+    /// Set the number of context lines shown around differences.
+    pub fn with_context(self, context: usize) -> Self {
+        Self { context, ..self }
+    }
+
+    pub fn assert(&self, actual: &str) {
+        let update = std::env::var_os(UPDATE_ENV_VAR).is_some_and(|value| value == "1");
+
+        self.assert_with_update(actual, update);
+    }
+
+    fn assert_with_update(&self, actual: &str, update: bool) {
+        let actual = self
+            .normalizers
+            .iter()
+            .fold(actual.to_string(), |actual, normalizer| normalizer(&actual));
+
+        if update {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent).expect("could not create golden file directory");
+            }
+
+            std::fs::write(&self.path, &actual).expect("could not write golden file");
+            return;
+        }
+
+        let expected = match std::fs::read_to_string(&self.path) {
+            Ok(expected) => expected,
+            Err(error) => panic!(
+                "could not read golden file {} ({error}); run with {UPDATE_ENV_VAR}=1 to create it",
+                self.path.display()
+            ),
+        };
+
+        if expected != actual {
+            let diff = diff::unified(
+                &expected,
+                &actual,
+                &self.path.to_string_lossy(),
+                "actual",
+                self.context,
+            );
+
+            panic!(
+                "output does not match golden file (run with {UPDATE_ENV_VAR}=1 to update):\n{}",
+                if diff.is_empty() {
+                    "(differences in line endings or trailing newline only)\n".to_string()
+                } else {
+                    diff
+                }
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+    use crate::testing::normalize::rfc3339_timestamps;
+
+    #[test]
+    fn test_golden() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        let golden = Golden::new(dir.join("output.txt")).with_normalizer(rfc3339_timestamps);
+
+        golden.assert_with_update("started at 2023-08-25T08:47:09Z\ndone\n", true);
+        golden.assert_with_update("started at 2024-01-01T00:00:00Z\ndone\n", false);
+
+        let result = std::panic::catch_unwind(|| {
+            Golden::new(dir.join("output.txt")).assert_with_update("started\nfailed\n", false)
+        });
+        let message = *result.unwrap_err().downcast::<String>().unwrap();
+        assert!(message.contains("-started at [TIMESTAMP]\n-done\n+started\n+failed\n"));
+    }
+}
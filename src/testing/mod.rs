@@ -0,0 +1,54 @@
+//! Utilities for testing applications built with this crate.
+//!
+//! Log capture (`capture_logs`) requires the `testing` feature, since it wraps the logger installed by
+//! [`logging::Builder::init`](crate::logging::Builder::init) (which is otherwise installed directly).
+
+#[cfg(feature = "testing-cmd")]
+pub mod cmd;
+pub mod golden;
+pub mod normalize;
+
+#[cfg(any(test, feature = "testing"))]
+mod capture;
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+use crate::clock::Clock;
+
+#[cfg(any(test, feature = "testing"))]
+pub(crate) use capture::install_logger;
+#[cfg(any(test, feature = "testing"))]
+pub use capture::{capture_logs, CapturedRecord};
+
+/// A clock that only changes when it is explicitly set or advanced.
+///
+/// Clones share the same time, so a clone can be installed with [`with_clock`](crate::clock::with_clock) and the
+/// original used to advance it.
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl FixedClock {
+    pub fn new<T: Into<DateTime<Utc>>>(now: T) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now.into())),
+        }
+    }
+
+    pub fn set<T: Into<DateTime<Utc>>>(&self, now: T) {
+        *self.now.lock().unwrap_or_else(|error| error.into_inner()) = now.into();
+    }
+
+    pub fn advance(&self, duration: chrono::Duration) {
+        *self.now.lock().unwrap_or_else(|error| error.into_inner()) += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
@@ -0,0 +1,132 @@
+//! Normalizers for making command output stable across runs.
+
+/// The replacement for timestamps in normalized output.
+pub const TIMESTAMP_PLACEHOLDER: &str = "[TIMESTAMP]";
+
+/// Remove ANSI escape sequences (colors, cursor movement, and OSC 8 hyperlinks).
+pub fn strip_ansi(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // Control sequence: parameters and intermediate bytes followed by a final byte.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Operating system command: terminated by BEL or ST (ESC \).
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    output
+}
+
+/// Replace RFC 3339 (or ISO 8601 with a space separator) date-times with [`TIMESTAMP_PLACEHOLDER`].
+pub fn rfc3339_timestamps(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut output = String::with_capacity(input.len());
+    let mut last = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match timestamp_len(&bytes[i..]) {
+            Some(len) if i == 0 || !bytes[i - 1].is_ascii_digit() => {
+                output.push_str(&input[last..i]);
+                output.push_str(TIMESTAMP_PLACEHOLDER);
+                i += len;
+                last = i;
+            }
+            _ => i += 1,
+        }
+    }
+
+    output.push_str(&input[last..]);
+    output
+}
+
+/// The length of the timestamp at the start of the input, if there is one.
+fn timestamp_len(bytes: &[u8]) -> Option<usize> {
+    const PATTERN: &[u8] = b"dddd-dd-ddTdd:dd:dd";
+
+    if bytes.len() < PATTERN.len() {
+        return None;
+    }
+
+    for (byte, expected) in bytes.iter().zip(PATTERN) {
+        let matches = match expected {
+            b'd' => byte.is_ascii_digit(),
+            b'T' => matches!(byte, b'T' | b't' | b' '),
+            expected => byte == expected,
+        };
+
+        if !matches {
+            return None;
+        }
+    }
+
+    let digits = |start: usize| {
+        bytes[start.min(bytes.len())..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+
+    let mut len = PATTERN.len();
+
+    if bytes.get(len) == Some(&b'.') && digits(len + 1) > 0 {
+        len += 1 + digits(len + 1);
+    }
+
+    match bytes.get(len) {
+        Some(b'Z' | b'z') => len += 1,
+        Some(b'+' | b'-')
+            if bytes.len() >= len + 6
+                && digits(len + 1) == 2
+                && bytes[len + 3] == b':'
+                && digits(len + 4) == 2 =>
+        {
+            len += 6
+        }
+        _ => {}
+    }
+
+    Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            strip_ansi(
+                "\x1b[1;31merror:\x1b[0m \x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07!"
+            ),
+            "error: link!"
+        );
+        assert_eq!(
+            rfc3339_timestamps(
+                "at 2023-08-25T08:47:09Z, 2023-08-25 08:47:09.123+02:00 and 12023-08-25T08:47:09"
+            ),
+            "at [TIMESTAMP], [TIMESTAMP] and 12023-08-25T08:47:09"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! A clap value parser for [`Timestamp`] with descriptive errors.
+//!
+//! [`Timestamp`] arguments use [`TimestampValueParser`] by default. When a value cannot be parsed, the error lists
+//! the accepted formats with examples, and points out near-misses:
+//!
+//! ```text
+//! error: invalid value '2023-13-01' for '--since <SINCE>': looks like a date, but the month is 13
+//!
+//! Accepted formats:
+//!   epoch seconds       1692946029
+//!   ...
+//! ```
+
+use std::ffi::OsStr;
+
+use chrono::NaiveDate;
+use clap::builder::{TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
+use clap::{Arg, Command};
+
+use crate::Timestamp;
+
+/// Descriptions and examples of the accepted formats, in the order in which they are tried.
+const ACCEPTED_FORMATS: [(&str, &str); 9] = [
+    ("epoch seconds", "1692946029"),
+    ("epoch milliseconds", "1692946029632"),
+    ("date(1) output", "Fri Aug 25 08:47:09 AM +0200 2023"),
+    ("RFC 3339", "2023-08-25T08:47:09.123+02:00"),
+    ("RFC 2822", "Fri, 25 Aug 2023 08:47:09 +0200"),
+    ("HTTP date", "Fri, 25 Aug 2023 06:47:09 GMT"),
+    ("git log", "Fri Aug 25 08:47:09 2023 +0200"),
+    ("date (midnight UTC)", "2023-08-25"),
+    ("relative", "now, today, yesterday, 3 hours ago"),
+];
+
+pub(crate) const RELATIVE_UNITS: &str = "s, m, h, d, or w (or their full names)";
+
+/// The accepted formats as an indented list of descriptions and examples (one per line).
+pub(crate) fn accepted_formats() -> String {
+    ACCEPTED_FORMATS
+        .iter()
+        .map(|(name, example)| format!("  {name:<20}{example}\n"))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TimestampValueParser;
+
+impl TimestampValueParser {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TypedValueParser for TimestampValueParser {
+    type Value = Timestamp;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let input = value.to_string_lossy();
+
+        input.parse().map_err(|_| {
+            let arg = arg.map_or_else(|| "...".to_string(), ToString::to_string);
+            let mut message = format!("invalid value '{input}' for '{arg}'");
+
+            if let Some(near_miss) = near_miss(&input) {
+                message.push_str(": ");
+                message.push_str(&near_miss);
+            }
+
+            message.push_str("\n\nAccepted formats:\n");
+            message.push_str(&accepted_formats());
+
+            clap::Error::raw(ErrorKind::ValueValidation, message).with_cmd(cmd)
+        })
+    }
+}
+
+impl ValueParserFactory for Timestamp {
+    type Parser = TimestampValueParser;
+
+    fn value_parser() -> Self::Parser {
+        TimestampValueParser
+    }
+}
+
+/// Explain why an input that resembles an accepted format was rejected.
+fn near_miss(input: &str) -> Option<String> {
+    let input = input.trim();
+
+    if let Some(explanation) = date_near_miss(input) {
+        return Some(explanation);
+    }
+
+    if !input.is_empty()
+        && input
+            .trim_start_matches('-')
+            .chars()
+            .all(|c| c.is_ascii_digit())
+    {
+        return Some("looks like an epoch timestamp, but is out of range".to_string());
+    }
+
+    if input.contains('/') && input.split('/').count() == 3 {
+        return Some("looks like a date, but dates must be written as YYYY-MM-DD".to_string());
+    }
+
+    let lowercase = input.to_lowercase();
+
+    if let Some(amount) = lowercase.strip_suffix(" ago") {
+        let unit = amount
+            .trim()
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .trim();
+
+        return Some(if unit.is_empty() {
+            format!("looks like a relative time, but has no unit (use {RELATIVE_UNITS})")
+        } else if amount.trim().starts_with(|c: char| c.is_ascii_digit()) {
+            format!("looks like a relative time, but '{unit}' is not a unit (use {RELATIVE_UNITS})")
+        } else {
+            "looks like a relative time, but does not start with a number".to_string()
+        });
+    }
+
+    if format!("{input} ago").parse::<Timestamp>().is_ok() {
+        return Some(format!(
+            "looks like a relative time; did you mean '{input} ago'?"
+        ));
+    }
+
+    None
+}
+
+/// Check an input that starts with a `YYYY-MM-DD` date.
+fn date_near_miss(input: &str) -> Option<String> {
+    let date = input.get(..10)?;
+    let rest = &input[10..];
+
+    let mut parts = date.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+
+    let year = year.parse::<i32>().ok()?;
+    let month = month.parse::<u32>().ok()?;
+    let day = day.parse::<u32>().ok()?;
+
+    if !(1..=12).contains(&month) {
+        return Some(format!("looks like a date, but the month is {month}"));
+    }
+
+    if NaiveDate::from_ymd_opt(year, month, day).is_none() {
+        let days = days_in_month(year, month)?;
+        let month_name = NaiveDate::from_ymd_opt(year, month, 1)?.format("%B %Y");
+
+        return Some(format!(
+            "looks like a date, but the day is {day} (and {month_name} has {days} days)"
+        ));
+    }
+
+    let time = rest.strip_prefix(['T', 't', ' '])?;
+    let mut parts = time.get(..8.min(time.len()))?.split(':');
+    let hour = parts.next().and_then(|hour| hour.parse::<u32>().ok())?;
+    let minute = parts
+        .next()
+        .and_then(|minute| minute.get(..2)?.parse::<u32>().ok())?;
+    let second = parts
+        .next()
+        .and_then(|second| second.get(..2)?.parse::<u32>().ok());
+
+    if hour > 23 {
+        Some(format!(
+            "looks like a date and time, but the hour is {hour}"
+        ))
+    } else if minute > 59 {
+        Some(format!(
+            "looks like a date and time, but the minute is {minute}"
+        ))
+    } else if let Some(second) = second.filter(|second| *second > 60) {
+        Some(format!(
+            "looks like a date and time, but the second is {second}"
+        ))
+    } else if !time.contains(['Z', 'z', '+'])
+        && time.get(8..).is_none_or(|rest| !rest.contains('-'))
+    {
+        Some(
+            "looks like a date and time, but has no UTC offset (add Z or an offset such as +02:00)"
+                .to_string(),
+        )
+    } else {
+        None
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> Option<u32> {
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = NaiveDate::from_ymd_opt(next_year, next_month, 1)?;
+
+    u32::try_from(next.signed_duration_since(first).num_days()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Debug, Parser)]
+    struct Opts {
+        #[clap(long)]
+        since: Option<Timestamp>,
+    }
+
+    fn error(value: &str) -> String {
+        Opts::try_parse_from(["test", "--since", value])
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_value_parser() {
+        let opts = Opts::try_parse_from(["test", "--since", "2023-08-25T06:47:09Z"]).unwrap();
+        assert_eq!(opts.since.map(|since| since.timestamp()), Some(1692946029));
+
+        let message = error("2023-13-01");
+        assert!(message.starts_with(
+            "error: invalid value '2023-13-01' for '--since <SINCE>': looks like a date, but the month is 13\n"
+        ));
+        assert!(message.contains("\nAccepted formats:\n  epoch seconds       1692946029\n"));
+        assert!(message.contains("  relative            now, today, yesterday, 3 hours ago\n"));
+
+        assert!(error("nonsense").contains("for '--since <SINCE>'\n\nAccepted formats:\n"));
+        assert!(error("2023-08-25T12:00:61Z").contains("but the second is 61"));
+    }
+
+    #[test]
+    fn test_accepted_format_examples() {
+        for (name, example) in ACCEPTED_FORMATS {
+            if name == "relative" {
+                for example in example.split(", ") {
+                    assert!(example.parse::<Timestamp>().is_ok(), "{example}");
+                }
+            } else {
+                assert!(example.parse::<Timestamp>().is_ok(), "{name}: {example}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_near_miss() {
+        assert_eq!(
+            near_miss("2023-02-30").unwrap(),
+            "looks like a date, but the day is 30 (and February 2023 has 28 days)"
+        );
+        assert_eq!(
+            near_miss("2023-08-25T25:00:00Z").unwrap(),
+            "looks like a date and time, but the hour is 25"
+        );
+        assert_eq!(
+            near_miss("2023-08-25 14:61").unwrap(),
+            "looks like a date and time, but the minute is 61"
+        );
+        assert_eq!(
+            near_miss("2023-08-25 14:00").unwrap(),
+            "looks like a date and time, but has no UTC offset (add Z or an offset such as +02:00)"
+        );
+        assert_eq!(
+            near_miss("99999999999999999999").unwrap(),
+            "looks like an epoch timestamp, but is out of range"
+        );
+        assert_eq!(
+            near_miss("08/25/2023").unwrap(),
+            "looks like a date, but dates must be written as YYYY-MM-DD"
+        );
+        assert_eq!(
+            near_miss("3 fortnights ago").unwrap(),
+            "looks like a relative time, but 'fortnights' is not a unit (use s, m, h, d, or w (or their full names))"
+        );
+        assert_eq!(
+            near_miss("3 hours").unwrap(),
+            "looks like a relative time; did you mean '3 hours ago'?"
+        );
+        assert_eq!(near_miss("nonsense"), None);
+    }
+}
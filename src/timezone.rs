@@ -0,0 +1,176 @@
+//! Timezone arguments for rendering timestamps in output.
+//!
+//! A timezone may be `UTC`, `local` (the machine's timezone), or a fixed offset such as `+02:00` or `-0530`. With the
+//! `tz` feature, IANA names such as `Europe/Berlin` are also accepted.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{FixedOffset, Offset, TimeZone};
+
+use crate::{Error, FormattedTimestamp, Timestamp, TimestampFormat};
+
+/// Standard output timezone argument.
+#[derive(clap::Args, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimezoneArgs {
+    /// Timezone for displayed timestamps (UTC, local, an offset such as +02:00, or an IANA name)
+    #[clap(long, global = true)]
+    tz: Option<TimezoneArg>,
+}
+
+impl TimezoneArgs {
+    pub fn new(tz: Option<TimezoneArg>) -> Self {
+        Self { tz }
+    }
+
+    /// The selected timezone (UTC by default).
+    pub fn tz(&self) -> TimezoneArg {
+        self.tz.unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimezoneArg {
+    #[default]
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+    #[cfg(feature = "tz")]
+    Named(chrono_tz::Tz),
+}
+
+impl TimezoneArg {
+    /// The offset from UTC in this timezone at the given instant.
+    pub fn offset_at(&self, timestamp: Timestamp) -> FixedOffset {
+        match self {
+            Self::Utc => FixedOffset::east_opt(0).expect("valid offset"),
+            Self::Local => chrono::Local
+                .offset_from_utc_datetime(&timestamp.0.naive_utc())
+                .fix(),
+            Self::Fixed(offset) => *offset,
+            #[cfg(feature = "tz")]
+            Self::Named(tz) => tz.offset_from_utc_datetime(&timestamp.0.naive_utc()).fix(),
+        }
+    }
+
+    /// Pair the timestamp with a format for display in this timezone.
+    pub fn format(&self, timestamp: Timestamp, format: TimestampFormat) -> FormattedTimestamp {
+        FormattedTimestamp {
+            timestamp: timestamp.0.with_timezone(&self.offset_at(timestamp)),
+            format,
+        }
+    }
+}
+
+impl Display for TimezoneArg {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utc => f.write_str("UTC"),
+            Self::Local => f.write_str("local"),
+            Self::Fixed(offset) => write!(f, "{offset}"),
+            #[cfg(feature = "tz")]
+            Self::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+impl FromStr for TimezoneArg {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            _ if s.eq_ignore_ascii_case("utc") || s == "Z" => Ok(Self::Utc),
+            _ if s.eq_ignore_ascii_case("local") => Ok(Self::Local),
+            _ if s.starts_with(['+', '-']) => parse_offset(s)
+                .map(Self::Fixed)
+                .ok_or_else(|| Error::InvalidTimezone(s.to_string())),
+            #[cfg(feature = "tz")]
+            _ => s
+                .parse()
+                .map(Self::Named)
+                .map_err(|_| Error::InvalidTimezone(s.to_string())),
+            #[cfg(not(feature = "tz"))]
+            _ => Err(Error::InvalidTimezone(s.to_string())),
+        }
+    }
+}
+
+/// Parse an offset of the form `+hh:mm` or `+hhmm`.
+fn parse_offset(input: &str) -> Option<FixedOffset> {
+    let (sign, rest) = match input.split_at_checked(1)? {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return None,
+    };
+
+    if !rest.is_ascii() {
+        return None;
+    }
+
+    let (hours, minutes) = match rest.len() {
+        5 if rest.as_bytes()[2] == b':' => (&rest[..2], &rest[3..]),
+        4 => rest.split_at(2),
+        _ => return None,
+    };
+
+    if !hours
+        .bytes()
+        .chain(minutes.bytes())
+        .all(|byte| byte.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let (hours, minutes) = (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?);
+
+    if minutes >= 60 {
+        return None;
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timezone_arg() {
+        let timestamp = "2023-08-25T06:47:09Z".parse::<Timestamp>().unwrap();
+        let format = TimestampFormat::Rfc3339 { use_z: false };
+        let render = |tz: &str| {
+            tz.parse::<TimezoneArg>()
+                .unwrap()
+                .format(timestamp, format)
+                .to_string()
+        };
+
+        assert_eq!(render("UTC"), "2023-08-25T06:47:09+00:00");
+        assert_eq!(render("+02:00"), "2023-08-25T08:47:09+02:00");
+        assert_eq!(render("-0530"), "2023-08-25T01:17:09-05:30");
+        assert_eq!(
+            "+02:00".parse::<TimezoneArg>().unwrap().to_string(),
+            "+02:00"
+        );
+
+        #[cfg(feature = "tz")]
+        assert_eq!(render("Europe/Berlin"), "2023-08-25T08:47:09+02:00");
+
+        for tz in [
+            "+2:00",
+            "+02:60",
+            "+25:00",
+            "+0:530",
+            "+05:3:0",
+            "+053:0",
+            "+02",
+            "+02:0",
+            "+0200:",
+            "+éé:00",
+            "+0é0",
+            "Mars/Olympus_Mons",
+        ] {
+            assert!(tz.parse::<TimezoneArg>().is_err(), "{tz}");
+        }
+    }
+}
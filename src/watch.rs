@@ -0,0 +1,266 @@
+//! Re-running on file changes (`--watch`).
+//!
+//! [`WatchArgs`] adds `--watch` and `--clear` flags. [`WatchArgs::run`] runs a closure once, or with `--watch`, runs
+//! it again whenever any of the given files (or any file under the given directories) changes. Changes are detected
+//! by polling modification times and sizes, and a run only starts once the files have stopped changing for the
+//! debounce interval, so that a burst of writes (such as an editor saving several files) triggers a single run.
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::watch::WatchArgs;
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     watch: WatchArgs,
+//!     input: std::path::PathBuf,
+//! }
+//!
+//! # fn main() -> Result<(), cli_helpers::Error> {
+//! let opts = Opts::parse();
+//!
+//! opts.watch.run(&[&opts.input], || -> Result<(), cli_helpers::Error> {
+//!     // ...
+//!     Ok(())
+//! })
+//! # }
+//! ```
+//!
+//! In watch mode, errors returned by the closure are logged and watching continues until the [`Shutdown`] token is
+//! triggered (by default, by Ctrl-C once the handlers are installed), at which point the current run is allowed to
+//! finish first.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::color::Stream;
+use crate::shutdown::Shutdown;
+use crate::Error;
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Clear the screen and move the cursor to the top left.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Standard watch mode flags.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WatchArgs {
+    /// Run again whenever the input files change
+    #[clap(long)]
+    watch: bool,
+    /// Clear the screen before each run in watch mode
+    #[clap(long, requires = "watch")]
+    clear: bool,
+}
+
+impl WatchArgs {
+    pub fn new(watch: bool, clear: bool) -> Self {
+        Self { watch, clear }
+    }
+
+    pub fn is_watch(&self) -> bool {
+        self.watch
+    }
+
+    pub fn watcher<P: AsRef<Path>>(&self, paths: &[P]) -> Watcher {
+        Watcher::new(paths).with_clear(self.clear)
+    }
+
+    /// Run the closure once, or with `--watch`, run it again on every change (until interrupted).
+    pub fn run<P: AsRef<Path>, E: From<Error> + Display, F: FnMut() -> Result<(), E>>(
+        &self,
+        paths: &[P],
+        f: F,
+    ) -> Result<(), E> {
+        if self.watch {
+            self.watcher(paths).run(f)
+        } else {
+            let mut f = f;
+            f()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    poll_interval: Duration,
+    debounce: Duration,
+    clear: bool,
+    shutdown: Shutdown,
+}
+
+impl Watcher {
+    pub fn new<P: AsRef<Path>>(paths: &[P]) -> Self {
+        Self {
+            paths: paths
+                .iter()
+                .map(|path| path.as_ref().to_path_buf())
+                .collect(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            debounce: DEFAULT_DEBOUNCE,
+            clear: false,
+            shutdown: Shutdown::signals(),
+        }
+    }
+
+    pub fn with_poll_interval(self, poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            ..self
+        }
+    }
+
+    /// Wait until files have not changed for this long before running.
+    pub fn with_debounce(self, debounce: Duration) -> Self {
+        Self { debounce, ..self }
+    }
+
+    /// Clear the screen before each run (only if standard output is a terminal).
+    pub fn with_clear(self, clear: bool) -> Self {
+        Self { clear, ..self }
+    }
+
+    /// Stop watching when this token is triggered (instead of on `SIGTERM` or `SIGINT`).
+    pub fn with_shutdown(self, shutdown: Shutdown) -> Self {
+        Self { shutdown, ..self }
+    }
+
+    /// Run the closure, and then run it again after every change, logging any errors (until interrupted).
+    pub fn run<E: From<Error> + Display, F: FnMut() -> Result<(), E>>(
+        &self,
+        mut f: F,
+    ) -> Result<(), E> {
+        let mut snapshot = self.snapshot()?;
+
+        loop {
+            if self.clear && Stream::Stdout.is_terminal() {
+                let mut stdout = std::io::stdout().lock();
+                stdout
+                    .write_all(CLEAR_SCREEN.as_bytes())
+                    .and_then(|()| stdout.flush())
+                    .map_err(Error::from)?;
+            }
+
+            if let Err(error) = f() {
+                log::error!("{error}");
+            }
+
+            match self.wait_for_change(snapshot)? {
+                Some(next) => snapshot = next,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Block until the files change and then settle, returning the new snapshot (or `None` on shutdown).
+    fn wait_for_change(&self, previous: Snapshot) -> Result<Option<Snapshot>, Error> {
+        let mut current = previous.clone();
+
+        while current == previous {
+            if !self.shutdown.sleep(self.poll_interval) {
+                return Ok(None);
+            }
+
+            current = self.snapshot()?;
+        }
+
+        loop {
+            std::thread::sleep(self.debounce);
+            let next = self.snapshot()?;
+
+            if next == current {
+                return Ok(Some(next));
+            }
+
+            current = next;
+        }
+    }
+
+    /// The modification times and sizes of every watched file.
+    fn snapshot(&self) -> Result<Snapshot, Error> {
+        let mut snapshot = Snapshot::new();
+
+        for path in &self.paths {
+            add_to_snapshot(path, &mut snapshot)?;
+        }
+
+        Ok(snapshot)
+    }
+}
+
+type Snapshot = BTreeMap<PathBuf, Option<(SystemTime, u64)>>;
+
+fn add_to_snapshot(path: &Path, snapshot: &mut Snapshot) -> Result<(), Error> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        // Deleted files are recorded as missing, so that recreating them counts as a change.
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            snapshot.insert(path.to_path_buf(), None);
+            return Ok(());
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    if metadata.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            add_to_snapshot(&entry?.path(), snapshot)?;
+        }
+    } else {
+        snapshot.insert(
+            path.to_path_buf(),
+            Some((metadata.modified()?, metadata.len())),
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::test_dir;
+
+    #[test]
+    fn test_watcher() {
+        let temp_dir = test_dir();
+        let dir = temp_dir.path();
+        std::fs::write(dir.join("input.txt"), b"first").unwrap();
+
+        let watcher = Watcher::new(&[dir])
+            .with_poll_interval(Duration::from_millis(10))
+            .with_debounce(Duration::from_millis(10));
+        let snapshot = watcher.snapshot().unwrap();
+        assert_eq!(snapshot.len(), 1);
+
+        let writer = {
+            let dir = dir.to_path_buf();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                std::fs::write(dir.join("input.txt"), b"second").unwrap();
+                std::fs::write(dir.join("other.txt"), b"new").unwrap();
+            })
+        };
+
+        let next = watcher.wait_for_change(snapshot).unwrap().unwrap();
+        writer.join().unwrap();
+
+        // Debouncing means that both writes are seen.
+        assert_eq!(next, watcher.snapshot().unwrap());
+        assert_eq!(next.len(), 2);
+
+        let shutdown = Shutdown::new();
+        shutdown.request();
+        assert_eq!(
+            watcher
+                .with_shutdown(shutdown)
+                .wait_for_change(next)
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,491 @@
+//! Running jobs on a bounded number of threads.
+//!
+//! A [`WorkQueue`] pulls jobs from an iterator, runs a worker closure on each with a fixed number of threads, and
+//! passes each result to a sink closure on the calling thread. By default results are passed on in the order of the
+//! jobs (results that finish early are held back until the earlier ones are done); [`Order::Completion`] relaxes
+//! this, passing on each result as soon as it is ready. Jobs are only pulled from the iterator as threads become free,
+//! so the input can be arbitrarily long. When the [`Shutdown`] token is triggered (by default, by Ctrl-C once the
+//! handlers are installed), no new jobs are started, and the results of the jobs that have already started are
+//! passed on.
+//!
+//! The queue can report each completed job to a [`Progress`] frontend and count successes and failures in a
+//! [`Summary`]:
+//!
+//! ```rust,no_run
+//! use cli_helpers::prelude::*;
+//! use cli_helpers::{output::OutputFormat, progress::ProgressArgs, summary::Summary, work_queue::JobsArgs};
+//!
+//! #[derive(Debug, Parser)]
+//! struct Opts {
+//!     #[clap(flatten)]
+//!     jobs: JobsArgs,
+//!     #[clap(flatten)]
+//!     progress: ProgressArgs,
+//! }
+//!
+//! let opts = Opts::parse();
+//! let progress = opts.progress.progress();
+//! let summary = Summary::new().with_noun("files");
+//!
+//! opts.jobs
+//!     .queue()
+//!     .with_progress(progress.as_ref())
+//!     .with_summary(&summary)
+//!     .for_each(
+//!         std::env::args().skip(1).collect::<Vec<_>>(),
+//!         |path| std::fs::read(&path).map(|contents| (path, contents.len())),
+//!         |_, result| match result {
+//!             Ok((path, len)) => println!("{path}: {len}"),
+//!             Err(error) => eprintln!("{error}"),
+//!         },
+//!     );
+//!
+//! progress.finish();
+//! summary.print(OutputFormat::Table);
+//! ```
+//!
+//! For streaming pipelines, [`par_map_ordered`] is an iterator adapter that maps items on worker threads and yields
+//! the results in input order. At most twice as many items as there are workers are in flight (or waiting to be
+//! yielded) at once, so memory use is bounded however long the input is:
+//!
+//! ```rust,no_run
+//! use cli_helpers::work_queue::par_map_ordered;
+//! use std::io::BufRead;
+//! use std::num::NonZeroUsize;
+//!
+//! let lines = std::io::stdin().lock().lines().map_while(Result::ok);
+//! let workers = NonZeroUsize::new(8).unwrap();
+//!
+//! for line in par_map_ordered(lines, workers, |line| line.to_uppercase()) {
+//!     println!("{line}");
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::panic::AssertUnwindSafe;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::progress::Progress;
+use crate::shutdown::Shutdown;
+use crate::summary::Summary;
+
+/// The order in which results are passed to the sink.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub enum Order {
+    /// The order of the jobs
+    #[default]
+    Input,
+    /// The order in which the jobs finish
+    Completion,
+}
+
+/// Standard concurrency argument.
+#[derive(clap::Args, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JobsArgs {
+    /// Number of jobs to run at once (defaults to the number of CPUs)
+    #[clap(long, short = 'j')]
+    jobs: Option<NonZeroUsize>,
+}
+
+impl JobsArgs {
+    pub fn new(jobs: Option<NonZeroUsize>) -> Self {
+        Self { jobs }
+    }
+
+    /// The number of threads to use.
+    pub fn threads(&self) -> NonZeroUsize {
+        self.jobs.unwrap_or_else(default_threads)
+    }
+
+    pub fn queue<'a>(&self) -> WorkQueue<'a> {
+        WorkQueue::new(self.threads())
+    }
+}
+
+#[derive(Clone)]
+pub struct WorkQueue<'a> {
+    threads: NonZeroUsize,
+    order: Order,
+    progress: Option<&'a dyn Progress>,
+    summary: Option<&'a Summary>,
+    shutdown: Shutdown,
+}
+
+impl<'a> WorkQueue<'a> {
+    pub fn new(threads: NonZeroUsize) -> Self {
+        Self {
+            threads,
+            order: Order::default(),
+            progress: None,
+            summary: None,
+            shutdown: Shutdown::signals(),
+        }
+    }
+
+    pub fn with_order(self, order: Order) -> Self {
+        Self { order, ..self }
+    }
+
+    /// Tick the progress frontend as each job finishes.
+    pub fn with_progress(self, progress: &'a dyn Progress) -> Self {
+        Self {
+            progress: Some(progress),
+            ..self
+        }
+    }
+
+    /// Record a success or error in the summary as each job finishes.
+    pub fn with_summary(self, summary: &'a Summary) -> Self {
+        Self {
+            summary: Some(summary),
+            ..self
+        }
+    }
+
+    /// Stop starting new jobs when this token is triggered (instead of on `SIGTERM` or `SIGINT`).
+    pub fn with_shutdown(self, shutdown: Shutdown) -> Self {
+        Self { shutdown, ..self }
+    }
+
+    pub fn threads(&self) -> NonZeroUsize {
+        self.threads
+    }
+
+    /// Run the worker on every job, passing each result (with the job's index) to the sink on the calling thread.
+    ///
+    /// A panic in the worker is propagated once the other threads have stopped.
+    pub fn for_each<I, R, E, F, S>(&self, jobs: I, worker: F, mut sink: S)
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        R: Send,
+        E: Send,
+        F: Fn(I::Item) -> Result<R, E> + Sync,
+        S: FnMut(usize, Result<R, E>),
+    {
+        let jobs = Mutex::new(jobs.into_iter().enumerate());
+        let threads = self.threads.get();
+
+        std::thread::scope(|scope| {
+            // The channel is bounded so that workers don't get too far ahead of the sink.
+            let (sender, receiver) = mpsc::sync_channel(threads * 2);
+
+            for _ in 0..threads {
+                let sender = sender.clone();
+                let (jobs, worker, shutdown) = (&jobs, &worker, &self.shutdown);
+
+                scope.spawn(move || loop {
+                    if shutdown.is_requested() {
+                        break;
+                    }
+
+                    let next = jobs.lock().unwrap_or_else(|e| e.into_inner()).next();
+
+                    match next {
+                        Some((index, job)) => {
+                            if sender.send((index, worker(job))).is_err() {
+                                break;
+                            }
+                        }
+                        None => break,
+                    }
+                });
+            }
+
+            drop(sender);
+
+            let mut pending = BTreeMap::new();
+            let mut next_index = 0;
+
+            for (index, result) in receiver {
+                self.report(&result);
+
+                match self.order {
+                    Order::Completion => sink(index, result),
+                    Order::Input => {
+                        pending.insert(index, result);
+
+                        while let Some(result) = pending.remove(&next_index) {
+                            sink(next_index, result);
+                            next_index += 1;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    /// Run the worker on every job, collecting the results (in the configured order).
+    pub fn run<I, R, E, F>(&self, jobs: I, worker: F) -> Vec<Result<R, E>>
+    where
+        I: IntoIterator,
+        I::IntoIter: Send,
+        I::Item: Send,
+        R: Send,
+        E: Send,
+        F: Fn(I::Item) -> Result<R, E> + Sync,
+    {
+        let mut results = Vec::new();
+        self.for_each(jobs, worker, |_, result| results.push(result));
+        results
+    }
+
+    fn report<R, E>(&self, result: &Result<R, E>) {
+        if let Some(progress) = self.progress {
+            progress.tick();
+        }
+
+        if let Some(summary) = self.summary {
+            match result {
+                Ok(_) => summary.record(),
+                Err(_) => summary.error(),
+            }
+        }
+    }
+}
+
+impl Default for WorkQueue<'_> {
+    fn default() -> Self {
+        Self::new(default_threads())
+    }
+}
+
+impl std::fmt::Debug for WorkQueue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WorkQueue")
+            .field("threads", &self.threads)
+            .field("order", &self.order)
+            .field("shutdown", &self.shutdown)
+            .finish()
+    }
+}
+
+/// Map the items on `workers` threads, yielding the results in input order (see the [module docs](self)).
+///
+/// The input iterator is consumed on the calling thread. A panic in `f` is propagated when its result would be yielded.
+/// When the [`Shutdown`] token is triggered, no more items are taken from the input, and the iterator ends once the
+/// results of the items already taken have been yielded.
+pub fn par_map_ordered<I, R, F>(
+    iter: I,
+    workers: NonZeroUsize,
+    f: F,
+) -> ParMapOrdered<I::IntoIter, R>
+where
+    I: IntoIterator,
+    I::Item: Send + 'static,
+    R: Send + 'static,
+    F: Fn(I::Item) -> R + Send + Sync + 'static,
+{
+    let (job_sender, job_receiver) = mpsc::channel::<(usize, I::Item)>();
+    let (result_sender, result_receiver) = mpsc::channel();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let f = Arc::new(f);
+
+    let handles = (0..workers.get())
+        .map(|_| {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            let f = f.clone();
+
+            std::thread::spawn(move || loop {
+                let next = job_receiver
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .recv();
+
+                match next {
+                    Ok((index, item)) => {
+                        let result = std::panic::catch_unwind(AssertUnwindSafe(|| f(item)));
+
+                        if result_sender.send((index, result)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            })
+        })
+        .collect();
+
+    ParMapOrdered {
+        iter: iter.into_iter(),
+        capacity: workers.get() * 2,
+        job_sender: Some(job_sender),
+        result_receiver,
+        handles,
+        pending: BTreeMap::new(),
+        next_index: 0,
+        issued: 0,
+        done: false,
+        shutdown: Shutdown::signals(),
+    }
+}
+
+/// The iterator returned by [`par_map_ordered`].
+pub struct ParMapOrdered<I: Iterator, R> {
+    iter: I,
+    /// The maximum number of items that have been sent to workers but not yet yielded.
+    capacity: usize,
+    job_sender: Option<mpsc::Sender<(usize, I::Item)>>,
+    result_receiver: mpsc::Receiver<(usize, std::thread::Result<R>)>,
+    handles: Vec<JoinHandle<()>>,
+    pending: BTreeMap<usize, std::thread::Result<R>>,
+    next_index: usize,
+    issued: usize,
+    done: bool,
+    shutdown: Shutdown,
+}
+
+impl<I: Iterator, R> ParMapOrdered<I, R> {
+    /// Stop taking items from the input when this token is triggered (instead of on `SIGTERM` or `SIGINT`).
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+impl<I: Iterator, R> Iterator for ParMapOrdered<I, R> {
+    type Item = R;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && self.issued - self.next_index < self.capacity {
+            if self.shutdown.is_requested() {
+                self.done = true;
+                break;
+            }
+
+            match (self.iter.next(), &self.job_sender) {
+                (Some(item), Some(job_sender)) => {
+                    // The workers only stop when the sender is dropped.
+                    let _ = job_sender.send((self.issued, item));
+                    self.issued += 1;
+                }
+                _ => {
+                    self.done = true;
+                    self.job_sender = None;
+                }
+            }
+        }
+
+        if self.next_index == self.issued {
+            return None;
+        }
+
+        let result = loop {
+            if let Some(result) = self.pending.remove(&self.next_index) {
+                break result;
+            }
+
+            // Every worker catches panics, so results for all issued items will arrive.
+            let (index, result) = self.result_receiver.recv().ok()?;
+            self.pending.insert(index, result);
+        };
+
+        self.next_index += 1;
+
+        match result {
+            Ok(value) => Some(value),
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl<I: Iterator, R> Drop for ParMapOrdered<I, R> {
+    fn drop(&mut self) {
+        self.job_sender = None;
+
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl<I: Iterator, R> std::fmt::Debug for ParMapOrdered<I, R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParMapOrdered")
+            .field("workers", &self.handles.len())
+            .field("next_index", &self.next_index)
+            .field("issued", &self.issued)
+            .finish()
+    }
+}
+
+fn default_threads() -> NonZeroUsize {
+    std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn worker(job: u64) -> Result<u64, String> {
+        // Later jobs finish first.
+        std::thread::sleep(Duration::from_millis(20 - job * 2));
+
+        if job.is_multiple_of(3) {
+            Err(format!("job {job} failed"))
+        } else {
+            Ok(job * 10)
+        }
+    }
+
+    #[test]
+    fn test_work_queue() {
+        let summary = Summary::new();
+        let queue = WorkQueue::new(NonZeroUsize::new(4).unwrap()).with_summary(&summary);
+        let results = queue.run(0..10, worker);
+
+        assert_eq!(results, (0..10).map(worker).collect::<Vec<_>>());
+        assert_eq!(summary.records(), 6);
+        assert_eq!(summary.errors(), 4);
+
+        let mut indices = Vec::new();
+        queue
+            .clone()
+            .with_order(Order::Completion)
+            .for_each(0..10, worker, |index, result| {
+                assert_eq!(result, worker(index as u64));
+                indices.push(index);
+            });
+
+        indices.sort();
+        assert_eq!(indices, (0..10).collect::<Vec<_>>());
+
+        let shutdown = Shutdown::new();
+        shutdown.request();
+        assert!(queue.with_shutdown(shutdown).run(0..10, worker).is_empty());
+    }
+
+    #[test]
+    fn test_par_map_ordered() {
+        let workers = NonZeroUsize::new(3).unwrap();
+        let results = par_map_ordered(0..10, workers, worker).collect::<Vec<_>>();
+        assert_eq!(results, (0..10).map(worker).collect::<Vec<_>>());
+
+        // Dropping the iterator early stops the workers.
+        let mut results = par_map_ordered(0.., workers, |i: u64| i * 2);
+        assert_eq!(results.next(), Some(0));
+        assert_eq!(results.next(), Some(2));
+        drop(results);
+
+        let shutdown = Shutdown::new();
+        let mut results = par_map_ordered(0.., workers, |i: u64| i).with_shutdown(shutdown.clone());
+        assert_eq!(results.next(), Some(0));
+        shutdown.request();
+        // Only the items that had already been taken are yielded.
+        assert!(results.count() < 6);
+
+        let panicked = std::panic::catch_unwind(|| {
+            par_map_ordered(0..10, workers, |i| {
+                assert!(i != 5);
+                i
+            })
+            .count()
+        });
+        assert!(panicked.is_err());
+    }
+}